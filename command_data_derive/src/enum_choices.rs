@@ -44,6 +44,7 @@ pub fn enum_impl(ty: &Ident, data: DataEnum) -> TokenStream2 {
             type Options = ::discorsd::model::interaction::InteractionDataOption;
 
             fn from_options(
+                _: &C,
                 option: Self::Options,
             ) -> ::std::result::Result<Self, ::discorsd::errors::CommandParseError> {
                 use ::discorsd::errors::*;
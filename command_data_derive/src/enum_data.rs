@@ -7,7 +7,7 @@ use syn::{Attribute, DataEnum, Fields, Ident, LitStr, Path, Type, TypeParam};
 use syn::spanned::Spanned;
 
 use crate::struct_data::{description_len_check, Field, Struct};
-use crate::utils::{command_data_impl, use_generics};
+use crate::utils::{command_data_impl, CommandAttrs, doc_comment, use_generics};
 
 pub fn enum_impl(ty: &Ident, generics: Vec<TypeParam>, data: DataEnum, attrs: &[Attribute]) -> TokenStream2 {
     let mut variants: Enum = data.variants
@@ -53,6 +53,7 @@ impl From<syn::Variant> for Variant {
         if variant.discriminant.is_some() {
             abort!(variant, "Command variants can't have discriminants (ex, `= 1`)");
         }
+        let span = variant.span();
         let attrs = variant.attrs;
         let mut variant = Self {
             attrs: Vec::new(),
@@ -66,6 +67,15 @@ impl From<syn::Variant> for Variant {
             if !attr.path.is_ident("command") { continue; }
             variant.handle_attribute(attr);
         }
+        if variant.desc.is_none() {
+            variant.desc = doc_comment(&attrs);
+        }
+        if variant.desc.is_none() {
+            emit_warning!(
+                span, "command option has no description, so its name will be used";
+                help = "add a doc comment or `#[command(desc = \"...\")]`"
+            );
+        }
         variant.attrs = attrs;
 
         variant
@@ -78,6 +88,8 @@ pub struct Enum {
     /// settable with `#[command(type = MyCommand)]` on an enum
     pub command_type: Option<Type>,
     generics: Vec<TypeParam>,
+    /// `nsfw`, `integration_types`, and `contexts`
+    pub command_attrs: CommandAttrs,
 }
 
 impl Enum {
@@ -262,7 +274,7 @@ impl Enum {
                 let ty = &first.ty;
                 quote_spanned! { first.span() =>
                     #ident(
-                        <#ty as ::discorsd::commands::CommandData<#c_ty>>::from_options(options)?
+                        <#ty as ::discorsd::commands::CommandData<#c_ty>>::from_options(command, options)?
                     )
                 }
             } else {
@@ -275,9 +287,12 @@ impl Enum {
             }
         });
         let variants_array = self.variants_array();
+        let command_consts = self.command_attrs.consts();
 
         quote! {
             #command_data_impl for #generic_ty {
+                #command_consts
+
                 // god that's ugly v2
                 type Options =
                 <
@@ -287,6 +302,7 @@ impl Enum {
                 >::Raise;
 
                 fn from_options(
+                    command: &#c_ty,
                     Self::Options { name, name_localizations, data: ::discorsd::model::interaction::HasOptions { options }, focused }: Self::Options,
                 ) -> ::std::result::Result<Self, ::discorsd::errors::CommandParseError> {
                     match name.as_str() {
@@ -340,13 +356,17 @@ impl Enum {
         let from_option_branches = self.from_options_branches(ty, &c_ty);
         let variants_array = self.variants_array();
         let make_args_vec = self.make_args_vec(&c_ty);
+        let command_consts = self.command_attrs.consts();
 
         quote! {
             #command_data_impl_statement for #ty {
+                #command_consts
+
                 // All inline struct enums are SubCommands
                 type Options = ::discorsd::model::interaction::DataOption<::discorsd::model::interaction::SubCommand>;
 
                 fn from_options(
+                    command: &#c_ty,
                     Self::Options { name, name_localizations, data: ::discorsd::model::interaction::HasOptions { options }, focused }: Self::Options
                 ) -> ::std::result::Result<Self, ::discorsd::errors::CommandParseError> {
                     use ::discorsd::errors::*;
@@ -375,6 +395,6 @@ impl Enum {
 
 impl FromIterator<Variant> for Enum {
     fn from_iter<T: IntoIterator<Item=Variant>>(iter: T) -> Self {
-        Self { variants: iter.into_iter().collect(), command_type: None, generics: Vec::new() }
+        Self { variants: iter.into_iter().collect(), command_type: None, generics: Vec::new(), command_attrs: CommandAttrs::NONE }
     }
 }
\ No newline at end of file
@@ -544,7 +544,7 @@ fn dummy_command_impl(ty: &Ident) {
 
         impl<C: ::discorsd::commands::SlashCommandRaw> ::discorsd::model::commands::CommandData<C> for #ty {
             type Options = #fail_enum;
-            fn from_options(_: Self::Options) -> ::std::result::Result<Self, ::discorsd::errors::CommandParseError> {
+            fn from_options(_: &C, _: Self::Options) -> ::std::result::Result<Self, ::discorsd::errors::CommandParseError> {
                 unimplemented!()
             }
             type VecArg = #fail_enum;
@@ -716,13 +716,19 @@ handle_attribute! {
                 named.rename = Some(str);
             }
         }]
-        /// The description of this command option. If omitted, will use the field's name as the
-        /// description.
+        /// The description of this command option. If omitted, will use the field's doc comment, or
+        /// if it has none, the field's name as the description.
+        /// Falling back to the name emits a warning (only shown on nightly).
         ["desc" => self.desc = Some(str)]
         /// Marks this field as optional in the Command in Discord, and if the user omits it, will use
         /// this function to provide the default if this field is missing. Must be callable as
         /// `fn() -> T`, where `T` is this field's type.
         ["default" => self.default = Some(str.parse()?)]
+        /// Marks this field as optional in the Command in Discord, and if the user omits it, will use
+        /// this function to provide the default from the command. Must be callable as
+        /// `fn<C: SlashCommand>(&C) -> T`, where `T` is this field's type and the generic is not
+        /// necessary if the struct's type is specified (with `#[command(command = "MyCommand")]`).
+        ["default_with" => self.default_with = Some(str.parse()?)]
         // todo make a va_desc_name thing
         /// Marks this field as a vararg argument to the command, with the name and description
         /// created by appending a counting integer to `{str}`. Allows the user to chose multiple
@@ -744,6 +750,12 @@ handle_attribute! {
         /// `fn<C: SlashCommand>(&C) -> bool`, where the generic is not necessary if the
         /// struct's type is specified (with `#[command(command = "MyCommand")]` as above).
         ["required" => self.required = Some(str.parse()?)]
+        /// Function to validate this field after it is parsed, must be callable as
+        /// `fn(&T) -> Result<(), String>`, where `T` is this field's type. If it returns `Err`,
+        /// parsing fails with `CommandParseError::InvalidOption` carrying the message.
+        ///
+        /// Not run on values filled in by `default` or `default_with`.
+        ["validate" => self.validate = Some(str.parse()?)]
         /// `fn<C: SlashCommand>(&C) -> usize` to pick how many vararg options to display.
         /// The the same generic rules apply as above. If you want a fixed number of varargs in the
         /// command, set `va_req` to an [`{int}`](macro.Documentation_For_Field.html#va_count-1).
@@ -798,6 +810,29 @@ handle_attribute! {
     /// ```
     self: Struct =>
 
+    "": Meta::Path(path), path =>
+        /// Assume that Discord sends this struct's options in the order its fields are declared,
+        /// so that each option is first checked against the field after the previous one, instead
+        /// of against every field.
+        ///
+        /// This is only correct as long as Discord keeps sending options in declaration order,
+        /// which it doesn't guarantee. If an option does arrive out of order, parsing the data fails
+        /// with `CommandParseError::BadOrder`.
+        ["positional" => self.positional = true]
+        /// Mark the command this is data for as age-restricted, so that it is only shown to users
+        /// who have verified their age. Only has an effect on the command's top level data.
+        ["nsfw" => self.command_attrs.nsfw = true];
+
+    "(...)": Meta::List(MetaList { path, nested, .. }), path =>
+        /// Where the command this is data for can be installed, any of `GuildInstall` and
+        /// `UserInstall`. If omitted, Discord uses the app's configured installation contexts.
+        /// Only has an effect on the command's top level data.
+        ["integration_types" => self.command_attrs.integration_types = Some(utils::list_idents(&nested, utils::CommandAttrs::INTEGRATION_TYPES)?)]
+        /// Where the command this is data for can be used, any of `Guild`, `BotDm`, and
+        /// `PrivateChannel`. If omitted, the command can be used everywhere. Only has an effect on
+        /// the command's top level data.
+        ["contexts" => self.command_attrs.contexts = Some(utils::list_idents(&nested, utils::CommandAttrs::CONTEXTS)?)];
+
     " = {str}": Meta::NameValue(MetaNameValue { path, lit: Lit::Str(str), .. }), path =>
         /// Specify the type of the `SlashCommand` that this is data for. Useful for annotations that
         /// can make decisions at runtime by taking functions callable as `fn(&CommandType) -> SomeType`.
@@ -823,7 +858,9 @@ handle_attribute! {
     self: Variant =>
 
     " = {str}": Meta::NameValue(MetaNameValue { path, lit: Lit::Str(str), .. }), path =>
-        /// The description of this command option.
+        /// The description of this command option. If omitted, will use the variant's doc comment,
+        /// or if it has none, the variant's name as the description.
+        /// Falling back to the name emits a warning (only shown on nightly).
         ["desc" => self.desc = Some(str)]
         /// What to rename this field as in the Command.
         ["rename" => self.rename = Some(str)]
@@ -859,6 +896,21 @@ handle_attribute! {
     /// All variants will be shown as lowercase in Discord.
     self: Enum =>
 
+    "": Meta::Path(path), path =>
+        /// Mark the command this is data for as age-restricted, so that it is only shown to users
+        /// who have verified their age. Only has an effect on the command's top level data.
+        ["nsfw" => self.command_attrs.nsfw = true];
+
+    "(...)": Meta::List(MetaList { path, nested, .. }), path =>
+        /// Where the command this is data for can be installed, any of `GuildInstall` and
+        /// `UserInstall`. If omitted, Discord uses the app's configured installation contexts.
+        /// Only has an effect on the command's top level data.
+        ["integration_types" => self.command_attrs.integration_types = Some(utils::list_idents(&nested, utils::CommandAttrs::INTEGRATION_TYPES)?)]
+        /// Where the command this is data for can be used, any of `Guild`, `BotDm`, and
+        /// `PrivateChannel`. If omitted, the command can be used everywhere. Only has an effect on
+        /// the command's top level data.
+        ["contexts" => self.command_attrs.contexts = Some(utils::list_idents(&nested, utils::CommandAttrs::CONTEXTS)?)];
+
     " = {str}": Meta::NameValue(MetaNameValue { path, lit: Lit::Str(str), .. }), path =>
         /// Specify the type of the `SlashCommand` that this is data for. Useful for annotations that
         /// can make decisions at runtime by taking functions callable as `fn(CommandType) -> SomeType`.
@@ -903,7 +955,15 @@ handle_attribute! {
         /// The label to show in Discord for this choice, as well as for the `Display` impl
         ["label" => self.label = Some(str)]
         /// The description to show in Discord for this choice
-        ["desc" => self.description = Some(str)];
+        ["desc" => self.description = Some(str)]
+        /// The description to show in Discord for this choice
+        ["description" => self.description = Some(str)]
+        /// The (unicode) emoji to show in Discord for this choice
+        ["emoji" => self.emoji = Some(str)];
+
+    "": Meta::Path(path), path =>
+        /// Show this choice as selected by default
+        ["default" => self.default = true];
 }
 
 handle_attribute! {
@@ -914,6 +974,12 @@ handle_attribute! {
     "": Meta::Path(path), path =>
         /// Don't impl Display for this type
         ["skip_display" => self.skip_display = true];
+
+    " = {int}": Meta::NameValue(MetaNameValue { path, lit: Lit::Int(int), .. }), path =>
+        /// The minimum number of options that must be chosen (default 1, min 0, max 25)
+        ["min_values" => self.min_values = Some(int.base10_parse()?)]
+        /// The maximum number of options that can be chosen (default 1, max 25)
+        ["max_values" => self.max_values = Some(int.base10_parse()?)];
 }
 
 handle_attribute! {
@@ -4,7 +4,7 @@ use quote::{quote, quote_spanned};
 use syn::{Attribute, DataEnum, Ident, LitStr};
 
 pub fn menu_impl(ty: &Ident, data: DataEnum, attrs: &[Attribute]) -> TokenStream2 {
-    let mut enm = Enum { skip_display: false };
+    let mut enm = Enum { skip_display: false, min_values: None, max_values: None };
     attrs.iter()
         .filter(|a| a.path.is_ident("menu"))
         .for_each(|a| enm.handle_attribute(a));
@@ -13,21 +13,32 @@ pub fn menu_impl(ty: &Ident, data: DataEnum, attrs: &[Attribute]) -> TokenStream
         .into_iter()
         .map(Variant::from)
         .collect();
+    // the options' values are the variants' names, so they're always unique
+    if variants.len() > 25 {
+        abort!(ty, "Menus can have at most 25 options, but `{}` has {}", ty, variants.len())
+    }
+    let max = enm.max_values.unwrap_or(1);
+    if max > variants.len() {
+        abort!(ty, "`max_values` is {}, but `{}` only has {} options", max, ty, variants.len())
+    }
+    if let Some(min) = enm.min_values {
+        if min > max {
+            abort!(ty, "`min_values` ({}) can't be greater than `max_values` ({})", min, max)
+        }
+    }
+    let opt_usize = |n: Option<usize>| if let Some(n) = n {
+        quote! { ::std::option::Option::Some(#n) }
+    } else {
+        quote! { ::std::option::Option::None }
+    };
+    let min_values = opt_usize(enm.min_values);
+    let max_values = opt_usize(enm.max_values);
 
     let into_branches = variants.iter().map(|v| {
         let ident = &v.ident;
-        let value = &v.ident_str;
-        let label = v.display();
-        let desc = v.desc();
-        // todo allow for setting description, emoji, etc
+        let option = v.option();
         quote_spanned! { v.ident.span() =>
-            Self::#ident => ::discorsd::model::components::SelectOption {
-                label: #label.to_string(),
-                value: #value.to_string(),
-                description: #desc,
-                emoji: ::std::option::Option::None,
-                default: false,
-            }
+            Self::#ident => #option
         }
     });
 
@@ -36,21 +47,7 @@ pub fn menu_impl(ty: &Ident, data: DataEnum, attrs: &[Attribute]) -> TokenStream
         quote! { Self::#ident }
     });
 
-    let options = variants.iter().map(|v| {
-        let value = &v.ident_str;
-        let label = v.display();
-        let desc = v.desc();
-        // todo allow for setting description, emoji, etc
-        quote_spanned! { v.ident.span() =>
-            ::discorsd::model::components::SelectOption {
-                label: #label.to_string(),
-                value: #value.to_string(),
-                description: #desc,
-                emoji: ::std::option::Option::None,
-                default: false,
-            }
-        }
-    });
+    let options = variants.iter().map(Variant::option);
 
     let from_str_branches = variants.iter().map(|v| {
         let str = &v.ident_str;
@@ -97,6 +94,8 @@ pub fn menu_impl(ty: &Ident, data: DataEnum, attrs: &[Attribute]) -> TokenStream
 
         impl ::discorsd::commands::MenuData for #ty {
             type Data = ::std::string::String;
+            const MIN_VALUES: ::std::option::Option<usize> = #min_values;
+            const MAX_VALUES: ::std::option::Option<usize> = #max_values;
 
             fn into_option(self) -> ::discorsd::model::components::SelectOption {
                 match self {
@@ -117,6 +116,8 @@ pub fn menu_impl(ty: &Ident, data: DataEnum, attrs: &[Attribute]) -> TokenStream
 
 pub struct Enum {
     pub skip_display: bool,
+    pub min_values: Option<usize>,
+    pub max_values: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -125,6 +126,8 @@ pub struct Variant {
     ident_str: String,
     pub label: Option<LitStr>,
     pub description: Option<LitStr>,
+    pub emoji: Option<LitStr>,
+    pub default: bool,
 }
 
 impl Variant {
@@ -144,6 +147,34 @@ impl Variant {
             quote! { ::std::option::Option::None }
         }
     }
+
+    fn emoji(&self) -> TokenStream2 {
+        if let Some(emoji) = &self.emoji {
+            let emoji = emoji.value();
+            quote! {
+                ::std::option::Option::Some(::discorsd::model::emoji::Emoji::Unicode { name: #emoji.into() })
+            }
+        } else {
+            quote! { ::std::option::Option::None }
+        }
+    }
+
+    fn option(&self) -> TokenStream2 {
+        let value = &self.ident_str;
+        let label = self.display();
+        let desc = self.desc();
+        let emoji = self.emoji();
+        let default = self.default;
+        quote_spanned! { self.ident.span() =>
+            ::discorsd::model::components::SelectOption {
+                label: #label.to_string(),
+                value: #value.to_string(),
+                description: #desc,
+                emoji: #emoji,
+                default: #default,
+            }
+        }
+    }
 }
 
 impl From<syn::Variant> for Variant {
@@ -161,6 +192,8 @@ impl From<syn::Variant> for Variant {
             ident_str,
             label: None,
             description: None,
+            emoji: None,
+            default: false,
         };
         attrs.iter()
             .filter(|a| a.path.is_ident("menu"))
@@ -1,7 +1,7 @@
 use std::iter::FromIterator;
 
 use proc_macro2::{Span, TokenStream as TokenStream2};
-use proc_macro_error::emit_error;
+use proc_macro_error::{emit_error, emit_warning};
 use quote::{quote, quote_spanned};
 use syn::{Attribute, Fields, Ident, Index, LitInt, LitStr, Path, Type, TypeParam};
 use syn::spanned::Spanned;
@@ -24,13 +24,17 @@ pub fn struct_impl(ty: &Ident, generics: Vec<TypeParam>, fields: Fields, attribu
         &command_type,
     );
     let data_options = strukt.data_options(&command_type);
+    let command_consts = strukt.command_attrs.consts();
 
     let tokens = quote! {
         #command_data_impl for #generic_ty {
+            #command_consts
+
             // all structs are built from a Vec<ValueOption>
             type Options = ::std::vec::Vec<::discorsd::model::interaction::InteractionDataOption>;
 
             fn from_options(
+                command: &#command_type,
                 options: Self::Options,
             ) -> ::std::result::Result<Self, ::discorsd::errors::CommandParseError> {
                 #from_options_body
@@ -56,6 +60,10 @@ pub struct Field {
     pub ty: Type,
     /// for example, Default::default or Instant::now
     pub default: Option<Path>,
+    /// function to provide the default from the command, must be callable as
+    /// `fn<C: SlashCommand>(command: &C) -> T`, where the generic is not necessary if the
+    /// struct's type is specified (`#[command(command = "MyCommand")]`)
+    pub default_with: Option<Path>,
     /// function to determine if this field is required, must be callable as
     /// `fn<C: SlashCommand>(command: &C) -> bool`, where the generic is not necessary if the
     /// struct's type is specified (`#[command(type = "MyCommand")]`)
@@ -76,6 +84,9 @@ pub struct Field {
     pub retain: Option<Path>,
     /// The description of this `DataOption`
     pub desc: Option<LitStr>,
+    /// function to validate the parsed value, must be callable as
+    /// `fn(&T) -> Result<(), String>`, where `T` is this field's type
+    pub validate: Option<Path>,
 }
 
 #[derive(Debug)]
@@ -210,6 +221,22 @@ impl Field {
             }
         }
     }
+
+    /// The name of this field's option as written, for error messages. Unlike
+    /// [`arg_name`](Self::arg_name), generics in a `rename` aren't filled in, so that this is a
+    /// string literal.
+    fn static_name(&self) -> String {
+        let rename = match &self.name {
+            FieldIdent::Named(named) => named.rename.as_ref().map(LitStr::value),
+            FieldIdent::Unnamed(_) => None,
+        };
+        rename
+            .or_else(|| self.vararg.as_ref().and_then(|v| v.names.as_index()).map(LitStr::value))
+            .unwrap_or_else(|| match &self.name {
+                FieldIdent::Named(named) => named.ident.to_string(),
+                FieldIdent::Unnamed(unnamed) => unnamed.index.index.to_string(),
+            })
+    }
 }
 
 
@@ -268,6 +295,7 @@ impl VarargNames {
 #[allow(clippy::fallible_impl_from)]
 impl From<syn::Field> for Field {
     fn from(field: syn::Field) -> Self {
+        let span = field.span();
         let attrs = field.attrs;
         let mut field = Self {
             name: FieldIdent::Named(NamedField {
@@ -275,6 +303,7 @@ impl From<syn::Field> for Field {
                 rename: None,
             }),
             default: None,
+            default_with: None,
             ty: field.ty,
             vararg: Default::default(),
             retain: None,
@@ -282,16 +311,18 @@ impl From<syn::Field> for Field {
             required: None,
             min_value: None,
             max_value: None,
+            validate: None,
         };
 
         if field.ty.generic_type_of("Option").is_some() {
             field.default = Some(syn::parse_str("::std::default::Default::default").unwrap());
         }
-        for attr in attrs {
+        for attr in &attrs {
             if !attr.path.is_ident("command") { continue; }
 
-            field.handle_attribute(&attr);
+            field.handle_attribute(attr);
         }
+        field.doc_comment_desc(&attrs, span);
 
         field
     }
@@ -300,12 +331,14 @@ impl From<syn::Field> for Field {
 #[allow(clippy::fallible_impl_from)]
 impl From<(usize, syn::Field)> for Field {
     fn from((i, field): (usize, syn::Field)) -> Self {
+        let span = field.span();
         let attrs = field.attrs;
         let mut field = Self {
             name: FieldIdent::Unnamed(UnnamedField {
                 index: Index::from(i)
             }),
             default: None,
+            default_with: None,
             ty: field.ty,
             vararg: Default::default(),
             retain: None,
@@ -313,16 +346,18 @@ impl From<(usize, syn::Field)> for Field {
             required: None,
             min_value: None,
             max_value: None,
+            validate: None,
         };
 
         if field.ty.generic_type_of("Option").is_some() {
             field.default = Some(syn::parse_str("::std::default::Default::default").unwrap());
         }
-        for attr in attrs {
+        for attr in &attrs {
             if !attr.path.is_ident("command") { continue; }
 
-            field.handle_attribute(&attr);
+            field.handle_attribute(attr);
         }
+        field.doc_comment_desc(&attrs, span);
 
         field
     }
@@ -334,10 +369,14 @@ pub struct Struct {
     /// settable with `#[command(type = MyCommand)]` on a struct
     pub command_type: Option<Type>,
     generics: Vec<TypeParam>,
+    /// settable with `#[command(positional)]` on a struct
+    pub positional: bool,
+    /// `nsfw`, `integration_types`, and `contexts`
+    pub command_attrs: CommandAttrs,
 }
 
 impl Struct {
-    const UNIT: Self = Self { fields: Vec::new(), command_type: None, generics: Vec::new() };
+    const UNIT: Self = Self { fields: Vec::new(), command_type: None, generics: Vec::new(), positional: false, command_attrs: CommandAttrs::NONE };
 
     pub fn from_fields(fields: Fields, attributes: &[Attribute], generics: Vec<TypeParam>) -> Self {
         let mut strukt = match fields {
@@ -370,8 +409,46 @@ impl Struct {
         let builder_struct = self.builder_struct(return_type, return_ctor);
         let varargs_match = self.varargs_match(command_ty);
         let fields_array = self.fields_array();
+        let names_array = self.names_array();
         let fields_match = self.match_branches(command_ty);
         let varargs_array = self.varargs_array();
+        let validate = self.validate();
+        let empty_varargs = self.empty_varargs(command_ty);
+        let defaults_with = self.defaults_with();
+
+        // with `#[command(positional)]`, expect each option to be the field after the last one that
+        // was found, and only search the later fields if it isn't (since optional fields can be
+        // skipped)
+        let (positional_start, find_field) = if self.positional {
+            (
+                quote! { let mut start = 0; },
+                quote! {{
+                    let idx = if fields.get(start).is_some_and(|f| f == option.name()) {
+                        ::std::option::Option::Some(start)
+                    } else {
+                        fields[start..].iter()
+                            .position(|f| f == option.name())
+                            .map(|pos| start + pos)
+                    };
+                    if let ::std::option::Option::Some(idx) = idx {
+                        start = idx + 1;
+                    } else if let ::std::option::Option::Some(declared) = fields[..start].iter()
+                        .position(|f| f == option.name())
+                    {
+                        // any field after the last one found could have come next
+                        return ::std::result::Result::Err(CommandParseError::BadOrder(
+                            option.name().to_string(), declared, start..#num_fields
+                        ));
+                    }
+                    idx
+                }},
+            )
+        } else {
+            (
+                TokenStream2::new(),
+                quote! { fields.iter().position(|f| f == option.name()) },
+            )
+        };
 
         let build_struct = if self.fields.is_empty() {
             // if there are no fields (ie, is Unit struct), don't have to parse any options
@@ -380,44 +457,40 @@ impl Struct {
             quote! {
                 const VARARGS: [fn(&str, usize) -> bool; #num_fields] = #varargs_array;
                 let fields: [::std::borrow::Cow<'static, str>; #num_fields] = #fields_array;
-
-                let mut options = options;
-                let mut options = options.drain(0..).peekable();
-                let mut idx = 0;
-
-                while let ::std::option::Option::Some(option) = options.next() {
-                    // this first ^ option is always a single option or the first of a vararg
-                    let matches_vararg = VARARGS[idx];
-                    if matches_vararg(option.name(), 1) {
-                        // start of a vararg
-                        let mut varargs = vec![option];
-                        let mut vararg_idx = 1;
-                        loop {
-                            let next_is_vararg = matches!(
-                                options.peek(),
-                                ::std::option::Option::Some(next) if {
-                                    vararg_idx += 1;
-                                    matches_vararg(&next.name(), vararg_idx)
-                                }
-                            );
-                            if next_is_vararg {
-                                varargs.push(options.next().unwrap());
-                            } else {
-                                break
-                            }
-                        }
-                        #[allow(clippy::used_underscore_binding)]
-                        #varargs_match
+                // Discord doesn't guarantee that options arrive in the order they were declared, so
+                // match each option to its field by name. Varargs are collected with their position
+                // in the vararg and sorted once all options have been seen.
+                let mut all_varargs: [::std::vec::Vec<(usize, ::discorsd::model::interaction::InteractionDataOption)>; #num_fields]
+                    = ::std::array::from_fn(|_| ::std::vec::Vec::new());
+                #positional_start
+
+                for option in options {
+                    if let ::std::option::Option::Some(idx) = #find_field {
+                        #fields_match
+                    } else if let ::std::option::Option::Some((idx, n)) = (0..#num_fields)
+                        // commands can have at most 25 options
+                        .find_map(|idx| (1..=25).find(|&n| VARARGS[idx](option.name(), n)).map(|n| (idx, n)))
+                    {
+                        all_varargs[idx].push((n, option));
                     } else {
-                        loop {
-                            // maybe?
-                            // #[allow(clippy::used_underscore_binding)]
-                            #fields_match
-                            idx += 1;
-                        }
+                        return ::std::result::Result::Err(CommandParseError::UnknownOption(UnknownOption {
+                            name: option.name().to_string(),
+                            options: &#names_array,
+                        }));
                     }
-                    idx += 1;
                 }
+
+                for (idx, mut varargs) in all_varargs.into_iter().enumerate() {
+                    if varargs.is_empty() { continue }
+                    varargs.sort_by_key(|(n, _)| *n);
+                    let varargs: ::std::vec::Vec<_> = varargs.into_iter().map(|(_, option)| option).collect();
+                    #[allow(clippy::used_underscore_binding)]
+                    #varargs_match
+                }
+
+                #validate
+                #empty_varargs
+                #defaults_with
             }
         };
         quote! {
@@ -433,11 +506,65 @@ impl Struct {
         }
     }
 
+    /// run the `validate` functions on any fields the user provided
+    fn validate(&self) -> TokenStream2 {
+        let validations = self.fields.iter().enumerate().filter_map(|(i, f)| {
+            let path = f.validate.as_ref()?;
+            let builder_ident = f.name.builder_ident();
+            Some(quote_spanned! { path.span() =>
+                if let ::std::option::Option::Some(value) = &builder.#builder_ident {
+                    if let ::std::result::Result::Err(message) = #path(value) {
+                        return ::std::result::Result::Err(CommandParseError::InvalidOption(fields[#i].to_string(), message));
+                    }
+                }
+            })
+        });
+        quote! { #(#validations)* }
+    }
+
+    /// parse varargs that had no options as if they were sent with no options, so that optional
+    /// varargs are empty collections (and arrays still error)
+    fn empty_varargs(&self, command_ty: &TokenStream2) -> TokenStream2 {
+        let empty = self.fields.iter()
+            .filter(|f| f.vararg.is_some() && f.default.is_none() && f.default_with.is_none())
+            .map(|f| {
+                let ty = &f.ty;
+                let builder_ident = f.name.builder_ident();
+                quote_spanned! { ty.span() =>
+                    if builder.#builder_ident.is_none() {
+                        builder.#builder_ident = ::std::option::Option::Some(
+                            <#ty as ::discorsd::commands::CommandData<#command_ty>>::from_options(command, ::std::vec::Vec::new())?
+                        );
+                    }
+                }
+            });
+        quote! { #(#empty)* }
+    }
+
+    /// fill in any missing fields that get their default from the command
+    fn defaults_with(&self) -> TokenStream2 {
+        let defaults = self.fields.iter().filter_map(|f| {
+            let path = f.default_with.as_ref()?;
+            let builder_ident = f.name.builder_ident();
+            Some(quote_spanned! { path.span() =>
+                if builder.#builder_ident.is_none() {
+                    builder.#builder_ident = ::std::option::Option::Some(#path(command));
+                }
+            })
+        });
+        quote! { #(#defaults)* }
+    }
+
     fn fields_array(&self) -> TokenStream2 {
         let fields = self.fields.iter().map(|f| f.arg_name(&self.generics));
         quote! { [#(#fields.into()),*] }
     }
 
+    fn names_array(&self) -> TokenStream2 {
+        let names = self.fields.iter().map(Field::static_name);
+        quote! { [#(#names),*] }
+    }
+
     fn match_branches(&self, command_ty: &TokenStream2) -> TokenStream2 {
         let branches = self.fields.iter().enumerate().map(|(i, f)| {
             let builder_ident = f.name.builder_ident();
@@ -448,20 +575,15 @@ impl Struct {
                 }
             } else {
                 quote_spanned! { ty.span() =>
-                    #i => if option.name() == fields[#i] {
-                        builder.#builder_ident = ::std::option::Option::Some(
-                            <#ty as ::discorsd::commands::CommandData<#command_ty>>::from_options(option)?
-                        );
-                        break
-                    }
+                    #i => builder.#builder_ident = ::std::option::Option::Some(
+                        <#ty as ::discorsd::commands::CommandData<#command_ty>>::from_options(command, option)?
+                    )
                 }
             }
         });
-        let num_fields = self.fields.len();
         quote! {
             match idx {
                 #(#branches,)*
-                #num_fields => return ::std::result::Result::Err(CommandParseError::BadOrder(option.name().to_string(), idx, 0..#num_fields)),
                 _ => {}
             }
         }
@@ -544,7 +666,7 @@ impl Struct {
             if f.vararg.is_some() {
                 quote_spanned! { ty.span() =>
                     #i => {
-                        let varargs = <#ty as ::discorsd::commands::CommandData<#command_ty>>::from_options(varargs)?;
+                        let varargs = <#ty as ::discorsd::commands::CommandData<#command_ty>>::from_options(command, varargs)?;
                         builder.#builder_ident = ::std::option::Option::Some(varargs);
                     }
                 }
@@ -632,6 +754,21 @@ pub fn description_len_check(desc: &Option<LitStr>, generics: &[TypeParam]) -> O
 }
 
 impl Field {
+    /// Use the doc comment as the description if `desc` wasn't set, and warn if neither was set,
+    /// since the name will then be used as the description
+    fn doc_comment_desc(&mut self, attrs: &[Attribute], span: Span) {
+        if self.desc.is_none() {
+            self.desc = doc_comment(attrs);
+        }
+        // varargs' descriptions are their names
+        if self.desc.is_none() && self.vararg.is_none() {
+            emit_warning!(
+                span, "command option has no description, so its name will be used";
+                help = "add a doc comment or `#[command(desc = \"...\")]`"
+            );
+        }
+    }
+
     /// data options for not varargs
     fn single_option(
         &self,
@@ -651,7 +788,7 @@ impl Field {
             quote! {
                 if i < #less_than { option.extra_data.required = true; }
             }
-        } else if self.default.is_none() {
+        } else if self.default.is_none() && self.default_with.is_none() {
             quote! {
                 option.extra_data.required = true;
             }
@@ -739,6 +876,6 @@ impl Field {
 impl FromIterator<Field> for Struct {
     fn from_iter<I: IntoIterator<Item=Field>>(iter: I) -> Self {
         let fields: Vec<Field> = iter.into_iter().collect();
-        Self { fields, command_type: None, generics: Vec::new() }
+        Self { fields, command_type: None, generics: Vec::new(), positional: false, command_attrs: CommandAttrs::NONE }
     }
 }
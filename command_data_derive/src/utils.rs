@@ -3,7 +3,7 @@ use std::ops::Not;
 
 use proc_macro2::{Ident, TokenStream as TokenStream2};
 use quote::{quote, quote_spanned};
-use syn::{GenericArgument, PathArguments, spanned::Spanned, Type, TypeParam};
+use syn::{Attribute, GenericArgument, Lit, LitStr, Meta, MetaNameValue, NestedMeta, PathArguments, spanned::Spanned, Type, TypeParam};
 
 /// Generics including type bounds
 pub fn declaration_generics<I>(generics: &[TypeParam], additional_bounds: I) -> TokenStream2
@@ -43,6 +43,26 @@ pub fn replace_generics(format_string: &mut String, generics: &[TypeParam]) -> O
         .then(|| quote! { format!(#format_string #(, #generics_used::ARG_NAME)*) })
 }
 
+/// The doc comment in `attrs`, with each line trimmed and joined by spaces, or `None` if there is
+/// no (non-empty) doc comment
+pub fn doc_comment(attrs: &[Attribute]) -> Option<LitStr> {
+    let mut span = None;
+    let doc = attrs.iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue { lit: Lit::Str(lit), .. })) => {
+                span.get_or_insert_with(|| lit.span());
+                Some(lit.value())
+            }
+            _ => None,
+        })
+        .flat_map(|doc| doc.lines().map(str::trim).map(ToString::to_string).collect::<Vec<_>>())
+        .filter(|line| !line.is_empty())
+        .join(" ");
+    span.filter(|_| !doc.is_empty())
+        .map(|span| LitStr::new(&doc, span))
+}
+
 /// returns (impl statement, command type)
 pub fn command_data_impl(command_type: Option<&Type>, generics: &[TypeParam]) -> (TokenStream2, TokenStream2) {
     let ty = match command_type {
@@ -70,6 +90,62 @@ pub fn command_data_impl(command_type: Option<&Type>, generics: &[TypeParam]) ->
     (impl_statement, ty)
 }
 
+/// Attributes that apply to the whole command, rather than to its options. Only used on a
+/// command's top level data.
+#[derive(Debug)]
+pub struct CommandAttrs {
+    /// settable with `#[command(nsfw)]`
+    pub nsfw: bool,
+    /// settable with `#[command(integration_types(...))]`
+    pub integration_types: Option<Vec<Ident>>,
+    /// settable with `#[command(contexts(...))]`
+    pub contexts: Option<Vec<Ident>>,
+}
+
+impl CommandAttrs {
+    pub const NONE: Self = Self { nsfw: false, integration_types: None, contexts: None };
+
+    pub const INTEGRATION_TYPES: &'static [&'static str] = &["GuildInstall", "UserInstall"];
+    pub const CONTEXTS: &'static [&'static str] = &["Guild", "BotDm", "PrivateChannel"];
+
+    /// the `CommandData` consts for the attributes that were set, so that the trait's defaults are
+    /// used for the rest
+    pub fn consts(&self) -> TokenStream2 {
+        let nsfw = self.nsfw.then(|| quote! { const NSFW: bool = true; });
+        let integration_types = self.integration_types.as_ref().map(|types| quote! {
+            const INTEGRATION_TYPES: ::std::option::Option<&'static [::discorsd::model::command::ApplicationIntegrationType]>
+                = ::std::option::Option::Some(&[#(::discorsd::model::command::ApplicationIntegrationType::#types),*]);
+        });
+        let contexts = self.contexts.as_ref().map(|contexts| quote! {
+            const CONTEXTS: ::std::option::Option<&'static [::discorsd::model::command::InteractionContextType]>
+                = ::std::option::Option::Some(&[#(::discorsd::model::command::InteractionContextType::#contexts),*]);
+        });
+        quote! { #nsfw #integration_types #contexts }
+    }
+}
+
+/// The idents in an attribute list like `contexts(Guild, BotDm)`, each of which must be one of
+/// `allowed`.
+pub fn list_idents<'a, I>(nested: I, allowed: &[&str]) -> syn::Result<Vec<Ident>>
+    where I: IntoIterator<Item=&'a NestedMeta>
+{
+    nested.into_iter()
+        .map(|nested| {
+            let ident = match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path.get_ident(),
+                _ => None,
+            };
+            match ident {
+                Some(ident) if allowed.contains(&ident.to_string().as_str()) => Ok(ident.clone()),
+                _ => Err(syn::Error::new(
+                    nested.span(),
+                    format!("expected one of {}", allowed.iter().map(|a| format!("`{a}`")).join(", ")),
+                )),
+            }
+        })
+        .collect()
+}
+
 pub trait TypeExt {
     /// get the first generic type of `self` as long as self matches the predicate (ex
     /// `Type<A, B, C>` ->  `if pred(self) { A } else { None }`
@@ -98,7 +174,7 @@ pub trait TypeExt {
 impl TypeExt for Type {
     fn generic_type_by<F: FnOnce(&Ident) -> bool>(&self, pred: F) -> Option<&Type> {
         if let Self::Path(path) = self {
-            let seg = path.path.segments.first()?;
+            let seg = path.path.segments.last()?;
             if !pred(&seg.ident) { return None; }
             if let PathArguments::AngleBracketed(args) = &seg.arguments {
                 if let Some(GenericArgument::Type(ty)) = args.args.first() {
@@ -0,0 +1,147 @@
+//! Benchmarks for turning the options Discord sends with a slash command into its `CommandData`.
+//!
+//! Run with `cargo bench -p derive_test`.
+
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::hint::black_box;
+use std::sync::Arc;
+
+use command_data_derive::CommandData;
+use criterion::{Criterion, criterion_group, criterion_main};
+use discorsd::BotState;
+use discorsd::commands::{AppCommandData, CommandData, InteractionUse, SlashCommand, Unused, Used};
+use discorsd::errors::BotError;
+use discorsd::model::commands::OptionsLadder;
+use discorsd::model::ids::{ChannelId, RoleId, UserId};
+use discorsd::model::interaction::{ApplicationCommandData, InteractionOption};
+
+struct BenchBot;
+
+impl discorsd::Bot for BenchBot {
+    type Error = Infallible;
+
+    fn token(&self) -> String {
+        String::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Perms;
+
+#[discorsd::async_trait]
+impl SlashCommand for Perms {
+    type Bot = BenchBot;
+    type Data = Data;
+    type Use = Used;
+    const NAME: &'static str = "permissions";
+
+    fn description(&self) -> Cow<'static, str> {
+        "Get or edit permissions for a user or a role".into()
+    }
+
+    async fn run(
+        &self,
+        _: Arc<BotState<BenchBot>>,
+        interaction: InteractionUse<AppCommandData, Unused>,
+        _: Self::Data,
+    ) -> Result<InteractionUse<AppCommandData, Used>, BotError<Infallible>> {
+        Ok(interaction.into())
+    }
+}
+
+#[derive(CommandData, Debug)]
+enum Data {
+    #[command(desc = "Get or edit permissions for a user")]
+    User(GetEditUser),
+    #[command(desc = "Get or edit permissions for a role")]
+    Role(GetEditRole),
+}
+
+#[derive(CommandData, Debug)]
+enum GetEditUser {
+    #[command(desc = "Get permissions for a user")]
+    Get {
+        #[command(desc = "The user to get")]
+        user: UserId,
+        #[command(desc = "The channel permissions to get. If omitted, the guild permissions will be returned")]
+        channel: Option<ChannelId>,
+    },
+    #[command(desc = "Edit permissions for a user")]
+    Edit {
+        #[command(desc = "The user to edit")]
+        user: UserId,
+        #[command(desc = "The channel permissions to edit. If omitted, the guild permissions will be edited")]
+        channel: Option<ChannelId>,
+    },
+}
+
+#[derive(CommandData, Debug)]
+enum GetEditRole {
+    #[command(desc = "Get permissions for a role")]
+    Get {
+        #[command(desc = "The role to get")]
+        role: RoleId,
+        #[command(desc = "The channel permissions to get. If omitted, the guild permissions will be returned")]
+        channel: Option<ChannelId>,
+    },
+    #[command(desc = "Edit permissions for a role")]
+    Edit {
+        #[command(desc = "The role to edit")]
+        role: RoleId,
+        #[command(desc = "The channel permissions to edit. If omitted, the guild permissions will be edited")]
+        channel: Option<ChannelId>,
+    },
+}
+
+/// `/permissions user get user:<user> channel:<channel>`
+const USER_GET: &str = r#"{
+    "type": 1,
+    "id": "771825006014889984",
+    "name": "permissions",
+    "options": [{
+        "type": 2,
+        "name": "user",
+        "options": [{
+            "type": 1,
+            "name": "get",
+            "options": [
+                { "type": 6, "name": "user", "value": "243418816510558208" },
+                { "type": 7, "name": "channel", "value": "645027906669510667" }
+            ]
+        }]
+    }]
+}"#;
+
+fn options(data: ApplicationCommandData) -> InteractionOption {
+    match data {
+        ApplicationCommandData::SlashCommand { options, .. } => options,
+        _ => unreachable!("USER_GET is a slash command"),
+    }
+}
+
+fn parse(options: InteractionOption) -> Data {
+    let options = <Data as CommandData<Perms>>::Options::from_data_option(options).unwrap();
+    <Data as CommandData<Perms>>::from_options(&Perms, options).unwrap()
+}
+
+fn parse_options(c: &mut Criterion) {
+    let data: ApplicationCommandData = serde_json::from_str(USER_GET).unwrap();
+    let interaction_option = options(data);
+
+    c.bench_function("deserialize nested command", |b| b.iter(|| {
+        serde_json::from_str::<ApplicationCommandData>(black_box(USER_GET)).unwrap()
+    }));
+    c.bench_function("from_options nested command", |b| b.iter_batched(
+        || interaction_option.clone(),
+        |options| parse(black_box(options)),
+        criterion::BatchSize::SmallInput,
+    ));
+    c.bench_function("deserialize and parse nested command", |b| b.iter(|| {
+        let data = serde_json::from_str(black_box(USER_GET)).unwrap();
+        parse(options(data))
+    }));
+}
+
+criterion_group!(benches, parse_options);
+criterion_main!(benches);
@@ -7,6 +7,14 @@ mod tests {
 
     struct TestBot;
 
+    impl discorsd::Bot for TestBot {
+        type Error = std::convert::Infallible;
+
+        fn token(&self) -> String {
+            String::new()
+        }
+    }
+
     macro_rules! make_slash_command {
         ($data:ty) => {
             #[derive(Debug, Clone)]
@@ -25,9 +33,9 @@ mod tests {
                 async fn run(
                     &self,
                     _: std::sync::Arc<discorsd::BotState<TestBot>>,
-                    interaction: discorsd::commands::InteractionUse<discorsd::commands::SlashCommandData, discorsd::commands::Unused>,
+                    interaction: discorsd::commands::InteractionUse<discorsd::commands::AppCommandData, discorsd::commands::Unused>,
                     data: Self::Data
-                ) -> Result<discorsd::commands::InteractionUse<discorsd::commands::SlashCommandData, discorsd::commands::Used>, discorsd::errors::BotError> {
+                ) -> Result<discorsd::commands::InteractionUse<discorsd::commands::AppCommandData, discorsd::commands::Used>, discorsd::errors::BotError<std::convert::Infallible>> {
                     // Ok to let the test know we succeeded
                     println!("data = {:?}", data);
                     Ok(interaction.into())
@@ -81,6 +89,304 @@ mod tests {
         }
     }
 
+    #[test]
+    fn out_of_order_options() {
+        use discorsd::commands::CommandData;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        #[derive(CommandData, Debug, PartialEq)]
+        struct Data {
+            age: i64,
+            name: String,
+            nick: Option<String>,
+            #[command(vararg = "num", va_count = 3, va_req = 1)]
+            nums: Vec<i64>,
+        }
+        make_slash_command!(Data);
+
+        let options: Vec<InteractionDataOption> = serde_json::from_str(r#"[
+            {"type": 4, "name": "num2", "value": 2},
+            {"type": 3, "name": "name", "value": "Andrew"},
+            {"type": 4, "name": "num1", "value": 1},
+            {"type": 4, "name": "age", "value": 21}
+        ]"#).unwrap();
+        let data = <Data as CommandData<Perms>>::from_options(&Perms, options).unwrap();
+        assert_eq!(
+            data,
+            Data { age: 21, name: "Andrew".into(), nick: None, nums: vec![1, 2] },
+        );
+    }
+
+    #[test]
+    fn unknown_option() {
+        use discorsd::commands::CommandData;
+        use discorsd::errors::CommandParseError;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        #[derive(CommandData, Debug, PartialEq)]
+        struct Data {
+            age: i64,
+            #[command(rename = "nickname")]
+            nick: Option<String>,
+        }
+        make_slash_command!(Data);
+
+        let options: Vec<InteractionDataOption> = serde_json::from_str(r#"[
+            {"type": 4, "name": "age", "value": 21},
+            {"type": 3, "name": "height", "value": "tall"}
+        ]"#).unwrap();
+        match <Data as CommandData<Perms>>::from_options(&Perms, options) {
+            Err(CommandParseError::UnknownOption(unknown)) => {
+                assert_eq!(unknown.name, "height");
+                assert_eq!(unknown.options, ["age", "nickname"]);
+            }
+            other => panic!("expected UnknownOption, got {other:?}"),
+        }
+    }
+
+    #[derive(CommandData, Debug, PartialEq)]
+    #[command(positional)]
+    struct PositionalData {
+        age: i64,
+        name: String,
+        nick: Option<String>,
+        #[command(vararg = "num", va_count = 3, va_req = 1)]
+        nums: Vec<i64>,
+    }
+
+    #[test]
+    fn positional_options() {
+        use discorsd::commands::CommandData;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        make_slash_command!(PositionalData);
+
+        let options: Vec<InteractionDataOption> = serde_json::from_str(r#"[
+            {"type": 4, "name": "age", "value": 21},
+            {"type": 3, "name": "name", "value": "Andrew"},
+            {"type": 4, "name": "num1", "value": 1},
+            {"type": 4, "name": "num2", "value": 2}
+        ]"#).unwrap();
+        let data = <PositionalData as CommandData<Perms>>::from_options(&Perms, options).unwrap();
+        assert_eq!(
+            data,
+            PositionalData { age: 21, name: "Andrew".into(), nick: None, nums: vec![1, 2] },
+        );
+    }
+
+    #[test]
+    fn positional_options_reordered() {
+        use discorsd::commands::CommandData;
+        use discorsd::errors::CommandParseError;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        make_slash_command!(PositionalData);
+
+        let options: Vec<InteractionDataOption> = serde_json::from_str(r#"[
+            {"type": 3, "name": "name", "value": "Andrew"},
+            {"type": 4, "name": "age", "value": 21},
+            {"type": 4, "name": "num1", "value": 1}
+        ]"#).unwrap();
+        let result = <PositionalData as CommandData<Perms>>::from_options(&Perms, options);
+        assert!(
+            matches!(result, Err(CommandParseError::BadOrder(ref name, 0, ref expected)) if name == "age" && *expected == (2..4)),
+            "{result:?}",
+        );
+    }
+
+    #[test]
+    fn optional_varargs() {
+        use discorsd::commands::CommandData;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        #[derive(CommandData, Debug, PartialEq)]
+        struct Data {
+            #[command(vararg = "num", va_count = 3, va_req = 0)]
+            nums: Vec<i64>,
+        }
+        #[derive(CommandData, Debug, PartialEq)]
+        struct SetData {
+            #[command(vararg = "num", va_count = 3, va_req = 0)]
+            nums: std::collections::HashSet<i64>,
+        }
+        make_slash_command!(Data);
+
+        let parse = |json: &str| {
+            let options: Vec<InteractionDataOption> = serde_json::from_str(json).unwrap();
+            let data = <Data as CommandData<Perms>>::from_options(&Perms, options.clone()).unwrap();
+            let set = <SetData as CommandData<Perms>>::from_options(&Perms, options).unwrap();
+            assert_eq!(data.nums.iter().copied().collect::<std::collections::HashSet<_>>(), set.nums);
+            data.nums
+        };
+
+        assert_eq!(parse("[]"), Vec::<i64>::new());
+        assert_eq!(parse(r#"[{"type": 4, "name": "num1", "value": 1}]"#), [1]);
+        assert_eq!(
+            parse(r#"[
+                {"type": 4, "name": "num1", "value": 1},
+                {"type": 4, "name": "num2", "value": 2},
+                {"type": 4, "name": "num3", "value": 3}
+            ]"#),
+            [1, 2, 3],
+        );
+
+        // every vararg option is optional
+        let command = discorsd::commands::SlashCommandRaw::command(&Perms);
+        let json = serde_json::to_value(&command).unwrap();
+        let required = json["options"].as_array().unwrap().iter()
+            .map(|option| option["required"].as_bool())
+            .collect::<Vec<_>>();
+        assert_eq!(required, [Some(false); 3]);
+    }
+
+    #[test]
+    fn validate() {
+        use discorsd::commands::CommandData;
+        use discorsd::errors::CommandParseError;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn even(n: &i64) -> Result<(), String> {
+            if n % 2 == 0 {
+                Ok(())
+            } else {
+                Err(format!("{n} is not even"))
+            }
+        }
+
+        #[derive(CommandData, Debug, PartialEq)]
+        struct Data {
+            #[command(validate = "even")]
+            num: i64,
+        }
+        make_slash_command!(Data);
+
+        let options = |json: &str| -> Vec<InteractionDataOption> { serde_json::from_str(json).unwrap() };
+
+        let even = options(r#"[{"type": 4, "name": "num", "value": 4}]"#);
+        assert_eq!(<Data as CommandData<Perms>>::from_options(&Perms, even).unwrap(), Data { num: 4 });
+        let odd = options(r#"[{"type": 4, "name": "num", "value": 3}]"#);
+        match <Data as CommandData<Perms>>::from_options(&Perms, odd) {
+            Err(CommandParseError::InvalidOption(option, message)) => {
+                assert_eq!(option, "num");
+                assert_eq!(message, "3 is not even");
+            }
+            other => panic!("expected InvalidOption, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn default_with_command_state() {
+        use discorsd::commands::CommandData;
+        use discorsd::model::interaction::InteractionDataOption;
+
+        #[derive(Debug, Clone)]
+        struct Roll {
+            default_sides: i64,
+        }
+        #[discorsd::async_trait]
+        impl discorsd::commands::SlashCommand for Roll {
+            type Bot = TestBot;
+            type Data = RollData;
+            type Use = discorsd::commands::Used;
+            const NAME: &'static str = "roll";
+
+            fn description(&self) -> std::borrow::Cow<'static, str> {
+                "Roll a die".into()
+            }
+
+            async fn run(
+                &self,
+                _: std::sync::Arc<discorsd::BotState<TestBot>>,
+                interaction: discorsd::commands::InteractionUse<discorsd::commands::AppCommandData, discorsd::commands::Unused>,
+                _: Self::Data
+            ) -> Result<discorsd::commands::InteractionUse<discorsd::commands::AppCommandData, discorsd::commands::Used>, discorsd::errors::BotError<std::convert::Infallible>> {
+                Ok(interaction.into())
+            }
+        }
+
+        #[derive(CommandData, Debug, PartialEq)]
+        #[command(command = "Roll")]
+        struct RollData {
+            #[command(default_with = "default_sides")]
+            sides: i64,
+        }
+        fn default_sides(command: &Roll) -> i64 {
+            command.default_sides
+        }
+
+        let command = Roll { default_sides: 20 };
+        let data = RollData::from_options(&command, Vec::new()).unwrap();
+        assert_eq!(data, RollData { sides: 20 });
+
+        let options: Vec<InteractionDataOption> = serde_json::from_str(
+            r#"[{"type": 4, "name": "sides", "value": 6}]"#
+        ).unwrap();
+        let data = RollData::from_options(&command, options).unwrap();
+        assert_eq!(data, RollData { sides: 6 });
+
+        let args = RollData::make_args(&command);
+        let args = serde_json::to_value(&args).unwrap();
+        assert_eq!(args[0]["required"], false);
+    }
+
+    #[test]
+    fn menu_options() {
+        use discorsd::commands::MenuData;
+
+        #[derive(MenuCommand, Debug, Copy, Clone, PartialEq)]
+        enum Color {
+            #[menu(label = "Bright Red", description = "the color of fire", emoji = "🔥")]
+            Red,
+            #[menu(desc = "the color of the sky", default)]
+            Blue,
+            Green,
+        }
+
+        let options = serde_json::to_value(Color::options()).unwrap();
+        assert_eq!(options, serde_json::json!([
+            {
+                "label": "Bright Red",
+                "value": "Red",
+                "description": "the color of fire",
+                "emoji": { "name": "🔥" },
+            },
+            {
+                "label": "Blue",
+                "value": "Blue",
+                "description": "the color of the sky",
+                "default": true,
+            },
+            {
+                "label": "Green",
+                "value": "Green",
+            },
+        ]));
+        assert!(Color::Blue.into_option().default);
+        assert_eq!(Color::Red.to_string(), "Bright Red");
+        assert_eq!("Green".parse::<Color>(), Ok(Color::Green));
+    }
+
+    #[test]
+    fn multi_select_menu() {
+        use discorsd::commands::MenuData;
+
+        #[derive(MenuCommand, Debug, Copy, Clone, PartialEq)]
+        #[menu(min_values = 1, max_values = 3)]
+        enum Topping {
+            Cheese,
+            Pepperoni,
+            Mushroom,
+            Olive,
+        }
+
+        assert_eq!(Topping::MIN_VALUES, Some(1));
+        assert_eq!(Topping::MAX_VALUES, Some(3));
+        let values = vec!["Mushroom".to_string(), "Cheese".to_string()];
+        assert_eq!(Topping::from_values(values).unwrap(), [Topping::Mushroom, Topping::Cheese]);
+        assert_eq!(Topping::from_values(vec!["Pineapple".to_string()]).unwrap_err(), "Pineapple".into());
+    }
+
     // fn expanded() {
     //     struct MyStruct {
     //         age: u64,
@@ -91,6 +397,116 @@ mod tests {
     //     }
     // }
 
+    #[test]
+    fn doc_comment_descriptions() {
+        #[derive(CommandData, Debug)]
+        enum Data {
+            /// Look up a user
+            User {
+                /// The user to look up
+                user: UserId,
+                /// Where to look them up.
+                ///   If omitted, looks them up in this channel
+                channel: Option<ChannelId>,
+                /// Ignored, since `desc` is set
+                #[command(desc = "Whether to reply privately")]
+                ephemeral: Option<bool>,
+                name: Option<String>,
+            },
+            Guild,
+        }
+        make_slash_command!(Data);
+
+        assert_same_json_value(r#"{
+            "name": "permissions",
+            "description": "Get or edit permissions for a user or a role",
+            "type": 1,
+            "options": [
+                {
+                    "name": "user",
+                    "description": "Look up a user",
+                    "type": 1,
+                    "options": [
+                        {
+                            "name": "user",
+                            "description": "The user to look up",
+                            "type": 6,
+                            "required": true
+                        },
+                        {
+                            "name": "channel",
+                            "description": "Where to look them up. If omitted, looks them up in this channel",
+                            "type": 7,
+                            "required": false
+                        },
+                        {
+                            "name": "ephemeral",
+                            "description": "Whether to reply privately",
+                            "type": 5,
+                            "required": false
+                        },
+                        {
+                            "name": "name",
+                            "description": "name",
+                            "type": 3,
+                            "required": false
+                        }
+                    ]
+                },
+                {
+                    "name": "guild",
+                    "description": "guild",
+                    "type": 1,
+                    "options": []
+                }
+            ]
+        }"#, Perms);
+    }
+
+    #[test]
+    fn nsfw_command() {
+        use discorsd::commands::SlashCommandRaw;
+
+        #[derive(CommandData, Debug)]
+        #[command(nsfw)]
+        struct Data {
+            #[command(desc = "The user to check")]
+            user: UserId,
+        }
+        make_slash_command!(Data);
+
+        let json = serde_json::to_value(Perms.command()).unwrap();
+        assert_eq!(json["nsfw"], serde_json::Value::Bool(true));
+
+        // not age-restricted by default, and then `nsfw` is left out
+        #[derive(CommandData, Debug)]
+        struct SafeData {
+            user: UserId,
+        }
+        let mut command = Perms.command().into_owned();
+        command.set_nsfw(<SafeData as discorsd::commands::CommandData<Perms>>::NSFW);
+        assert!(!command.is_nsfw());
+        assert!(serde_json::to_value(command).unwrap().get("nsfw").is_none());
+    }
+
+    #[test]
+    fn user_installable_command() {
+        use discorsd::commands::SlashCommandRaw;
+
+        #[derive(CommandData, Debug)]
+        #[command(integration_types(GuildInstall, UserInstall), contexts(Guild, BotDm, PrivateChannel))]
+        struct Data {
+            #[command(desc = "The user to check")]
+            user: UserId,
+        }
+        make_slash_command!(Data);
+
+        let json = serde_json::to_value(Perms.command()).unwrap();
+        assert_eq!(json["integration_types"], serde_json::json!([0, 1]));
+        assert_eq!(json["contexts"], serde_json::json!([0, 1, 2]));
+        assert!(json.get("nsfw").is_none());
+    }
+
     #[test]
     fn test_derive_enum_one_level() {
         #[derive(CommandData)]
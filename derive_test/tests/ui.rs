@@ -0,0 +1,7 @@
+/// `proc-macro-error` only emits warnings on nightly, so there's nothing to check on stable
+#[rustversion::attr(not(nightly), ignore)]
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,13 @@
+#![allow(dead_code)]
+
+use command_data_derive::CommandData;
+
+#[derive(CommandData)]
+struct Data {
+    age: u64,
+}
+
+fn main() {
+    // fail to compile so that trybuild checks the warning above
+    let _: () = 0;
+}
@@ -11,34 +11,46 @@
 //! This allows you to pass a reference to any of the above state types, or a reference to a
 //! `DiscordClient` or `&DiscordClient`.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
 use std::error::Error;
 use std::fmt::{self, Debug};
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::str::FromStr;
 use std::sync::{Arc, OnceLock};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use chrono::Local;
-use log::{error, LevelFilter};
-use tokio::sync::RwLock;
+use futures::{stream, Stream};
+use log::{error, info, LevelFilter, warn};
+use serde::Deserialize;
+use tokio::sync::{broadcast, RwLock};
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::cache::Cache;
 use crate::commands::*;
 use crate::errors::BotError;
-use crate::http::{ClientResult, DiscordClient};
-use crate::model::commands::{AppCommandData, InteractionUse};
+use crate::http::{ClientResult, DiscordClient, DiscordClientBuilder};
+use crate::http::channel::MessageLimits;
+use crate::model::Application;
+use crate::model::commands::{AppCommandData, InteractionPayload, InteractionUse, Usability};
 use crate::model::components::{Button, ComponentId, Menu, SelectMenuType, TextInput};
-use crate::model::guild::{Guild, Integration};
+use crate::model::guild::{Guild, GuildMember, Integration};
 use crate::model::ids::*;
 use crate::model::interaction;
 use crate::model::interaction::{ApplicationCommandData, MessageComponentData};
 use crate::model::interaction_response::Modal;
-use crate::model::message::Message;
+use crate::model::message::{Message, MessageType};
 use crate::model::permissions::Role;
+use crate::model::stage_instance::StageInstance;
 use crate::model::user::User;
 use crate::shard;
-use crate::shard::{Shard, WsStream};
-use crate::shard::dispatch::{MessageUpdate, ReactionUpdate};
+use crate::shard::{IdentifyGate, Shard, ShardConnection, ShardError, WsStream};
+use crate::shard::dispatch::{Event, GuildMemberUpdate, GuildScheduledEventUserAdd, GuildScheduledEventUserRemove, MessageUpdate, ReactionUpdate};
+use crate::shard::intents::Intents;
 use crate::shard::model::Identify;
 
 /// Maps `GuildId` to a `RwLock<V>`.
@@ -46,6 +58,9 @@ pub type GuildIdMap<V> = HashMap<GuildId, RwLock<V>>;
 /// Maps `CommandId` to a `SlashCommand`.
 pub type GuildCommands<B> = HashMap<CommandId, Box<dyn SlashCommandRaw<Bot=B>>>;
 
+/// How many events [`BotState::events`](BotState::events) buffers for each subscriber.
+const EVENT_CAPACITY: usize = 256;
+
 /// Stores the state of your Bot.
 pub struct BotState<B: 'static> {
     /// The client, including your bot's token.
@@ -79,13 +94,184 @@ pub struct BotState<B: 'static> {
     pub buttons: std::sync::RwLock<HashMap<ComponentId, Box<dyn ButtonCommand<Bot=B>>>>,
     pub menus: std::sync::RwLock<HashMap<ComponentId, Box<dyn MenuCommandRaw<Bot=B>>>>,
     pub modals: std::sync::RwLock<HashMap<ComponentId, Box<dyn ModalCommandRaw<Bot=B>>>>,
+    /// The [`ButtonCommand`]s handling every button with a [namespaced](ComponentId::namespaced)
+    /// id, by namespace.
+    pub button_namespaces: std::sync::RwLock<HashMap<String, Box<dyn ButtonCommand<Bot=B>>>>,
+    /// The [`MenuCommand`]s handling every menu with a [namespaced](ComponentId::namespaced) id, by
+    /// namespace.
+    pub menu_namespaces: std::sync::RwLock<HashMap<String, Box<dyn MenuCommandRaw<Bot=B>>>>,
+    /// The [`InteractionMiddleware`]s run before each interaction is dispatched, in order, see
+    /// [`add_middleware`](Self::add_middleware).
+    pub(crate) middleware: std::sync::RwLock<Vec<Arc<dyn InteractionMiddleware<Bot=B>>>>,
     // todo need to also have a way to distinguish between separate bot runs, like the first
     //  interaction will always be 0 so you could use the old button or w/e and the new one would
     //  trigger
     pub count: AtomicUsize,
+    /// Sends every [`Event`] to the streams returned by [`events`](Self::events).
+    pub(crate) events: broadcast::Sender<Event>,
+    /// The raw JSON of the latest dispatch of each event type being captured, see
+    /// [`capture_raw`](Self::capture_raw).
+    pub(crate) raw_events: std::sync::Mutex<HashMap<String, Option<String>>>,
+    /// Whether the missing `MESSAGE_CONTENT` intent warning has been logged, see
+    /// [`warn_if_missing_message_content`](Self::warn_if_missing_message_content).
+    pub(crate) message_content_warned: AtomicBool,
+    /// The intents from [`Bot::identify`], see
+    /// [`has_message_content_intent`](Self::has_message_content_intent).
+    pub(crate) intents: OnceLock<Intents>,
+    /// When each recent interaction was received, see
+    /// [`first_interaction_use`](Self::first_interaction_use).
+    pub(crate) seen_interactions: std::sync::Mutex<HashMap<InteractionId, Instant>>,
+    /// The guilds from [`Ready`](crate::shard::dispatch::Ready) that haven't been created yet, see
+    /// [`Bot::cache_ready`].
+    pub(crate) pending_guilds: std::sync::Mutex<PendingGuilds>,
+}
+
+/// Tracks which guilds sent in [`Ready`](crate::shard::dispatch::Ready) are still unavailable, so
+/// that [`Bot::cache_ready`] can be called once they are all cached.
+#[derive(Debug, Default)]
+pub(crate) struct PendingGuilds {
+    /// guilds that haven't been sent in a `GuildCreate` yet
+    waiting: HashSet<GuildId>,
+    /// every guild sent in a `Ready` since the cache was last ready
+    guilds: Vec<GuildId>,
+    /// whether a `Ready` has been received since the cache was last ready
+    loading: bool,
+    /// incremented each time the cache becomes ready, so that an old timeout can't end a newer wait
+    generation: u64,
+}
+
+impl PendingGuilds {
+    fn finish(&mut self) -> Vec<GuildId> {
+        self.waiting.clear();
+        self.loading = false;
+        self.generation += 1;
+        std::mem::take(&mut self.guilds)
+    }
 }
 
 impl<B> BotState<B> {
+    pub(crate) fn new(client: DiscordClient, bot: B) -> Self {
+        Self {
+            client,
+            stream: Default::default(),
+            cache: Default::default(),
+            bot,
+            slash_commands: Default::default(),
+            // command_names: Default::default(),
+            global_slash_commands: Default::default(),
+            global_user_commands: Default::default(),
+            global_message_commands: Default::default(),
+            // global_command_names: Default::default(),
+            // global_user_command_names: Default::default(),
+            // global_message_command_names: Default::default(),
+            reaction_commands: Default::default(),
+            buttons: Default::default(),
+            menus: Default::default(),
+            modals: Default::default(),
+            button_namespaces: Default::default(),
+            menu_namespaces: Default::default(),
+            middleware: Default::default(),
+            count: Default::default(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            raw_events: Default::default(),
+            message_content_warned: Default::default(),
+            intents: Default::default(),
+            seen_interactions: Default::default(),
+            pending_guilds: Default::default(),
+        }
+    }
+
+    /// Subscribe to every [`Event`] this bot receives from here on, as an alternative to
+    /// implementing the corresponding methods of [`Bot`](Bot), which are still called as normal.
+    ///
+    /// Events are yielded after the cache has been updated with them. A subscriber that falls more
+    /// than 256 events behind misses the oldest ones, which is logged as a warning.
+    pub fn events(&self) -> impl Stream<Item = Event> {
+        stream::unfold(self.events.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => return Some((event, rx)),
+                    Err(RecvError::Lagged(missed)) => warn!("Event stream fell behind, skipped {missed} events"),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Start capturing the raw JSON of `event_name` dispatches (for example `"MESSAGE_CREATE"`),
+    /// which can then be retrieved with [`last_raw`](Self::last_raw). Useful for diagnosing
+    /// payloads that don't deserialize as expected.
+    pub fn capture_raw<S: Into<String>>(&self, event_name: S) {
+        self.raw_events.lock().unwrap().entry(event_name.into()).or_default();
+    }
+
+    /// The raw JSON of the most recent `event_name` dispatch, if `event_name` is being captured
+    /// with [`capture_raw`](Self::capture_raw) and has been received since then.
+    pub fn last_raw(&self, event_name: &str) -> Option<String> {
+        self.raw_events.lock().unwrap().get(event_name).cloned().flatten()
+    }
+
+    /// Marks `interaction` as seen, returning `false` if it was already seen in the last `window`.
+    /// Discord can replay events after a resume, so this keeps commands from running twice.
+    pub(crate) fn first_interaction_use(&self, interaction: InteractionId, window: Duration) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen_interactions.lock().unwrap();
+        seen.retain(|_, received| now.duration_since(*received) < window);
+        match seen.entry(interaction) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(vacant) => {
+                vacant.insert(now);
+                true
+            }
+        }
+    }
+
+    /// Starts waiting for `guilds` (from a `Ready`) to be created. Returns the generation to pass to
+    /// [`guilds_timed_out`](Self::guilds_timed_out), and every guild if the cache is already ready.
+    pub(crate) fn guilds_pending<I>(&self, guilds: I) -> (u64, Option<Vec<GuildId>>)
+        where I: IntoIterator<Item=GuildId>
+    {
+        let mut pending = self.pending_guilds.lock().unwrap();
+        pending.loading = true;
+        for guild in guilds {
+            pending.waiting.insert(guild);
+            pending.guilds.push(guild);
+        }
+        let generation = pending.generation;
+        let ready = pending.waiting.is_empty().then(|| pending.finish());
+        (generation, ready)
+    }
+
+    /// Marks `guild` as created, returning every guild from `Ready` if it was the last one.
+    pub(crate) fn guild_loaded(&self, guild: GuildId) -> Option<Vec<GuildId>> {
+        let mut pending = self.pending_guilds.lock().unwrap();
+        let last = pending.waiting.remove(&guild) && pending.waiting.is_empty();
+        last.then(|| pending.finish())
+    }
+
+    /// Stops waiting for guilds that still haven't been created, returning every guild from `Ready`
+    /// if the cache hasn't become ready since `generation`.
+    pub(crate) fn guilds_timed_out(&self, generation: u64) -> Option<Vec<GuildId>> {
+        let mut pending = self.pending_guilds.lock().unwrap();
+        (pending.loading && pending.generation == generation).then(|| pending.finish())
+    }
+
+    /// Save `text` if it is a dispatch of an event type being captured.
+    pub(crate) fn record_raw(&self, text: &str) {
+        #[derive(Deserialize)]
+        struct DispatchName {
+            t: Option<String>,
+        }
+
+        let mut raw_events = self.raw_events.lock().unwrap();
+        if raw_events.is_empty() { return; }
+        if let Ok(DispatchName { t: Some(name) }) = serde_json::from_str(text) {
+            if let Some(raw) = raw_events.get_mut(&name) {
+                *raw = Some(text.to_owned());
+            }
+        }
+    }
+
     fn create_id(&self) -> ComponentId {
         let id = self.count.fetch_add(1, Ordering::Relaxed);
         id.to_string().into()
@@ -113,9 +299,101 @@ impl<B> BotState<B> {
         modal.custom_id = id.clone();
         self.modals.write().unwrap().insert(id, command);
     }
+
+    /// Handle every button whose id is in `namespace` (see [`ComponentId::namespaced`]) with
+    /// `command`, which can tell the buttons apart by their [local](ComponentId::local) ids.
+    pub fn register_button_namespace<S, C>(&self, namespace: S, command: C)
+        where S: Into<String>,
+              C: ButtonCommand<Bot=B>,
+    {
+        self.button_namespaces.write().unwrap().insert(namespace.into(), Box::new(command));
+    }
+
+    /// Handle every menu whose id is in `namespace` (see [`ComponentId::namespaced`]) with
+    /// `command`, which can tell the menus apart by their [local](ComponentId::local) ids.
+    pub fn register_menu_namespace<S, C>(&self, namespace: S, command: C)
+        where S: Into<String>,
+              C: MenuCommand<Bot=B>,
+              <C::Data as FromStr>::Err: Debug,
+    {
+        self.menu_namespaces.write().unwrap().insert(namespace.into(), Box::new(command));
+    }
+
+    /// Run `middleware` on every interaction before it is dispatched to its command, after any
+    /// middleware that was already added.
+    pub fn add_middleware<M: InteractionMiddleware<Bot=B> + 'static>(&self, middleware: M) {
+        self.middleware.write().unwrap().push(Arc::new(middleware));
+    }
+
+    /// The command for the button with this `id`, either registered for exactly this `id` or for its
+    /// namespace.
+    pub(crate) fn button_command(&self, id: &ComponentId) -> Option<Box<dyn ButtonCommand<Bot=B>>> {
+        if let Some(command) = self.buttons.read().unwrap().get(id) {
+            return Some(command.clone());
+        }
+        let namespace = id.namespace()?;
+        self.button_namespaces.read().unwrap().get(namespace).cloned()
+    }
+
+    /// The command for the menu with this `id`, either registered for exactly this `id` or for its
+    /// namespace.
+    pub(crate) fn menu_command(&self, id: &ComponentId) -> Option<Box<dyn MenuCommandRaw<Bot=B>>> {
+        if let Some(command) = self.menus.read().unwrap().get(id) {
+            return Some(command.clone());
+        }
+        let namespace = id.namespace()?;
+        self.menu_namespaces.read().unwrap().get(namespace).cloned()
+    }
 }
 
 impl<B: Bot + Send + Sync> BotState<B> {
+    /// Remove the [`ReactionCommand`]s for any of `messages` (see [`ReactionCommand::message`]).
+    /// This happens automatically when the messages are deleted.
+    pub async fn remove_reaction_commands(&self, messages: &[MessageId]) {
+        self.reaction_commands.write().await
+            .retain(|command| command.message().map_or(true, |message| !messages.contains(&message)));
+    }
+
+    /// Whether this bot identifies with the privileged
+    /// [`MESSAGE_CONTENT`](Intents::MESSAGE_CONTENT) intent. Without it, Discord sends most messages
+    /// with empty `content`, `embeds`, `attachments`, and `components`, except for messages in DMs,
+    /// that mention the bot, or that the bot sent.
+    pub fn has_message_content_intent(&self) -> bool {
+        self.intents
+            .get_or_init(|| self.bot.identify().intents())
+            .contains(Intents::MESSAGE_CONTENT)
+    }
+
+    /// Logs a warning the first time a `message` looks like it was emptied because this bot doesn't
+    /// have the `MESSAGE_CONTENT` intent. Returns whether the warning was logged.
+    pub(crate) async fn warn_if_missing_message_content(&self, message: &Message) -> bool {
+        let emptied = matches!(message.message_type, MessageType::Default | MessageType::Reply)
+            && message.content.is_empty()
+            && message.embeds.is_empty()
+            && message.attachments.is_empty()
+            && message.components.is_empty()
+            && message.sticker_items.is_empty();
+        if !emptied
+            || self.message_content_warned.load(Ordering::Relaxed)
+            || self.has_message_content_intent() {
+            return false;
+        }
+        let own_id = self.cache.user.read().await.as_ref().map(|user| user.id);
+        if own_id == Some(message.author.id) || message.mentions.iter().any(|user| Some(user.id) == own_id) {
+            return false;
+        }
+
+        let first = !self.message_content_warned.swap(true, Ordering::Relaxed);
+        if first {
+            warn!(
+                "Received a message with no content, because this bot doesn't have the `MESSAGE_CONTENT` \
+                 intent. Add it to `Bot::identify` and enable it in the Developer Portal to receive \
+                 message content."
+            );
+        }
+        first
+    }
+
     pub async fn register_guild_commands<G, I>(
         &self,
         guild: G,
@@ -130,7 +408,7 @@ impl<B: Bot + Send + Sync> BotState<B> {
             let application_command = self.client.create_guild_command(
                 self.cache.application_id(),
                 guild,
-                command.command(),
+                command.command().into_owned(),
             ).await?;
             // let name = command.name();
             self.slash_commands.write()
@@ -150,6 +428,22 @@ impl<B: Bot + Send + Sync> BotState<B> {
         }
         Ok(())
     }
+
+    /// Runs each [middleware](Self::add_middleware) on `interaction` in order, stopping at the first
+    /// one that breaks and sending the response it broke with, if any.
+    pub(crate) async fn run_middleware(self: &Arc<Self>, interaction: &interaction::Interaction) -> ClientResult<ControlFlow<()>> {
+        // clone the list so that the lock isn't held while the middleware run
+        let middleware = self.middleware.read().unwrap().clone();
+        for middleware in middleware {
+            if let ControlFlow::Break(response) = middleware.before(interaction, self).await {
+                if let (Some(response), Some(id), Some(token)) = (response, interaction.id(), interaction.token()) {
+                    self.client.create_interaction_response(id, token.clone(), response).await?;
+                }
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
 }
 
 impl<B> AsRef<Self> for BotState<B> {
@@ -180,6 +474,18 @@ impl<B: Send + Sync> BotState<B> {
             .id
     }
 
+    /// Gets the bot's full [`Application`], which includes its owner, team, and description.
+    ///
+    /// The first call gets it with [`DiscordClient::application_information`], which is then cached
+    /// (see [`Cache::application`]) for later calls.
+    ///
+    /// # Errors
+    ///
+    /// If it isn't cached and the http request fails, in which case it stays uncached.
+    pub async fn application(&self) -> ClientResult<Application> {
+        self.cache.application_or_fetch(|| self.client.application_information()).await
+    }
+
     // /// Get the id of command `C` in this `guild`.
     // ///
     // /// # Note
@@ -344,6 +650,23 @@ pub trait Bot: Send + Sync + Sized {
     /// See [`Identify`](Identify) for more information.
     fn identify(&self) -> Identify { Identify::new(self.token()) }
 
+    /// The maximum lengths of messages this bot sends, which are checked before sending a message
+    /// instead of waiting for Discord to reject it. Defaults to [`MessageLimits::BOT`].
+    fn message_limits(&self) -> MessageLimits { MessageLimits::default() }
+
+    /// How to configure the [`DiscordClient`] used to make requests to Discord, such as how long
+    /// requests can take before timing out. See [`DiscordClientBuilder`] for the defaults.
+    fn http_client(&self) -> DiscordClientBuilder { DiscordClient::builder(self.token()) }
+
+    /// How long to remember interactions, so that an interaction Discord sends again (such as when
+    /// replaying events after resuming) isn't passed to [`interaction`](Self::interaction) twice.
+    /// Defaults to one minute.
+    fn duplicate_interaction_window(&self) -> Duration { Duration::from_mins(1) }
+
+    /// How long to wait for the guilds sent in [`Ready`](crate::shard::dispatch::Ready) to be
+    /// created before calling [`cache_ready`](Self::cache_ready) anyway. Defaults to 30 seconds.
+    fn cache_ready_timeout(&self) -> Duration { Duration::from_secs(30) }
+
     /// All of the bot's global commands as a static slice. This is called once when the bot
     /// receives the [`Ready`](crate::shard::dispatch::Ready) event, sending these commands to
     /// Discord and registering them in the bot's [`BotState`](crate::BotState) in order to run
@@ -360,8 +683,64 @@ pub trait Bot: Send + Sync + Sized {
 
     async fn resumed(&self, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
+    /// Called when the gateway connection of the shard `shard` (`(id, total)`) transitions, as
+    /// described by `connection`. Unlike [`ready`](Self::ready) and [`resumed`](Self::resumed), this
+    /// also fires on transport-level transitions like the websocket being (re)opened, so it is
+    /// useful for tracking the health of the connection.
+    ///
+    /// The shard waits for this to complete, so it should not do anything slow.
+    async fn shard_connected(&self, shard: (u64, u64), connection: ShardConnection, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when the websocket of the shard `shard` (`(id, total)`) is closed or dropped. The
+    /// shard will then reconnect or resume, calling [`shard_connected`](Self::shard_connected).
+    ///
+    /// The shard waits for this to complete, so it should not do anything slow.
+    async fn shard_disconnected(&self, shard: (u64, u64), state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
     async fn guild_create(&self, guild: Guild, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
+    /// Called once every guild sent in [`Ready`](crate::shard::dispatch::Ready) has been received
+    /// in a [`GuildCreate`](crate::shard::dispatch::GuildCreate) and cached, or once
+    /// [`cache_ready_timeout`](Self::cache_ready_timeout) has passed. `guilds` are the guilds that
+    /// were in `Ready`.
+    ///
+    /// This is called again if the bot reconnects and receives another `Ready`.
+    async fn cache_ready(&self, guilds: Vec<GuildId>, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when a cached guild is updated, with `old` being the guild before the update was
+    /// applied. Updates to guilds that aren't cached are not passed to this method.
+    async fn guild_update(&self, old: Option<Guild>, new: Guild, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when `member` joins `guild`.
+    ///
+    /// Requires the [`GUILD_MEMBERS`](Intents::GUILD_MEMBERS) intent.
+    async fn guild_member_add(&self, guild: GuildId, member: GuildMember, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when `user` leaves or is removed from `guild`.
+    ///
+    /// Requires the [`GUILD_MEMBERS`](Intents::GUILD_MEMBERS) intent.
+    async fn guild_member_remove(&self, guild: GuildId, user: User, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when a guild member is updated, with `old` being the cached member before `update`
+    /// was applied, if they were cached.
+    ///
+    /// Requires the [`GUILD_MEMBERS`](Intents::GUILD_MEMBERS) intent.
+    async fn guild_member_update(&self, old: Option<GuildMember>, update: GuildMemberUpdate, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when `member` starts boosting `guild`, after
+    /// [`guild_member_update`](Self::guild_member_update).
+    ///
+    /// Only fires if `member` was already cached, since otherwise there's no way to tell whether
+    /// they were boosting before. Requires the [`GUILD_MEMBERS`](Intents::GUILD_MEMBERS) intent.
+    async fn member_boost_start(&self, guild: GuildId, member: GuildMember, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    /// Called when `member` stops boosting `guild`, after
+    /// [`guild_member_update`](Self::guild_member_update).
+    ///
+    /// Only fires if `member` was already cached, since otherwise there's no way to tell whether
+    /// they were boosting before. Requires the [`GUILD_MEMBERS`](Intents::GUILD_MEMBERS) intent.
+    async fn member_boost_end(&self, guild: GuildId, member: GuildMember, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
     async fn message_create(&self, message: Message, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
     async fn message_update(&self, message: Message, state: Arc<BotState<Self>>, updates: MessageUpdate) -> Result<(), BotError<Self::Error>> { Ok(()) }
@@ -372,18 +751,82 @@ pub trait Bot: Send + Sync + Sized {
 
     async fn reaction(&self, reaction: ReactionUpdate, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
+    async fn integration_create(&self, guild: GuildId, integration: Integration, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
     async fn integration_update(&self, guild: GuildId, integration: Integration, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
+    async fn integration_delete(&self, guild: GuildId, integration: IntegrationId, application: Option<ApplicationId>, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
     async fn role_create(&self, guild: GuildId, role: Role, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
     async fn role_update(&self, guild: GuildId, role: Role, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
 
+    async fn stage_instance_create(&self, stage: StageInstance, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    async fn stage_instance_update(&self, stage: StageInstance, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    async fn stage_instance_delete(&self, stage: StageInstance, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    async fn scheduled_event_user_add(&self, add: GuildScheduledEventUserAdd, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
+    async fn scheduled_event_user_remove(&self, remove: GuildScheduledEventUserRemove, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> { Ok(()) }
+
     async fn error(&self, error: BotError<Self::Error>, state: Arc<BotState<Self>>) {
         let err = error.display_error(&state).await;
         error!("{}", err);
     }
 }
 
+/// The `log` target of the records about each command that is run, so that they can be filtered
+/// separately from the rest of a bot's logs.
+///
+/// Each record starts with the interaction's id, the command's name (or custom id, for components
+/// and modals), and the guild and user that used it, like
+/// `interaction=846462639134605312 command=ping guild=290926798626357999 user=53908232506183680`.
+pub const COMMAND_LOG_TARGET: &str = "discorsd::command";
+
+/// Identifies a command being run in its [log records](COMMAND_LOG_TARGET).
+struct CommandLog {
+    interaction: InteractionId,
+    command: String,
+    guild: Option<GuildId>,
+    user: UserId,
+}
+
+impl CommandLog {
+    fn new<D: InteractionPayload, U: Usability>(interaction: &InteractionUse<D, U>, command: String) -> Self {
+        Self {
+            interaction: interaction.id,
+            command,
+            guild: interaction.guild(),
+            user: interaction.user().id,
+        }
+    }
+
+    /// Log before and after `command` runs.
+    async fn run<T, E, F: Future<Output=Result<T, E>>>(self, command: F) -> Result<T, E> {
+        info!(target: COMMAND_LOG_TARGET, "{self} running");
+        let start = Instant::now();
+        let result = command.await;
+        match &result {
+            Ok(_) => info!(target: COMMAND_LOG_TARGET, "{self} finished in {:?}", start.elapsed()),
+            Err(_) => warn!(target: COMMAND_LOG_TARGET, "{self} failed after {:?}", start.elapsed()),
+        }
+        result
+    }
+}
+
+impl fmt::Display for CommandLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "interaction={} command={} ", self.interaction, self.command)?;
+        match self.guild {
+            Some(guild) => write!(f, "guild={guild}")?,
+            None => f.write_str("guild=dm")?,
+        }
+        write!(f, " user={}", self.user)
+    }
+}
+
 /// Extension methods for [Bot]s.
 #[async_trait]
 pub trait BotExt: Bot + 'static {
@@ -394,8 +837,14 @@ pub trait BotExt: Bot + 'static {
 
     /// Respond to an interaction with the matching [SlashCommand]. Should likely be used in the
     /// [Bot::interaction](Bot::interaction) method.
+    ///
+    /// Each [`InteractionMiddleware`] added with [`BotState::add_middleware`] is run first, and the
+    /// interaction is dropped if any of them break.
     #[allow(clippy::too_many_lines)]
     async fn handle_interaction(interaction: interaction::Interaction, state: Arc<BotState<Self>>) -> Result<(), BotError<Self::Error>> {
+        if state.run_middleware(&interaction).await?.is_break() {
+            return Ok(());
+        }
         match interaction {
             interaction::Interaction::Ping => println!("PING!"),
             interaction::Interaction::ApplicationCommand(data) => {
@@ -420,9 +869,10 @@ pub trait BotExt: Bot + 'static {
                             user,
                             token,
                         );
+                        let log = CommandLog::new(&interaction, interaction.data.command_name.clone());
                         let global_command = state.global_slash_commands.get().unwrap().get(&id);
                         if let Some(command) = global_command {
-                            command.run(Arc::clone(&state), interaction, options).await?;
+                            log.run(command.run(Arc::clone(&state), interaction, options)).await?;
                         } else {
                             let command = {
                                 let guard = state.slash_commands.read().await;
@@ -431,7 +881,7 @@ pub trait BotExt: Bot + 'static {
                                 commands.get(&id).cloned()
                             };
                             if let Some(command) = command {
-                                command.run(Arc::clone(&state), interaction, options).await?;
+                                log.run(command.run(Arc::clone(&state), interaction, options)).await?;
                             }
                         }
                     }
@@ -450,7 +900,8 @@ pub trait BotExt: Bot + 'static {
                             let target_user = resolved.users.get(target_id);
                             if let Some(u) = target_user {
                                 let guild_member = resolved.members.get(&target_id);
-                                command.run(Arc::clone(&state), interaction, u.clone(), guild_member.cloned()).await?;
+                                let log = CommandLog::new(&interaction, interaction.data.command_name.clone());
+                                log.run(command.run(Arc::clone(&state), interaction, u.clone(), guild_member.cloned())).await?;
                             }
                         }
                     }
@@ -468,7 +919,8 @@ pub trait BotExt: Bot + 'static {
                             // todo double check this and rename variables?
                             let target_message = resolved.messages.get(target_id);
                             if let Some(m) = target_message {
-                                command.run(Arc::clone(&state), interaction, m.clone()).await?;
+                                let log = CommandLog::new(&interaction, interaction.data.command_name.clone());
+                                log.run(command.run(Arc::clone(&state), interaction, m.clone())).await?;
                             }
                         }
                     }
@@ -487,9 +939,10 @@ pub trait BotExt: Bot + 'static {
                     locale
                 } = data;
                 match data {
-                    MessageComponentData::Button(data) => {
-                        let command = state.buttons.read().unwrap().get(&data.custom_id).cloned();
+                    MessageComponentData::Button(mut data) => {
+                        let command = state.button_command(&data.custom_id);
                         if let Some(command) = command {
+                            data.message = message;
                             let interaction = InteractionUse::new(
                                 interaction_id,
                                 application_id,
@@ -498,16 +951,18 @@ pub trait BotExt: Bot + 'static {
                                 user,
                                 token,
                             );
-                            command.run(Arc::clone(&state), interaction).await?;
+                            let log = CommandLog::new(&interaction, interaction.data.custom_id.0.clone());
+                            log.run(command.run(Arc::clone(&state), interaction)).await?;
                         }
                     }
-                    MessageComponentData::StringMenu(data)
-                    | MessageComponentData::UserMenu(data)
-                    | MessageComponentData::RoleMenu(data)
-                    | MessageComponentData::MentionableMenu(data)
-                    | MessageComponentData::ChannelMenu(data) => {
-                        let command = state.menus.read().unwrap().get(&data.custom_id).cloned();
+                    MessageComponentData::StringMenu(mut data)
+                    | MessageComponentData::UserMenu(mut data)
+                    | MessageComponentData::RoleMenu(mut data)
+                    | MessageComponentData::MentionableMenu(mut data)
+                    | MessageComponentData::ChannelMenu(mut data) => {
+                        let command = state.menu_command(&data.custom_id);
                         if let Some(command) = command {
+                            data.message = message;
                             let interaction = InteractionUse::new(
                                 interaction_id,
                                 application_id,
@@ -516,7 +971,8 @@ pub trait BotExt: Bot + 'static {
                                 user,
                                 token,
                             );
-                            command.run(Arc::clone(&state), interaction).await?;
+                            let log = CommandLog::new(&interaction, interaction.data.custom_id.0.clone());
+                            log.run(command.run(Arc::clone(&state), interaction)).await?;
                         }
                     }
                     MessageComponentData::TextInput(_) => unreachable!("True while inline text fields aren't supported"),
@@ -546,7 +1002,8 @@ pub trait BotExt: Bot + 'static {
                         user,
                         token,
                     );
-                    command.run(Arc::clone(&state), interaction).await?;
+                    let log = CommandLog::new(&interaction, interaction.data.custom_id.0.clone());
+                    log.run(command.run(Arc::clone(&state), interaction)).await?;
                 }
             }
         }
@@ -558,33 +1015,19 @@ pub trait BotExt: Bot + 'static {
 impl<B: Bot + 'static> BotExt for B {}
 
 struct BotRunner<B: Bot + 'static> {
+    state: Arc<BotState<B>>,
     shards: Vec<Shard<B>>,
 }
 
 impl<B: Bot + 'static> From<B> for BotRunner<B> {
     fn from(bot: B) -> Self {
-        let state = Arc::new(BotState {
-            client: DiscordClient::single(bot.token()),
-            stream: Default::default(),
-            cache: Default::default(),
-            bot,
-            slash_commands: Default::default(),
-            // command_names: Default::default(),
-            global_slash_commands: Default::default(),
-            global_user_commands: Default::default(),
-            global_message_commands: Default::default(),
-            // global_command_names: Default::default(),
-            // global_user_command_names: Default::default(),
-            // global_message_command_names: Default::default(),
-            reaction_commands: Default::default(),
-            buttons: Default::default(),
-            menus: Default::default(),
-            modals: Default::default(),
-            count: Default::default(),
-        });
+        let mut client = bot.http_client().build();
+        client.message_limits = bot.message_limits();
+        let state = Arc::new(BotState::new(client, bot));
         // todo more than one shard
         let shard = Shard::new(Arc::clone(&state));
         Self {
+            state,
             shards: vec![shard]
         }
     }
@@ -605,8 +1048,33 @@ impl<B: Bot + 'static> BotRunner<B> {
             .filter(None, LevelFilter::Info)
             .init();
 
+        if let Some(shard) = self.shards.first() {
+            match shard.check_intents().await {
+                Ok(()) => {}
+                Err(e @ ShardError::DisallowedIntents(_)) => {
+                    error!("{e}");
+                    return Err(e);
+                }
+                Err(e) => warn!("Could not check the bot's intents: {e}"),
+            }
+        }
+
+        let Self { state, mut shards } = self;
+        let mut max_concurrency = 1;
+        match state.client.gateway_bot().await {
+            Ok(gateway) => {
+                max_concurrency = gateway.session_start_limit.max_concurrency;
+                for shard in &mut shards {
+                    shard.use_gateway(&gateway);
+                }
+            }
+            Err(e) => warn!("Could not get the gateway: {e}"),
+        }
+
+        let identify_gate = Arc::new(IdentifyGate::new(max_concurrency, IdentifyGate::INTERVAL));
         let mut handles = Vec::new();
-        for mut shard in self.shards {
+        for mut shard in shards {
+            shard.identify_gate = Some(Arc::clone(&identify_gate));
             let handle = tokio::spawn(async move {
                 (shard.shard_info, shard.run().await)
             });
@@ -628,4 +1096,29 @@ impl<B: Bot + 'static> BotRunner<B> {
         unreachable!()
         // Err(ShardError::Other("Shouldn't stop running".into()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod bot_tests {
+    use super::*;
+
+    #[test]
+    fn command_log_format() {
+        let log = CommandLog {
+            interaction: InteractionId(846_462_639_134_605_312),
+            command: "polls:confirm".into(),
+            guild: Some(GuildId(290_926_798_626_357_999)),
+            user: UserId(53_908_232_506_183_680),
+        };
+        assert_eq!(
+            format!("{log} running"),
+            "interaction=846462639134605312 command=polls:confirm guild=290926798626357999 user=53908232506183680 running",
+        );
+
+        let dm = CommandLog { guild: None, ..log };
+        assert_eq!(
+            dm.to_string(),
+            "interaction=846462639134605312 command=polls:confirm guild=dm user=53908232506183680",
+        );
+    }
+}
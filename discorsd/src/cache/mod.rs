@@ -1,35 +1,48 @@
+use std::collections::HashSet;
 use std::collections::hash_map::{self, Entry, HashMap};
 use std::fmt;
 use std::fmt::Debug;
+use std::future::Future;
 use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::sync::OnceLock;
 
+use chrono::Utc;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{MapAccess, SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 use tokio::sync::{RwLock, RwLockReadGuard};
 
-use crate::model::channel::{CategoryChannel, Channel, ChannelType, DmChannel, AnnouncementChannel, TextChannel};
-use crate::model::guild::{Guild, GuildMember, UnavailableGuild};
+use crate::model::channel::{CategoryChannel, Channel, ChannelType, DmChannel, AnnouncementChannel, GuildDirectory, GuildForum, GuildStageVoice, TextChannel, Thread, ThreadMember, VoiceChannel};
+use crate::http::ClientResult;
+use crate::model::Application;
+use crate::model::guild::{Guild, GuildMember, Integration, UnavailableGuild};
 use crate::model::ids::*;
 use crate::model::message::{Message, Reaction};
 use crate::model::interaction::{ApplicationCommandData, InteractionData};
 use crate::model::permissions::Role;
 use crate::model::user::User;
+use crate::model::voice::{PendingVoiceConnection, VoiceConnectionInfo};
 use crate::shard::dispatch::PartialApplication;
 
+/// How long an interaction's token is valid for. After this, its response can't be edited or
+/// deleted, so it is dropped from the cache.
+pub(crate) const INTERACTION_TOKEN_LIFETIME: chrono::Duration = chrono::Duration::minutes(15);
+
 #[derive(Default, Debug)]
 pub struct Cache {
     // not a OnceCell because the User can be updated
     pub(crate) user: RwLock<Option<User>>,
     pub(crate) application: OnceLock<PartialApplication>,
+    /// fetched the first time it's needed, see [`BotState::application`](crate::BotState::application)
+    pub(crate) full_application: tokio::sync::OnceCell<Application>,
 
     pub(crate) users: RwLock<IdMap<User>>,
 
     pub(crate) unavailable_guilds: RwLock<IdMap<UnavailableGuild>>,
     pub(crate) guilds: RwLock<IdMap<Guild>>,
     pub(crate) members: RwLock<HashMap<UserId, HashMap<GuildId, GuildMember>>>,
+    pub(crate) integrations: RwLock<HashMap<GuildId, IdMap<Integration>>>,
 
     pub(crate) channel_types: RwLock<HashMap<ChannelId, ChannelType>>,
     pub(crate) channels: RwLock<IdMap<TextChannel>>,
@@ -37,12 +50,21 @@ pub struct Cache {
     pub(crate) dms: RwLock<(HashMap<UserId, ChannelId>, IdMap<DmChannel>)>,
     pub(crate) categories: RwLock<IdMap<CategoryChannel>>,
     pub(crate) news: RwLock<IdMap<AnnouncementChannel>>,
+    pub(crate) forums: RwLock<IdMap<GuildForum>>,
+    pub(crate) voice: RwLock<IdMap<VoiceChannel>>,
+    pub(crate) stages: RwLock<IdMap<GuildStageVoice>>,
+    pub(crate) directories: RwLock<IdMap<GuildDirectory>>,
+    // all three thread types, `channel_types` knows which one each is
+    pub(crate) threads: RwLock<IdMap<Thread>>,
+    pub(crate) thread_members: RwLock<HashMap<ChannelId, ThreadMembers>>,
     // pub(crate) stores: RwLock<IdMap<StoreChannel>>,
 
     pub(crate) messages: RwLock<IdMap<Message>>,
     pub(crate) interaction_responses: RwLock<HashMap<InteractionId, Message>>,
 
     pub(crate) commands: RwLock<IdMap<InteractionData<ApplicationCommandData>>>,
+
+    pub(crate) voice_connections: RwLock<HashMap<GuildId, PendingVoiceConnection>>,
 }
 
 impl Cache {
@@ -57,6 +79,21 @@ impl Cache {
             .id
     }
 
+    /// Gets the bot's full [`Application`], if it has been fetched by
+    /// [`BotState::application`](crate::BotState::application).
+    pub fn application(&self) -> Option<Application> {
+        self.full_application.get().cloned()
+    }
+
+    /// Gets the cached [`Application`], or gets it with `fetch` and caches it if it isn't cached
+    /// yet. If `fetch` fails, nothing is cached and the error is returned.
+    pub(crate) async fn application_or_fetch<F, Fut>(&self, fetch: F) -> ClientResult<Application>
+        where F: FnOnce() -> Fut + Send,
+              Fut: Future<Output=ClientResult<Application>> + Send,
+    {
+        self.full_application.get_or_try_init(fetch).await.cloned()
+    }
+
     /// Gets the current user.
     ///
     /// # Panics
@@ -93,6 +130,15 @@ impl Cache {
             .and_then(|map| map.get(&guild.id()).cloned())
     }
 
+    /// Gets the bot's own member in `guild`, for checking its roles and permissions there.
+    ///
+    /// Returns `None` if [`Ready`](crate::shard::dispatch::Ready) hasn't been received yet, or if
+    /// the bot's member in `guild` isn't cached.
+    pub async fn own_member<G: Id<Id=GuildId> + Send>(&self, guild: G) -> Option<GuildMember> {
+        let id = self.user.read().await.as_ref()?.id;
+        self.member(guild, id).await
+    }
+
     pub async fn channel<C: Id<Id=ChannelId> + Send>(&self, id: C) -> Option<Channel> {
         let id = id.id();
         let channel_type = self.channel_types.read().await.get(&id).copied();
@@ -102,14 +148,14 @@ impl Cache {
             Some(ChannelType::Category) => self.categories.read().await.get(&id).cloned().map(Channel::Category),
             Some(ChannelType::Announcement) => self.news.read().await.get(&id).cloned().map(Channel::Announcement),
             // Some(ChannelType::GuildStore) => self.stores.read().await.get(&id).cloned().map(Channel::Store),
-            Some(ChannelType::GroupDm | ChannelType::Voice) | None => None,
-            // todo
-            Some(ChannelType::AnnouncementThread) => None,
-            Some(ChannelType::PublicThread) => None,
-            Some(ChannelType::PrivateThread) => None,
-            Some(ChannelType::GuildStageVoice) => None,
-            Some(ChannelType::GuildDirectory) => None,
-            Some(ChannelType::GuildForum) => None,
+            Some(ChannelType::Voice) => self.voice.read().await.get(&id).cloned().map(Channel::Voice),
+            Some(ChannelType::GroupDm | ChannelType::Unknown(_)) | None => None,
+            Some(ChannelType::AnnouncementThread) => self.threads.read().await.get(&id).cloned().map(Channel::AnnouncementThread),
+            Some(ChannelType::PublicThread) => self.threads.read().await.get(&id).cloned().map(Channel::PublicThread),
+            Some(ChannelType::PrivateThread) => self.threads.read().await.get(&id).cloned().map(Channel::PrivateThread),
+            Some(ChannelType::GuildStageVoice) => self.stages.read().await.get(&id).cloned().map(Channel::GuildStageVoice),
+            Some(ChannelType::GuildDirectory) => self.directories.read().await.get(&id).cloned().map(Channel::GuildDirectory),
+            Some(ChannelType::GuildForum) => self.forums.read().await.get(&id).cloned().map(Channel::GuildForum),
         }
     }
 
@@ -117,6 +163,10 @@ impl Cache {
         self.channels.read().await.get(id).cloned()
     }
 
+    pub async fn forum_channel<C: Id<Id=ChannelId> + Send>(&self, id: C) -> Option<GuildForum> {
+        self.forums.read().await.get(id).cloned()
+    }
+
     pub async fn dm_channel<U: Id<Id=UserId> + Send>(&self, id: U) -> Option<DmChannel> {
         let (by_user, by_channel) = &*self.dms.read().await;
         let id = id.id();
@@ -152,10 +202,54 @@ impl Cache {
             .collect()
     }
 
-    /// Assumes that the guild exists
+    /// The [`TextChannel`]s in `guild`.
+    pub async fn text_channels<G: Id<Id=GuildId> + Send>(&self, guild: G) -> IdMap<TextChannel> {
+        self.guild_channels(guild, Channel::text).await
+    }
+
+    /// The [`VoiceChannel`]s in `guild`.
+    pub async fn voice_channels<G: Id<Id=GuildId> + Send>(&self, guild: G) -> IdMap<VoiceChannel> {
+        self.guild_channels(guild, |c| match c {
+            Channel::Voice(voice) => Some(voice),
+            _ => None,
+        }).await
+    }
+
+    /// The [`CategoryChannel`]s in `guild`.
+    pub async fn categories<G: Id<Id=GuildId> + Send>(&self, guild: G) -> IdMap<CategoryChannel> {
+        self.guild_channels(guild, |c| match c {
+            Channel::Category(category) => Some(category),
+            _ => None,
+        }).await
+    }
+
+    /// Every channel in `guild` that is in the category `category`.
+    pub async fn channels_in_category<G, C>(&self, guild: G, category: C) -> IdMap<Channel>
+        where
+            G: Id<Id=GuildId> + Send,
+            C: Id<Id=ChannelId> + Send,
+    {
+        let category = category.id();
+        self.guild_channels(guild, |c| (c.parent_id() == Some(category)).then_some(c)).await
+    }
+
+    /// The `@everyone` role in `guild`.
+    ///
+    /// # Panics
+    ///
+    /// If `guild` isn't cached yet, such as before its [`GuildCreate`](crate::shard::dispatch::GuildCreate)
+    /// event is received. Use [`try_everyone_role`](Self::try_everyone_role) if that is possible.
     pub async fn everyone_role<G>(&self, guild: G) -> Role
         where
             G: Id<Id=GuildId> + Send,
+    {
+        self.try_everyone_role(guild).await.expect("the guild exists")
+    }
+
+    /// The `@everyone` role in `guild`, or `None` if `guild` isn't cached.
+    pub async fn try_everyone_role<G>(&self, guild: G) -> Option<Role>
+        where
+            G: Id<Id=GuildId> + Send,
     {
         let guard = self.guilds.read().await;
         guard.get(guild)
@@ -163,15 +257,164 @@ impl Cache {
                 .find(|r| r.name == "@everyone")
                 .expect("all guilds have `@everyone` role"))
             .cloned()
-            .expect("the guild exists")
     }
 
     pub async fn command<C: Id<Id=CommandId> + Send>(&self, id: C) -> Option<InteractionData<ApplicationCommandData>> {
         self.commands.read().await.get(id).cloned()
     }
 
+    /// The members of `thread` that this bot has been told about by
+    /// [`ThreadMemberUpdate`](crate::shard::dispatch::ThreadMemberUpdate) and
+    /// [`ThreadMembersUpdate`](crate::shard::dispatch::ThreadMembersUpdate) events.
+    pub async fn thread_members<C: Id<Id=ChannelId> + Send>(&self, thread: C) -> Vec<ThreadMember> {
+        self.thread_members.read().await
+            .get(&thread.id())
+            .map(|thread| thread.members.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The integrations in `guild` that this bot has been told about by
+    /// [`IntegrationCreate`](crate::shard::dispatch::IntegrationCreate) and
+    /// [`IntegrationUpdate`](crate::shard::dispatch::IntegrationUpdate) events.
+    pub async fn integrations<G: Id<Id=GuildId> + Send>(&self, guild: G) -> Vec<Integration> {
+        self.integrations.read().await
+            .get(&guild.id())
+            .map(|integrations| integrations.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn integration<G, I>(&self, guild: G, integration: I) -> Option<Integration>
+        where
+            G: Id<Id=GuildId> + Send,
+            I: Id<Id=IntegrationId> + Send,
+    {
+        self.integrations.read().await
+            .get(&guild.id())
+            .and_then(|integrations| integrations.get(integration).cloned())
+    }
+
+    /// The message that responded to the interaction `id`, if it was sent in the last
+    /// [15 minutes](INTERACTION_TOKEN_LIFETIME).
     pub async fn interaction_response<I: Id<Id=InteractionId> + Send>(&self, id: I) -> Option<Message> {
-        self.interaction_responses.read().await.get(&id.id()).cloned()
+        let id = id.id();
+        if Self::is_expired(id) {
+            return None;
+        }
+        self.interaction_responses.read().await.get(&id).cloned()
+    }
+
+    /// Remember the response to `interaction`.
+    pub(crate) async fn insert_interaction_response(&self, interaction: InteractionId, message: Message) {
+        let mut responses = self.interaction_responses.write().await;
+        Self::evict_expired_responses(&mut responses);
+        responses.insert(interaction, message);
+    }
+
+    /// Forget any responses whose interaction is older than [`INTERACTION_TOKEN_LIFETIME`], since
+    /// they can't be edited or deleted anymore. Done whenever a response is inserted.
+    fn evict_expired_responses(responses: &mut HashMap<InteractionId, Message>) {
+        responses.retain(|&id, _| !Self::is_expired(id));
+    }
+
+    /// Whether the interaction `id` is older than [`INTERACTION_TOKEN_LIFETIME`].
+    fn is_expired(id: InteractionId) -> bool {
+        id <= InteractionId::from(Utc::now() - INTERACTION_TOKEN_LIFETIME)
+    }
+
+    /// Gets the info needed to connect to the voice server in `guild`, once both the
+    /// [`VoiceStateUpdate`](crate::shard::dispatch::VoiceStateUpdate) and
+    /// [`VoiceServerUpdate`](crate::shard::dispatch::VoiceServerUpdate) for the bot's
+    /// [`join_voice`](crate::BotState::join_voice) have been received.
+    pub async fn voice_connection<G: Id<Id=GuildId> + Send>(&self, guild: G) -> Option<VoiceConnectionInfo> {
+        let guild = guild.id();
+        self.voice_connections.read().await.get(&guild)?.info(guild)
+    }
+
+    /// Removes everything cached about guilds, channels, members, messages, and commands.
+    ///
+    /// The bot's own user and application are kept, since they are only sent on
+    /// [`Ready`](crate::shard::dispatch::Ready).
+    pub async fn clear(&self) {
+        self.users.write().await.0.clear();
+        self.unavailable_guilds.write().await.0.clear();
+        self.guilds.write().await.0.clear();
+        self.members.write().await.clear();
+        self.integrations.write().await.clear();
+        self.channel_types.write().await.clear();
+        self.channels.write().await.0.clear();
+        *self.dms.write().await = Default::default();
+        self.categories.write().await.0.clear();
+        self.news.write().await.0.clear();
+        self.forums.write().await.0.clear();
+        self.voice.write().await.0.clear();
+        self.stages.write().await.0.clear();
+        self.directories.write().await.0.clear();
+        self.threads.write().await.0.clear();
+        self.thread_members.write().await.clear();
+        self.messages.write().await.0.clear();
+        self.interaction_responses.write().await.clear();
+        self.commands.write().await.0.clear();
+        self.voice_connections.write().await.clear();
+    }
+
+    /// Removes `guild` from the cache, along with its channels, the messages in those channels,
+    /// and its members.
+    ///
+    /// Users are kept, since they may share other guilds or DMs with the bot.
+    pub async fn clear_guild<G: Id<Id=GuildId> + Send>(&self, guild: G) {
+        let guild = guild.id();
+        let removed = self.guilds.write().await.remove(guild);
+        self.unavailable_guilds.write().await.remove(guild);
+
+        // channels created after `GuildCreate` are not necessarily in `Guild::channels`
+        let mut channels: HashSet<ChannelId> = removed.iter()
+            .flat_map(|g| g.channels.iter())
+            .map(Id::id)
+            .collect();
+        let in_guild = |id: Option<GuildId>| id == Some(guild);
+        channels.extend(self.channels.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.categories.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.news.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.forums.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.voice.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.stages.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.directories.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+        channels.extend(self.threads.read().await.iter().filter(|c| in_guild(c.guild_id)).map(Id::id));
+
+        {
+            let mut channel_types = self.channel_types.write().await;
+            let mut text = self.channels.write().await;
+            let mut categories = self.categories.write().await;
+            let mut news = self.news.write().await;
+            let mut forums = self.forums.write().await;
+            let mut voice = self.voice.write().await;
+            let mut stages = self.stages.write().await;
+            let mut directories = self.directories.write().await;
+            let mut threads = self.threads.write().await;
+            let mut thread_members = self.thread_members.write().await;
+            for &channel in &channels {
+                channel_types.remove(&channel);
+                text.remove(channel);
+                categories.remove(channel);
+                news.remove(channel);
+                forums.remove(channel);
+                voice.remove(channel);
+                stages.remove(channel);
+                directories.remove(channel);
+                threads.remove(channel);
+            }
+            // the thread itself might not be cached
+            thread_members.retain(|id, thread| thread.guild != guild && !channels.contains(id));
+        }
+        self.messages.write().await.0.retain(|_, m| !channels.contains(&m.channel));
+        self.interaction_responses.write().await.retain(|_, m| !channels.contains(&m.channel));
+
+        self.members.write().await.retain(|_, guilds| {
+            guilds.remove(&guild);
+            !guilds.is_empty()
+        });
+        self.integrations.write().await.remove(&guild);
+        self.voice_connections.write().await.remove(&guild);
     }
 }
 
@@ -180,37 +423,55 @@ impl Cache {
         let Self {
             user,
             application,
+            full_application,
             users,
             unavailable_guilds,
             guilds,
             members,
+            integrations,
             channel_types,
             dms,
             channels,
             categories,
             news,
+            forums,
+            voice,
+            stages,
+            directories,
+            threads,
+            thread_members,
             // stores,
             messages,
             interaction_responses,
-            commands
+            commands,
+            voice_connections,
         } = self;
         #[allow(clippy::mixed_read_write_in_expression)]
         DebugCache {
             user: user.read().await,
             application: application.get(),
+            full_application: full_application.get(),
             users: users.read().await,
             unavailable_guilds: unavailable_guilds.read().await,
             guilds: guilds.read().await,
             members: members.read().await,
+            integrations: integrations.read().await,
             channel_types: channel_types.read().await,
             channels: channels.read().await,
             dms: dms.read().await,
             categories: categories.read().await,
             news: news.read().await,
+            forums: forums.read().await,
+            voice: voice.read().await,
+            stages: stages.read().await,
+            directories: directories.read().await,
+            threads: threads.read().await,
+            thread_members: thread_members.read().await,
             // stores: stores.read().await,
             messages: messages.read().await,
             interaction_responses: interaction_responses.read().await,
             commands: commands.read().await,
+            voice_connections: voice_connections.read().await,
         }
     }
 }
@@ -221,18 +482,42 @@ impl Cache {
 pub struct DebugCache<'a> {
     user: RwLockReadGuard<'a, Option<User>>,
     application: Option<&'a PartialApplication>,
+    full_application: Option<&'a Application>,
     users: RwLockReadGuard<'a, IdMap<User>>,
     unavailable_guilds: RwLockReadGuard<'a, IdMap<UnavailableGuild>>,
     guilds: RwLockReadGuard<'a, IdMap<Guild>>,
     members: RwLockReadGuard<'a, HashMap<UserId, HashMap<GuildId, GuildMember>>>,
+    integrations: RwLockReadGuard<'a, HashMap<GuildId, IdMap<Integration>>>,
     channel_types: RwLockReadGuard<'a, HashMap<ChannelId, ChannelType>>,
     channels: RwLockReadGuard<'a, IdMap<TextChannel>>,
     dms: RwLockReadGuard<'a, (HashMap<UserId, ChannelId>, IdMap<DmChannel>)>,
     categories: RwLockReadGuard<'a, IdMap<CategoryChannel>>,
     news: RwLockReadGuard<'a, IdMap<AnnouncementChannel>>,
+    forums: RwLockReadGuard<'a, IdMap<GuildForum>>,
+    voice: RwLockReadGuard<'a, IdMap<VoiceChannel>>,
+    stages: RwLockReadGuard<'a, IdMap<GuildStageVoice>>,
+    directories: RwLockReadGuard<'a, IdMap<GuildDirectory>>,
+    threads: RwLockReadGuard<'a, IdMap<Thread>>,
+    thread_members: RwLockReadGuard<'a, HashMap<ChannelId, ThreadMembers>>,
     messages: RwLockReadGuard<'a, IdMap<Message>>,
     interaction_responses: RwLockReadGuard<'a, HashMap<InteractionId, Message>>,
     commands: RwLockReadGuard<'a, IdMap<InteractionData<ApplicationCommandData>>>,
+    voice_connections: RwLockReadGuard<'a, HashMap<GuildId, PendingVoiceConnection>>,
+}
+
+/// The members of a thread, see [`Cache::thread_members`].
+#[derive(Debug, Clone)]
+pub(crate) struct ThreadMembers {
+    /// The guild the thread is in, so that [`Cache::clear_guild`] can find it even if the thread
+    /// isn't cached.
+    pub(crate) guild: GuildId,
+    pub(crate) members: HashMap<UserId, ThreadMember>,
+}
+
+impl ThreadMembers {
+    pub(crate) fn new(guild: GuildId) -> Self {
+        Self { guild, members: HashMap::new() }
+    }
 }
 
 /// A map of objects, with keys given by the object's id
@@ -385,3 +670,446 @@ pub trait Update {
     /// update the cache, lazily cloning whatever is needed out of `self`
     async fn update(&self, cache: &Cache);
 }
+
+#[cfg(test)]
+mod cache_tests {
+    use crate::shard::dispatch::{ChannelCreate, ChannelDelete, ChannelUpdate, GuildCreate};
+    use crate::test_fixtures::{guild, message};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn try_everyone_role() {
+        let cache = Cache::default();
+        assert!(cache.try_everyone_role(GuildId(1)).await.is_none());
+
+        let guild_create: GuildCreate = serde_json::from_str(&guild(1, r#"[
+            { "id": "1", "name": "@everyone", "permissions": "0", "position": 0, "color": 0, "hoist": false, "managed": false, "mentionable": false },
+            { "id": "2", "name": "mods", "permissions": "0", "position": 1, "color": 0, "hoist": false, "managed": false, "mentionable": false }
+        ]"#, "[]", "[]")).unwrap();
+        guild_create.update(&cache).await;
+
+        let everyone = cache.try_everyone_role(GuildId(1)).await;
+        assert_eq!(everyone.map(|r| r.id), Some(RoleId(1)));
+        assert_eq!(cache.everyone_role(GuildId(1)).await.id, RoleId(1));
+        assert!(cache.try_everyone_role(GuildId(2)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn forum_channels() {
+        fn forum(topic: &str) -> String {
+            format!(r#"{{
+              "type": 15,
+              "id": "20",
+              "guild_id": "1",
+              "name": "help",
+              "position": 0,
+              "permission_overwrites": [],
+              "parent_id": null,
+              "topic": "{topic}",
+              "default_auto_archive_duration": null,
+              "flags": 0,
+              "available_tags": [],
+              "default_reaction_emoji": null,
+              "default_thread_rate_limit_per_user": 0,
+              "default_sort_order": null
+            }}"#)
+        }
+
+        let cache = Cache::default();
+        let create: ChannelCreate = serde_json::from_str(&forum("questions")).unwrap();
+        create.update(&cache).await;
+        assert_eq!(cache.forum_channel(ChannelId(20)).await.unwrap().topic.as_deref(), Some("questions"));
+        assert!(matches!(cache.channel(ChannelId(20)).await, Some(Channel::GuildForum(_))));
+
+        let update: ChannelUpdate = serde_json::from_str(&forum("answers")).unwrap();
+        update.update(&cache).await;
+        assert_eq!(cache.forum_channel(ChannelId(20)).await.unwrap().topic.as_deref(), Some("answers"));
+
+        let delete: ChannelDelete = serde_json::from_str(&forum("answers")).unwrap();
+        delete.update(&cache).await;
+        assert!(cache.forum_channel(ChannelId(20)).await.is_none());
+        assert!(cache.channel(ChannelId(20)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn application_fetched_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::http::ClientError;
+        use crate::http::routes::Route;
+
+        let application: Application = serde_json::from_str(r#"{
+          "id": "4",
+          "name": "Test Bot",
+          "icon": null,
+          "description": "tests things",
+          "bot_public": true,
+          "bot_require_code_grant": false,
+          "owner": { "id": "10", "username": "owner", "discriminator": "0", "avatar": null },
+          "summary": "",
+          "verify_key": "key",
+          "team": null
+        }"#).unwrap();
+        let cache = Cache::default();
+        let fetches = AtomicUsize::new(0);
+        let fetch = |result: ClientResult<Application>| {
+            let fetches = &fetches;
+            move || async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                result
+            }
+        };
+        assert!(cache.application().is_none());
+
+        // failures aren't cached
+        let failed = cache.application_or_fetch(fetch(Err(ClientError::Timeout(Route::ApplicationInfo)))).await;
+        assert!(matches!(failed, Err(ClientError::Timeout(Route::ApplicationInfo))));
+        assert!(cache.application().is_none());
+
+        let first = cache.application_or_fetch(fetch(Ok(application.clone()))).await.unwrap();
+        assert_eq!(first.description, "tests things");
+        let second = cache.application_or_fetch(fetch(Err(ClientError::Timeout(Route::ApplicationInfo)))).await.unwrap();
+        assert_eq!(second.owner.id, UserId(10));
+        assert_eq!(cache.application().map(|a| a.id), Some(ApplicationId(4)));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn reaction_removed_at_zero() {
+        use crate::shard::dispatch::{MessageCreate, ReactionAdd, ReactionRemove};
+
+        let cache = Cache::default();
+        let message_create: MessageCreate = serde_json::from_str(&message(3, 2)).unwrap();
+        message_create.update(&cache).await;
+        let reaction = r#"{"user_id":"1","channel_id":"2","message_id":"3","emoji":{"id":null,"name":"🔥"}}"#;
+        let add: ReactionAdd = serde_json::from_str(reaction).unwrap();
+        let remove: ReactionRemove = serde_json::from_str(reaction).unwrap();
+
+        add.update(&cache).await;
+        let message = cache.message(MessageId(3)).await.unwrap();
+        assert_eq!(message.reactions.len(), 1);
+        assert_eq!(message.reactions[0].count, 1);
+
+        remove.update(&cache).await;
+        assert!(cache.message(MessageId(3)).await.unwrap().reactions.is_empty());
+        // removing a reaction that isn't there doesn't underflow
+        remove.update(&cache).await;
+        assert!(cache.message(MessageId(3)).await.unwrap().reactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn own_reaction_replayed() {
+        use crate::shard::dispatch::{MessageCreate, ReactionAdd, ReactionRemove};
+
+        let cache = Cache::default();
+        *cache.user.write().await = Some(serde_json::from_str(
+            r#"{ "id": "10", "username": "bot", "discriminator": "0", "avatar": null }"#
+        ).unwrap());
+        let message_create: MessageCreate = serde_json::from_str(&message(3, 2)).unwrap();
+        message_create.update(&cache).await;
+        let reaction = |user: &str| format!(
+            r#"{{"user_id":"{user}","channel_id":"2","message_id":"3","emoji":{{"id":null,"name":"🔥"}}}}"#
+        );
+        let own_add: ReactionAdd = serde_json::from_str(&reaction("10")).unwrap();
+        let other_add: ReactionAdd = serde_json::from_str(&reaction("1")).unwrap();
+        let own_remove: ReactionRemove = serde_json::from_str(&reaction("10")).unwrap();
+        let count = || async {
+            let message = cache.message(MessageId(3)).await.unwrap();
+            message.reactions.first().map(|r| (r.count, r.me))
+        };
+
+        own_add.update(&cache).await;
+        own_add.update(&cache).await;
+        assert_eq!(count().await, Some((1, true)));
+        other_add.update(&cache).await;
+        assert_eq!(count().await, Some((2, true)));
+
+        own_remove.update(&cache).await;
+        own_remove.update(&cache).await;
+        assert_eq!(count().await, Some((1, false)));
+    }
+
+    #[tokio::test]
+    async fn own_member() {
+        let cache = Cache::default();
+        assert!(cache.own_member(GuildId(1)).await.is_none());
+
+        let user: User = serde_json::from_str(
+            r#"{ "id": "10", "username": "bot", "discriminator": "0", "avatar": null }"#
+        ).unwrap();
+        *cache.user.write().await = Some(user.clone());
+        assert!(cache.own_member(GuildId(1)).await.is_none());
+
+        let member: GuildMember = serde_json::from_str(r#"{
+          "user": { "id": "10", "username": "bot", "discriminator": "0", "avatar": null },
+          "roles": ["5"],
+          "joined_at": "2015-04-26T06:26:56.936000+00:00",
+          "deaf": false,
+          "mute": false
+        }"#).unwrap();
+        cache.members.write().await
+            .entry(user.id)
+            .or_default()
+            .insert(GuildId(1), member);
+        let own = cache.own_member(GuildId(1)).await.unwrap();
+        assert_eq!(own.user.id, UserId(10));
+        assert_eq!(own.roles.into_iter().collect::<Vec<_>>(), [RoleId(5)]);
+        assert!(cache.own_member(GuildId(2)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn clear_guild() {
+        use crate::shard::dispatch::{MessageCreate, ThreadMemberUpdate};
+
+        fn guild_create(id: u64, channel: u64) -> GuildCreate {
+            serde_json::from_str(&guild(
+                id,
+                "[]",
+                &format!(r#"[{{ "id": "{channel}", "type": 0, "name": "general", "position": 0, "permission_overwrites": [] }}]"#),
+                r#"[{ "user": { "id": "11", "username": "mason", "discriminator": "0", "avatar": null }, "roles": [], "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false }]"#,
+            )).unwrap()
+        }
+        fn message_create(id: u64, channel: u64) -> MessageCreate {
+            serde_json::from_str(&message(id, channel)).unwrap()
+        }
+        fn thread_member_update(thread: u64, guild: u64) -> ThreadMemberUpdate {
+            serde_json::from_str(&format!(
+                r#"{{ "id": "{thread}", "user_id": "11", "join_timestamp": "2022-01-09T17:27:07.299Z", "flags": 0, "guild_id": "{guild}" }}"#
+            )).unwrap()
+        }
+
+        let cache = Cache::default();
+        guild_create(1, 20).update(&cache).await;
+        guild_create(2, 30).update(&cache).await;
+        message_create(3, 20).update(&cache).await;
+        message_create(4, 30).update(&cache).await;
+        // threads that aren't cached
+        thread_member_update(70, 1).update(&cache).await;
+        thread_member_update(71, 2).update(&cache).await;
+
+        cache.clear_guild(GuildId(1)).await;
+        assert!(cache.guild(GuildId(1)).await.is_none());
+        assert!(cache.channel(ChannelId(20)).await.is_none());
+        assert!(!cache.channel_types.read().await.contains_key(&ChannelId(20)));
+        assert!(cache.member(GuildId(1), UserId(11)).await.is_none());
+        assert!(cache.message(MessageId(3)).await.is_none());
+        assert!(cache.thread_members(ChannelId(70)).await.is_empty());
+
+        assert!(cache.guild(GuildId(2)).await.is_some());
+        assert!(cache.text_channel(ChannelId(30)).await.is_some());
+        assert!(cache.member(GuildId(2), UserId(11)).await.is_some());
+        assert!(cache.message(MessageId(4)).await.is_some());
+        assert_eq!(cache.thread_members(ChannelId(71)).await.len(), 1);
+        assert!(cache.user(UserId(11)).await.is_some());
+
+        cache.clear().await;
+        assert!(cache.guild(GuildId(2)).await.is_none());
+        assert!(cache.channel(ChannelId(30)).await.is_none());
+        assert!(cache.members.read().await.is_empty());
+        assert!(cache.message(MessageId(4)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn member_update_timeout() {
+        use crate::shard::dispatch::GuildMemberUpdate;
+
+        let cache = Cache::default();
+        let member: GuildMember = serde_json::from_str(r#"{
+          "user": { "id": "11", "username": "mason", "discriminator": "0", "avatar": null },
+          "roles": [],
+          "joined_at": "2017-07-11T17:27:07.299Z",
+          "deaf": false,
+          "mute": false
+        }"#).unwrap();
+        cache.members.write().await
+            .entry(UserId(11))
+            .or_default()
+            .insert(GuildId(1), member);
+        assert!(!cache.member(GuildId(1), UserId(11)).await.unwrap().is_timed_out());
+
+        let update: GuildMemberUpdate = serde_json::from_str(r#"{
+          "guild_id": "1",
+          "roles": [],
+          "user": { "id": "11", "username": "mason", "discriminator": "0", "avatar": null },
+          "nick": null,
+          "joined_at": "2017-07-11T17:27:07.299Z",
+          "premium_since": null,
+          "communication_disabled_until": "9999-01-01T00:00:00+00:00"
+        }"#).unwrap();
+        update.update(&cache).await;
+        assert!(cache.member(GuildId(1), UserId(11)).await.unwrap().is_timed_out());
+    }
+
+    #[tokio::test]
+    async fn typed_guild_channels() {
+        let cache = Cache::default();
+        let guild_create: GuildCreate = serde_json::from_str(&guild(1, "[]", r#"[
+            { "id": "2", "type": 4, "name": "Text Channels", "position": 0, "permission_overwrites": [] },
+            { "id": "3", "type": 4, "name": "Voice Channels", "position": 1, "permission_overwrites": [] },
+            { "id": "4", "type": 0, "name": "general", "position": 0, "permission_overwrites": [], "parent_id": "2" },
+            { "id": "5", "type": 0, "name": "off-topic", "position": 1, "permission_overwrites": [], "parent_id": null },
+            { "id": "6", "type": 2, "name": "General", "position": 0, "permission_overwrites": [], "parent_id": "3", "bitrate": 64000, "user_limit": 0 },
+            { "id": "7", "type": 5, "name": "announcements", "position": 2, "permission_overwrites": [], "parent_id": "2" }
+        ]"#, "[]")).unwrap();
+        guild_create.update(&cache).await;
+
+        use itertools::Itertools;
+
+        let ids = |ids: Vec<ChannelId>| ids.into_iter().sorted().collect_vec();
+        let text = cache.text_channels(GuildId(1)).await;
+        assert_eq!(ids(text.iter().map(Id::id).collect()), [ChannelId(4), ChannelId(5)]);
+        let voice = cache.voice_channels(GuildId(1)).await;
+        assert_eq!(ids(voice.iter().map(Id::id).collect()), [ChannelId(6)]);
+        let categories = cache.categories(GuildId(1)).await;
+        assert_eq!(ids(categories.iter().map(Id::id).collect()), [ChannelId(2), ChannelId(3)]);
+
+        let in_text = cache.channels_in_category(GuildId(1), ChannelId(2)).await;
+        assert_eq!(ids(in_text.iter().map(Id::id).collect()), [ChannelId(4), ChannelId(7)]);
+        let in_voice = cache.channels_in_category(GuildId(1), ChannelId(3)).await;
+        assert_eq!(ids(in_voice.iter().map(Id::id).collect()), [ChannelId(6)]);
+
+        assert!(cache.text_channels(GuildId(2)).await.is_empty());
+        assert!(cache.channels_in_category(GuildId(1), ChannelId(4)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn channel_of_every_type() {
+        use crate::shard::dispatch::{ThreadCreate, ThreadDelete};
+
+        let cache = Cache::default();
+        let channels = [
+            r#"{ "id": "2", "type": 2, "guild_id": "1", "name": "General", "position": 0, "permission_overwrites": [], "parent_id": null, "bitrate": 64000, "user_limit": 0, "rtc_region": null }"#,
+            r#"{ "id": "3", "type": 13, "guild_id": "1", "name": "Stage", "position": 1, "permission_overwrites": [], "parent_id": null, "bitrate": 64000, "user_limit": 0, "rtc_region": null }"#,
+            r#"{ "id": "4", "type": 14, "guild_id": "1", "name": "Directory", "position": 2, "permission_overwrites": [], "parent_id": null }"#,
+        ];
+        for channel in channels {
+            let create: ChannelCreate = serde_json::from_str(channel).unwrap();
+            create.update(&cache).await;
+        }
+        assert!(matches!(cache.channel(ChannelId(2)).await, Some(Channel::Voice(_))));
+        assert!(matches!(cache.channel(ChannelId(3)).await, Some(Channel::GuildStageVoice(_))));
+        assert!(matches!(cache.channel(ChannelId(4)).await, Some(Channel::GuildDirectory(_))));
+
+        let thread = |id: u64, kind: u8| -> ThreadCreate {
+            serde_json::from_str(&format!(r#"{{
+              "id": "{id}",
+              "type": {kind},
+              "guild_id": "1",
+              "parent_id": "5",
+              "owner_id": "10",
+              "name": "thread",
+              "last_message_id": null,
+              "message_count": 0,
+              "member_count": 1,
+              "total_message_sent": 0,
+              "thread_metadata": {{
+                "archived": false,
+                "auto_archive_duration": 1440,
+                "archive_timestamp": "2022-01-09T17:27:07.299Z",
+                "locked": false
+              }}
+            }}"#)).unwrap()
+        };
+        thread(10, 10).update(&cache).await;
+        thread(11, 11).update(&cache).await;
+        thread(12, 12).update(&cache).await;
+        assert!(matches!(cache.channel(ChannelId(10)).await, Some(Channel::AnnouncementThread(_))));
+        assert!(matches!(cache.channel(ChannelId(11)).await, Some(Channel::PublicThread(_))));
+        assert!(matches!(cache.channel(ChannelId(12)).await, Some(Channel::PrivateThread(_))));
+
+        let delete: ThreadDelete = serde_json::from_str(
+            r#"{ "id": "11", "guild_id": "1", "parent_id": "5", "type": 11 }"#
+        ).unwrap();
+        delete.update(&cache).await;
+        assert!(cache.channel(ChannelId(11)).await.is_none());
+
+        let delete: ChannelDelete = serde_json::from_str(channels[0]).unwrap();
+        delete.update(&cache).await;
+        assert!(cache.channel(ChannelId(2)).await.is_none());
+
+        cache.clear_guild(GuildId(1)).await;
+        for id in [3, 4, 10, 12] {
+            assert!(cache.channel(ChannelId(id)).await.is_none(), "{id} is still cached");
+        }
+    }
+
+    #[tokio::test]
+    async fn interaction_resolved_users() {
+        use crate::shard::dispatch::InteractionCreate;
+
+        let cache = Cache::default();
+        let interaction: InteractionCreate = serde_json::from_str(r#"{
+          "application_id": "775799577604522054",
+          "channel_id": "772908445358620702",
+          "data": {
+            "id": "866818195033292850",
+            "name": "context-menu-user-2",
+            "resolved": {
+              "members": {
+                "809850198683418695": {
+                  "avatar": null,
+                  "joined_at": "2021-02-12T18:25:07.972000+00:00",
+                  "nick": "demo",
+                  "pending": false,
+                  "permissions": "246997699136",
+                  "premium_since": null,
+                  "roles": []
+                }
+              },
+              "users": {
+                "809850198683418695": {
+                  "avatar": "afc428077119df8aabbbd84b0dc90c74",
+                  "bot": true,
+                  "discriminator": "7302",
+                  "id": "809850198683418695",
+                  "public_flags": 0,
+                  "username": "VoltyDemo"
+                }
+              }
+            },
+            "target_id": "809850198683418695",
+            "type": 2
+          },
+          "guild_id": "772904309264089089",
+          "id": "867794291820986368",
+          "member": {
+            "avatar": null,
+            "deaf": false,
+            "joined_at": "2020-11-02T20:46:57.364000+00:00",
+            "mute": false,
+            "nick": null,
+            "pending": false,
+            "premium_since": null,
+            "roles": [],
+            "user": { "avatar": null, "discriminator": "6538", "id": "167348773423415296", "username": "ian" }
+          },
+          "token": "UNIQUE_TOKEN",
+          "type": 2,
+          "version": 1
+        }"#).unwrap();
+        interaction.update(&cache).await;
+
+        let user = cache.user(UserId(809850198683418695)).await.unwrap();
+        assert_eq!(user.username, "VoltyDemo");
+        // resolved members are partial, so they aren't cached on their own
+        assert!(cache.member(GuildId(772904309264089089), UserId(809850198683418695)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn interaction_response_ttl() {
+        let cache = Cache::default();
+        let message: Message = serde_json::from_str(&message(3, 2)).unwrap();
+        let old = InteractionId::from(Utc::now() - chrono::Duration::minutes(20));
+        let recent = InteractionId::from(Utc::now() - chrono::Duration::minutes(5));
+
+        // expired responses aren't returned, even before they're evicted
+        cache.interaction_responses.write().await.insert(old, message.clone());
+        assert!(cache.interaction_response(old).await.is_none());
+
+        // evicted when another response is inserted
+        cache.insert_interaction_response(recent, message).await;
+        assert!(!cache.interaction_responses.read().await.contains_key(&old));
+        assert!(cache.interaction_response(recent).await.is_some());
+    }
+}
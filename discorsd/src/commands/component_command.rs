@@ -73,17 +73,128 @@ impl<M: MenuCommand> MenuCommandRaw for M
             data: MenuSelectData {
                 custom_id: data.custom_id,
                 resolved: data.resolved,
+                message: data.message,
             },
             channel,
             source,
             token,
             _priv,
         };
-        let data = data.values.into_iter()
-            .map(|string| string.parse())
-            // todo handle errors better maybe
-            .map(Result::unwrap)
-            .collect();
+        // todo handle errors better maybe
+        let data = M::Data::from_values(data.values).unwrap();
         M::run(self, state, interaction, data).await
     }
 }
+
+#[cfg(test)]
+mod component_command_tests {
+    use crate::http::DiscordClient;
+    use crate::model::components::ComponentId;
+
+    use super::*;
+
+    struct TestBot;
+
+    impl Bot for TestBot {
+        type Error = std::convert::Infallible;
+
+        fn token(&self) -> String { String::new() }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Confirm(&'static str);
+
+    #[async_trait]
+    impl ButtonCommand for Confirm {
+        type Bot = TestBot;
+
+        async fn run(&self,
+                     _: Arc<BotState<TestBot>>,
+                     interaction: InteractionUse<ButtonPressData, Unused>,
+        ) -> Result<InteractionUse<ButtonPressData, Used>, BotError<std::convert::Infallible>> {
+            Ok(interaction.into())
+        }
+    }
+
+    #[test]
+    fn namespaced_buttons() {
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), TestBot);
+        state.register_button_namespace("polls", Confirm("polls"));
+        state.register_button_namespace("roles", Confirm("roles"));
+        let command = |id: &ComponentId| state.button_command(id)
+            .map(|command| command.downcast_ref::<Confirm>().unwrap().clone());
+
+        // the same local id goes to the command that owns its namespace
+        let polls = ComponentId::namespaced("polls", "confirm").unwrap();
+        let roles = ComponentId::namespaced("roles", "confirm").unwrap();
+        assert_eq!(command(&polls), Some(Confirm("polls")));
+        assert_eq!(command(&roles), Some(Confirm("roles")));
+        assert_eq!(command(&ComponentId::namespaced("games", "confirm").unwrap()), None);
+        assert_eq!(command(&"confirm".into()), None);
+
+        // individually registered buttons still work
+        let mut button = crate::model::components::make_button(|_| {});
+        state.register_button(&mut button, Box::new(Confirm("single")));
+        let json = serde_json::to_value(&button).unwrap();
+        let id = ComponentId::from(json["custom_id"].as_str().unwrap());
+        assert_eq!(id.namespace(), None);
+        assert_eq!(command(&id), Some(Confirm("single")));
+    }
+
+    #[derive(Clone)]
+    struct SourceMessage(tokio::sync::mpsc::UnboundedSender<Option<crate::model::ids::MessageId>>);
+
+    #[async_trait]
+    impl ButtonCommand for SourceMessage {
+        type Bot = TestBot;
+
+        async fn run(&self,
+                     _: Arc<BotState<TestBot>>,
+                     interaction: InteractionUse<ButtonPressData, Unused>,
+        ) -> Result<InteractionUse<ButtonPressData, Used>, BotError<std::convert::Infallible>> {
+            self.0.send(interaction.source_message_id()).unwrap();
+            Ok(interaction.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn button_source_message() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), TestBot);
+        state.register_button_namespace("polls", SourceMessage(tx));
+        let interaction = serde_json::from_str(r#"{
+            "version": 1,
+            "type": 3,
+            "token": "unique_interaction_token",
+            "id": "846462639134605312",
+            "application_id": "290926444748734465",
+            "channel_id": "345626669114982402",
+            "user": { "id": "53908232506183680", "username": "Mason", "discriminator": "1337", "avatar": null },
+            "message": {
+                "type": 0,
+                "tts": false,
+                "timestamp": "2021-05-19T02:12:51.710000+00:00",
+                "pinned": false,
+                "mentions": [],
+                "mention_roles": [],
+                "mention_everyone": false,
+                "id": "844397162624450620",
+                "flags": 0,
+                "embeds": [],
+                "edited_timestamp": null,
+                "content": "Vote!",
+                "components": [{
+                    "type": 1,
+                    "components": [{ "type": 2, "label": "Yes", "style": 1, "custom_id": "polls:yes" }]
+                }],
+                "channel_id": "345626669114982402",
+                "author": { "id": "290926444748734465", "username": "bot", "discriminator": "0", "avatar": null, "bot": true },
+                "attachments": []
+            },
+            "data": { "custom_id": "polls:yes", "component_type": 2 }
+        }"#).unwrap();
+        <TestBot as crate::BotExt>::handle_interaction(interaction, Arc::new(state)).await.unwrap();
+
+        assert_eq!(rx.recv().await, Some(Some(crate::model::ids::MessageId(844_397_162_624_450_620))));
+    }
+}
@@ -0,0 +1,132 @@
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{Bot, BotState};
+use crate::model::interaction::Interaction;
+use crate::model::interaction_response::InteractionResponse;
+
+/// Runs before every interaction is dispatched to its command, for concerns shared by many
+/// commands like permission checks, logging, or cooldowns. Added with
+/// [`BotState::add_middleware`], and run by [`BotExt::handle_interaction`](crate::BotExt::handle_interaction)
+/// in the order they were added.
+#[async_trait]
+pub trait InteractionMiddleware: Send + Sync {
+    type Bot: Bot;
+
+    /// Called with each `interaction` before the next middleware, and then its command.
+    ///
+    /// Return [`ControlFlow::Continue`] to let the interaction through, or [`ControlFlow::Break`] to
+    /// stop it from reaching any later middleware or its command. Breaking with `Some(response)`
+    /// responds to the interaction with `response`, for example to tell the user why it was denied.
+    async fn before(
+        &self,
+        interaction: &Interaction,
+        state: &Arc<BotState<Self::Bot>>,
+    ) -> ControlFlow<Option<InteractionResponse>>;
+}
+
+#[cfg(test)]
+mod middleware_tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    use crate::commands::{ButtonCommand, InteractionUse, Unused, Used};
+    use crate::errors::BotError;
+    use crate::http::DiscordClient;
+    use crate::model::ids::UserId;
+    use crate::model::interaction::{ButtonPressData, InteractionUser};
+
+    use super::*;
+
+    struct TestBot;
+
+    impl Bot for TestBot {
+        type Error = std::convert::Infallible;
+
+        fn token(&self) -> String { String::new() }
+    }
+
+    #[derive(Clone)]
+    struct Count(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl ButtonCommand for Count {
+        type Bot = TestBot;
+
+        async fn run(&self,
+                     _: Arc<BotState<TestBot>>,
+                     interaction: InteractionUse<ButtonPressData, Unused>,
+        ) -> Result<InteractionUse<ButtonPressData, Used>, BotError<std::convert::Infallible>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(interaction.into())
+        }
+    }
+
+    /// Stops a user from using any interaction more than once per `cooldown`.
+    struct Cooldown {
+        cooldown: Duration,
+        last_use: std::sync::Mutex<HashMap<UserId, Instant>>,
+    }
+
+    #[async_trait]
+    impl InteractionMiddleware for Cooldown {
+        type Bot = TestBot;
+
+        async fn before(&self, interaction: &Interaction, _: &Arc<BotState<TestBot>>) -> ControlFlow<Option<InteractionResponse>> {
+            let Interaction::MessageComponent(data) = interaction else { return ControlFlow::Continue(()) };
+            let user = match &data.user {
+                InteractionUser::Guild(guild) => guild.member.user.id,
+                InteractionUser::Dm(dm) => dm.user.id,
+            };
+            let now = Instant::now();
+            let mut last_use = self.last_use.lock().unwrap();
+            match last_use.insert(user, now) {
+                Some(last) if now.duration_since(last) < self.cooldown => ControlFlow::Break(None),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+    }
+
+    /// Counts how many interactions make it past the middleware before it.
+    struct Passed(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl InteractionMiddleware for Passed {
+        type Bot = TestBot;
+
+        async fn before(&self, _: &Interaction, _: &Arc<BotState<TestBot>>) -> ControlFlow<Option<InteractionResponse>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[tokio::test]
+    async fn cooldown_blocks_rapid_use() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let passed = Arc::new(AtomicUsize::new(0));
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), TestBot);
+        state.register_button_namespace("counter", Count(Arc::clone(&runs)));
+        state.add_middleware(Cooldown { cooldown: Duration::from_secs(60), last_use: Default::default() });
+        state.add_middleware(Passed(Arc::clone(&passed)));
+        let state = Arc::new(state);
+
+        let press = |id: &str| serde_json::from_str::<Interaction>(&format!(r#"{{
+            "version": 1,
+            "type": 3,
+            "token": "unique_interaction_token",
+            "id": "{id}",
+            "application_id": "290926444748734465",
+            "channel_id": "345626669114982402",
+            "user": {{ "id": "53908232506183680", "username": "Mason", "discriminator": "1337", "avatar": null }},
+            "data": {{ "custom_id": "counter:add", "component_type": 2 }}
+        }}"#)).unwrap();
+        <TestBot as crate::BotExt>::handle_interaction(press("846462639134605312"), Arc::clone(&state)).await.unwrap();
+        <TestBot as crate::BotExt>::handle_interaction(press("846462639134605313"), Arc::clone(&state)).await.unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(passed.load(Ordering::SeqCst), 1);
+    }
+}
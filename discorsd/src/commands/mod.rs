@@ -7,9 +7,11 @@ pub mod reaction_command;
 pub mod modal_command;
 pub mod app_command;
 pub mod component_command;
+pub mod middleware;
 
 pub use slash_command::*;
 pub use reaction_command::*;
 pub use modal_command::*;
 pub use app_command::*;
-pub use component_command::*;
\ No newline at end of file
+pub use component_command::*;
+pub use middleware::*;
\ No newline at end of file
@@ -5,6 +5,7 @@ use dyn_clone::DynClone;
 use std::sync::Arc;
 use crate::{Bot, BotState};
 use crate::errors::BotError;
+use crate::model::ids::MessageId;
 use crate::shard::dispatch::ReactionUpdate;
 
 /// Allow your bot to respond to reactions.
@@ -14,6 +15,10 @@ pub trait ReactionCommand: Send + Sync + Debug + Downcast + DynClone {
 
     fn applies(&self, reaction: &ReactionUpdate) -> bool;
 
+    /// The message this command handles reactions on, if it only applies to one message. If so, it
+    /// is removed from [`BotState::reaction_commands`] when that message is deleted.
+    fn message(&self) -> Option<MessageId> { None }
+
     async fn run(&self,
                  state: Arc<BotState<Self::Bot>>,
                  reaction: ReactionUpdate,
@@ -1,6 +1,8 @@
+use std::any::TypeId;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use async_trait::async_trait;
 use downcast_rs::{Downcast, impl_downcast};
@@ -9,11 +11,12 @@ use dyn_clone::DynClone;
 use crate::{Bot, BotState};
 use crate::errors::{BotError, CommandParseErrorInfo};
 use crate::http::ClientResult;
-use crate::model::command::{ApplicationCommand, Command, CommandOption};
+use crate::model::command::{ApplicationCommand, ApplicationIntegrationType, Command, CommandOption, InteractionContextType};
 use crate::model::guild::GuildId;
 use crate::model::ids::CommandId;
-use crate::model::interaction::InteractionOption;
+use crate::model::interaction::{GuildUser, InteractionOption, InteractionUser};
 use crate::model::interaction_response::ephemeral;
+use crate::model::permissions::Permissions;
 
 pub use crate::model::commands::*;
 
@@ -92,6 +95,12 @@ pub trait SlashCommand: Sized + Send + Sync + Debug + Downcast + DynClone + Slas
     /// The name that this command is invoked in Discord with.
     const NAME: &'static str;
 
+    /// Whether the [`Command`] this builds depends on the command's state, such as if it is updated
+    /// with [`edit_command`](SlashCommandExt::edit_command). Defaults to `false`, in which case
+    /// [`command`](SlashCommandRaw::command) is only built the first time it's called for this type
+    /// of command, and then reused. Set this to `true` to build it every time instead.
+    const DYNAMIC: bool = false;
+
     /// The description of this command that is displayed in the Command picker in Discord.
     ///
     /// `Cow<'static, str>` implements both `From<'static str>` and `From<String>`, you will
@@ -101,6 +110,31 @@ pub trait SlashCommand: Sized + Send + Sync + Debug + Downcast + DynClone + Slas
     /// All members of a guild this command is in are able to use it. Defaults to `true`.
     fn default_permissions(&self) -> bool { true }
 
+    /// The permissions a member must have in the guild to run this command. If they are missing
+    /// any of these, they are told so in an ephemeral response and [`run`](Self::run) is not
+    /// called. If any permissions are required, the command can't be used in dms.
+    ///
+    /// Unlike setting the command's permissions in Discord, this is checked by your bot every time
+    /// the command is used, so it can't be overridden by a guild's settings. Defaults to no
+    /// permissions.
+    fn required_permissions(&self) -> Permissions { Permissions::empty() }
+
+    /// Whether this command is age-restricted, so that it is only shown to users who have verified
+    /// their age. Defaults to whether [`Data`](Self::Data) was derived with `#[command(nsfw)]`.
+    fn nsfw(&self) -> bool { <Self::Data as CommandData<Self>>::NSFW }
+
+    /// Where this command can be installed, or `None` to use the app's default. Defaults to the
+    /// `#[command(integration_types(...))]` that [`Data`](Self::Data) was derived with.
+    fn integration_types(&self) -> Option<Vec<ApplicationIntegrationType>> {
+        <Self::Data as CommandData<Self>>::INTEGRATION_TYPES.map(<[_]>::to_vec)
+    }
+
+    /// Where this command can be used, or `None` to allow it everywhere. Defaults to the
+    /// `#[command(contexts(...))]` that [`Data`](Self::Data) was derived with.
+    fn contexts(&self) -> Option<Vec<InteractionContextType>> {
+        <Self::Data as CommandData<Self>>::CONTEXTS.map(<[_]>::to_vec)
+    }
+
     // todo should this be a method??? or just invoked in the impl of SCR?
     /// The structure of the command sent to Discord. By default, uses [`Data`](Self::Data)'s impl
     /// of [`CommandData::make_args`](CommandData::make_args), but can be overridden. Note: if you
@@ -130,13 +164,21 @@ impl<SC: SlashCommand> SlashCommandRaw for SC
         Self::NAME
     }
 
-    fn command(&self) -> Command {
-        Command::slash_command(
-            Self::NAME,
-            self.description(),
-            self.options(),
-            // self.default_permissions(),
-        )
+    fn command(&self) -> Cow<'_, Command> {
+        /// The [`Command`] of every non-[`DYNAMIC`](SlashCommand::DYNAMIC) command type
+        static COMMANDS: RwLock<BTreeMap<TypeId, &'static Command>> = RwLock::new(BTreeMap::new());
+
+        if Self::DYNAMIC { return Cow::Owned(build_command(self)) }
+        let id = TypeId::of::<Self>();
+        if let Some(command) = COMMANDS.read().unwrap().get(&id) {
+            return Cow::Borrowed(*command);
+        }
+        // build it outside the lock, so that building can't block on (or poison) the cache
+        let command = build_command(self);
+        Cow::Borrowed(*COMMANDS.write()
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| Box::leak(Box::new(command))))
     }
 
     async fn run(&self,
@@ -144,8 +186,15 @@ impl<SC: SlashCommand> SlashCommandRaw for SC
                  interaction: InteractionUse<AppCommandData, Unused>,
                  data: InteractionOption,
     ) -> Result<InteractionUse<AppCommandData, Used>, BotError<<Self::Bot as Bot>::Error>> {
+        let required = self.required_permissions();
+        if let Some(missing) = missing_permissions(&state, &interaction.source, required).await {
+            return interaction.respond(
+                state,
+                ephemeral(format!("You need the {missing:?} permissions to use this command")),
+            ).await.map_err(Into::into);
+        }
         match <<Self as SlashCommand>::Data as CommandData<Self>>::Options::from_data_option(data) {
-            Ok(options) => match <Self as SlashCommand>::Data::from_options(options) {
+            Ok(options) => match <Self as SlashCommand>::Data::from_options(self, options) {
                 Ok(data) => {
                     let self_use = SlashCommand::run(self, Arc::clone(&state), interaction, data).await?;
                     self_use.finalize(&state).await.map_err(Into::into)
@@ -179,6 +228,37 @@ impl<SC: SlashCommand> SlashCommandRaw for SC
     }
 }
 
+fn build_command<SC: SlashCommand>(command: &SC) -> Command {
+    let mut built = Command::slash_command(
+        SC::NAME,
+        command.description(),
+        command.options(),
+        // command.default_permissions(),
+    );
+    built.set_nsfw(command.nsfw());
+    built.set_integration_types(command.integration_types());
+    built.set_contexts(command.contexts());
+    built
+}
+
+/// The permissions in `required` that `source` doesn't have in its guild, or `None` if it has all
+/// of them. Users in dms have no permissions.
+pub(crate) async fn missing_permissions<B: Send + Sync>(
+    state: &BotState<B>,
+    source: &InteractionUser,
+    required: Permissions,
+) -> Option<Permissions> {
+    if required.is_empty() { return None; }
+
+    let permissions = match source {
+        InteractionUser::Guild(GuildUser { id, member, .. }) => member.permissions(&state.cache, *id).await
+            .unwrap_or_else(Permissions::empty),
+        InteractionUser::Dm(_) => Permissions::empty(),
+    };
+    let missing = required - permissions;
+    (!missing.is_empty()).then_some(missing)
+}
+
 /// The lower level Slash Command trait. You should always prefer to implement [SlashCommand]
 /// instead of this.
 ///
@@ -197,7 +277,8 @@ pub trait SlashCommandRaw: Send + Sync + Debug + Downcast + DynClone {
 
     fn name(&self) -> &'static str;
 
-    fn command(&self) -> Command;
+    /// The structure of the command sent to Discord, which is borrowed if it's only built once.
+    fn command(&self) -> Cow<'_, Command>;
 
     async fn run(&self,
                  state: Arc<BotState<Self::Bot>>,
@@ -228,8 +309,8 @@ pub trait SlashCommandExt: SlashCommandRaw {
         where B: 'static + Send + Sync,
               State: AsRef<BotState<B>> + Send,
     {
-        match self.command() {
-            Command::SlashCommand { name, name_localizations, description, description_localizations, options } => {
+        match self.command().into_owned() {
+            Command::SlashCommand { description, options, .. } => {
                 let state = state.as_ref();
                 state.client.edit_guild_command(
                     state.application_id(),
@@ -251,3 +332,98 @@ pub trait SlashCommandExt: SlashCommandRaw {
 
 #[async_trait]
 impl<C: SlashCommandRaw> SlashCommandExt for C {}
+
+#[cfg(test)]
+mod slash_command_tests {
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::http::DiscordClient;
+    use crate::model::guild::{Guild, GuildMember};
+    use crate::model::interaction::DmUser;
+
+    use super::*;
+
+    fn member(id: u64, roles: &str) -> GuildMember {
+        serde_json::from_str(&format!(r#"{{
+            "user": {{ "id": "{id}", "username": "user{id}", "discriminator": "0", "avatar": null }},
+            "nick": null, "roles": {roles}, "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false
+        }}"#)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn required_permissions() {
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), ());
+        let guild: Guild = serde_json::from_str(&crate::test_fixtures::guild(1, r#"[
+            { "id": "1", "name": "@everyone", "permissions": "1024", "position": 0, "color": 0, "hoist": false, "managed": false, "mentionable": false },
+            { "id": "2", "name": "Mods", "permissions": "32", "position": 1, "color": 0, "hoist": false, "managed": false, "mentionable": false }
+        ]"#, "[]", "[]")).unwrap();
+        state.cache.guilds.write().await.insert(guild);
+
+        let in_guild = |member| InteractionUser::Guild(GuildUser { id: GuildId(1), member, locale: None });
+        let unauthorized = in_guild(member(11, "[]"));
+        let authorized = in_guild(member(12, r#"["2"]"#));
+
+        assert_eq!(missing_permissions(&state, &unauthorized, Permissions::empty()).await, None);
+        assert_eq!(missing_permissions(&state, &unauthorized, Permissions::VIEW_CHANNEL).await, None);
+        assert_eq!(
+            missing_permissions(&state, &unauthorized, Permissions::MANAGE_GUILD | Permissions::VIEW_CHANNEL).await,
+            Some(Permissions::MANAGE_GUILD),
+        );
+        assert_eq!(missing_permissions(&state, &authorized, Permissions::MANAGE_GUILD).await, None);
+
+        let dm = InteractionUser::Dm(DmUser { user: member(12, "[]").user });
+        assert_eq!(missing_permissions(&state, &dm, Permissions::MANAGE_GUILD).await, Some(Permissions::MANAGE_GUILD));
+        assert_eq!(missing_permissions(&state, &dm, Permissions::empty()).await, None);
+    }
+
+    struct TestBot;
+
+    impl Bot for TestBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+    }
+
+    /// Counts how many times its [`Command`] is built.
+    #[derive(Debug, Clone, Default)]
+    struct CountBuilds<const DYNAMIC: bool> {
+        builds: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl<const DYNAMIC: bool> SlashCommand for CountBuilds<DYNAMIC> {
+        type Bot = TestBot;
+        type Data = ();
+        type Use = Used;
+        const NAME: &'static str = "count";
+        const DYNAMIC: bool = DYNAMIC;
+
+        fn description(&self) -> Cow<'static, str> {
+            self.builds.fetch_add(1, Ordering::SeqCst);
+            "Counts its builds".into()
+        }
+
+        async fn run(&self, _: Arc<BotState<TestBot>>, interaction: InteractionUse<AppCommandData, Unused>, _: ()) -> Result<InteractionUse<AppCommandData, Used>, BotError<Infallible>> {
+            Ok(interaction.into())
+        }
+    }
+
+    #[test]
+    fn static_command_built_once() {
+        let command = CountBuilds::<false>::default();
+        let (first, second) = (command.command(), command.command());
+        assert!(matches!((&first, &second), (Cow::Borrowed(first), Cow::Borrowed(second)) if std::ptr::eq(*first, *second)));
+        assert_eq!(command.builds.load(Ordering::SeqCst), 1);
+
+        // other instances reuse it too
+        let other = CountBuilds::<false>::default();
+        assert_eq!(other.command(), first);
+        assert_eq!(other.builds.load(Ordering::SeqCst), 0);
+
+        let dynamic = CountBuilds::<true>::default();
+        assert!(matches!(dynamic.command(), Cow::Owned(_)));
+        dynamic.command();
+        assert_eq!(dynamic.builds.load(Ordering::SeqCst), 2);
+    }
+}
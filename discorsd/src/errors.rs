@@ -103,8 +103,8 @@ impl CommandParseErrorInfo {
                 )
             },
             InteractionUser::Dm(DmUser { user }) => format!(
-                "dm with `{}#{}` ({})",
-                user.username, user.discriminator, user.id
+                "dm with `{}` ({})",
+                user.tag(), user.id
             ),
         };
         match &self.source {
@@ -160,6 +160,8 @@ pub enum CommandParseError {
     /// found a single option when expecting to find a vararg
     UnexpectedSingleOption(String, usize),
     MissingOption(String),
+    /// the option named `0` was rejected by its `validate` function with the message `1`
+    InvalidOption(String, String),
     /// Command named `String` didn't have a subcommand option
     NoSubtype(String),
     /// InteractionDataOption::Group(_) when parsing data for an struct
@@ -7,17 +7,22 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::fmt::Debug;
+use std::future::Future;
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::str::FromStr;
+use std::task::{Context, Poll};
 
 use async_trait::async_trait;
 use chrono::{DateTime, TimeZone, Utc};
+use futures::{Stream, StreamExt, TryStreamExt};
+use futures::stream::{self, BoxStream};
 use reqwest::{IntoUrl, Url};
 use reqwest::multipart::{Form, Part};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
-use serde_derive::Serialize;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::BotState;
 use crate::commands::MenuData;
@@ -27,13 +32,15 @@ use crate::http::ClientResult;
 use crate::http::interaction::WebhookMessage;
 use crate::http::routes::Route::*;
 use crate::http::routes::Route;
-use crate::model::channel::{AnnouncementChannel, Channel, DmChannel, GroupDmChannel, TextChannel};
+use crate::model::channel::{AnnouncementChannel, Channel, DmChannel, GroupDmChannel, TextChannel, ThreadArchiveDuration};
 use crate::model::components::{ActionRow, Button, Component, Menu};
 use crate::model::emoji::Emoji;
 use crate::model::ids::*;
+use crate::model::DiscordErrorType;
 use crate::model::interaction_response::InteractionResponse;
 use crate::model::message::*;
 use crate::model::permissions::Permissions;
+use crate::model::poll::CreatePoll;
 use crate::model::user::User;
 
 /// Channel related http requests
@@ -72,6 +79,24 @@ impl DiscordClient {
         self.get_query(GetChannelMessages(channel), get).await
     }
 
+    /// Streams the messages in `channel` from newest to oldest, starting before `before` if it is
+    /// given. Messages are fetched in pages of 100 as the stream is polled, so combinators like
+    /// [`take`](StreamExt::take) and [`try_take_while`](TryStreamExt::try_take_while) only fetch
+    /// the pages they need. Each page goes through the client's rate limiter like any other
+    /// request, so paging quickly waits out the rate limit instead of getting 429s.
+    ///
+    /// The stream ends after the first error, such as if the http request for a page fails.
+    pub fn message_history(&self, channel: ChannelId, before: Option<MessageId>) -> MessageHistory<'_> {
+        MessageHistory::new(before, move |before| {
+            let get = GetMessages::new().limit(MessageHistory::PAGE_SIZE);
+            let get = match before {
+                Some(before) => get.before(before),
+                None => get,
+            };
+            self.get_messages(channel, get)
+        })
+    }
+
     /// Returns a specific message in the channel. If operating on a guild channel, this endpoint
     /// requires the user to have the [`VIEW_CHANNEL`](Permissions::VIEW_CHANNEL) and
     /// [`READ_MESSAGE_HISTORY`](Permissions::READ_MESSAGE_HISTORY) permission. If the channel is a
@@ -90,6 +115,7 @@ impl DiscordClient {
     ///
     /// If the http request fails, or fails to deserialize the response into a `Message`.
     pub async fn create_message(&self, channel: ChannelId, message: CreateMessage) -> ClientResult<Message> {
+        message.check_limits(self.message_limits)?;
         self.send_message_with_files(PostMessage(channel), message).await
     }
 
@@ -104,6 +130,7 @@ impl DiscordClient {
     pub async fn edit_message(&self, channel: ChannelId, message: MessageId, edit: EditMessage) -> ClientResult<Message> {
         // not an error to send other flags
         // let flags = flags & MessageFlags::SUPPRESS_EMBEDS;
+        edit.check_limits(self.message_limits)?;
         self.patch(EditMessage(channel, message), edit).await
     }
 
@@ -222,6 +249,139 @@ impl DiscordClient {
     pub async fn delete_pinned_message(&self, channel: ChannelId, message: MessageId) -> ClientResult<()> {
         self.delete(UnpinMessage(channel, message)).await
     }
+
+    /// Immediately end the poll on `message`, which must have been sent by the current user.
+    ///
+    /// Fires a [`MessageUpdate`](crate::shard::dispatch::MessageUpdate) event.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails, or fails to deserialize the response into a `Message`
+    pub async fn end_poll(&self, channel: ChannelId, message: MessageId) -> ClientResult<Message> {
+        self.post(EndPoll(channel, message), "").await
+    }
+
+    /// Get the users who voted for `answer` in the poll on `message`.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails, or fails to deserialize the response into a list of users
+    pub async fn get_poll_answer_voters(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+        answer: u32,
+        get: GetAnswerVoters,
+    ) -> ClientResult<Vec<User>> {
+        #[derive(Deserialize)]
+        struct AnswerVoters {
+            users: Vec<User>,
+        }
+
+        self.get_query::<AnswerVoters, _>(GetPollAnswerVoters(channel, message, answer), get).await
+            .map(|voters| voters.users)
+    }
+
+    /// Start a new thread in a [`GuildForum`](crate::model::channel::GuildForum) channel, with
+    /// `thread.message` as its first message. Requires the `SEND_MESSAGES` permission.
+    ///
+    /// Fires a [`ChannelCreate`](crate::shard::dispatch::ChannelCreate) event for the new thread and
+    /// a [`MessageCreate`](crate::shard::dispatch::MessageCreate) event for its first message.
+    ///
+    /// # Errors
+    ///
+    /// If the message is over the [limits](MessageLimits), the http request fails, or fails to
+    /// deserialize the response into a `Channel`.
+    pub async fn start_thread_in_forum(&self, channel: ChannelId, thread: StartForumThread) -> ClientResult<Channel> {
+        thread.message.check_limits(self.message_limits)?;
+        self.send_message_with_files(StartThreadInForum(channel), thread).await
+    }
+}
+
+/// Sent to Discord to start a thread in a forum with [`DiscordClient::start_thread_in_forum`].
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct StartForumThread {
+    /// the name of the thread (1-100 characters)
+    pub name: Cow<'static, str>,
+    /// the thread will stop showing in the channel list after `auto_archive_duration` minutes of
+    /// inactivity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_archive_duration: Option<ThreadArchiveDuration>,
+    /// amount of seconds a user has to wait before sending another message (0-21600)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_user: Option<u32>,
+    /// the first message in the thread
+    pub message: CreateMessage,
+    /// the ids of the forum's [tags](crate::model::channel::Tag) to apply to the thread
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub applied_tags: Vec<TagId>,
+}
+
+impl StartForumThread {
+    /// A thread named `name` starting with `message`, with no tags applied.
+    pub fn new<S, M>(name: S, message: M) -> Self
+        where S: Into<Cow<'static, str>>,
+              M: Into<CreateMessage>,
+    {
+        Self {
+            name: name.into(),
+            auto_archive_duration: None,
+            rate_limit_per_user: None,
+            message: message.into(),
+            applied_tags: Vec::new(),
+        }
+    }
+
+    /// Apply `tag` to the thread.
+    pub fn tag(&mut self, tag: TagId) {
+        self.applied_tags.push(tag);
+    }
+}
+
+/// Channel related caching http requests
+impl<B: Send + Sync> BotState<B> {
+    /// Fetches the message with Discord's API (see [`DiscordClient::get_message`]), then caches it
+    /// for future reference before returning it. Useful for messages sent before the bot started,
+    /// such as one set up for reaction roles.
+    ///
+    /// Returns `Ok(None)` if the message doesn't exist, removing it from the cache if it was still
+    /// cached.
+    pub async fn cache_message(&self, channel: ChannelId, message: MessageId) -> ClientResult<Option<Message>> {
+        let fetched = self.client.get_message(channel, message).await;
+        self.cache_fetched_message(message, fetched).await
+    }
+
+    /// Get the message from the cache, or if it isn't cached (because it was sent before the bot
+    /// started, or was evicted), [fetch and cache it](Self::cache_message).
+    ///
+    /// Returns `Ok(None)` if the message isn't cached and doesn't exist.
+    pub async fn message_or_fetch(&self, channel: ChannelId, message: MessageId) -> ClientResult<Option<Message>> {
+        self.message_or_else(message, || self.client.get_message(channel, message)).await
+    }
+
+    async fn message_or_else<F, Fut>(&self, id: MessageId, fetch: F) -> ClientResult<Option<Message>>
+        where F: FnOnce() -> Fut + Send,
+              Fut: Future<Output=ClientResult<Message>> + Send,
+    {
+        if let Some(message) = self.cache.message(id).await {
+            return Ok(Some(message));
+        }
+        self.cache_fetched_message(id, fetch().await).await
+    }
+
+    async fn cache_fetched_message(&self, id: MessageId, fetched: ClientResult<Message>) -> ClientResult<Option<Message>> {
+        match fetched {
+            Ok(message) => {
+                self.cache.messages.write().await.insert(message.clone());
+                Ok(Some(message))
+            }
+            Err(ClientError::Discord(e)) if e.code == DiscordErrorType::UnknownMessage => {
+                self.cache.messages.write().await.remove(id);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
 }
 
 /// A set of methods on channels that make interacting with messages in that channel easier. Most
@@ -375,6 +535,11 @@ impl ChannelMessageId {
         client.delete_pinned_message(self.channel, self.message).await
     }
 
+    /// Send a message to this message's channel, replying to this message.
+    ///
+    /// # Errors
+    ///
+    /// See [`MessageChannelExt::send`](MessageChannelExt::send)
     pub async fn reply<B, State, Msg>(&self, state: State, message: Msg) -> ClientResult<Message>
         where B: 'static + Send + Sync,
               State: AsRef<BotState<B>> + Send,
@@ -408,7 +573,7 @@ impl Message {
     /// # Errors
     ///
     /// See [`ChannelMessageId::delete`](ChannelMessageId)
-    pub async fn delete<Client: AsRef<DiscordClient> + Send>(&mut self, client: Client) -> ClientResult<()> {
+    pub async fn delete<Client: AsRef<DiscordClient> + Send>(&self, client: Client) -> ClientResult<()> {
         self.cmid().delete(client).await
     }
 
@@ -442,6 +607,11 @@ impl Message {
         self.cmid().unpin(client).await
     }
 
+    /// Send a message to this message's channel, replying to this message.
+    ///
+    /// # Errors
+    ///
+    /// See [`ChannelMessageId::reply`](ChannelMessageId)
     pub async fn reply<B, State, Msg>(&self, state: State, message: Msg) -> ClientResult<Self>
         where B: 'static + Send + Sync,
               State: AsRef<BotState<B>> + Send,
@@ -502,6 +672,104 @@ impl GetMessages {
     }
 }
 
+/// The messages in a channel, newest first, from [`DiscordClient::message_history`].
+pub struct MessageHistory<'a>(BoxStream<'a, ClientResult<Message>>);
+
+impl<'a> MessageHistory<'a> {
+    /// The most messages Discord will return at once.
+    const PAGE_SIZE: usize = 100;
+
+    /// Pages through messages with `fetch`, which gets up to [`PAGE_SIZE`](Self::PAGE_SIZE)
+    /// messages before the given message, or the most recent messages if it is `None`.
+    fn new<F, Fut>(before: Option<MessageId>, fetch: F) -> Self
+        where F: FnMut(Option<MessageId>) -> Fut + Send + 'a,
+              Fut: Future<Output=ClientResult<Vec<Message>>> + Send + 'a,
+    {
+        // `None` once there are no more pages
+        let pages = stream::try_unfold((Some(before), fetch), |(cursor, mut fetch)| async move {
+            let Some(before) = cursor else { return Ok::<_, ClientError>(None) };
+            let page = fetch(before).await?;
+            let next = page.last()
+                .filter(|_| page.len() == Self::PAGE_SIZE)
+                .map(|oldest| Some(oldest.id));
+            Ok(Some((page, (next, fetch))))
+        });
+        let messages = pages
+            .map_ok(|page| stream::iter(page).map(Ok::<_, ClientError>))
+            .try_flatten();
+        Self(messages.boxed())
+    }
+
+    /// Collects messages until one matches `predicate`, without fetching any more pages after
+    /// that. The matching message is not included, so this can be used to get every message sent
+    /// after a known message:
+    ///
+    /// ```rust,no_run
+    /// # use discorsd::http::{ClientResult, DiscordClient};
+    /// # use discorsd::model::ids::{ChannelId, MessageId};
+    /// # async fn foo(client: DiscordClient, channel: ChannelId, known: MessageId) -> ClientResult<()> {
+    /// let new_messages = client.message_history(channel, None)
+    ///     .collect_until(|message| message.id <= known)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If fetching any of the pages fails.
+    pub async fn collect_until<P>(self, mut predicate: P) -> ClientResult<Vec<Message>>
+        where P: FnMut(&Message) -> bool,
+    {
+        self.try_take_while(|message| std::future::ready(Ok(!predicate(message))))
+            .try_collect()
+            .await
+    }
+}
+
+impl Stream for MessageHistory<'_> {
+    type Item = ClientResult<Message>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+impl Debug for MessageHistory<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageHistory").finish_non_exhaustive()
+    }
+}
+
+/// Which voters to get with [`DiscordClient::get_poll_answer_voters`].
+#[derive(Serialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct GetAnswerVoters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<UserId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+}
+
+impl GetAnswerVoters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get voters after this user.
+    #[must_use]
+    pub fn after(mut self, user: UserId) -> Self {
+        self.after = Some(user);
+        self
+    }
+
+    /// Max number of voters to return (1-100). Defaults to 25.
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
 /// An attachment (often an image) on a message.
 /// Instances of this struct come from its `impl`s of `From<P>, From<(String, P)> where P: AsRef<Path>`
 /// (for sending files, with an optionally specified name) and `From<(String, Vec<u8>)>` for sending
@@ -669,9 +937,12 @@ pub struct CreateMessage {
     pub files: HashSet<MessageAttachment>,
     // todo make sure this is working
     pub attachments: Vec<AttachmentId>,
-    /// [MessageFlags] (only [MessageFlags::SUPPRESS_EMBEDS] can be set)
+    /// [MessageFlags] (only [`SETTABLE_FLAGS`](Self::SETTABLE_FLAGS) can be set)
     #[serde(skip_serializing_if = "MessageFlags::is_empty")]
     pub flags: MessageFlags,
+    /// a poll to attach to the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll: Option<CreatePoll>,
 }
 
 impl From<&'static str> for CreateMessage {
@@ -734,11 +1005,11 @@ impl From<Message> for CreateMessage {
             message_reference: message.message_reference,
             components: vec![],
             sticker_ids: message.sticker_items.into_iter().map(|s| s.id).collect(),
-            // todo just the ones that matter
-            flags: message.flags,
+            flags: message.flags & Self::SETTABLE_FLAGS,
             // todo
             attachments: vec![],
             // attachments: message.attachments.into_iter().map(|a| a.id).collect(),
+            poll: None,
         }
     }
 }
@@ -762,6 +1033,10 @@ pub fn create_message<F: FnOnce(&mut CreateMessage)>(builder: F) -> CreateMessag
 }
 
 impl CreateMessage {
+    /// The only [`MessageFlags`] that can be set when creating a message.
+    pub const SETTABLE_FLAGS: MessageFlags = MessageFlags::SUPPRESS_EMBEDS
+        .union(MessageFlags::SUPPRESS_NOTIFICATIONS);
+
     /// Easily build a message to create in some channel with [`MessageChannelExt::send`] and similar
     /// methods. Most useful for creating messages with both content and an embed, since
     /// `CreateMessage`'s `From` impls provide simpler ways to get a `CreateMessage` for just one of
@@ -823,6 +1098,71 @@ impl CreateMessage {
         self.files.insert(attachment.into());
     }
 
+    /// Check that this message's content and embeds are within `limits`, so that an over-length
+    /// message can be caught before Discord rejects it.
+    ///
+    /// # Errors
+    ///
+    /// If the content or the total length of the embeds is longer than allowed by `limits`.
+    pub fn check_limits(&self, limits: MessageLimits) -> Result<(), MessageLimitError> {
+        limits.check(&self.content, &self.embeds)
+    }
+
+    /// Set this message's [flags](Self::flags).
+    ///
+    /// # Panics
+    ///
+    /// If `flags` has any flags not in [`SETTABLE_FLAGS`](Self::SETTABLE_FLAGS). See also
+    /// [`try_flags`](Self::try_flags).
+    pub fn flags(&mut self, flags: MessageFlags) {
+        self.try_flags(flags)
+            .map_err(|extra| format!("can't set {extra:?} when creating a message"))
+            .unwrap();
+    }
+
+    /// Set this message's [flags](Self::flags).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` with the flags not in [`SETTABLE_FLAGS`](Self::SETTABLE_FLAGS) if there are
+    /// any, in which case the flags aren't changed. See also [`flags`](Self::flags).
+    pub fn try_flags(&mut self, flags: MessageFlags) -> Result<(), MessageFlags> {
+        let extra = flags - Self::SETTABLE_FLAGS;
+        if extra.is_empty() {
+            self.flags = flags;
+            Ok(())
+        } else {
+            Err(extra)
+        }
+    }
+
+    /// Send this message without triggering push and desktop notifications.
+    pub fn silent(&mut self) {
+        self.flags.insert(MessageFlags::SUPPRESS_NOTIFICATIONS);
+    }
+
+    /// Don't show embeds for any links in this message.
+    pub fn suppress_embeds(&mut self) {
+        self.flags.insert(MessageFlags::SUPPRESS_EMBEDS);
+    }
+
+    /// Attach a poll asking `question` to this message, with its answers and other options set by
+    /// `builder`.
+    ///
+    /// ```rust
+    /// # use discorsd::http::channel::create_message;
+    /// create_message(|m| m.poll("Best color?", |p| {
+    ///     p.answer("Red");
+    ///     p.answer("Blue");
+    /// }));
+    /// ```
+    pub fn poll<Q, F>(&mut self, question: Q, builder: F)
+        where Q: Into<Cow<'static, str>>,
+              F: FnOnce(&mut CreatePoll),
+    {
+        self.poll = Some(CreatePoll::build(question, builder));
+    }
+
     // todo also set whether it pings the message sender or not
     /// Send this message a a reply to another message.
     pub fn reply(&mut self, message: MessageId) {
@@ -865,8 +1205,7 @@ impl CreateMessage {
               Component: From<Menu<D::Data>>,
               F: FnOnce(&mut Menu<D::Data>),
     {
-        let mut menu = Menu::<D::Data>::new();
-        menu.options = D::options();
+        let mut menu = Menu::for_data::<D>();
         builder(&mut menu);
         state.as_ref().register_menu(&mut menu, Box::new(command));
         self.components.push(ActionRow::menu(menu));
@@ -937,6 +1276,53 @@ impl From<Embed> for RichEmbed {
     }
 }
 
+/// The maximum lengths of a message, which [`DiscordClient`] checks before sending or editing any
+/// message, such as with [`DiscordClient::create_message`]. Set by [`Bot::message_limits`](crate::Bot::message_limits).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MessageLimits {
+    /// the maximum number of characters in a message's content
+    pub content: usize,
+    /// the maximum number of characters in all of a message's embeds combined
+    pub embeds: usize,
+}
+
+impl MessageLimits {
+    /// Discord's limits for bots: 2000 characters of content and 6000 characters of embeds.
+    pub const BOT: Self = Self { content: 2000, embeds: 6000 };
+
+    /// Check that a message's `content` and `embeds` are within these limits.
+    ///
+    /// # Errors
+    ///
+    /// If `content` or the total length of `embeds` is longer than allowed.
+    pub fn check(self, content: &str, embeds: &[RichEmbed]) -> Result<(), MessageLimitError> {
+        let len = content.chars().count();
+        if len > self.content {
+            return Err(MessageLimitError::Content { len, limit: self.content });
+        }
+        let len = embeds.iter().map(RichEmbed::char_count).sum();
+        if len > self.embeds {
+            return Err(MessageLimitError::Embeds { len, limit: self.embeds });
+        }
+        Ok(())
+    }
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self::BOT
+    }
+}
+
+/// A message was too long to be sent. See [`MessageLimits`].
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MessageLimitError {
+    #[error("message content is {len} characters, but at most {limit} are allowed")]
+    Content { len: usize, limit: usize },
+    #[error("message embeds are {len} characters, but at most {limit} are allowed")]
+    Embeds { len: usize, limit: usize },
+}
+
 /// Easily build an embed to be sent in a message.
 ///
 /// ```rust
@@ -1012,6 +1398,17 @@ impl RichEmbed {
         self
     }
 
+    /// The number of characters in this embed that count towards Discord's limit for the embeds in
+    /// a message: the title, description, field names and values, footer text, and author name.
+    pub fn char_count(&self) -> usize {
+        let count = |s: &str| s.chars().count();
+        self.title.as_deref().map_or(0, count)
+            + self.description.as_deref().map_or(0, count)
+            + self.fields.iter().map(|f| count(&f.name) + count(&f.value)).sum::<usize>()
+            + self.footer.as_ref().map_or(0, |f| count(&f.text))
+            + self.author.as_ref().and_then(|a| a.name.as_deref()).map_or(0, count)
+    }
+
     // todo images of each of these? maybe just one image of all of them set
     /// Set this embed's [title](Self::title).
     pub fn title<S: Into<Cow<'static, str>>>(&mut self, title: S) {
@@ -1044,8 +1441,8 @@ impl RichEmbed {
     }
 
     /// Sets this embed's [color](Self::color).
-    pub fn color(&mut self, color: Color) {
-        self.color = Some(color);
+    pub fn color<C: Into<Color>>(&mut self, color: C) {
+        self.color = Some(color.into());
     }
 
     /// Adds a footer to this embed with the specified text.
@@ -1304,6 +1701,19 @@ impl EditMessage {
     pub fn clear_embed(&mut self) {
         self.embeds = Some(vec![]);
     }
+
+    /// Check that the new content and embeds of this message are within `limits`. See
+    /// [`CreateMessage::check_limits`].
+    ///
+    /// # Errors
+    ///
+    /// If the content or the total length of the embeds is longer than allowed by `limits`.
+    pub fn check_limits(&self, limits: MessageLimits) -> Result<(), MessageLimitError> {
+        limits.check(
+            self.content.as_ref().and_then(Option::as_deref).unwrap_or_default(),
+            self.embeds.as_deref().unwrap_or_default(),
+        )
+    }
 }
 
 pub(in super) trait MessageWithFiles: Serialize {
@@ -1338,24 +1748,45 @@ impl DiscordClient {
         if files.is_empty() {
             self.post(route, message).await
         } else {
-            let files = files.into_iter()
-                .map(|MessageAttachment { name, source }|
-                    source.into_bytes().map(|contents| (name, contents))
-                )
-                .collect::<ClientResult<Vec<(String, Vec<u8>)>>>()?;
-            let make_multipart = || {
-                let mut form = files
-                    .clone()
-                    .into_iter()
-                    .map(|(name, contents)| Part::bytes(contents).file_name(name))
-                    .enumerate()
-                    .fold(Form::new(), |form, (i, part)| form.part(i.to_string(), part));
-                if message.has_other_content() {
-                    form = form.text("payload_json", serde_json::to_string(&message).ok()?);
-                }
-                Some(form)
-            };
-            self.post_multipart(route, make_multipart).await
+            let multipart = MultipartMessage::new(files, &message)?;
+            self.post_multipart(route, || Some(multipart.form())).await
+        }
+    }
+}
+
+/// The parts of a message sent with files: each file as `files[n]`, and the rest of the message
+/// as `payload_json`.
+#[derive(Debug)]
+struct MultipartMessage {
+    /// (part name, file name, contents)
+    files: Vec<(String, String, Vec<u8>)>,
+    payload_json: Option<String>,
+}
+
+impl MultipartMessage {
+    #[allow(clippy::result_large_err)]
+    fn new<M: MessageWithFiles>(files: HashSet<MessageAttachment>, message: &M) -> ClientResult<Self> {
+        let files = files.into_iter()
+            .enumerate()
+            .map(|(i, MessageAttachment { name, source })|
+                source.into_bytes().map(|contents| (format!("files[{i}]"), name, contents))
+            )
+            .collect::<ClientResult<_>>()?;
+        let payload_json = message.has_other_content()
+            .then(|| serde_json::to_string(message).ok())
+            .flatten();
+        Ok(Self { files, payload_json })
+    }
+
+    fn form(&self) -> Form {
+        let form = self.files.iter()
+            .cloned()
+            .fold(Form::new(), |form, (part, name, contents)|
+                form.part(part, Part::bytes(contents).file_name(name)),
+            );
+        match &self.payload_json {
+            Some(json) => form.text("payload_json", json.clone()),
+            None => form,
         }
     }
 }
@@ -1374,6 +1805,21 @@ impl MessageWithFiles for CreateMessage {
     }
 }
 
+impl MessageWithFiles for StartForumThread {
+    fn files(&mut self) -> Option<&mut HashSet<MessageAttachment>> {
+        self.message.files()
+    }
+
+    fn embeds(&mut self) -> Option<&mut Vec<RichEmbed>> {
+        self.message.embeds()
+    }
+
+    fn has_other_content(&self) -> bool {
+        // always has a name
+        true
+    }
+}
+
 impl MessageWithFiles for WebhookMessage {
     fn files(&mut self) -> Option<&mut HashSet<MessageAttachment>> {
         Some(&mut self.files)
@@ -1424,4 +1870,249 @@ impl MessageWithFiles for InteractionResponse {
             Self::Modal(_) => true,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod channel_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::http::test_server::timeout_client;
+    use crate::model::interaction::Token;
+    use crate::model::interaction_response::InteractionMessage;
+
+    use super::*;
+
+    #[test]
+    fn content_limit() {
+        let message = CreateMessage::from("a".repeat(2000));
+        assert_eq!(message.check_limits(MessageLimits::BOT), Ok(()));
+
+        let message = CreateMessage::from("a".repeat(2001));
+        assert_eq!(
+            message.check_limits(MessageLimits::BOT),
+            Err(MessageLimitError::Content { len: 2001, limit: 2000 }),
+        );
+        let nitro = MessageLimits { content: 4000, ..MessageLimits::BOT };
+        assert_eq!(message.check_limits(nitro), Ok(()));
+    }
+
+    #[test]
+    fn embed_limit() {
+        let message = create_message(|m| {
+            m.content("short");
+            m.embed(|e| {
+                e.title("t".repeat(256));
+                e.description("d".repeat(4096));
+                e.footer_text("f".repeat(1000));
+            });
+            m.embed(|e| e.add_field("n".repeat(200), "v".repeat(500)));
+        });
+        assert_eq!(
+            message.check_limits(MessageLimits::BOT),
+            Err(MessageLimitError::Embeds { len: 6052, limit: 6000 }),
+        );
+    }
+
+    #[tokio::test]
+    async fn sending_checks_limits() {
+        fn assert_too_long<T: Debug>(result: ClientResult<T>) {
+            assert!(matches!(
+                result,
+                Err(ClientError::MessageLimit(MessageLimitError::Content { len: 2001, limit: 2000 }))
+            ), "{result:?}");
+        }
+
+        let client = DiscordClient::builder(String::new()).build();
+        let content = || "a".repeat(2001);
+        let token = || Token("t".into());
+        assert_too_long(client.create_message(ChannelId(1), content().into()).await);
+        assert_too_long(client.edit_message(ChannelId(1), MessageId(2), content().into()).await);
+        assert_too_long(client.create_interaction_response(
+            InteractionId(3),
+            token(),
+            InteractionResponse::ChannelMessageWithSource(content().into()),
+        ).await);
+        assert_too_long(client.edit_interaction_response(ApplicationId(4), token(), content().into()).await);
+        assert_too_long(client.create_followup_message(ApplicationId(4), token(), content().into()).await);
+        assert_too_long(client.edit_followup_message(ApplicationId(4), token(), MessageId(5), content().into()).await);
+    }
+
+    fn message() -> Message {
+        serde_json::from_str(&crate::test_fixtures::message(3, 2)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn message_methods_use_message_ids() {
+        let client = timeout_client().await;
+        let mut message = message();
+
+        let result = message.edit(&client, "edited").await;
+        assert!(matches!(result, Err(ClientError::Timeout(EditMessage(ChannelId(2), MessageId(3))))));
+
+        let result = message.react(&client, '🔥').await;
+        assert!(matches!(
+            result,
+            Err(ClientError::Timeout(CreateReaction(ChannelId(2), MessageId(3), Emoji::Unicode { name })))
+                if name == "🔥"
+        ));
+
+        let result = message.delete(&client).await;
+        assert!(matches!(result, Err(ClientError::Timeout(DeleteMessage(ChannelId(2), MessageId(3))))));
+    }
+
+    #[test]
+    fn interaction_response_with_file() {
+        let mut response = InteractionResponse::ChannelMessageWithSource(InteractionMessage::build(|m| {
+            m.content("here's your chart");
+            m.attach(("chart.png", vec![1, 2, 3]));
+        }));
+        let files = response.take_files();
+        let multipart = MultipartMessage::new(files, &response).unwrap();
+
+        assert_eq!(multipart.files, [("files[0]".to_string(), "chart.png".to_string(), vec![1, 2, 3])]);
+        let payload: serde_json::Value = serde_json::from_str(multipart.payload_json.as_deref().unwrap()).unwrap();
+        assert_eq!(payload["type"], 4);
+        assert_eq!(payload["data"]["content"], "here's your chart");
+    }
+
+    #[tokio::test]
+    async fn reply_sends_to_message_channel() {
+        let state = BotState::new(timeout_client().await, ());
+        let message = message();
+
+        let result = message.reply(&state, "reply").await;
+        assert!(matches!(result, Err(ClientError::Timeout(PostMessage(ChannelId(2))))));
+    }
+
+    #[tokio::test]
+    async fn start_thread_in_forum() {
+        let mut thread = StartForumThread::new("help", "how do I do this?");
+        thread.tag(TagId(5));
+        assert_eq!(
+            serde_json::to_value(&thread).unwrap(),
+            serde_json::json!({
+                "name": "help",
+                "message": serde_json::to_value(CreateMessage::from("how do I do this?")).unwrap(),
+                "applied_tags": ["5"],
+            })
+        );
+
+        let client = timeout_client().await;
+        let result = client.start_thread_in_forum(ChannelId(1), thread).await;
+        assert!(matches!(result, Err(ClientError::Timeout(StartThreadInForum(ChannelId(1))))));
+    }
+
+    #[test]
+    fn message_flags() {
+        let message = create_message(|m| {
+            m.content("https://example.com");
+            m.silent();
+            m.suppress_embeds();
+        });
+        assert_eq!(serde_json::to_value(&message).unwrap()["flags"], (1 << 2) | (1 << 12));
+
+        let mut message = CreateMessage::from("hi");
+        assert_eq!(message.try_flags(MessageFlags::SUPPRESS_NOTIFICATIONS), Ok(()));
+        assert_eq!(serde_json::to_value(&message).unwrap()["flags"], 1 << 12);
+        assert_eq!(
+            message.try_flags(MessageFlags::EPHEMERAL | MessageFlags::SUPPRESS_EMBEDS),
+            Err(MessageFlags::EPHEMERAL),
+        );
+        assert_eq!(message.flags, MessageFlags::SUPPRESS_NOTIFICATIONS);
+        assert!(serde_json::to_value(CreateMessage::from("hi")).unwrap().get("flags").is_none());
+    }
+
+    #[tokio::test]
+    async fn poll_message() {
+        let message = create_message(|m| m.poll("Best color?", |p| {
+            p.answer("Red");
+            p.answer("Blue");
+        }));
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["poll"]["question"]["text"], "Best color?");
+        assert_eq!(json["poll"]["answers"][1]["poll_media"]["text"], "Blue");
+        assert_eq!(json["poll"]["duration"], 24);
+        assert!(serde_json::to_value(CreateMessage::from("no poll")).unwrap().get("poll").is_none());
+
+        let client = timeout_client().await;
+        let result = client.end_poll(ChannelId(1), MessageId(2)).await;
+        assert!(matches!(result, Err(ClientError::Timeout(EndPoll(ChannelId(1), MessageId(2))))));
+    }
+
+    #[tokio::test]
+    async fn cache_message() {
+        let state = BotState::new(timeout_client().await, ());
+        let result = state.cache_message(ChannelId(2), MessageId(3)).await;
+        assert!(matches!(result, Err(ClientError::Timeout(GetChannelMessage(ChannelId(2), MessageId(3))))));
+        assert!(state.cache.message(MessageId(3)).await.is_none());
+
+        let cached = state.cache_fetched_message(MessageId(3), Ok(message())).await.unwrap();
+        assert_eq!(cached.map(|m| m.content), Some("Supa Hot".to_owned()));
+        assert_eq!(state.cache.message(MessageId(3)).await.unwrap().author.id, UserId(1));
+
+        let unknown = serde_json::from_str(r#"{"code": 10008, "message": "Unknown Message"}"#).unwrap();
+        let deleted = state.cache_fetched_message(MessageId(3), Err(ClientError::Discord(unknown))).await;
+        assert!(matches!(deleted, Ok(None)));
+        assert!(state.cache.message(MessageId(3)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn message_or_fetch() {
+        let state = BotState::new(timeout_client().await, ());
+        let fetch_count = AtomicUsize::new(0);
+        let fetch = || async {
+            fetch_count.fetch_add(1, Ordering::SeqCst);
+            Ok(message())
+        };
+
+        let fetched = state.message_or_else(MessageId(3), fetch).await.unwrap();
+        assert_eq!(fetched.map(|m| m.content), Some("Supa Hot".to_owned()));
+        assert_eq!(fetch_count.load(Ordering::SeqCst), 1);
+        assert!(state.cache.message(MessageId(3)).await.is_some());
+
+        let cached = state.message_or_else(MessageId(3), fetch).await.unwrap();
+        assert_eq!(cached.map(|m| m.content), Some("Supa Hot".to_owned()));
+        assert_eq!(fetch_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn collect_until_stops_paging() {
+        let fetches = AtomicUsize::new(0);
+        // 1000 messages, with ids 1000 (newest) to 1
+        let history = MessageHistory::new(None, |before: Option<MessageId>| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            let newest = before.map_or(1000, |before| before.0 - 1);
+            async move {
+                Ok((0..MessageHistory::PAGE_SIZE as u64)
+                    .map(|i| Message { id: MessageId(newest - i), ..message() })
+                    .collect())
+            }
+        });
+
+        let messages = history.collect_until(|m| m.id == MessageId(850)).await.unwrap();
+        assert_eq!(messages.len(), 150);
+        assert_eq!(messages.first().map(|m| m.id), Some(MessageId(1000)));
+        assert_eq!(messages.last().map(|m| m.id), Some(MessageId(851)));
+        // the boundary was in the second page, so the third was never fetched
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn message_history_ends_on_short_page() {
+        let fetches = AtomicUsize::new(0);
+        let history = MessageHistory::new(Some(MessageId(31)), |before: Option<MessageId>| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            let newest = before.map_or(0, |before| before.0 - 1);
+            async move {
+                Ok((1..=newest)
+                    .rev()
+                    .map(|id| Message { id: MessageId(id), ..message() })
+                    .collect())
+            }
+        });
+
+        let messages: Vec<_> = history.try_collect().await.unwrap();
+        assert_eq!(messages.len(), 30);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+}
@@ -3,23 +3,129 @@
 //! Use these [`impl DiscordClient`](../struct.DiscordClient.html#impl-1) methods for the low level api
 //! for channel related requests.
 
-use serde_derive::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::BotState;
-use crate::http::{ClientResult, DiscordClient};
+use crate::http::{ClientError, ClientResult, DiscordClient};
 use crate::http::routes::Route::*;
-use crate::model::guild::GuildMember;
-use crate::model::ids::{GuildId, RoleId, UserId};
+use crate::model::guild::{Guild, GuildMember, GuildPreview, WelcomeScreen, WelcomeScreenChannel};
+use crate::model::ids::{ChannelId, GuildId, RoleId, UserId};
+use crate::model::DiscordErrorType;
 use crate::model::message::Color;
+use crate::model::onboarding::{GuildOnboarding, OnboardingMode, OnboardingPrompt};
 use crate::model::permissions::{Permissions, Role};
 
 /// Guild related http requests
 impl DiscordClient {
+    /// Returns the [`Guild`] with the given id.
+    ///
+    /// Fields only sent in the [`GuildCreate`](crate::shard::dispatch::GuildCreate) event, such as
+    /// [`members`](Guild::members) and [`channels`](Guild::channels), will be empty. To also get
+    /// the approximate member and presence counts, use
+    /// [`get_guild_with_counts`](Self::get_guild_with_counts).
+    pub async fn get_guild(&self, guild: GuildId) -> ClientResult<Guild> {
+        self.get_query(GetGuild(guild), GuildQuery { with_counts: false }).await
+    }
+
+    /// Returns the [`Guild`] with the given id, including its
+    /// [`approximate_member_count`](Guild::approximate_member_count) and
+    /// [`approximate_presence_count`](Guild::approximate_presence_count).
+    pub async fn get_guild_with_counts(&self, guild: GuildId) -> ClientResult<Guild> {
+        self.get_query(GetGuild(guild), GuildQuery { with_counts: true }).await
+    }
+
     /// Returns a [`GuildMember`] for the specified user.
+    ///
+    /// If `user` is not a member of the guild, this fails with a [`ClientError::Discord`] whose
+    /// code is [`UnknownMember`](DiscordErrorType::UnknownMember).
     pub async fn get_guild_member(&self, guild: GuildId, user: UserId) -> ClientResult<GuildMember> {
         self.get(GetGuildMember(guild, user)).await
     }
 
+    /// Returns the [`GuildMember`]s whose username or nickname starts with `query`. At most `limit`
+    /// (1-1000) members are returned.
+    pub async fn search_guild_members(&self, guild: GuildId, query: &str, limit: u32) -> ClientResult<Vec<GuildMember>> {
+        self.get_query(SearchGuildMembers(guild), SearchMembersQuery { query, limit }).await
+    }
+
+    /// Modify attributes of a guild member, showing `reason` in the guild's audit log. Each field of
+    /// `modify` needs its own permission, see [`ModifyMember`].
+    ///
+    /// Returns the updated [`GuildMember`], and fires a
+    /// [`GuildMemberUpdate`](crate::shard::dispatch::GuildMemberUpdate) Gateway event.
+    ///
+    /// # Errors
+    ///
+    /// If `modify` moves the member between voice channels but they aren't connected to voice, this
+    /// fails with a [`ClientError::Discord`] whose code is
+    /// [`TargetNotInVoice`](DiscordErrorType::TargetNotInVoice).
+    pub async fn modify_guild_member(
+        &self,
+        guild: GuildId,
+        user: UserId,
+        modify: ModifyMember,
+        reason: Option<&str>,
+    ) -> ClientResult<GuildMember> {
+        self.patch_with_reason(ModifyGuildMember(guild, user), modify, reason).await
+    }
+
+    /// Move a member who is connected to voice to the voice channel `channel`, showing `reason` in
+    /// the guild's audit log. Requires the [`MOVE_MEMBERS`](Permissions::MOVE_MEMBERS) permission,
+    /// and the member must be allowed to [`CONNECT`](Permissions::CONNECT) to `channel`.
+    ///
+    /// # Errors
+    ///
+    /// If `user` isn't connected to voice, this fails with a [`ClientError::Discord`] whose code is
+    /// [`TargetNotInVoice`](DiscordErrorType::TargetNotInVoice).
+    pub async fn move_member_to_voice(
+        &self,
+        guild: GuildId,
+        user: UserId,
+        channel: ChannelId,
+        reason: Option<&str>,
+    ) -> ClientResult<GuildMember> {
+        let modify = ModifyMember { channel_id: Some(Some(channel)), ..Default::default() };
+        self.modify_guild_member(guild, user, modify, reason).await
+    }
+
+    /// Disconnect a member from voice, showing `reason` in the guild's audit log. Requires the
+    /// [`MOVE_MEMBERS`](Permissions::MOVE_MEMBERS) permission.
+    ///
+    /// # Errors
+    ///
+    /// If `user` isn't connected to voice, this fails with a [`ClientError::Discord`] whose code is
+    /// [`TargetNotInVoice`](DiscordErrorType::TargetNotInVoice).
+    pub async fn disconnect_member(&self, guild: GuildId, user: UserId, reason: Option<&str>) -> ClientResult<GuildMember> {
+        let modify = ModifyMember { channel_id: Some(None), ..Default::default() };
+        self.modify_guild_member(guild, user, modify, reason).await
+    }
+
+    /// Remove (kick) a member from the guild, showing `reason` in the guild's audit log. Requires
+    /// the [`KICK_MEMBERS`](Permissions::KICK_MEMBERS) permission.
+    ///
+    /// Fires a [`GuildMemberRemove`](crate::shard::dispatch::GuildMemberRemove) Gateway event.
+    pub async fn remove_guild_member(&self, guild: GuildId, user: UserId, reason: Option<&str>) -> ClientResult<()> {
+        self.delete_with_reason(RemoveGuildMember(guild, user), reason).await
+    }
+
+    /// Create a guild ban, and optionally delete previous messages sent by the banned user, showing
+    /// `reason` in the guild's audit log. Requires the
+    /// [`BAN_MEMBERS`](Permissions::BAN_MEMBERS) permission.
+    ///
+    /// Fires a [`GuildBanAdd`](crate::shard::dispatch::BanAdd) Gateway event.
+    pub async fn create_guild_ban(&self, guild: GuildId, user: UserId, ban: CreateBan, reason: Option<&str>) -> ClientResult<()> {
+        self.put_with_reason(CreateGuildBan(guild, user), ban, reason).await
+    }
+
+    /// Remove the ban for a user, showing `reason` in the guild's audit log. Requires the
+    /// [`BAN_MEMBERS`](Permissions::BAN_MEMBERS) permission.
+    ///
+    /// Fires a [`GuildBanRemove`](crate::shard::dispatch::BanRemove) Gateway event.
+    pub async fn remove_guild_ban(&self, guild: GuildId, user: UserId, reason: Option<&str>) -> ClientResult<()> {
+        self.delete_with_reason(RemoveGuildBan(guild, user), reason).await
+    }
+
     /// Adds a role to a guild member.
     /// Requires the [`MANAGE_ROLES`](Permissions::MANAGE_ROLES) permission
     ///
@@ -58,23 +164,155 @@ impl DiscordClient {
     pub async fn create_guild_role(&self, guild: GuildId, role: CreateRole) -> ClientResult<Role> {
         self.post(CreateGuildRole(guild), role).await
     }
+
+    /// Returns the [`GuildPreview`] for the given guild. If the bot is not in the guild, the guild
+    /// must be [discoverable](crate::model::guild::GuildFeature::Discoverable).
+    pub async fn get_guild_preview(&self, guild: GuildId) -> ClientResult<GuildPreview> {
+        self.get(GetGuildPreview(guild)).await
+    }
+
+    /// Returns the [`WelcomeScreen`] for the given guild. If the welcome screen is not enabled,
+    /// requires the [`MANAGE_GUILD`](Permissions::MANAGE_GUILD) permission.
+    pub async fn get_guild_welcome_screen(&self, guild: GuildId) -> ClientResult<WelcomeScreen> {
+        self.get(GetGuildWelcomeScreen(guild)).await
+    }
+
+    /// Modify the guild's [`WelcomeScreen`], showing `reason` in the guild's audit log. Requires the
+    /// [`MANAGE_GUILD`](Permissions::MANAGE_GUILD) permission.
+    ///
+    /// Returns the updated welcome screen.
+    pub async fn modify_guild_welcome_screen(
+        &self,
+        guild: GuildId,
+        modify: ModifyWelcomeScreen,
+        reason: Option<&str>,
+    ) -> ClientResult<WelcomeScreen> {
+        self.patch_with_reason(ModifyGuildWelcomeScreen(guild), modify, reason).await
+    }
+
+    /// Returns the [`GuildOnboarding`] flow for the given guild.
+    pub async fn get_guild_onboarding(&self, guild: GuildId) -> ClientResult<GuildOnboarding> {
+        self.get(GetGuildOnboarding(guild)).await
+    }
+
+    /// Modify the guild's [`GuildOnboarding`] flow, showing `reason` in the guild's audit log.
+    /// Requires the [`MANAGE_GUILD`](Permissions::MANAGE_GUILD) and
+    /// [`MANAGE_ROLES`](Permissions::MANAGE_ROLES) permissions.
+    ///
+    /// Onboarding must still meet Discord's constraints, such as having at least 7 default
+    /// channels, at least 5 of which allow `@everyone` to send messages.
+    ///
+    /// Returns the updated onboarding.
+    pub async fn modify_guild_onboarding(
+        &self,
+        guild: GuildId,
+        modify: ModifyOnboarding,
+        reason: Option<&str>,
+    ) -> ClientResult<GuildOnboarding> {
+        self.put_with_reason(ModifyGuildOnboarding(guild), modify, reason).await
+    }
 }
 
 // todo more of these (only getters, since other ones trigger events), also document this in the
 //  mod level docs
 /// Guild related caching http requests
 impl<B: Send + Sync> BotState<B> {
+    /// Fetches the guild (with approximate counts) with Discord's API, then cache the result for
+    /// future reference before returning it. Fields that are only sent over the gateway (such as
+    /// [`members`](Guild::members)) are kept from the cached guild, if it is already cached.
+    pub async fn cache_guild(&self, guild: GuildId) -> ClientResult<Guild> {
+        use std::mem::take;
+
+        let mut guild = self.client.get_guild_with_counts(guild).await?;
+        let mut guard = self.cache.guilds.write().await;
+        if let Some(cached) = guard.get_mut(guild.id) {
+            guild.joined_at = cached.joined_at;
+            guild.large = cached.large;
+            guild.unavailable = cached.unavailable;
+            guild.member_count = cached.member_count;
+            guild.voice_states = take(&mut cached.voice_states);
+            guild.members = take(&mut cached.members);
+            guild.channels = take(&mut cached.channels);
+            guild.presences = take(&mut cached.presences);
+            guild.stage_instances = take(&mut cached.stage_instances);
+            guild.application_command_counts = take(&mut cached.application_command_counts);
+        }
+        guard.insert(guild.clone());
+        Ok(guild)
+    }
+
     /// Fetches the relevant guild member with Discord's API, then cache the result for future
     /// reference before returning it.
+    ///
+    /// If `user` is not a member of the guild, they are removed from the cache if they were still
+    /// cached, and the [`UnknownMember`](DiscordErrorType::UnknownMember) error is returned.
     pub async fn cache_guild_member(&self, guild: GuildId, user: UserId) -> ClientResult<GuildMember> {
-        let member = self.client.get_guild_member(guild, user).await?;
+        match self.client.get_guild_member(guild, user).await {
+            Ok(member) => {
+                let mut guard = self.cache.members.write().await;
+                let members = guard.entry(user).or_default();
+                members.insert(guild, member.clone());
+                Ok(member)
+            }
+            Err(ClientError::Discord(e)) if e.code == DiscordErrorType::UnknownMember => {
+                if let Some(members) = self.cache.members.write().await.get_mut(&user) {
+                    members.remove(&guild);
+                }
+                if let Some(guild) = self.cache.guilds.write().await.get_mut(guild) {
+                    guild.members.remove(user);
+                }
+                Err(ClientError::Discord(e))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like [`cache_guild_member`](Self::cache_guild_member), but returns `Ok(None)` if `user` is
+    /// not a member of the guild.
+    pub async fn try_cache_guild_member(&self, guild: GuildId, user: UserId) -> ClientResult<Option<GuildMember>> {
+        match self.cache_guild_member(guild, user).await {
+            Ok(member) => Ok(Some(member)),
+            Err(ClientError::Discord(e)) if e.code == DiscordErrorType::UnknownMember => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Searches for guild members with Discord's API (see
+    /// [`DiscordClient::search_guild_members`]), then caches all of the members found before
+    /// returning them.
+    pub async fn cache_search_guild_members(&self, guild: GuildId, query: &str, limit: u32) -> ClientResult<Vec<GuildMember>> {
+        let found = self.client.search_guild_members(guild, query, limit).await?;
         let mut guard = self.cache.members.write().await;
-        let members = guard.entry(user).or_default();
-        members.insert(guild, member.clone());
-        Ok(member)
+        for member in &found {
+            guard.entry(member.user.id)
+                .or_default()
+                .insert(guild, member.clone());
+        }
+        Ok(found)
     }
 }
 
+#[derive(Serialize, Debug, Copy, Clone)]
+struct GuildQuery {
+    with_counts: bool,
+}
+
+#[derive(Serialize, Debug, Copy, Clone)]
+struct SearchMembersQuery<'a> {
+    query: &'a str,
+    limit: u32,
+}
+
+/// Options for banning a user with [`DiscordClient::create_guild_ban`].
+#[derive(Serialize, Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct CreateBan {
+    /// number of seconds to delete messages for, between 0 and 604800 (7 days)
+    ///
+    /// default: `0`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_message_seconds: Option<u32>,
+}
+
 /// Data needed to create a new role in a guild, with the [`DiscordClient::create_guild_role`] method.
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct CreateRole {
@@ -100,6 +338,76 @@ pub struct CreateRole {
     pub mentionable: bool,
 }
 
+/// Changes to a guild's [`WelcomeScreen`], made with the
+/// [`DiscordClient::modify_guild_welcome_screen`] method. Fields that are `None` are left unchanged.
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct ModifyWelcomeScreen {
+    /// whether the welcome screen is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// channels linked in the welcome screen and their display options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub welcome_channels: Option<Vec<WelcomeScreenChannel>>,
+    /// the server description to show in the welcome screen
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Changes to a guild's [`GuildOnboarding`], made with the
+/// [`DiscordClient::modify_guild_onboarding`] method. Fields that are `None` are left unchanged.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ModifyOnboarding {
+    /// the prompts shown during onboarding and in customize community
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompts: Option<Vec<OnboardingPrompt>>,
+    /// the channels that members get opted into automatically
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_channel_ids: Option<Vec<ChannelId>>,
+    /// whether onboarding is enabled in the guild
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// the criteria used to decide whether onboarding is satisfied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<OnboardingMode>,
+}
+
+/// Changes to a [`GuildMember`], made with the [`DiscordClient::modify_guild_member`] method.
+///
+/// Params with nested `Option`s are serialized as follows:
+///
+/// `None` => field is not changed
+///
+/// `Some(None)` => field is removed
+///
+/// `Some(Some(foo))` => field is changed to be `foo`
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct ModifyMember {
+    /// the member's nickname. Requires the
+    /// [`MANAGE_NICKNAMES`](Permissions::MANAGE_NICKNAMES) permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nick: Option<Option<String>>,
+    /// every role the member should have. Requires the
+    /// [`MANAGE_ROLES`](Permissions::MANAGE_ROLES) permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<RoleId>>,
+    /// whether the member is muted in voice channels. Requires the
+    /// [`MUTE_MEMBERS`](Permissions::MUTE_MEMBERS) permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mute: Option<bool>,
+    /// whether the member is deafened in voice channels. Requires the
+    /// [`DEAFEN_MEMBERS`](Permissions::DEAFEN_MEMBERS) permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deaf: Option<bool>,
+    /// the voice channel to move the member to, or `Some(None)` to disconnect them. Requires the
+    /// [`MOVE_MEMBERS`](Permissions::MOVE_MEMBERS) permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<Option<ChannelId>>,
+    /// when the member's timeout will expire, up to 28 days in the future, or `Some(None)` to
+    /// remove it. Requires the [`MODERATE_MEMBERS`](Permissions::MODERATE_MEMBERS) permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub communication_disabled_until: Option<Option<DateTime<Utc>>>,
+}
+
 // // todo impl a similar trait on guild?
 // #[async_trait]
 // pub trait CommandPermsExt: Id<Id=CommandId> + Sized {
@@ -245,4 +553,296 @@ pub struct CreateRole {
 //     }
 // }
 //
-// impl<G: Id<Id=GuildId>> GuildCommandPermsExt for G {}
\ No newline at end of file
+// impl<G: Id<Id=GuildId>> GuildCommandPermsExt for G {}
+
+#[cfg(test)]
+mod guild_tests {
+    use reqwest::Method;
+
+    use crate::http::Request;
+    use crate::model::guild::GuildFeature;
+
+    use super::*;
+
+    fn request_url<Q: serde::Serialize>(route: &crate::http::routes::Route, query: &Q) -> reqwest::Url {
+        reqwest::Client::new()
+            .get(route.url())
+            .query(query)
+            .build()
+            .unwrap()
+            .url()
+            .clone()
+    }
+
+    #[test]
+    fn get_guild_query() {
+        let route = GetGuild(GuildId(1234));
+        let url = request_url(&route, &GuildQuery { with_counts: false });
+        assert_eq!(url.path(), "/api/v10/guilds/1234");
+        assert_eq!(url.query(), Some("with_counts=false"));
+
+        let url = request_url(&route, &GuildQuery { with_counts: true });
+        assert_eq!(url.query(), Some("with_counts=true"));
+    }
+
+    #[test]
+    fn get_guild_response() {
+        const JSON: &str = r#"{
+          "id": "2909267986263572999",
+          "name": "Mason's Test Server",
+          "icon": "389030ec9db118cb5b85a732333b7c98",
+          "description": null,
+          "splash": "75610b05a0dd09ec2c3c7df9f6975ea0",
+          "discovery_splash": null,
+          "approximate_member_count": 2,
+          "approximate_presence_count": 2,
+          "features": ["INVITE_SPLASH", "VANITY_URL", "BANNER", "NEWS", "VERIFIED", "VIP_REGIONS", "SOME_FUTURE_FEATURE"],
+          "emojis": [{
+            "name": "ultrafastparrot",
+            "roles": [],
+            "id": "393564762228785161",
+            "require_colons": true,
+            "managed": false,
+            "animated": true,
+            "available": true
+          }],
+          "banner": "5c3cb8d1bc159937fffe7e641ec96ca7",
+          "owner_id": "53908232506183680",
+          "application_id": null,
+          "region": "us-west",
+          "afk_channel_id": null,
+          "afk_timeout": 300,
+          "system_channel_id": null,
+          "widget_enabled": true,
+          "widget_channel_id": "639513352485470208",
+          "verification_level": 0,
+          "roles": [{
+            "id": "2909267986263572999",
+            "name": "@everyone",
+            "permissions": "49794752",
+            "position": 0,
+            "color": 0,
+            "hoist": false,
+            "managed": false,
+            "mentionable": false
+          }],
+          "default_message_notifications": 1,
+          "mfa_level": 0,
+          "explicit_content_filter": 0,
+          "max_presences": null,
+          "max_members": 250000,
+          "max_video_channel_users": 25,
+          "vanity_url_code": "no",
+          "premium_tier": 0,
+          "premium_subscription_count": 0,
+          "system_channel_flags": 0,
+          "preferred_locale": "en-US",
+          "rules_channel_id": null,
+          "public_updates_channel_id": null
+        }"#;
+        let guild: Guild = serde_json::from_str(JSON).unwrap();
+        assert_eq!(guild.id.to_string(), "2909267986263572999");
+        assert_eq!(guild.approximate_member_count, Some(2));
+        assert_eq!(guild.approximate_presence_count, Some(2));
+        assert!(guild.has_feature(GuildFeature::VipRegions));
+        assert!(guild.has_feature(GuildFeature::Unknown("SOME_FUTURE_FEATURE".into())));
+        assert_eq!(guild.roles.len(), 1);
+        assert!(guild.members.is_empty());
+        assert!(guild.channels.is_empty());
+    }
+
+    #[test]
+    fn guild_member_routes() {
+        let route = GetGuildMember(GuildId(1234), UserId(5678));
+        assert_eq!(route.url(), "https://discord.com/api/v10/guilds/1234/members/5678");
+
+        let url = request_url(&SearchGuildMembers(GuildId(1234)), &SearchMembersQuery { query: "abc def", limit: 10 });
+        assert_eq!(url.path(), "/api/v10/guilds/1234/members/search");
+        assert_eq!(url.query(), Some("query=abc+def&limit=10"));
+    }
+
+    #[test]
+    fn ban_request() {
+        let request = Request::with_body(
+            Method::PUT,
+            CreateGuildBan(GuildId(1234), UserId(5678)),
+            CreateBan { delete_message_seconds: Some(3600) },
+            || None,
+        ).reason(Some("spamming links"));
+        let request = request.builder(&reqwest::Client::new()).build().unwrap();
+        assert_eq!(request.method(), Method::PUT);
+        assert_eq!(request.url().path(), "/api/v10/guilds/1234/bans/5678");
+        assert_eq!(request.headers()["X-Audit-Log-Reason"], "spamming%20links");
+        let body = request.body().and_then(reqwest::Body::as_bytes).unwrap();
+        assert_eq!(body, br#"{"delete_message_seconds":3600}"#);
+
+        let default = serde_json::to_string(&CreateBan::default()).unwrap();
+        assert_eq!(default, "{}");
+    }
+
+    #[test]
+    fn voice_move_request() {
+        let body = |modify: ModifyMember| {
+            let request = Request::with_body(
+                Method::PATCH,
+                ModifyGuildMember(GuildId(1234), UserId(5678)),
+                modify,
+                || None,
+            );
+            let request = request.builder(&reqwest::Client::new()).build().unwrap();
+            assert_eq!(request.method(), Method::PATCH);
+            assert_eq!(request.url().path(), "/api/v10/guilds/1234/members/5678");
+            let body = request.body().and_then(reqwest::Body::as_bytes).unwrap().to_vec();
+            String::from_utf8(body).unwrap()
+        };
+
+        let move_to = ModifyMember { channel_id: Some(Some(ChannelId(42))), ..Default::default() };
+        assert_eq!(body(move_to), r#"{"channel_id":"42"}"#);
+        let disconnect = ModifyMember { channel_id: Some(None), ..Default::default() };
+        assert_eq!(body(disconnect), r#"{"channel_id":null}"#);
+        assert_eq!(body(ModifyMember::default()), "{}");
+    }
+
+    #[test]
+    fn kick_request() {
+        let request = Request::new(
+            Method::DELETE,
+            RemoveGuildMember(GuildId(1234), UserId(5678)),
+            || None,
+        );
+        let kick = request.builder(&reqwest::Client::new()).build().unwrap();
+        assert_eq!(kick.url().path(), "/api/v10/guilds/1234/members/5678");
+        assert!(!kick.headers().contains_key("X-Audit-Log-Reason"));
+
+        let kick = request.reason(Some("bye!"))
+            .builder(&reqwest::Client::new())
+            .build()
+            .unwrap();
+        assert_eq!(kick.headers()["X-Audit-Log-Reason"], "bye%21");
+    }
+
+    #[test]
+    fn guild_member_response() {
+        const JSON: &str = r#"{
+          "user": {
+            "id": "80351110224678912",
+            "username": "Nelly",
+            "discriminator": "1337",
+            "avatar": "8342729096ea3675442027381ff50dfe"
+          },
+          "nick": "NOT API SUPPORT",
+          "avatar": null,
+          "roles": ["41771983423143936"],
+          "joined_at": "2015-04-26T06:26:56.936000+00:00",
+          "deaf": false,
+          "mute": false
+        }"#;
+        let member: GuildMember = serde_json::from_str(JSON).unwrap();
+        assert_eq!(member.user.username, "Nelly");
+        assert_eq!(member.nick_or_name(), "NOT API SUPPORT");
+        assert_eq!(member.roles.len(), 1);
+        assert!(!member.pending);
+
+        let members: Vec<GuildMember> = serde_json::from_str(&format!("[{JSON}, {JSON}]")).unwrap();
+        assert_eq!(members.len(), 2);
+    }
+
+    #[test]
+    fn guild_preview_response() {
+        const JSON: &str = r#"{
+          "id": "197038439483310086",
+          "name": "Discord Testers",
+          "icon": "f64c482b807da4f539cff778d174971c",
+          "splash": null,
+          "discovery_splash": null,
+          "emojis": [],
+          "features": ["DISCOVERABLE", "VANITY_URL", "ANIMATED_ICON", "INVITE_SPLASH", "NEWS", "COMMUNITY", "BANNER", "VERIFIED"],
+          "approximate_member_count": 60814,
+          "approximate_presence_count": 20034,
+          "description": "The official place to report Discord Bugs!",
+          "stickers": []
+        }"#;
+        let preview: GuildPreview = serde_json::from_str(JSON).unwrap();
+        assert_eq!(preview.id, GuildId(197_038_439_483_310_086));
+        assert_eq!(preview.name, "Discord Testers");
+        assert!(preview.features.contains(&GuildFeature::Discoverable));
+        assert_eq!(preview.approximate_member_count, 60814);
+        assert_eq!(preview.description.as_deref(), Some("The official place to report Discord Bugs!"));
+    }
+
+    #[test]
+    fn welcome_screen_response() {
+        use crate::model::ids::{ChannelId, EmojiId};
+
+        const JSON: &str = r#"{
+          "description": "Discord Developers is a place to learn about Discord's API, bots, and SDKs and integrations. This is NOT a general Discord support server.",
+          "welcome_channels": [
+            {
+              "channel_id": "697138785317814292",
+              "description": "Follow for official Discord API updates",
+              "emoji_id": null,
+              "emoji_name": "📡"
+            },
+            {
+              "channel_id": "697236247739105340",
+              "description": "Get help with Bot Verifications",
+              "emoji_id": "733084442143506466",
+              "emoji_name": "verified"
+            }
+          ]
+        }"#;
+        let screen: WelcomeScreen = serde_json::from_str(JSON).unwrap();
+        assert!(screen.description.is_some());
+        assert_eq!(screen.welcome_channels.len(), 2);
+        assert_eq!(screen.welcome_channels[0].channel_id, ChannelId(697_138_785_317_814_292));
+        assert_eq!(screen.welcome_channels[0].emoji_id, None);
+        assert_eq!(screen.welcome_channels[0].emoji_name.as_deref(), Some("📡"));
+        assert_eq!(screen.welcome_channels[1].emoji_id, Some(EmojiId(733_084_442_143_506_466)));
+
+        let modify = ModifyWelcomeScreen {
+            enabled: Some(true),
+            welcome_channels: Some(screen.welcome_channels),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&modify).unwrap();
+        assert_eq!(json["enabled"], true);
+        assert_eq!(json["welcome_channels"][1]["emoji_name"], "verified");
+        assert!(json.get("description").is_none());
+    }
+
+    #[test]
+    fn modify_onboarding() {
+        use crate::model::onboarding::PromptType;
+
+        let prompt: OnboardingPrompt = serde_json::from_str(r#"{
+          "id": "20",
+          "type": 1,
+          "options": [{
+            "id": "30",
+            "channel_ids": [],
+            "role_ids": ["50"],
+            "emoji": null,
+            "title": "Red",
+            "description": null
+          }],
+          "title": "Pick a color",
+          "single_select": true,
+          "required": false,
+          "in_onboarding": true
+        }"#).unwrap();
+        assert_eq!(prompt.kind, PromptType::Dropdown);
+
+        let modify = ModifyOnboarding {
+            prompts: Some(vec![prompt]),
+            mode: Some(OnboardingMode::OnboardingDefault),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&modify).unwrap();
+        assert_eq!(json["mode"], 0);
+        assert_eq!(json["prompts"][0]["type"], 1);
+        assert_eq!(json["prompts"][0]["options"][0]["role_ids"][0], "50");
+        assert!(json["prompts"][0]["options"][0].get("emoji_name").is_none());
+        assert!(json.get("enabled").is_none());
+        assert!(json.get("default_channel_ids").is_none());
+    }
+}
@@ -5,13 +5,13 @@ use std::collections::HashSet;
 use std::fmt::Debug;
 use std::str::FromStr;
 
-use serde_derive::Serialize;
+use serde::Serialize;
 
 use crate::BotState;
 use crate::commands::MenuData;
 use crate::commands::component_command::{ButtonCommand, MenuCommand};
 use crate::http::{ClientResult, DiscordClient};
-use crate::http::channel::{embed, MessageAttachment, RichEmbed};
+use crate::http::channel::{embed, MessageAttachment, MessageLimitError, MessageLimits, RichEmbed};
 use crate::http::routes::Route::*;
 use crate::model::{command, message};
 use crate::model::command::{ApplicationCommand, Command};
@@ -297,6 +297,7 @@ impl DiscordClient {
         token: Token,
         response: InteractionResponse,
     ) -> ClientResult<InteractionResponse> {
+        response.check_limits(self.message_limits)?;
         self.send_message_with_files(
             CreateInteractionResponse(interaction, token),
             response.clone(),
@@ -333,6 +334,7 @@ impl DiscordClient {
         token: Token,
         message: InteractionMessage,
     ) -> ClientResult<Message> {
+        message.check_limits(self.message_limits)?;
         self.patch(
             EditInteractionResponse(application, token),
             &message,
@@ -364,6 +366,7 @@ impl DiscordClient {
         token: Token,
         message: WebhookMessage,
     ) -> ClientResult<Message> {
+        message.check_limits(self.message_limits)?;
         self.send_message_with_files(CreateFollowupMessage(application, token), message).await
     }
 
@@ -380,6 +383,7 @@ impl DiscordClient {
         message: MessageId,
         edit: WebhookMessage,
     ) -> ClientResult<Message> {
+        edit.check_limits(self.message_limits)?;
         self.patch(
             EditFollowupMessage(application, token, message),
             &edit,
@@ -537,6 +541,16 @@ impl WebhookMessage {
         self.avatar_url = Some(avatar_url.into());
     }
 
+    /// Check that this message's content and embeds are within `limits`. See
+    /// [`CreateMessage::check_limits`](crate::http::channel::CreateMessage::check_limits).
+    ///
+    /// # Errors
+    ///
+    /// If the content or the total length of the embeds is longer than allowed by `limits`.
+    pub fn check_limits(&self, limits: MessageLimits) -> Result<(), MessageLimitError> {
+        limits.check(&self.content, &self.embeds)
+    }
+
     // todo error, don't panic
     /// Add `n` embeds to this [`WebhookMessage`](WebhookMessage), by invoking a builder function
     /// that takes the embed number.
@@ -628,10 +642,26 @@ impl WebhookMessage {
               Component: From<Menu<D::Data>>,
               F: FnOnce(&mut Menu<D::Data>),
     {
-        let mut menu = Menu::<D::Data>::new();
-        menu.options = D::options();
+        let mut menu = Menu::for_data::<D>();
         builder(&mut menu);
         state.as_ref().register_menu(&mut menu, Box::new(command));
         self.components.push(ActionRow::menu(menu));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod interaction_tests {
+    use super::*;
+
+    #[test]
+    fn edit_only_sends_changes() {
+        let edit = Edit { name: None, description: Some("new"), options: None, default_permission: Some(false) };
+        assert_eq!(
+            serde_json::to_value(edit).unwrap(),
+            serde_json::json!({ "description": "new", "default_permission": false }),
+        );
+
+        let edit = Edit { name: None, description: None, options: None, default_permission: None };
+        assert_eq!(serde_json::to_value(edit).unwrap(), serde_json::json!({}));
+    }
+}
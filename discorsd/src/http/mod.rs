@@ -1,25 +1,27 @@
 //! Functionality for making http requests to Discord's API.
 
 use std::ffi::OsStr;
+use std::future::Future;
 use std::fmt::{self, Display};
 use std::io;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 
 use async_tungstenite::tungstenite::http::StatusCode;
 use backoff::ExponentialBackoff;
 use base64::Engine;
 use log::{error, warn};
-use reqwest::{Client, Method, multipart};
-use reqwest::header::{AUTHORIZATION, HeaderMap};
+use reqwest::{Client, Method, multipart, RequestBuilder};
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde_derive::Serialize;
 use thiserror::Error;
 use tokio::sync::Mutex;
 
 use crate::{BotState, serde_utils};
+use crate::http::channel::{MessageLimitError, MessageLimits};
 use crate::http::rate_limit::{BucketKey, RateLimiter};
 use crate::http::routes::Route;
 use crate::model::{BotGateway, DiscordError};
@@ -33,7 +35,10 @@ pub(crate) mod routes;
 pub mod channel;
 pub mod guild;
 pub mod interaction;
+pub mod stage_instance;
 pub mod user;
+#[cfg(test)]
+pub(crate) mod test_server;
 
 /// An error that happened while making a request to Discord's API.
 ///
@@ -69,9 +74,23 @@ pub enum ClientError {
     Discord(#[from] DiscordError),
     #[error("lacking permissions {0:?}")]
     Perms(Permissions),
+    #[error("{0}")]
+    MessageLimit(#[from] MessageLimitError),
+    /// The request took longer than the client's timeout. See [`DiscordClientBuilder`].
+    #[error("request timed out at {0:?}")]
+    Timeout(Route),
 }
 
 impl ClientError {
+    /// Distinguish timeouts from other request errors.
+    fn from_request(error: reqwest::Error, route: &Route) -> Self {
+        if error.is_timeout() {
+            Self::Timeout(route.clone())
+        } else {
+            Self::Request(error)
+        }
+    }
+
     /// Get cached information to make the error message have more context.
     pub async fn display_error<B: Send + Sync>(&self, state: &BotState<B>) -> DisplayClientError<'_> {
         match self {
@@ -81,6 +100,8 @@ impl ClientError {
             Self::Io(e) => DisplayClientError::Io(e),
             Self::Discord(e) => DisplayClientError::Discord(e),
             Self::Perms(p) => DisplayClientError::Perms(*p),
+            Self::MessageLimit(e) => DisplayClientError::MessageLimit(*e),
+            Self::Timeout(route) => DisplayClientError::Timeout(format!("request timed out at {:?}", route.debug_with_cache(&state.cache).await)),
         }
     }
 }
@@ -93,6 +114,8 @@ pub enum DisplayClientError<'a> {
     Io(&'a std::io::Error),
     Discord(&'a DiscordError),
     Perms(Permissions),
+    MessageLimit(MessageLimitError),
+    Timeout(String),
 }
 
 impl Display for DisplayClientError<'_> {
@@ -104,6 +127,8 @@ impl Display for DisplayClientError<'_> {
             Self::Io(e) => write!(f, "io error: {e}"),
             Self::Discord(e) => write!(f, "Discord error: {e:?}"),
             Self::Perms(p) => write!(f, "lacking permissions {p:?}"),
+            Self::MessageLimit(e) => write!(f, "{e}"),
+            Self::Timeout(e) => f.write_str(e),
         }
     }
 }
@@ -115,31 +140,297 @@ pub type ClientResult<T> = Result<T, ClientError>;
 /// limits.
 ///
 /// Wraps [Reqwest's Client](https://docs.rs/reqwest/*/reqwest/struct.Client.html).
-#[derive(Debug)]
 pub struct DiscordClient {
     pub(crate) token: String,
     pub client: Client,
     rate_limit: Arc<Mutex<RateLimiter>>,
+    /// The limits that messages are checked against before being sent.
+    pub message_limits: MessageLimits,
+    quiet_rate_limits: fn(&Route) -> bool,
+    retry: RetryPolicy,
 }
 
-/// General functionality
-impl DiscordClient {
-    /// Create a new [`DiscordClient`] using the specified bot `token`
-    pub(crate) fn single(token: String) -> Self {
-        Self::shared(token, Default::default())
+impl fmt::Debug for DiscordClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscordClient")
+            .field("token", &REDACTED_TOKEN)
+            .field("client", &self.client)
+            .field("rate_limit", &self.rate_limit)
+            .field("message_limits", &self.message_limits)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Shown instead of the bot's token when debug printing, so that it doesn't end up in logs.
+pub(crate) const REDACTED_TOKEN: RedactedToken = RedactedToken;
+
+pub(crate) struct RedactedToken;
+
+impl fmt::Debug for RedactedToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Bot ***")
+    }
+}
+
+/// Controls which failed requests a [`DiscordClient`] retries, and for how long. Set it with
+/// [`DiscordClientBuilder::retry`].
+///
+/// Retries use exponential backoff. By default, requests are retried for up to 10 seconds when
+/// they are rate limited, when Discord is
+/// [overloaded](crate::model::DiscordErrorType::ApiResourceOverloaded), or when a `GET`, `PUT`, or
+/// `DELETE` request has a server error. `POST` and `PATCH` requests are only retried after server
+/// errors with [`retry_all_methods`](Self::retry_all_methods).
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    /// Stop retrying once this much time has passed since the first attempt. `None` means there
+    /// is no time limit.
+    pub max_elapsed_time: Option<Duration>,
+    /// Stop retrying after this many retries. `None` means there is no limit, and `Some(0)` means
+    /// requests are never retried.
+    pub max_retries: Option<u32>,
+    /// Whether a request with this method that failed with this error should be retried.
+    pub is_transient: fn(&Method, &ClientError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl RetryPolicy {
+    /// The policy [`DiscordClient`]s use unless configured otherwise.
+    pub const DEFAULT: Self = Self {
+        max_elapsed_time: Some(Duration::from_secs(10)),
+        max_retries: None,
+        is_transient: Self::default_is_transient,
+    };
+
+    /// A policy that never retries, so that requests fail as soon as possible.
+    pub const NEVER: Self = Self {
+        max_elapsed_time: None,
+        max_retries: Some(0),
+        is_transient: |_, _| false,
+    };
+
+    /// The default transient errors: being rate limited, [transient Discord
+    /// errors](DiscordError::is_transient), and 5xx server errors for idempotent (`GET`, `PUT`, and
+    /// `DELETE`) requests. Errors making the request (including [timeouts](ClientError::Timeout)),
+    /// failing to parse the response, and other 4xx errors (such as `400`, `401`, and `403`) are
+    /// never retried, since the request may already have been received, or would just fail again.
+    ///
+    /// Server errors for `POST` and `PATCH` requests aren't retried, since Discord may have
+    /// already acted on them (for example, sending a message twice).
+    pub fn default_is_transient(method: &Method, error: &ClientError) -> bool {
+        match error {
+            ClientError::Http(status, _) if status.is_server_error() => {
+                matches!(*method, Method::GET | Method::PUT | Method::DELETE)
+            }
+            _ => Self::any_method_is_transient(method, error),
+        }
+    }
+
+    /// Like [`default_is_transient`](Self::default_is_transient), but also retries server errors
+    /// for `POST` and `PATCH` requests.
+    pub fn any_method_is_transient(_: &Method, error: &ClientError) -> bool {
+        match error {
+            ClientError::Http(status, _) => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            ClientError::Discord(error) => error.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// Retry server errors for every request, including `POST` and `PATCH` requests that Discord
+    /// may have already acted on. See [`any_method_is_transient`](Self::any_method_is_transient).
+    pub const fn retry_all_methods(self) -> Self {
+        Self { is_transient: Self::any_method_is_transient, ..self }
+    }
+
+    /// Whether to retry a `method` request after `error`, when `retries` retries have already been
+    /// made.
+    fn should_retry(&self, method: &Method, error: &ClientError, retries: u32) -> bool {
+        self.max_retries.is_none_or(|max| retries < max) && (self.is_transient)(method, error)
+    }
+}
+
+/// Configures how a [`DiscordClient`] connects to Discord. Create one with
+/// [`DiscordClient::builder`].
+///
+/// By default, requests time out after 30 seconds and connecting times out after 10 seconds, so
+/// that a hung request can't stall your bot forever.
+#[derive(Clone)]
+pub struct DiscordClientBuilder {
+    token: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    quiet_rate_limits: fn(&Route) -> bool,
+    retry: RetryPolicy,
+}
+
+impl fmt::Debug for DiscordClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscordClientBuilder")
+            .field("token", &REDACTED_TOKEN)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("pool_idle_timeout", &self.pool_idle_timeout)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DiscordClientBuilder {
+    /// Set the timeout for an entire request, from connecting until the response body is read.
+    /// `None` means requests never time out.
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
     }
 
-    /// Create a
-    pub(crate) fn shared(token: String, rate_limit: Arc<Mutex<RateLimiter>>) -> Self {
+    /// Set the timeout for connecting to Discord. `None` means connecting never times out.
+    #[must_use]
+    pub const fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set how long idle connections are kept open. `None` means they are never closed.
+    #[must_use]
+    pub const fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of idle connections to keep open.
+    #[must_use]
+    pub const fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// Set which routes don't log a warning when they are rate limited and retried. By default,
+    /// this is only [`CreateReaction`](Route::CreateReaction), since adding many reactions at
+    /// once is expected to hit the rate limit.
+    #[must_use]
+    pub const fn quiet_rate_limits(mut self, quiet: fn(&Route) -> bool) -> Self {
+        self.quiet_rate_limits = quiet;
+        self
+    }
+
+    /// Set which failed requests are retried, and for how long. See [`RetryPolicy`].
+    #[must_use]
+    pub const fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Create the [`DiscordClient`].
+    ///
+    /// # Panics
+    ///
+    /// If the token isn't a valid header value, or the underlying `reqwest::Client` can't be built.
+    pub fn build(self) -> DiscordClient {
+        self.build_from(Client::builder())
+    }
+
+    /// Create the [`DiscordClient`], applying these settings on top of `client`.
+    fn build_from(self, client: reqwest::ClientBuilder) -> DiscordClient {
+        let Self { token, timeout, connect_timeout, pool_idle_timeout, pool_max_idle_per_host, quiet_rate_limits, retry } = self;
         let mut headers = HeaderMap::new();
-        headers.insert(AUTHORIZATION, format!("Bot {token}").parse().expect("Unable to parse token!"));
+        let mut authorization: HeaderValue = format!("Bot {token}").parse().expect("Unable to parse token!");
+        // keeps the token out of `Debug` output and logs
+        authorization.set_sensitive(true);
+        headers.insert(AUTHORIZATION, authorization);
 
-        let client = Client::builder()
+        let mut client = client
             .default_headers(headers)
-            .build()
-            .expect("Unable to build client!");
+            .pool_idle_timeout(pool_idle_timeout)
+            .pool_max_idle_per_host(pool_max_idle_per_host);
+        if let Some(timeout) = timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        let client = client.build().expect("Unable to build client!");
+
+        DiscordClient {
+            token,
+            client,
+            rate_limit: Default::default(),
+            message_limits: MessageLimits::default(),
+            quiet_rate_limits,
+            retry,
+        }
+    }
+}
+
+/// General functionality
+impl DiscordClient {
+    /// Configure a new [`DiscordClient`] using the specified bot `token`.
+    pub const fn builder(token: String) -> DiscordClientBuilder {
+        DiscordClientBuilder {
+            token,
+            timeout: Some(Duration::from_secs(30)),
+            connect_timeout: Some(Duration::from_secs(10)),
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle_per_host: usize::MAX,
+            quiet_rate_limits: |route| matches!(route, Route::CreateReaction(_, _, _)),
+            retry: RetryPolicy::DEFAULT,
+        }
+    }
+
+    /// Create a new [`DiscordClient`] using the specified bot `token` and the default
+    /// [settings](DiscordClientBuilder).
+    pub fn single(token: String) -> Self {
+        Self::builder(token).build()
+    }
+
+    /// Create a new [`DiscordClient`] using the specified bot `token` and the default
+    /// [settings](DiscordClientBuilder), which shares `rate_limit` with other clients.
+    pub fn shared(token: String, rate_limit: Arc<Mutex<RateLimiter>>) -> Self {
+        Self { rate_limit, ..Self::single(token) }
+    }
+
+    /// Run `requests` concurrently, returning their results in the same order. This is
+    /// [`join_all`](futures::future::join_all), so every request is polled on the current task.
+    ///
+    /// Concurrency is limited by the rate limit buckets: each attempt at a request holds its
+    /// bucket's lock while it waits out the bucket's rate limit, sends the request, and reads the
+    /// response. Requests in the same bucket (such as messages sent to the same channel) therefore
+    /// run one at a time, while requests in different buckets run at the same time.
+    ///
+    /// ```rust
+    /// # use discorsd::http::{ClientResult, DiscordClient};
+    /// # use discorsd::model::guild::GuildMember;
+    /// # use discorsd::model::ids::{GuildId, UserId};
+    /// async fn members(client: &DiscordClient, guild: GuildId, users: &[UserId]) -> ClientResult<Vec<GuildMember>> {
+    ///     client.batch(users.iter().map(|&user| client.get_guild_member(guild, user)))
+    ///         .await
+    ///         .into_iter()
+    ///         .collect()
+    /// }
+    /// ```
+    pub async fn batch<I>(&self, requests: I) -> Vec<<I::Item as Future>::Output>
+        where I: IntoIterator,
+              I::Item: Future,
+    {
+        futures::future::join_all(requests).await
+    }
 
-        Self { token, client, rate_limit }
+    /// Whether to log a warning when a request is retried because of `error`.
+    fn warn_on_retry(&self, error: &ClientError) -> bool {
+        !matches!(
+            error,
+            ClientError::Http(StatusCode::TOO_MANY_REQUESTS, route) if (self.quiet_rate_limits)(route)
+        )
     }
 
     async fn request<Q, J, F, T>(&self, request: Request<Q, J, F>) -> ClientResult<T>
@@ -148,19 +439,13 @@ impl DiscordClient {
               F: Fn() -> Option<multipart::Form> + Send + Sync,
               T: DeserializeOwned,
     {
-        let Request { method, route, query, body, multipart } = request;
-        let key = BucketKey::from(&route);
+        let route = &request.route;
+        let key = BucketKey::from(route);
+        let retries = AtomicU32::new(0);
         let async_operation = || async {
-            let mut builder = self.client.request(method.clone(), route.url());
-            if let Some(query) = &query {
-                builder = builder.query(query);
-            }
-            if let Some(json) = &body {
-                builder = builder.json(json);
-            }
-            if let Some(multipart) = multipart() {
-                builder = builder.multipart(multipart);
-            }
+            let builder = request.builder(&self.client);
+            let bucket = self.rate_limit.lock().await.bucket_lock(key);
+            let _bucket = bucket.lock().await;
             let rate_limit = {
                 let guard = self.rate_limit.lock().await;
                 guard.get_rate_limit(&key)
@@ -168,37 +453,39 @@ impl DiscordClient {
             if let Some(sleep) = rate_limit {
                 sleep.await;
             }
-            let response = builder.send().await.map_err(ClientError::Request)?;
-            let headers = response.headers();
-            self.rate_limit.lock().await.update(key, headers);
-            if response.status().is_client_error() || response.status().is_server_error() {
+            let result = async {
+                let response = builder.send().await.map_err(|e| ClientError::from_request(e, route))?;
+                let headers = response.headers();
+                self.rate_limit.lock().await.update(key, headers);
                 let status = response.status();
-                let err = if status == StatusCode::TOO_MANY_REQUESTS {
-                    backoff::Error::transient(ClientError::Http(status, route.clone()))
-                } else {
-                    let permanent = if let Ok(error) = response.nice_json().await {
-                        println!("discord error = {error:?}");
-                        ClientError::Discord(error)
+                // server errors are reported by status so they can be retried
+                if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                    Err(ClientError::Http(status, route.clone()))
+                } else if status.is_client_error() {
+                    if let Ok(error) = response.nice_json().await {
+                        error!("discord error = {error:?}");
+                        Err(ClientError::Discord(error))
                     } else {
-                        ClientError::Http(status, route.clone())
-                    };
-                    // println!("response.text().await = {:?}", response.text().await);
-                    // todo!()
-                    backoff::Error::Permanent(permanent)
-                };
-                Err(err)
+                        Err(ClientError::Http(status, route.clone()))
+                    }
+                } else {
+                    response.nice_json().await
+                }
+            }.await;
+            result.map_err(|e| if self.retry.should_retry(&request.method, &e, retries.fetch_add(1, Ordering::Relaxed)) {
+                backoff::Error::transient(e)
             } else {
-                Ok(response.nice_json().await?)
-            }
+                backoff::Error::permanent(e)
+            })
         };
         backoff::future::retry_notify(
             ExponentialBackoff {
-                max_elapsed_time: Some(Duration::from_secs(10)),
+                max_elapsed_time: self.retry.max_elapsed_time,
                 ..Default::default()
             },
             async_operation,
             |e: ClientError, dur|
-                if !matches!(e, ClientError::Http(StatusCode::TOO_MANY_REQUESTS, Route::CreateReaction(_, _, _))) {
+                if self.warn_on_retry(&e) {
                     warn!("Error in request {route:?} after {dur:?}: {e}");
                 },
         ).await
@@ -229,6 +516,14 @@ impl DiscordClient {
     pub(crate) async fn post<T, J>(&self, route: Route, json: J) -> ClientResult<T>
         where T: DeserializeOwned,
               J: Serialize + Send + Sync,
+    {
+        self.post_with_reason(route, json, None).await
+    }
+
+    /// Same as [`post`](Self::post), but shows `reason` in the guild's audit log.
+    pub(crate) async fn post_with_reason<T, J>(&self, route: Route, json: J, reason: Option<&str>) -> ClientResult<T>
+        where T: DeserializeOwned,
+              J: Serialize + Send + Sync,
     {
         self.request(Request::with_body(
             Method::POST,
@@ -236,7 +531,7 @@ impl DiscordClient {
             json,
             || None,
             // NiceResponseJson::nice_json,
-        )).await
+        ).reason(reason)).await
     }
 
     pub(crate) async fn post_multipart<T, F>(&self, route: Route, multipart: F) -> ClientResult<T>
@@ -254,6 +549,14 @@ impl DiscordClient {
     pub(crate) async fn patch<T, J>(&self, route: Route, json: J) -> ClientResult<T>
         where T: DeserializeOwned,
               J: Serialize + Send + Sync,
+    {
+        self.patch_with_reason(route, json, None).await
+    }
+
+    /// Same as [`patch`](Self::patch), but shows `reason` in the guild's audit log.
+    pub(crate) async fn patch_with_reason<T, J>(&self, route: Route, json: J, reason: Option<&str>) -> ClientResult<T>
+        where T: DeserializeOwned,
+              J: Serialize + Send + Sync,
     {
         self.request(Request::with_body(
             Method::PATCH,
@@ -261,12 +564,20 @@ impl DiscordClient {
             json,
             || None,
             // NiceResponseJson::nice_json,
-        )).await
+        ).reason(reason)).await
     }
 
     pub(crate) async fn put<T, J>(&self, route: Route, json: J) -> ClientResult<T>
         where T: DeserializeOwned,
               J: Serialize + Send + Sync,
+    {
+        self.put_with_reason(route, json, None).await
+    }
+
+    /// Same as [`put`](Self::put), but shows `reason` in the guild's audit log.
+    pub(crate) async fn put_with_reason<T, J>(&self, route: Route, json: J, reason: Option<&str>) -> ClientResult<T>
+        where T: DeserializeOwned,
+              J: Serialize + Send + Sync,
     {
         self.request(Request::with_body(
             Method::PUT,
@@ -274,16 +585,21 @@ impl DiscordClient {
             json,
             || None,
             // NiceResponseJson::nice_json,
-        )).await
+        ).reason(reason)).await
     }
 
     pub(crate) async fn delete(&self, route: Route) -> ClientResult<()> {
+        self.delete_with_reason(route, None).await
+    }
+
+    /// Same as [`delete`](Self::delete), but shows `reason` in the guild's audit log.
+    pub(crate) async fn delete_with_reason(&self, route: Route, reason: Option<&str>) -> ClientResult<()> {
         self.request(Request::new(
             Method::DELETE,
             route,
             || None,
             // |_| async { Ok(()) },
-        )).await
+        ).reason(reason)).await
     }
 }
 
@@ -298,9 +614,56 @@ pub(crate) struct Request<Q, J, F>
     query: Option<Q>,
     body: Option<J>,
     multipart: F,
+    headers: HeaderMap,
     // getter: R,
 }
 
+impl<Q, J, F> Request<Q, J, F> where
+    Q: Serialize,
+    J: Serialize,
+    F: Fn() -> Option<multipart::Form>,
+{
+    /// Show `reason` in the guild's audit log for this request.
+    fn reason(mut self, reason: Option<&str>) -> Self {
+        if let Some(reason) = reason {
+            self.headers.insert(AUDIT_LOG_REASON, audit_log_reason(reason));
+        }
+        self
+    }
+
+    fn builder(&self, client: &Client) -> RequestBuilder {
+        let mut builder = client.request(self.method.clone(), self.route.url())
+            .headers(self.headers.clone());
+        if let Some(query) = &self.query {
+            builder = builder.query(query);
+        }
+        if let Some(json) = &self.body {
+            builder = builder.json(json);
+        }
+        if let Some(multipart) = (self.multipart)() {
+            builder = builder.multipart(multipart);
+        }
+        builder
+    }
+}
+
+const AUDIT_LOG_REASON: &str = "X-Audit-Log-Reason";
+
+/// Discord requires the audit log reason header to be url encoded, to allow for non-ascii reasons.
+/// Percent-encode every byte of `str` except unreserved characters, so it can be used in a url.
+pub(crate) fn percent_encode(str: &str) -> String {
+    str.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => char::from(b).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn audit_log_reason(reason: &str) -> HeaderValue {
+    HeaderValue::from_str(&percent_encode(reason)).expect("url encoded strings are valid header values")
+}
+
 impl<F> Request<SerializeNever, SerializeNever, F> where
     F: Fn() -> Option<multipart::Form>,
 {
@@ -311,6 +674,7 @@ impl<F> Request<SerializeNever, SerializeNever, F> where
             query: None,
             body: None,
             multipart,
+            headers: HeaderMap::new(),
         }
     }
 }
@@ -325,6 +689,7 @@ impl<J, F> Request<SerializeNever, J, F> where
             query: None,
             body: Some(body),
             multipart,
+            headers: HeaderMap::new(),
         }
     }
 }
@@ -339,6 +704,7 @@ impl<Q, F> Request<Q, SerializeNever, F> where
             query: Some(query),
             body: None,
             multipart,
+            headers: HeaderMap::new(),
         }
     }
 }
@@ -436,4 +802,190 @@ impl ImageData {
     fn into_inner(self) -> String {
         self.0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod http_tests {
+    use crate::http::test_server::{never_respond, proxied_client, server_error_client, slow_client};
+    use crate::model::ids::{ChannelId, MessageId};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn custom_timeout() {
+        let requests = Arc::new(AtomicU32::new(0));
+        let count = Arc::clone(&requests);
+        let builder = DiscordClient::builder(String::new()).timeout(Some(Duration::from_millis(100)));
+        let client = proxied_client(builder, move |stream| {
+            count.fetch_add(1, Ordering::SeqCst);
+            never_respond(stream)
+        }).await;
+        let result = client.get::<serde_json::Value>(Route::GetChannel(ChannelId(1234))).await;
+        assert!(matches!(result, Err(ClientError::Timeout(Route::GetChannel(ChannelId(1234))))));
+        // timeouts aren't retried
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn quiet_rate_limits() {
+        let rate_limited = |route| ClientError::Http(StatusCode::TOO_MANY_REQUESTS, route);
+        let reaction = || Route::CreateReaction(ChannelId(1), MessageId(2), 'x'.into());
+        let message = || Route::PostMessage(ChannelId(1));
+
+        let client = DiscordClient::builder(String::new()).build();
+        assert!(!client.warn_on_retry(&rate_limited(reaction())));
+        assert!(client.warn_on_retry(&rate_limited(message())));
+        assert!(client.warn_on_retry(&ClientError::Http(StatusCode::BAD_GATEWAY, reaction())));
+
+        let client = DiscordClient::builder(String::new())
+            .quiet_rate_limits(|route| matches!(route, Route::PostMessage(_)))
+            .build();
+        assert!(client.warn_on_retry(&rate_limited(reaction())));
+        assert!(!client.warn_on_retry(&rate_limited(message())));
+    }
+
+    #[test]
+    fn audit_log_reason_encoding() {
+        assert_eq!(audit_log_reason("spam"), "spam");
+        assert_eq!(audit_log_reason("too much spam!"), "too%20much%20spam%21");
+        assert_eq!(audit_log_reason("a/b?c=d&e"), "a%2Fb%3Fc%3Dd%26e");
+        assert_eq!(audit_log_reason("très spam 🤖"), "tr%C3%A8s%20spam%20%F0%9F%A4%96");
+    }
+
+    #[test]
+    fn reason_header() {
+        let client = Client::new();
+        let route = Route::DeleteMessage(ChannelId(1), MessageId(2));
+
+        let request = Request::new(Method::DELETE, route.clone(), || None)
+            .builder(&client)
+            .build()
+            .unwrap();
+        assert!(!request.headers().contains_key(AUDIT_LOG_REASON));
+
+        let request = Request::new(Method::DELETE, route.clone(), || None)
+            .reason(None)
+            .builder(&client)
+            .build()
+            .unwrap();
+        assert!(!request.headers().contains_key(AUDIT_LOG_REASON));
+
+        let request = Request::new(Method::DELETE, route, || None)
+            .reason(Some("ça suffit"))
+            .builder(&client)
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[AUDIT_LOG_REASON], "%C3%A7a%20suffit");
+    }
+
+    #[test]
+    fn bulk_overwrite_guild_commands_request() {
+        use crate::model::command::Command;
+        use crate::model::ids::{ApplicationId, GuildId};
+
+        let client = Client::new();
+        let commands = vec![
+            Command::slash_command("ping", "pong!".into(), Vec::new()),
+            Command::user_command("Info"),
+        ];
+        let route = Route::BulkOverwriteGuildCommands(ApplicationId(4), GuildId(5));
+
+        let request = Request::with_body(Method::PUT, route, commands, || None)
+            .builder(&client)
+            .build()
+            .unwrap();
+        assert_eq!(request.method(), Method::PUT);
+        assert!(request.url().as_str().ends_with("/applications/4/guilds/5/commands"));
+        let body: serde_json::Value = serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!([
+            { "type": 1, "name": "ping", "description": "pong!", "options": [] },
+            { "type": 2, "name": "Info" },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn zero_retries() {
+        let always_retry = RetryPolicy { max_retries: Some(0), is_transient: |_, _| true, ..RetryPolicy::default() };
+        let (client, requests) = server_error_client(always_retry).await;
+        let result = client.get::<serde_json::Value>(Route::GetChannel(ChannelId(1))).await;
+        assert!(result.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let two_retries = RetryPolicy { max_retries: Some(2), ..always_retry };
+        let (client, requests) = server_error_client(two_retries).await;
+        let result = client.get::<serde_json::Value>(Route::GetChannel(ChannelId(1))).await;
+        assert!(result.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn default_retry_policy() {
+        let policy = RetryPolicy::default();
+        let route = || Route::GetChannel(ChannelId(1));
+        assert!(policy.should_retry(&Method::GET, &ClientError::Http(StatusCode::TOO_MANY_REQUESTS, route()), 100));
+        assert!(policy.should_retry(&Method::POST, &ClientError::Http(StatusCode::TOO_MANY_REQUESTS, route()), 100));
+        assert!(!policy.should_retry(&Method::GET, &ClientError::Timeout(route()), 0));
+        assert!(!RetryPolicy::NEVER.should_retry(&Method::GET, &ClientError::Http(StatusCode::TOO_MANY_REQUESTS, route()), 0));
+    }
+
+    #[test]
+    fn server_errors_are_transient() {
+        let policy = RetryPolicy::default();
+        let route = || Route::GetChannel(ChannelId(1));
+        for status in [StatusCode::INTERNAL_SERVER_ERROR, StatusCode::BAD_GATEWAY, StatusCode::SERVICE_UNAVAILABLE] {
+            for method in [Method::GET, Method::PUT, Method::DELETE] {
+                assert!(policy.should_retry(&method, &ClientError::Http(status, route()), 0), "{method} {status}");
+            }
+            for method in [Method::POST, Method::PATCH] {
+                assert!(!policy.should_retry(&method, &ClientError::Http(status, route()), 0), "{method} {status}");
+                assert!(policy.retry_all_methods().should_retry(&method, &ClientError::Http(status, route()), 0), "{method} {status}");
+            }
+        }
+        for status in [StatusCode::BAD_REQUEST, StatusCode::UNAUTHORIZED, StatusCode::FORBIDDEN, StatusCode::NOT_FOUND] {
+            assert!(!policy.should_retry(&Method::GET, &ClientError::Http(status, route()), 0), "{status}");
+        }
+    }
+
+    #[test]
+    fn overloaded_is_transient() {
+        let discord_error = |code: u32| ClientError::Discord(serde_json::from_value(serde_json::json!({
+            "code": code,
+            "message": "error",
+        })).unwrap());
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(&Method::POST, &discord_error(130_000), 0));
+        assert!(!policy.should_retry(&Method::GET, &discord_error(10008), 0));
+        assert!(!policy.should_retry(&Method::GET, &discord_error(50001), 0));
+    }
+
+    #[tokio::test]
+    async fn batch_serializes_buckets() {
+        async fn get_channel(client: &DiscordClient, channel: u64) -> ClientResult<serde_json::Value> {
+            client.get(Route::GetChannel(ChannelId(channel))).await
+        }
+
+        let (client, max_in_flight) = slow_client().await;
+        let results = client.batch([get_channel(&client, 1), get_channel(&client, 1)]).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+
+        let (client, max_in_flight) = slow_client().await;
+        let results = client.batch([get_channel(&client, 1), get_channel(&client, 2)]).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn token_redacted() {
+        let token = "MTIzNDU2Nzg5.secret.token";
+        let builder = DiscordClient::builder(token.into());
+        let builder_debug = format!("{builder:?}");
+        assert!(!builder_debug.contains(token));
+        assert!(builder_debug.contains("Bot ***"));
+
+        let client = builder.build();
+        let client_debug = format!("{client:?}");
+        assert!(!client_debug.contains(token));
+        assert!(client_debug.contains("Bot ***"));
+    }
+}
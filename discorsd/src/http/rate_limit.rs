@@ -1,12 +1,17 @@
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use reqwest::header::HeaderMap;
+use tokio::sync::Mutex;
 use tokio::time::Sleep;
 
 use crate::http::routes::Route;
 use crate::model::ids::*;
+use crate::model::interaction::Token;
 
 #[derive(Debug, Default)]
 pub struct RateLimit {
@@ -38,6 +43,8 @@ impl fmt::Display for RateLimit {
     }
 }
 
+/// Identifies which rate limit bucket a [`Route`] uses. Discord rate limits each route separately
+/// for each of its "major parameters" (channel, guild, or webhook), so those are included here.
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum BucketKey {
     GetGateway,
@@ -47,6 +54,7 @@ pub enum BucketKey {
     GetPinnedMessages(ChannelId),
     PinMessage(ChannelId),
     UnpinMessage(ChannelId),
+    StartThreadInForum(ChannelId),
     GetMessages(ChannelId),
     GetMessage(ChannelId),
     PostMessage(ChannelId),
@@ -56,6 +64,8 @@ pub enum BucketKey {
     DeleteOwnReaction(ChannelId),
     DeleteUserReaction(ChannelId),
     GetReactions(ChannelId),
+    EndPoll(ChannelId),
+    GetPollAnswerVoters(ChannelId),
     GetGlobalCommands,
     GetGlobalCommand,
     CreateGlobalCommand,
@@ -68,26 +78,42 @@ pub enum BucketKey {
     EditGuildCommand(GuildId),
     DeleteGuildCommand(GuildId),
     BulkOverwriteGuildCommands(GuildId),
-    CreateInteractionResponse,
-    GetOriginalInteractionResponse,
-    EditInteractionResponse,
-    DeleteInteractionResponse,
-    CreateFollowupMessage,
-    EditFollowupMessage,
-    DeleteFollowupMessage,
-    GetGuildApplicationCommandPermissions,
+    CreateInteractionResponse(InteractionId),
+    GetOriginalInteractionResponse(WebhookKey),
+    EditInteractionResponse(WebhookKey),
+    DeleteInteractionResponse(WebhookKey),
+    CreateFollowupMessage(WebhookKey),
+    EditFollowupMessage(WebhookKey),
+    DeleteFollowupMessage(WebhookKey),
+    GetGuildApplicationCommandPermissions(GuildId),
     GetApplicationCommandPermissions(GuildId),
     EditApplicationCommandPermissions(GuildId),
-    BatchEditApplicationCommandPermissions,
+    BatchEditApplicationCommandPermissions(GuildId),
     GetUser,
     ModifyCurrentUser,
     GetCurrentUserGuilds,
+    LeaveGuild,
     CreateDm,
+    GetGuild(GuildId),
     GetGuildMember(GuildId),
+    SearchGuildMembers(GuildId),
+    ModifyGuildMember(GuildId),
+    RemoveGuildMember(GuildId),
+    CreateGuildBan(GuildId),
+    RemoveGuildBan(GuildId),
     AddGuildMemberRole(GuildId),
     RemoveGuildMemberRole(GuildId),
     GetGuildRoles(GuildId),
     CreateGuildRole(GuildId),
+    GetGuildPreview(GuildId),
+    GetGuildWelcomeScreen(GuildId),
+    ModifyGuildWelcomeScreen(GuildId),
+    GetGuildOnboarding(GuildId),
+    ModifyGuildOnboarding(GuildId),
+    CreateStageInstance,
+    GetStageInstance(ChannelId),
+    ModifyStageInstance(ChannelId),
+    DeleteStageInstance(ChannelId),
 }
 
 impl From<&Route> for BucketKey {
@@ -100,6 +126,7 @@ impl From<&Route> for BucketKey {
             Route::GetPinnedMessages(c) => Self::GetPinnedMessages(*c),
             Route::PinMessage(c, _) => Self::PinMessage(*c),
             Route::UnpinMessage(c, _) => Self::UnpinMessage(*c),
+            Route::StartThreadInForum(c) => Self::StartThreadInForum(*c),
             Route::GetChannelMessages(c) => Self::GetMessages(*c),
             Route::GetChannelMessage(c, _) => Self::GetMessage(*c),
             Route::PostMessage(c) => Self::PostMessage(*c),
@@ -109,6 +136,8 @@ impl From<&Route> for BucketKey {
             Route::DeleteOwnReaction(c, _, _) => Self::DeleteOwnReaction(*c),
             Route::DeleteUserReaction(c, _, _, _) => Self::DeleteUserReaction(*c),
             Route::GetReactions(c, _, _) => Self::GetReactions(*c),
+            Route::EndPoll(c, _) => Self::EndPoll(*c),
+            Route::GetPollAnswerVoters(c, _, _) => Self::GetPollAnswerVoters(*c),
             Route::GetGlobalCommands(_) => Self::GetGlobalCommands,
             Route::GetGlobalCommand(_, _) => Self::GetGlobalCommand,
             Route::CreateGlobalCommand(_) => Self::CreateGlobalCommand,
@@ -121,36 +150,75 @@ impl From<&Route> for BucketKey {
             Route::EditGuildCommand(_, g, _) => Self::EditGuildCommand(*g),
             Route::DeleteGuildCommand(_, g, _) => Self::DeleteGuildCommand(*g),
             Route::BulkOverwriteGuildCommands(_, g) => Self::BulkOverwriteGuildCommands(*g),
-            Route::CreateInteractionResponse(_, _) => Self::CreateInteractionResponse,
-            Route::GetOriginalInteractionResponse(_, _) => Self::GetOriginalInteractionResponse,
-            Route::EditInteractionResponse(_, _) => Self::EditInteractionResponse,
-            Route::DeleteInteractionResponse(_, _) => Self::DeleteInteractionResponse,
-            Route::CreateFollowupMessage(_, _) => Self::CreateFollowupMessage,
-            Route::EditFollowupMessage(_, _, _) => Self::EditFollowupMessage,
-            Route::DeleteFollowupMessage(_, _, _) => Self::DeleteFollowupMessage,
-            Route::GetGuildApplicationCommandPermissions(_, _) => Self::GetGuildApplicationCommandPermissions,
+            Route::CreateInteractionResponse(i, _) => Self::CreateInteractionResponse(*i),
+            Route::GetOriginalInteractionResponse(a, t) => Self::GetOriginalInteractionResponse(WebhookKey::new(*a, t)),
+            Route::EditInteractionResponse(a, t) => Self::EditInteractionResponse(WebhookKey::new(*a, t)),
+            Route::DeleteInteractionResponse(a, t) => Self::DeleteInteractionResponse(WebhookKey::new(*a, t)),
+            Route::CreateFollowupMessage(a, t) => Self::CreateFollowupMessage(WebhookKey::new(*a, t)),
+            Route::EditFollowupMessage(a, t, _) => Self::EditFollowupMessage(WebhookKey::new(*a, t)),
+            Route::DeleteFollowupMessage(a, t, _) => Self::DeleteFollowupMessage(WebhookKey::new(*a, t)),
+            Route::GetGuildApplicationCommandPermissions(_, g) => Self::GetGuildApplicationCommandPermissions(*g),
             Route::GetApplicationCommandPermissions(_, g, _) => Self::GetApplicationCommandPermissions(*g),
             Route::EditApplicationCommandPermissions(_, g, _) => Self::EditApplicationCommandPermissions(*g),
-            Route::BatchEditApplicationCommandPermissions(_, _) => Self::BatchEditApplicationCommandPermissions,
+            Route::BatchEditApplicationCommandPermissions(_, g) => Self::BatchEditApplicationCommandPermissions(*g),
             Route::GetUser(_) => Self::GetUser,
             Route::ModifyCurrentUser => Self::ModifyCurrentUser,
             Route::GetCurrentUserGuilds => Self::GetCurrentUserGuilds,
+            Route::LeaveGuild(_) => Self::LeaveGuild,
             Route::CreateDm => Self::CreateDm,
+            Route::GetGuild(g) => Self::GetGuild(*g),
             Route::GetGuildMember(g, _) => Self::GetGuildMember(*g),
+            Route::SearchGuildMembers(g) => Self::SearchGuildMembers(*g),
+            Route::ModifyGuildMember(g, _) => Self::ModifyGuildMember(*g),
+            Route::RemoveGuildMember(g, _) => Self::RemoveGuildMember(*g),
+            Route::CreateGuildBan(g, _) => Self::CreateGuildBan(*g),
+            Route::RemoveGuildBan(g, _) => Self::RemoveGuildBan(*g),
             Route::AddGuildMemberRole(g, _, _) => Self::AddGuildMemberRole(*g),
             Route::RemoveGuildMemberRole(g, _, _) => Self::RemoveGuildMemberRole(*g),
             Route::GetGuildRoles(g) => Self::GetGuildRoles(*g),
             Route::CreateGuildRole(g) => Self::CreateGuildRole(*g),
+            Route::GetGuildPreview(g) => Self::GetGuildPreview(*g),
+            Route::GetGuildWelcomeScreen(g) => Self::GetGuildWelcomeScreen(*g),
+            Route::ModifyGuildWelcomeScreen(g) => Self::ModifyGuildWelcomeScreen(*g),
+            Route::GetGuildOnboarding(g) => Self::GetGuildOnboarding(*g),
+            Route::ModifyGuildOnboarding(g) => Self::ModifyGuildOnboarding(*g),
+            Route::CreateStageInstance => Self::CreateStageInstance,
+            Route::GetStageInstance(c) => Self::GetStageInstance(*c),
+            Route::ModifyStageInstance(c) => Self::ModifyStageInstance(*c),
+            Route::DeleteStageInstance(c) => Self::DeleteStageInstance(*c),
         }
     }
 }
 
+/// The major parameter of a webhook route: the webhook's id and token. The token is stored as a
+/// hash so that keys stay `Copy` and don't hold on to every interaction's token.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct WebhookKey(ApplicationId, u64);
+
+impl WebhookKey {
+    fn new(application: ApplicationId, token: &Token) -> Self {
+        let mut hasher = DefaultHasher::new();
+        token.hash(&mut hasher);
+        Self(application, hasher.finish())
+    }
+}
+
+/// How often [`RateLimiter`] looks for buckets whose rate limit has reset.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Default)]
-pub struct RateLimiter(HashMap<BucketKey, RateLimit>);
+pub struct RateLimiter {
+    limits: HashMap<BucketKey, RateLimit>,
+    /// When [`evict_stale`](Self::evict_stale) last looked for buckets to remove.
+    last_eviction: Option<Instant>,
+    /// Held while a request is made in each bucket, so that requests in the same bucket wait for
+    /// the rate limit headers of the previous request.
+    locks: HashMap<BucketKey, Arc<Mutex<()>>>,
+}
 
 impl RateLimiter {
     // pub async fn rate_limit(&self, key: &BucketKey) {
-    //     if let Some(rate_limit) = self.0.get(key) {
+    //     if let Some(rate_limit) = self.limits.get(key) {
     //         if let Some(duration) = rate_limit.limit() {
     //             log::info!("{:?} ==> {}", key, rate_limit);
     //             tokio::time::sleep(duration).await;
@@ -158,8 +226,14 @@ impl RateLimiter {
     //     }
     // }
 
+    /// The lock for `key`'s bucket.
+    pub fn bucket_lock(&mut self, key: BucketKey) -> Arc<Mutex<()>> {
+        self.evict_stale(Instant::now());
+        Arc::clone(self.locks.entry(key).or_default())
+    }
+
     pub fn get_rate_limit(&self, key: &BucketKey) -> Option<Sleep> {
-        if let Some(rate_limit) = self.0.get(key) {
+        if let Some(rate_limit) = self.limits.get(key) {
             if let Some(duration) = rate_limit.limit() {
                 log::info!("{:?} ==> {}", key, rate_limit);
                 Some(tokio::time::sleep(duration))
@@ -172,7 +246,8 @@ impl RateLimiter {
     }
 
     pub fn update(&mut self, key: BucketKey, headers: &HeaderMap) {
-        let rate_limit = self.0.entry(key).or_default();
+        self.evict_stale(Instant::now());
+        let rate_limit = self.limits.entry(key).or_default();
         if let Some(limit) = headers.get("X-RateLimit-Limit") {
             rate_limit.limit = Some(limit.to_str().unwrap().parse().unwrap());
         }
@@ -184,4 +259,127 @@ impl RateLimiter {
             rate_limit.reset = Some(Instant::now() + Duration::from_secs_f64(secs));
         }
     }
-}
\ No newline at end of file
+
+    /// Removes the rate limits of buckets that have reset, since they no longer limit anything, and
+    /// the locks of those buckets that no request is using. Interaction response buckets are keyed
+    /// by interaction id or token, so without this there would be a new entry for every interaction
+    /// that is ever responded to.
+    ///
+    /// Only looks through the buckets once every [`EVICTION_INTERVAL`].
+    fn evict_stale(&mut self, now: Instant) {
+        if self.last_eviction.is_some_and(|last| now.duration_since(last) < EVICTION_INTERVAL) {
+            return;
+        }
+        self.last_eviction = Some(now);
+        self.limits.retain(|_, limit| limit.reset.is_some_and(|reset| reset > now));
+        let limits = &self.limits;
+        self.locks.retain(|key, lock| Arc::strong_count(lock) > 1 || limits.contains_key(key));
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use crate::model::emoji::Emoji;
+
+    use super::*;
+
+    fn key(route: &Route) -> BucketKey {
+        BucketKey::from(route)
+    }
+
+    #[test]
+    fn channel_major_parameter() {
+        let (a, b) = (ChannelId(1), ChannelId(2));
+        assert_ne!(key(&Route::PostMessage(a)), key(&Route::PostMessage(b)));
+        assert_eq!(key(&Route::PostMessage(a)), key(&Route::PostMessage(a)));
+
+        // the message isn't a major parameter
+        assert_eq!(
+            key(&Route::EditMessage(a, MessageId(3))),
+            key(&Route::EditMessage(a, MessageId(4))),
+        );
+        let emoji = Emoji::Unicode { name: "x".into() };
+        assert_ne!(
+            key(&Route::CreateReaction(a, MessageId(3), emoji.clone())),
+            key(&Route::CreateReaction(b, MessageId(3), emoji)),
+        );
+
+        // different routes have different buckets, even in the same channel
+        assert_ne!(key(&Route::PostMessage(a)), key(&Route::GetChannelMessages(a)));
+    }
+
+    #[test]
+    fn guild_major_parameter() {
+        let (a, b) = (GuildId(1), GuildId(2));
+        assert_ne!(key(&Route::GetGuildRoles(a)), key(&Route::GetGuildRoles(b)));
+        assert_eq!(
+            key(&Route::GetGuildMember(a, UserId(3))),
+            key(&Route::GetGuildMember(a, UserId(4))),
+        );
+        let app = ApplicationId(5);
+        assert_ne!(
+            key(&Route::GetGuildApplicationCommandPermissions(app, a)),
+            key(&Route::GetGuildApplicationCommandPermissions(app, b)),
+        );
+        assert_ne!(
+            key(&Route::BatchEditApplicationCommandPermissions(app, a)),
+            key(&Route::BatchEditApplicationCommandPermissions(app, b)),
+        );
+    }
+
+    #[test]
+    fn webhook_major_parameter() {
+        let app = ApplicationId(1);
+        let (a, b) = (Token("a".into()), Token("b".into()));
+        assert_ne!(
+            key(&Route::CreateFollowupMessage(app, a.clone())),
+            key(&Route::CreateFollowupMessage(app, b)),
+        );
+        assert_eq!(
+            key(&Route::EditFollowupMessage(app, a.clone(), MessageId(2))),
+            key(&Route::EditFollowupMessage(app, a, MessageId(3))),
+        );
+        assert_ne!(
+            key(&Route::CreateInteractionResponse(InteractionId(4), Token("a".into()))),
+            key(&Route::CreateInteractionResponse(InteractionId(5), Token("a".into()))),
+        );
+    }
+
+    #[test]
+    fn reset_buckets_evicted() {
+        let now = Instant::now();
+        let limit = |reset| RateLimit { limit: Some(5), remaining: Some(0), reset: Some(reset) };
+        let webhook = |token: &str| key(&Route::CreateFollowupMessage(ApplicationId(1), Token(token.into())));
+
+        let mut limiter = RateLimiter::default();
+        limiter.evict_stale(now);
+        limiter.limits.insert(webhook("a"), limit(now + Duration::from_secs(1)));
+        limiter.limits.insert(webhook("b"), limit(now + Duration::from_secs(120)));
+
+        // too soon since the last eviction to look again
+        limiter.evict_stale(now + Duration::from_secs(30));
+        assert_eq!(limiter.limits.len(), 2);
+
+        limiter.evict_stale(now + EVICTION_INTERVAL);
+        assert!(!limiter.limits.contains_key(&webhook("a")));
+        assert!(limiter.limits.contains_key(&webhook("b")));
+    }
+
+    #[test]
+    fn unused_locks_evicted() {
+        let now = Instant::now();
+        let response = |id| key(&Route::CreateInteractionResponse(InteractionId(id), Token("a".into())));
+
+        let mut limiter = RateLimiter::default();
+        let in_use = limiter.bucket_lock(response(1));
+        drop(limiter.bucket_lock(response(2)));
+        limiter.limits.insert(response(3), RateLimit { limit: Some(1), remaining: Some(0), reset: Some(now + Duration::from_secs(300)) });
+        drop(limiter.bucket_lock(response(3)));
+
+        limiter.evict_stale(now + EVICTION_INTERVAL * 2);
+        assert!(limiter.locks.contains_key(&response(1)));
+        assert!(!limiter.locks.contains_key(&response(2)));
+        assert!(limiter.locks.contains_key(&response(3)));
+        drop(in_use);
+    }
+}
@@ -18,6 +18,7 @@ pub enum Route {
     GetPinnedMessages(ChannelId),
     PinMessage(ChannelId, MessageId),
     UnpinMessage(ChannelId, MessageId),
+    StartThreadInForum(ChannelId),
 
     // messages
     GetChannelMessages(ChannelId),
@@ -32,6 +33,10 @@ pub enum Route {
     DeleteUserReaction(ChannelId, MessageId, Emoji, UserId),
     GetReactions(ChannelId, MessageId, Emoji),
 
+    // polls
+    EndPoll(ChannelId, MessageId),
+    GetPollAnswerVoters(ChannelId, MessageId, u32),
+
     // commands
     // although not used in the actual url, names are included to help debugging
     GetGlobalCommands(ApplicationId),
@@ -62,14 +67,32 @@ pub enum Route {
     GetUser(UserId),
     ModifyCurrentUser,
     GetCurrentUserGuilds,
+    LeaveGuild(GuildId),
     CreateDm,
 
     // guilds
+    GetGuild(GuildId),
     GetGuildMember(GuildId, UserId),
+    SearchGuildMembers(GuildId),
+    ModifyGuildMember(GuildId, UserId),
+    RemoveGuildMember(GuildId, UserId),
+    CreateGuildBan(GuildId, UserId),
+    RemoveGuildBan(GuildId, UserId),
     AddGuildMemberRole(GuildId, UserId, RoleId),
     RemoveGuildMemberRole(GuildId, UserId, RoleId),
     GetGuildRoles(GuildId),
     CreateGuildRole(GuildId),
+    GetGuildPreview(GuildId),
+    GetGuildWelcomeScreen(GuildId),
+    ModifyGuildWelcomeScreen(GuildId),
+    GetGuildOnboarding(GuildId),
+    ModifyGuildOnboarding(GuildId),
+
+    // stage instances
+    CreateStageInstance,
+    GetStageInstance(ChannelId),
+    ModifyStageInstance(ChannelId),
+    DeleteStageInstance(ChannelId),
 }
 
 impl Route {
@@ -83,6 +106,7 @@ impl Route {
             GetPinnedMessages(c) => api!("/channels/{}/pins", c),
             PinMessage(c, m) => api!("/channels/{}/pins/{}", c, m),
             UnpinMessage(c, m) => api!("/channels/{}/pins/{}", c, m),
+            StartThreadInForum(c) => api!("/channels/{}/threads", c),
 
             GetChannelMessages(c) => api!("/channels/{}/messages", c),
             GetChannelMessage(c, m) => api!("/channels/{}/messages/{}", c, m),
@@ -95,6 +119,9 @@ impl Route {
             DeleteUserReaction(c, m, e, u) => api!("/channels/{}/messages/{}/reactions/{}/{}", c, m, e.as_reaction(), u),
             GetReactions(c, m, e) => api!("/channels/{}/messages/{}/reactions/{}", c, m, e.as_reaction()),
 
+            EndPoll(c, m) => api!("/channels/{}/polls/{}/expire", c, m),
+            GetPollAnswerVoters(c, m, a) => api!("/channels/{}/polls/{}/answers/{}", c, m, a),
+
             GetGlobalCommands(a) => api!("/applications/{}/commands", a),
             CreateGlobalCommand(a) => api!("/applications/{}/commands", a),
             GetGlobalCommand(a, c) => api!("/applications/{}/commands/{}", a,c ),
@@ -123,13 +150,30 @@ impl Route {
             GetUser(u) => api!("/users/{}", u),
             ModifyCurrentUser => api!("/users/@me"),
             GetCurrentUserGuilds => api!("/users/@me/guilds"),
+            LeaveGuild(g) => api!("/users/@me/guilds/{}", g),
 
             CreateDm => api!("/users/@me/channels"),
+            GetGuild(g) => api!("/guilds/{}", g),
             GetGuildMember(g, u) => api!("/guilds/{}/members/{}", g, u),
-            AddGuildMemberRole(g, u, r) => api!("/guild/{}/members/{}/roles/{}", g, u, r),
-            RemoveGuildMemberRole(g, u, r) => api!("/guild/{}/members/{}/roles/{}", g, u, r),
+            SearchGuildMembers(g) => api!("/guilds/{}/members/search", g),
+            ModifyGuildMember(g, u) => api!("/guilds/{}/members/{}", g, u),
+            RemoveGuildMember(g, u) => api!("/guilds/{}/members/{}", g, u),
+            CreateGuildBan(g, u) => api!("/guilds/{}/bans/{}", g, u),
+            RemoveGuildBan(g, u) => api!("/guilds/{}/bans/{}", g, u),
+            AddGuildMemberRole(g, u, r) => api!("/guilds/{}/members/{}/roles/{}", g, u, r),
+            RemoveGuildMemberRole(g, u, r) => api!("/guilds/{}/members/{}/roles/{}", g, u, r),
             GetGuildRoles(g) => api!("/guilds/{}/roles", g),
             CreateGuildRole(g) => api!("/guilds/{}/roles", g),
+            GetGuildPreview(g) => api!("/guilds/{}/preview", g),
+            GetGuildWelcomeScreen(g) => api!("/guilds/{}/welcome-screen", g),
+            ModifyGuildWelcomeScreen(g) => api!("/guilds/{}/welcome-screen", g),
+            GetGuildOnboarding(g) => api!("/guilds/{}/onboarding", g),
+            ModifyGuildOnboarding(g) => api!("/guilds/{}/onboarding", g),
+
+            CreateStageInstance => api!("/stage-instances"),
+            GetStageInstance(c) => api!("/stage-instances/{}", c),
+            ModifyStageInstance(c) => api!("/stage-instances/{}", c),
+            DeleteStageInstance(c) => api!("/stage-instances/{}", c),
         }
     }
 
@@ -196,6 +240,7 @@ impl Route {
             &GetPinnedMessages(c) => format!("GetPinnedMessages({})", channel(c).await),
             &PinMessage(c, m) => format!("PinMessage({}, {})", channel(c).await, m),
             &UnpinMessage(c, m) => format!("UnpinMessage({}, {})", channel(c).await, m),
+            &StartThreadInForum(c) => format!("StartThreadInForum({})", channel(c).await),
             &GetChannelMessages(c) => format!("GetMessage({})", channel(c).await),
             &GetChannelMessage(c, m) => format!("GetMessage({}, {})", channel(c).await, m),
             &PostMessage(c) => format!("PostMessage({})", channel(c).await),
@@ -203,20 +248,22 @@ impl Route {
             &DeleteMessage(c, m) => format!("DeleteMessage({}, {})", channel(c).await, m),
             CreateReaction(c, m, e) => format!(
                 "CreateReaction({}, {}, {})",
-                channel(*c).await, m, e.as_reaction()
+                channel(*c).await, m, e
             ),
             DeleteOwnReaction(c, m, e) => format!(
                 "DeleteOwnReaction({}, {}, {})",
-                channel(*c).await, m, e.as_reaction()
+                channel(*c).await, m, e
             ),
             DeleteUserReaction(c, m, e, u) => format!(
                 "DeleteUserReaction({}, {}, {}, {})",
-                channel(*c).await, m, e.as_reaction(), user(*u).await
+                channel(*c).await, m, e, user(*u).await
             ),
             GetReactions(c, m, e) => format!(
                 "GetReactions({}, {}, {})",
-                channel(*c).await, m, e.as_reaction()
+                channel(*c).await, m, e
             ),
+            &EndPoll(c, m) => format!("EndPoll({}, {})", channel(c).await, m),
+            &GetPollAnswerVoters(c, m, a) => format!("GetPollAnswerVoters({}, {}, {})", channel(c).await, m, a),
             //  don't display ApplicationId because it'll always be the same
             GetGlobalCommands(_) => format!("GetGlobalCommands"),
             CreateGlobalCommand(_) => format!("CreateGlobalCommand"),
@@ -268,11 +315,30 @@ impl Route {
             &GetUser(u) => format!("GetUser({})", user(u).await),
             ModifyCurrentUser => format!("ModifyCurrentUser"),
             GetCurrentUserGuilds => format!("GetCurrentUserGuilds"),
+            &LeaveGuild(g) => format!("LeaveGuild({})", guild(g).await),
             CreateDm => format!("CreateDm"),
+            &GetGuild(g) => format!("GetGuild({})", guild(g).await),
             &GetGuildMember(g, u) => format!(
                 "GetGuildMember({}, {})",
                 guild(g).await, user(u).await
             ),
+            &SearchGuildMembers(g) => format!("SearchGuildMembers({})", guild(g).await),
+            &ModifyGuildMember(g, u) => format!(
+                "ModifyGuildMember({}, {})",
+                guild(g).await, user(u).await
+            ),
+            &RemoveGuildMember(g, u) => format!(
+                "RemoveGuildMember({}, {})",
+                guild(g).await, user(u).await
+            ),
+            &CreateGuildBan(g, u) => format!(
+                "CreateGuildBan({}, {})",
+                guild(g).await, user(u).await
+            ),
+            &RemoveGuildBan(g, u) => format!(
+                "RemoveGuildBan({}, {})",
+                guild(g).await, user(u).await
+            ),
             &AddGuildMemberRole(g, u, r) => format!(
                 "AddGuildMemberRole({}, {}, {})",
                 guild(g).await, user(u).await, role(g, r).await
@@ -283,6 +349,185 @@ impl Route {
             ),
             &GetGuildRoles(g) => format!("GetGuildRoles({})", guild(g).await),
             &CreateGuildRole(g) => format!("CreateGuildRole({})", guild(g).await),
+            &GetGuildPreview(g) => format!("GetGuildPreview({})", guild(g).await),
+            &GetGuildWelcomeScreen(g) => format!("GetGuildWelcomeScreen({})", guild(g).await),
+            &ModifyGuildWelcomeScreen(g) => format!("ModifyGuildWelcomeScreen({})", guild(g).await),
+            &GetGuildOnboarding(g) => format!("GetGuildOnboarding({})", guild(g).await),
+            &ModifyGuildOnboarding(g) => format!("ModifyGuildOnboarding({})", guild(g).await),
+            CreateStageInstance => format!("CreateStageInstance"),
+            &GetStageInstance(c) => format!("GetStageInstance({})", channel(c).await),
+            &ModifyStageInstance(c) => format!("ModifyStageInstance({})", channel(c).await),
+            &DeleteStageInstance(c) => format!("DeleteStageInstance({})", channel(c).await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod routes_tests {
+    use crate::macros::API_VERSION;
+
+    use super::*;
+
+    const C: ChannelId = ChannelId(1);
+    const M: MessageId = MessageId(2);
+    const U: UserId = UserId(3);
+    const A: ApplicationId = ApplicationId(4);
+    const G: GuildId = GuildId(5);
+    const CMD: CommandId = CommandId(6);
+    const I: InteractionId = InteractionId(7);
+    const R: RoleId = RoleId(8);
+
+    fn emoji() -> Emoji {
+        Emoji::Unicode { name: "x".into() }
+    }
+
+    fn token() -> Token {
+        Token("t".into())
+    }
+
+    /// The path (after the api version) that each route should have. This match is exhaustive so
+    /// that new routes have to be added here.
+    fn expected_path(route: &Route) -> &'static str {
+        match route {
+            GetGatewayBot => "/gateway/bot",
+            ApplicationInfo => "/oauth2/applications/@me",
+            GetChannel(_) => "/channels/1",
+            TriggerTyping(_) => "/channels/1/typing",
+            GetPinnedMessages(_) => "/channels/1/pins",
+            PinMessage(_, _) | UnpinMessage(_, _) => "/channels/1/pins/2",
+            StartThreadInForum(_) => "/channels/1/threads",
+            GetChannelMessages(_) | PostMessage(_) => "/channels/1/messages",
+            GetChannelMessage(_, _) | EditMessage(_, _) | DeleteMessage(_, _) => "/channels/1/messages/2",
+            CreateReaction(_, _, _) | DeleteOwnReaction(_, _, _) => "/channels/1/messages/2/reactions/x/@me",
+            DeleteUserReaction(_, _, _, _) => "/channels/1/messages/2/reactions/x/3",
+            GetReactions(_, _, _) => "/channels/1/messages/2/reactions/x",
+            EndPoll(_, _) => "/channels/1/polls/2/expire",
+            GetPollAnswerVoters(_, _, _) => "/channels/1/polls/2/answers/9",
+            GetGlobalCommands(_) | CreateGlobalCommand(_) | BulkOverwriteGlobalCommands(_) => "/applications/4/commands",
+            GetGlobalCommand(_, _) | EditGlobalCommand(_, _) | DeleteGlobalCommand(_, _) => "/applications/4/commands/6",
+            GetGuildCommands(_, _) | CreateGuildCommand(_, _) | BulkOverwriteGuildCommands(_, _) => "/applications/4/guilds/5/commands",
+            GetGuildCommand(_, _, _) | EditGuildCommand(_, _, _) | DeleteGuildCommand(_, _, _) => "/applications/4/guilds/5/commands/6",
+            CreateInteractionResponse(_, _) => "/interactions/7/t/callback",
+            GetOriginalInteractionResponse(_, _)
+            | EditInteractionResponse(_, _)
+            | DeleteInteractionResponse(_, _) => "/webhooks/4/t/messages/@original",
+            CreateFollowupMessage(_, _) => "/webhooks/4/t",
+            EditFollowupMessage(_, _, _) | DeleteFollowupMessage(_, _, _) => "/webhooks/4/t/messages/2",
+            GetGuildApplicationCommandPermissions(_, _)
+            | BatchEditApplicationCommandPermissions(_, _) => "/applications/4/guilds/5/commands/permissions",
+            GetApplicationCommandPermissions(_, _, _)
+            | EditApplicationCommandPermissions(_, _, _) => "/applications/4/guilds/5/commands/6/permissions",
+            GetUser(_) => "/users/3",
+            ModifyCurrentUser => "/users/@me",
+            GetCurrentUserGuilds => "/users/@me/guilds",
+            LeaveGuild(_) => "/users/@me/guilds/5",
+            CreateDm => "/users/@me/channels",
+            GetGuild(_) => "/guilds/5",
+            GetGuildMember(_, _) | ModifyGuildMember(_, _) | RemoveGuildMember(_, _) => "/guilds/5/members/3",
+            SearchGuildMembers(_) => "/guilds/5/members/search",
+            CreateGuildBan(_, _) | RemoveGuildBan(_, _) => "/guilds/5/bans/3",
+            AddGuildMemberRole(_, _, _) | RemoveGuildMemberRole(_, _, _) => "/guilds/5/members/3/roles/8",
+            GetGuildRoles(_) | CreateGuildRole(_) => "/guilds/5/roles",
+            GetGuildPreview(_) => "/guilds/5/preview",
+            GetGuildWelcomeScreen(_) | ModifyGuildWelcomeScreen(_) => "/guilds/5/welcome-screen",
+            GetGuildOnboarding(_) | ModifyGuildOnboarding(_) => "/guilds/5/onboarding",
+            CreateStageInstance => "/stage-instances",
+            GetStageInstance(_) | ModifyStageInstance(_) | DeleteStageInstance(_) => "/stage-instances/1",
+        }
+    }
+
+    fn all_routes() -> Vec<Route> {
+        vec![
+            GetGatewayBot,
+            ApplicationInfo,
+            GetChannel(C),
+            TriggerTyping(C),
+            GetPinnedMessages(C),
+            PinMessage(C, M),
+            UnpinMessage(C, M),
+            StartThreadInForum(C),
+            GetChannelMessages(C),
+            GetChannelMessage(C, M),
+            PostMessage(C),
+            EditMessage(C, M),
+            DeleteMessage(C, M),
+            CreateReaction(C, M, emoji()),
+            DeleteOwnReaction(C, M, emoji()),
+            DeleteUserReaction(C, M, emoji(), U),
+            GetReactions(C, M, emoji()),
+            EndPoll(C, M),
+            GetPollAnswerVoters(C, M, 9),
+            GetGlobalCommands(A),
+            CreateGlobalCommand(A),
+            GetGlobalCommand(A, CMD),
+            EditGlobalCommand(A, CMD),
+            DeleteGlobalCommand(A, CMD),
+            BulkOverwriteGlobalCommands(A),
+            GetGuildCommands(A, G),
+            CreateGuildCommand(A, G),
+            GetGuildCommand(A, G, CMD),
+            EditGuildCommand(A, G, CMD),
+            DeleteGuildCommand(A, G, CMD),
+            BulkOverwriteGuildCommands(A, G),
+            CreateInteractionResponse(I, token()),
+            GetOriginalInteractionResponse(A, token()),
+            EditInteractionResponse(A, token()),
+            DeleteInteractionResponse(A, token()),
+            CreateFollowupMessage(A, token()),
+            EditFollowupMessage(A, token(), M),
+            DeleteFollowupMessage(A, token(), M),
+            GetGuildApplicationCommandPermissions(A, G),
+            GetApplicationCommandPermissions(A, G, CMD),
+            EditApplicationCommandPermissions(A, G, CMD),
+            BatchEditApplicationCommandPermissions(A, G),
+            GetUser(U),
+            ModifyCurrentUser,
+            GetCurrentUserGuilds,
+            LeaveGuild(G),
+            CreateDm,
+            GetGuild(G),
+            GetGuildMember(G, U),
+            SearchGuildMembers(G),
+            ModifyGuildMember(G, U),
+            RemoveGuildMember(G, U),
+            CreateGuildBan(G, U),
+            RemoveGuildBan(G, U),
+            AddGuildMemberRole(G, U, R),
+            RemoveGuildMemberRole(G, U, R),
+            GetGuildRoles(G),
+            CreateGuildRole(G),
+            GetGuildPreview(G),
+            GetGuildWelcomeScreen(G),
+            ModifyGuildWelcomeScreen(G),
+            GetGuildOnboarding(G),
+            ModifyGuildOnboarding(G),
+            CreateStageInstance,
+            GetStageInstance(C),
+            ModifyStageInstance(C),
+            DeleteStageInstance(C),
+        ]
+    }
+
+    #[test]
+    fn urls_use_api_version() {
+        let base = format!("https://discord.com/api/v{API_VERSION}");
+        for route in all_routes() {
+            let url = route.url();
+            let path = url.strip_prefix(&base)
+                .unwrap_or_else(|| panic!("{route:?} is not under {base}: {url}"));
+            assert_eq!(path, expected_path(&route), "{route:?}");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn urls_are_well_formed() {
+        for route in all_routes() {
+            let url = route.url();
+            let parsed = reqwest::Url::parse(&url).unwrap();
+            assert_eq!(parsed.host_str(), Some("discord.com"), "{route:?}");
+            let path = url.trim_start_matches("https://");
+            assert!(!path.contains("//"), "{route:?} has an empty segment: {url}");
+            assert!(!path.ends_with('/'), "{route:?} has a trailing slash: {url}");
+        }
+    }
+}
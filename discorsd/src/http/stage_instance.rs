@@ -0,0 +1,118 @@
+//! Discord API requests involving stage instances.
+//!
+//! Use these [`impl DiscordClient`](../struct.DiscordClient.html#impl) methods for the low level api
+//! for stage instance related requests.
+
+use std::borrow::Cow;
+
+use serde::Serialize;
+
+use crate::http::{ClientResult, DiscordClient};
+use crate::http::routes::Route::*;
+use crate::model::ids::ChannelId;
+use crate::model::stage_instance::{PrivacyLevel, StageInstance};
+
+/// Stage instance related http requests
+impl DiscordClient {
+    /// Creates a new [`StageInstance`] associated to a Stage channel. Requires the user to be a
+    /// moderator of the Stage channel.
+    ///
+    /// Fires a [`StageInstanceCreate`](crate::shard::dispatch::StageInstanceCreate) Gateway event.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails, or fails to deserialize the response into a `StageInstance`
+    pub async fn create_stage_instance(&self, stage: CreateStageInstance) -> ClientResult<StageInstance> {
+        self.post(CreateStageInstance, stage).await
+    }
+
+    /// Gets the [`StageInstance`] associated with the Stage channel, if it exists.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails, or fails to deserialize the response into a `StageInstance`
+    pub async fn get_stage_instance(&self, channel: ChannelId) -> ClientResult<StageInstance> {
+        self.get(GetStageInstance(channel)).await
+    }
+
+    /// Updates fields of an existing [`StageInstance`]. Requires the user to be a moderator of the
+    /// Stage channel.
+    ///
+    /// Fires a [`StageInstanceUpdate`](crate::shard::dispatch::StageInstanceUpdate) Gateway event.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails, or fails to deserialize the response into a `StageInstance`
+    pub async fn modify_stage_instance(&self, channel: ChannelId, modify: ModifyStageInstance) -> ClientResult<StageInstance> {
+        self.patch(ModifyStageInstance(channel), modify).await
+    }
+
+    /// Deletes the [`StageInstance`]. Requires the user to be a moderator of the Stage channel.
+    ///
+    /// Fires a [`StageInstanceDelete`](crate::shard::dispatch::StageInstanceDelete) Gateway event.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails
+    pub async fn delete_stage_instance(&self, channel: ChannelId) -> ClientResult<()> {
+        self.delete(DeleteStageInstance(channel)).await
+    }
+}
+
+/// Data needed to start a stage, with the [`DiscordClient::create_stage_instance`] method.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct CreateStageInstance {
+    /// The id of the Stage channel
+    pub channel_id: ChannelId,
+    /// The topic of the Stage instance (1-120 characters)
+    pub topic: Cow<'static, str>,
+    /// The privacy level of the Stage instance
+    ///
+    /// default: [`GuildOnly`](PrivacyLevel::GuildOnly)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_level: Option<PrivacyLevel>,
+    /// Notify @everyone that a Stage instance has started
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub send_start_notification: bool,
+}
+
+impl CreateStageInstance {
+    /// Start a stage in `channel` about `topic`, with the default settings.
+    pub fn new<S: Into<Cow<'static, str>>>(channel: ChannelId, topic: S) -> Self {
+        Self {
+            channel_id: channel,
+            topic: topic.into(),
+            privacy_level: None,
+            send_start_notification: false,
+        }
+    }
+}
+
+/// The fields to change with the [`DiscordClient::modify_stage_instance`] method. Fields that are
+/// `None` are left unchanged.
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct ModifyStageInstance {
+    /// The topic of the Stage instance (1-120 characters)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<Cow<'static, str>>,
+    /// The privacy level of the Stage instance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_level: Option<PrivacyLevel>,
+}
+
+#[cfg(test)]
+mod stage_instance_tests {
+    use super::*;
+
+    #[test]
+    fn create_body() {
+        let create = CreateStageInstance::new(ChannelId(1), "Testing Testing, 123");
+        assert_eq!(
+            serde_json::to_value(&create).unwrap(),
+            serde_json::json!({ "channel_id": "1", "topic": "Testing Testing, 123" })
+        );
+
+        let modify = ModifyStageInstance { privacy_level: Some(PrivacyLevel::GuildOnly), ..Default::default() };
+        assert_eq!(serde_json::to_value(&modify).unwrap(), serde_json::json!({ "privacy_level": 2 }));
+    }
+}
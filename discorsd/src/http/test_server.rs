@@ -0,0 +1,93 @@
+//! Local servers for tests to send requests to instead of Discord.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use reqwest::Client;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::http::{DiscordClient, DiscordClientBuilder, RetryPolicy};
+
+/// A client built by `builder` whose requests are proxied through a local server, which handles
+/// each connection with `handle`.
+pub(crate) async fn proxied_client<F, Fut>(builder: DiscordClientBuilder, handle: F) -> DiscordClient
+    where F: Fn(TcpStream) -> Fut + Send + 'static,
+          Fut: Future<Output=()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(handle(stream));
+        }
+    });
+
+    builder.build_from(Client::builder().proxy(reqwest::Proxy::all(format!("http://{addr}")).unwrap()))
+}
+
+/// Reads the request on `stream`.
+async fn read_request(stream: &mut TcpStream) {
+    let mut buf = [0; 1024];
+    let _ = stream.read(&mut buf).await;
+}
+
+/// Responds to the request on `stream` with a 500.
+async fn respond_server_error(stream: &mut TcpStream) {
+    let _ = stream.write_all(
+        b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+    ).await;
+}
+
+/// Never responds on `stream`.
+pub(crate) async fn never_respond(stream: TcpStream) {
+    let _stream = stream;
+    std::future::pending::<()>().await;
+}
+
+/// A client whose requests time out after 100ms, because the server never responds, so that the
+/// error says which route was requested.
+pub(crate) async fn timeout_client() -> DiscordClient {
+    let builder = DiscordClient::builder(String::new()).timeout(Some(Duration::from_millis(100)));
+    proxied_client(builder, never_respond).await
+}
+
+/// A client with the `retry` policy whose requests all fail with a 500, and how many requests the
+/// server has received.
+pub(crate) async fn server_error_client(retry: RetryPolicy) -> (DiscordClient, Arc<AtomicU32>) {
+    let requests = Arc::new(AtomicU32::new(0));
+    let count = Arc::clone(&requests);
+    let client = proxied_client(DiscordClient::builder(String::new()).retry(retry), move |mut stream| {
+        count.fetch_add(1, Ordering::SeqCst);
+        async move {
+            read_request(&mut stream).await;
+            respond_server_error(&mut stream).await;
+        }
+    }).await;
+    (client, requests)
+}
+
+/// A client which never retries and whose requests fail with a 500 after 100ms, and the most
+/// requests the server has handled at the same time.
+pub(crate) async fn slow_client() -> (DiscordClient, Arc<AtomicU32>) {
+    let in_flight = Arc::new(AtomicU32::new(0));
+    let max_in_flight = Arc::new(AtomicU32::new(0));
+    let max = Arc::clone(&max_in_flight);
+    let builder = DiscordClient::builder(String::new()).retry(RetryPolicy::NEVER);
+    let client = proxied_client(builder, move |mut stream| {
+        let in_flight = Arc::clone(&in_flight);
+        let max = Arc::clone(&max);
+        async move {
+            read_request(&mut stream).await;
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            respond_server_error(&mut stream).await;
+        }
+    }).await;
+    (client, max_in_flight)
+}
@@ -1,7 +1,9 @@
 //! Discord API requests involving users.
 
+use std::future::Future;
+
 use async_trait::async_trait;
-use serde_derive::Serialize;
+use serde::Serialize;
 use serde_json::json;
 
 use crate::BotState;
@@ -13,6 +15,7 @@ use crate::model::guild::PartialGuild;
 use crate::model::ids::*;
 use crate::model::message::Message;
 use crate::model::user::User;
+use crate::serde_utils::BoolExt;
 
 impl DiscordClient {
     /// Returns a user object for a given user ID.
@@ -46,8 +49,9 @@ impl DiscordClient {
         self.patch(ModifyCurrentUser, Shim { username, avatar }).await
     }
 
-    /// Returns a list of partial guild objects the current user is a member of.
-    /// Requires the guilds `OAuth2` scope.
+    /// Returns a list of partial guild objects the current user is a member of, at most
+    /// [`CurrentGuildQuery::MAX_LIMIT`] at a time. Use
+    /// [`get_all_current_user_guilds`](Self::get_all_current_user_guilds) to get all of them.
     ///
     /// # Errors
     ///
@@ -56,6 +60,34 @@ impl DiscordClient {
         self.get_query(GetCurrentUserGuilds, query).await
     }
 
+    /// Returns every guild the current user is a member of, paging through
+    /// [`get_current_user_guilds`](Self::get_current_user_guilds) as many times as needed.
+    ///
+    /// # Errors
+    ///
+    /// If any of the http requests fail, or fail to deserialize the response into a
+    /// `Vec<PartialGuild>`.
+    pub async fn get_all_current_user_guilds(&self, with_counts: bool) -> ClientResult<Vec<PartialGuild>> {
+        all_current_user_guilds(|after| {
+            let query = CurrentGuildQuery::new().limit(CurrentGuildQuery::MAX_LIMIT);
+            let query = if with_counts { query.with_counts() } else { query };
+            let query = match after {
+                Some(after) => query.after(after),
+                None => query,
+            };
+            self.get_current_user_guilds(query)
+        }).await
+    }
+
+    /// Leave a guild. Fires a [`GuildDelete`](crate::shard::dispatch::GuildDelete) Gateway event.
+    ///
+    /// # Errors
+    ///
+    /// If the http request fails.
+    pub async fn leave_guild(&self, guild: GuildId) -> ClientResult<()> {
+        self.delete(LeaveGuild(guild)).await
+    }
+
     /// Create a new DM channel with a user.
     ///
     /// # Errors
@@ -66,28 +98,97 @@ impl DiscordClient {
     }
 }
 
-#[derive(Serialize)]
+/// User related caching http requests
+impl<B: Send + Sync> BotState<B> {
+    /// Get the user from the cache, or if it isn't cached (because it doesn't share a guild or DM
+    /// with the bot, such as the author of a referenced message), fetch it with
+    /// [`DiscordClient::get_user`] and cache it.
+    pub async fn user_or_fetch(&self, user: UserId) -> ClientResult<User> {
+        self.user_or_else(user, || self.client.get_user(user)).await
+    }
+
+    async fn user_or_else<F, Fut>(&self, id: UserId, fetch: F) -> ClientResult<User>
+        where F: FnOnce() -> Fut + Send,
+              Fut: Future<Output=ClientResult<User>> + Send,
+    {
+        if let Some(user) = self.cache.user(id).await {
+            return Ok(user);
+        }
+        let user = fetch().await?;
+        self.cache.users.write().await.insert(user.clone());
+        Ok(user)
+    }
+}
+
+/// Gets every guild in pages from `fetch`, which gets the guilds after the given guild, or the
+/// first guilds if it is `None`.
+async fn all_current_user_guilds<F, Fut>(mut fetch: F) -> ClientResult<Vec<PartialGuild>>
+    where F: FnMut(Option<GuildId>) -> Fut,
+          Fut: Future<Output=ClientResult<Vec<PartialGuild>>>,
+{
+    let mut guilds = Vec::new();
+    let mut after = None;
+    loop {
+        let page = fetch(after).await?;
+        let done = page.len() < CurrentGuildQuery::MAX_LIMIT as usize;
+        after = page.last().map(|guild| guild.id);
+        guilds.extend(page);
+        if done || after.is_none() {
+            return Ok(guilds);
+        }
+    }
+}
+
+/// Which guilds to get with [`DiscordClient::get_current_user_guilds`].
+#[derive(Serialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
 pub struct CurrentGuildQuery {
-    /// Get guilds before this guild ID
+    #[serde(skip_serializing_if = "Option::is_none")]
     before: Option<GuildId>,
-    /// Get guilds after this guild ID
+    #[serde(skip_serializing_if = "Option::is_none")]
     after: Option<GuildId>,
-    /// Max number of guilds to return (1-100), defaults to None
-    limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+    #[serde(skip_serializing_if = "bool::is_false")]
+    with_counts: bool,
 }
 
-impl Default for CurrentGuildQuery {
-    fn default() -> Self {
-        Self { before: None, after: None, limit: 100 }
+impl CurrentGuildQuery {
+    /// The most guilds Discord will return at once.
+    pub const MAX_LIMIT: u32 = 200;
+
+    pub fn new() -> Self {
+        Self::default()
     }
-}
 
-// todo
-// impl CurrentGuildQuery {
-//     pub fn before(id: GuildId) -> Self {
-//
-//     }
-// }
+    /// Get guilds before this guild.
+    #[must_use]
+    pub fn before(mut self, guild: GuildId) -> Self {
+        self.before = Some(guild);
+        self
+    }
+
+    /// Get guilds after this guild.
+    #[must_use]
+    pub fn after(mut self, guild: GuildId) -> Self {
+        self.after = Some(guild);
+        self
+    }
+
+    /// Max number of guilds to return (1-200). Defaults to 200.
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Include the [approximate member](PartialGuild::approximate_member_count) and
+    /// [presence](PartialGuild::approximate_presence_count) counts of each guild.
+    #[must_use]
+    pub fn with_counts(mut self) -> Self {
+        self.with_counts = true;
+        self
+    }
+}
 
 #[async_trait]
 pub trait UserExt: Id<Id=UserId> + Sized {
@@ -127,4 +228,105 @@ pub trait UserExt: Id<Id=UserId> + Sized {
 }
 
 #[async_trait]
-impl<U: Id<Id=UserId>> UserExt for U {}
\ No newline at end of file
+impl<U: Id<Id=UserId>> UserExt for U {}
+
+#[cfg(test)]
+mod user_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn partial_guild(id: u64) -> PartialGuild {
+        serde_json::from_value(serde_json::json!({
+            "id": id.to_string(),
+            "name": "1337 Krew",
+            "icon": "8342729096ea3675442027381ff50dfe",
+            "banner": null,
+            "owner": true,
+            "permissions": "36953089",
+            "features": ["COMMUNITY", "NEWS"],
+        })).unwrap()
+    }
+
+    #[test]
+    fn current_user_guilds_response() {
+        let guilds: Vec<PartialGuild> = serde_json::from_str(r#"[
+          {
+            "id": "80351110224678912",
+            "name": "1337 Krew",
+            "icon": "8342729096ea3675442027381ff50dfe",
+            "banner": "bb42bdc37653b7cf58c4c8cc622e76cb",
+            "owner": true,
+            "permissions": "36953089",
+            "features": ["COMMUNITY", "NEWS", "ANIMATED_ICON"],
+            "approximate_member_count": 3268,
+            "approximate_presence_count": 784
+          },
+          {
+            "id": "80351110224678913",
+            "name": "Other Guild",
+            "icon": null,
+            "owner": false,
+            "permissions": "0",
+            "features": []
+          }
+        ]"#).unwrap();
+        assert_eq!(guilds.len(), 2);
+        assert_eq!(guilds[0].id, GuildId(80_351_110_224_678_912));
+        assert!(guilds[0].owner);
+        assert!(guilds[0].features.contains(&crate::model::guild::GuildFeature::Community));
+        assert_eq!(guilds[0].approximate_member_count, Some(3268));
+        assert_eq!(guilds[1].banner, None);
+        assert_eq!(guilds[1].approximate_presence_count, None);
+    }
+
+    #[test]
+    fn current_user_guilds_query() {
+        let query = serde_json::to_value(CurrentGuildQuery::new()).unwrap();
+        assert_eq!(query, serde_json::json!({}));
+
+        let query = CurrentGuildQuery::new().after(GuildId(5)).limit(10).with_counts();
+        assert_eq!(
+            serde_json::to_value(query).unwrap(),
+            serde_json::json!({ "after": "5", "limit": 10, "with_counts": true }),
+        );
+    }
+
+    #[tokio::test]
+    async fn all_current_user_guilds_pages() {
+        let fetches = AtomicUsize::new(0);
+        // 450 guilds, with ids 1 to 450
+        let guilds = all_current_user_guilds(|after| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            let first = after.map_or(1, |after| after.0 + 1);
+            async move {
+                Ok((first..=450)
+                    .take(CurrentGuildQuery::MAX_LIMIT as usize)
+                    .map(partial_guild)
+                    .collect())
+            }
+        }).await.unwrap();
+        assert_eq!(guilds.len(), 450);
+        assert_eq!(guilds.last().map(|g| g.id), Some(GuildId(450)));
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn user_or_fetch() {
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), ());
+        let fetch_count = AtomicUsize::new(0);
+        let fetch = || async {
+            fetch_count.fetch_add(1, Ordering::SeqCst);
+            Ok(serde_json::from_str(r#"{ "id": "11", "username": "mason", "discriminator": "0", "avatar": null }"#).unwrap())
+        };
+
+        let fetched = state.user_or_else(UserId(11), fetch).await.unwrap();
+        assert_eq!(fetched.username, "mason");
+        assert_eq!(fetch_count.load(Ordering::SeqCst), 1);
+        assert!(state.cache.user(UserId(11)).await.is_some());
+
+        let cached = state.user_or_else(UserId(11), fetch).await.unwrap();
+        assert_eq!(cached.username, "mason");
+        assert_eq!(fetch_count.load(Ordering::SeqCst), 1);
+    }
+}
@@ -40,6 +40,8 @@ pub use cache::IdMap;
 mod macros;
 mod cache;
 mod serde_utils;
+#[cfg(test)]
+mod test_fixtures;
 mod utils;
 
 pub mod bot;
@@ -65,7 +65,69 @@ macro_rules! serde_bitflag {
 ///     }
 /// }
 /// ```
+///
+/// Enums that Discord adds variants to often can add an `unknown` variant, which unexpected values
+/// are deserialized into (with a warning) instead of failing:
+///
+/// ```ignore
+/// serde_repr! {
+///     pub enum Type: u8, unknown = Unknown {
+///         TheTuple = 1,
+///         TheUnit = 2,
+///     }
+/// }
+/// ```
 macro_rules! serde_repr {
+    (
+        $(#[$outer:meta])*
+        pub enum $enum_name:ident: $repr:tt, unknown = $unknown:ident {
+            $(
+                $(#[$inner:meta])*
+                $variant:ident = $num:literal
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $enum_name {
+            $(
+                $(#[$inner])*
+                $variant,
+            )*
+            /// A value discorsd doesn't know about yet, kept so that it serializes back to the
+            /// same value.
+            $unknown($repr),
+        }
+
+        impl ::serde::Serialize for $enum_name {
+            fn serialize<S: ::serde::ser::Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+                let value: $repr = match *self {
+                    $(
+                        Self::$variant => $num,
+                    )*
+                    Self::$unknown(value) => value,
+                };
+                value.serialize(s)
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $enum_name {
+            fn deserialize<D: ::serde::de::Deserializer<'de>>(d: D) -> ::std::result::Result<Self, D::Error> {
+                match <$repr>::deserialize(d)? {
+                    $(
+                        $num => ::std::result::Result::Ok(Self::$variant),
+                    )*
+                    other => {
+                        ::log::warn!(
+                            concat!("Unknown ", stringify!($enum_name), " {}, using `", stringify!($unknown), "`"),
+                            other,
+                        );
+                        ::std::result::Result::Ok(Self::$unknown(other))
+                    }
+                }
+            }
+        }
+    };
     (
         $(#[$outer:meta])*
         pub enum $enum_name:ident: $repr:tt {
@@ -151,7 +213,7 @@ macro_rules! serde_num_tag {
     // `type` as `new_enum`
     (
         make_serde_repr
-        [ ]
+        [ ] [ ]
         $enum_name:ident, $tag_type:ty,
         $($var_name:ident, [$($tag_value:tt)+])+
     ) => {
@@ -169,12 +231,12 @@ macro_rules! serde_num_tag {
     };
     (
         make_serde_repr
-        [ $repr_name:ident ]
+        [ $repr_name:ident ] [ $($unknown:ident)? ]
         $enum_name:ident, $tag_type:ty,
         $($var_name:ident, [$($tag_value:tt)+])+
     ) => {
         serde_repr! {
-            pub enum $repr_name: $tag_type {
+            pub enum $repr_name: $tag_type $(, unknown = $unknown)? {
                 $(
                     $var_name = $($tag_value)+,
                 )+
@@ -243,7 +305,9 @@ macro_rules! serde_num_tag {
         [ $rename:literal ]
         [ $tag_name:literal, $tag_type:ty ]
     ) => {
-        #[derive(::serde_derive::Serialize)]
+        // only used if there are tuple variants
+        #[allow(dead_code)]
+        #[derive(::serde::Serialize)]
         struct Shim<'t, T> {
             #[serde(rename = $tag_name)]
             variant: $tag_type,
@@ -256,7 +320,9 @@ macro_rules! serde_num_tag {
         [ ]
         [ $tag_name:literal, $tag_type:ty ]
     ) => {
-        #[derive(::serde_derive::Serialize)]
+        // only used if there are tuple variants
+        #[allow(dead_code)]
+        #[derive(::serde::Serialize)]
         struct Shim<'t, T> {
             #[serde(rename = $tag_name)]
             variant: $tag_type,
@@ -282,7 +348,7 @@ macro_rules! serde_num_tag {
         $tag_value:expr;
         $s:expr
     ) => {
-        #[derive(::serde_derive::Serialize)]
+        #[derive(::serde::Serialize)]
         struct Shim<'a> {
             #[serde(rename = $tag_name)]
             variant: $tag_type,
@@ -308,7 +374,7 @@ macro_rules! serde_num_tag {
         $(just $skip_ser_or_de:tt =>)?
         $(#[$enum_meta:meta])*
         pub enum $enum_name:ident = $tag_name:literal $(alias $($alias:literal),+ $(,)?)? :
-                 $tag_type:ty $(as $repr_name:ident)? $(, inner = $rename:literal)? {
+                 $tag_type:ty $(as $repr_name:ident)? $(, unknown = $unknown:ident)? $(, inner = $rename:literal)? {
             $(
                 // todo distinguish the docs from the other meta stuff and copy those into the repr
                 $(#[$variant_meta:meta])*
@@ -355,7 +421,7 @@ macro_rules! serde_num_tag {
         // make a serde_repr enum for the values of this
         serde_num_tag! {
             make_serde_repr
-            [ $($repr_name)? ]
+            [ $($repr_name)? ] [ $($unknown)? ]
             $enum_name, $tag_type,
             $($var_name, [$($tag_value)+])+
         }
@@ -379,7 +445,7 @@ macro_rules! serde_num_tag {
                                 $($tag_value)+ => {
                                     // extra stuff for handling braced variants
                                     $(
-                                        #[derive(::serde_derive::Deserialize)]
+                                        #[derive(::serde::Deserialize)]
                                         struct Shim {
                                             $(
                                                 $(#[serde($struct_field_serde)])*
@@ -416,7 +482,9 @@ macro_rules! serde_num_tag {
             {
                 impl ::serde::Serialize for $enum_name {
                     fn serialize<S: ::serde::Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
-                        #[derive(::serde_derive::Serialize)]
+                        // only used if there are unit variants
+                        #[allow(dead_code)]
+                        #[derive(::serde::Serialize)]
                         struct UnitShim {
                             #[serde(rename = $tag_name)]
                             variant: $tag_type,
@@ -464,9 +532,8 @@ macro_rules! serde_num_tag {
 mod tag_by_num {
     use std::fmt::Debug;
 
-    use serde_derive::{Deserialize, Serialize};
+    use serde::{Deserialize, Serialize};
     use serde::de::DeserializeOwned;
-    use serde::Serialize;
 
     use crate::model::ids::*;
 
@@ -1,4 +1,4 @@
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::model::ids::{ChannelId, GuildId, RoleId, RuleId, UserId};
 
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::model::ids::*;
 pub use crate::model::ids::ChannelId;
@@ -10,7 +10,7 @@ use crate::model::voice::VoiceRegion;
 serde_num_tag! {
     /// Represents a guild or DM channel within Discord.
     #[derive(Debug, Clone)]
-    pub enum Channel = "type": u8 as ChannelType {
+    pub enum Channel = "type": u8 as ChannelType, unknown = Unknown {
         /// a text channel within a server
         (0) = Text(TextChannel),
         /// a direct message between users
@@ -83,6 +83,37 @@ impl Channel {
         }
     }
 
+    /// The name of this channel, or `None` for [Dm](Self::Dm) channels, which don't have names.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Text(t) => Some(&t.name),
+            Self::Voice(v) => Some(&v.name),
+            Self::Category(c) => Some(&c.name),
+            Self::Announcement(n) => Some(&n.name),
+            Self::Dm(_) => None,
+            Self::GroupDm(g) => Some(&g.name),
+            Self::AnnouncementThread(t) | Self::PublicThread(t) | Self::PrivateThread(t) => Some(&t.name),
+            Self::GuildStageVoice(v) => Some(&v.name),
+            Self::GuildDirectory(d) => Some(&d.name),
+            Self::GuildForum(f) => Some(&f.name),
+        }
+    }
+
+    /// The id of the category this channel is in, or for threads, the channel the thread was
+    /// created in.
+    pub const fn parent_id(&self) -> Option<ChannelId> {
+        match self {
+            Self::Text(t) => t.parent_id,
+            Self::Voice(v) => v.parent_id,
+            Self::Announcement(n) => n.parent_id,
+            Self::AnnouncementThread(t) | Self::PublicThread(t) | Self::PrivateThread(t) => t.parent_id,
+            Self::GuildStageVoice(v) => v.parent_id,
+            Self::GuildDirectory(d) => d.parent_id,
+            Self::GuildForum(f) => f.parent_id,
+            Self::Dm(_) | Self::GroupDm(_) | Self::Category(_) => None,
+        }
+    }
+
     pub const fn text(&self) -> Option<&TextChannel> {
         match self {
             Self::Text(text) => Some(text),
@@ -109,9 +140,9 @@ impl Channel {
             Self::AnnouncementThread(_) => None,
             Self::PublicThread(_) => None,
             Self::PrivateThread(_) => None,
-            Self::GuildStageVoice(_) => None,
+            Self::GuildStageVoice(v) => Some(&v.permission_overwrites),
             Self::GuildDirectory(_) => None,
-            Self::GuildForum(_) => None,
+            Self::GuildForum(f) => Some(&f.permission_overwrites),
         }
     }
 }
@@ -503,7 +534,8 @@ serde_repr! {
     }
 }
 
-/// a voice channel within a server
+/// a voice channel for hosting events with an audience, see
+/// [`StageInstance`](crate::model::stage_instance::StageInstance)
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GuildStageVoice {
     /// the id of this channel
@@ -525,7 +557,8 @@ pub struct GuildStageVoice {
     /// voice region id for the voice channel, automatic when set to null
     pub rtc_region: Option<VoiceRegion>,
     /// the camera video quality mode of the voice channel, 1 when not present
-    pub video_quality_mode: VideoQualityMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_quality_mode: Option<VideoQualityMode>,
 }
 
 id_impl!(GuildStageVoice => id: ChannelId);
@@ -561,7 +594,8 @@ impl From<GuildDirectory> for Channel {
     }
 }
 
-/// a voice channel within a server
+/// a channel within a server that can only contain threads, such as those started with
+/// [`start_thread_in_forum`](crate::http::DiscordClient::start_thread_in_forum)
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GuildForum {
     /// the id of this channel
@@ -570,11 +604,25 @@ pub struct GuildForum {
     pub guild_id: Option<GuildId>,
     /// sorting position of the channel
     pub position: u32,
+    /// explicit permission overwrites for members and roles
+    #[serde(default)]
+    pub permission_overwrites: Vec<Overwrite>,
     /// the name of the channel (2-100 characters)
     pub name: String,
+    /// whether the channel is nsfw
+    #[serde(default)]
+    pub nsfw: bool,
     /// id of the parent category for a channel (each parent category can contain up to 50 channels)
     pub parent_id: Option<ChannelId>,
-    /// the channel topic (0-1024 characters)
+    /// the id of the last thread created in this channel (may not point to an existing or valid
+    /// thread)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_message_id: Option<MessageId>,
+    /// amount of seconds a user has to wait before creating another thread (0-21600); bots, as
+    /// well as users with the permission `manage_messages` or `manage_channel`, are unaffected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_user: Option<u32>,
+    /// the guidelines shown when creating a thread in this channel (0-4096 characters)
     pub topic: Option<String>,
     /// default duration, copied onto newly created threads, in minutes, threads will stop showing
     /// in the channel list after the specified period of inactivity
@@ -582,12 +630,14 @@ pub struct GuildForum {
     /// channel flags
     pub flags: Option<ChannelFlags>,
     /// the set of tags that can be used in a GUILD_FORUM channel
+    #[serde(default)]
     pub available_tags: Vec<Tag>,
     /// the emoji to show in the add reaction button on a thread in a GUILD_FORUM channel
     pub default_reaction_emoji: Option<TagEmoji>,
     /// the initial rate_limit_per_user to set on newly created threads in a channel. this field is
     /// copied to the thread at creation time and does not live update.
-    pub default_thread_rate_limit_per_user: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_thread_rate_limit_per_user: Option<u32>,
     /// the default sort order type used to order posts in GUILD_FORUM channels. Defaults to null,
     /// which indicates a preferred sort order hasn't been set by a channel admin
     pub default_sort_order: Option<SortOrder>,
@@ -601,12 +651,15 @@ impl From<GuildForum> for Channel {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+/// The emoji shown on a [`Tag`] or as a [`GuildForum`]'s default reaction.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum TagEmoji {
+    /// a unicode emoji
     Unicode {
         emoji_name: String,
     },
+    /// a custom guild emoji
     Custom {
         emoji_id: EmojiId,
     },
@@ -620,8 +673,9 @@ pub struct Tag {
     pub name: String,
     /// whether this tag can only be added to or removed from threads by a member with the MANAGE_THREADS permission
     pub moderated: bool,
+    /// the emoji shown with this tag, if any
     #[serde(flatten)]
-    default_reaction: TagEmoji,
+    pub emoji: Option<TagEmoji>,
 }
 
 serde_repr! {
@@ -835,4 +889,51 @@ mod channel_tests {
         let back = serde_json::to_string_pretty(&channel).unwrap();
         assert_eq!(json, back)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn guild_forum() {
+        let json = r#"{
+  "type": 15,
+  "id": "1000000000000000001",
+  "guild_id": "41771983423143937",
+  "name": "help",
+  "position": 3,
+  "permission_overwrites": [],
+  "nsfw": false,
+  "parent_id": null,
+  "last_message_id": "1000000000000000009",
+  "rate_limit_per_user": 0,
+  "topic": "Ask your questions here",
+  "default_auto_archive_duration": 4320,
+  "flags": 16,
+  "available_tags": [
+    { "id": "2", "name": "solved", "moderated": true, "emoji_id": null, "emoji_name": "✅" },
+    { "id": "3", "name": "bug", "moderated": false, "emoji_id": "4", "emoji_name": null },
+    { "id": "5", "name": "question", "moderated": false, "emoji_id": null, "emoji_name": null }
+  ],
+  "default_reaction_emoji": { "emoji_id": null, "emoji_name": "👍" },
+  "default_thread_rate_limit_per_user": 0,
+  "default_sort_order": 1
+}"#;
+        let forum = match serde_json::from_str(json).unwrap() {
+            Channel::GuildForum(forum) => forum,
+            other => panic!("expected a forum, got {other:?}"),
+        };
+        assert_eq!(forum.topic.as_deref(), Some("Ask your questions here"));
+        assert_eq!(forum.flags, Some(ChannelFlags::REQUIRE_TAG));
+        assert_eq!(forum.default_sort_order, Some(SortOrder::CreationDate));
+        assert_eq!(forum.default_reaction_emoji, Some(TagEmoji::Unicode { emoji_name: "👍".into() }));
+        let tags = forum.available_tags.iter()
+            .map(|tag| (tag.id, tag.name.as_str(), tag.moderated, tag.emoji.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(tags, [
+            (TagId(2), "solved", true, Some(TagEmoji::Unicode { emoji_name: "✅".into() })),
+            (TagId(3), "bug", false, Some(TagEmoji::Custom { emoji_id: EmojiId(4) })),
+            (TagId(5), "question", false, None),
+        ]);
+
+        // tags without an emoji don't serialize one
+        let back = serde_json::to_value(Channel::GuildForum(forum)).unwrap();
+        assert_eq!(back["available_tags"][2], serde_json::json!({ "id": "5", "name": "question", "moderated": false }));
+    }
+}
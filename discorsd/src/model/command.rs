@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::model::commands::{CommandData};
 use crate::commands::slash_command::SlashCommandRaw;
@@ -12,7 +12,7 @@ use crate::model::ids::{ApplicationId, ChannelId, CommandId, MentionableId, Role
 use crate::model::locales::Locale;
 use crate::model::message::Attachment;
 use crate::model::permissions::Permissions;
-use crate::serde_utils::null_as_default;
+use crate::serde_utils::{BoolExt, null_as_default};
 
 // todo
 /// `CHAT_INPUT` command names and command option names must match the following regex
@@ -25,6 +25,7 @@ static NAME_REGEX: Lazy<Regex> = Lazy::new(||
 );
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(from = "RawApplicationCommand")]
 pub struct ApplicationCommand {
     /// Unique ID of command
     pub id: CommandId,
@@ -41,8 +42,9 @@ pub struct ApplicationCommand {
     /// Indicates whether the command is available in DMs with the app, only for globally-scoped commands. By default, commands are visible.
     #[serde(default)]
     pub dm_permission: bool,
-    /// Indicates whether the command is age-restricted, defaults to false
-    #[serde(default)]
+    /// Indicates whether the command is age-restricted, defaults to false. Always the same as
+    /// [`command`](Self::command)'s [`CommandSettings::nsfw`], which is what gets serialized.
+    #[serde(skip_serializing)]
     pub nsfw: bool,
     // /// Auto-incrementing version identifier updated during substantial record changes
     // pub version: usize,
@@ -51,10 +53,41 @@ pub struct ApplicationCommand {
 }
 id_impl!(ApplicationCommand => CommandId);
 
+/// [`ApplicationCommand`] without `nsfw`, so that deserializing leaves `nsfw` for the flattened
+/// [`Command`] to read.
+#[derive(Deserialize)]
+struct RawApplicationCommand {
+    id: CommandId,
+    application_id: ApplicationId,
+    #[serde(default)]
+    guild_id: Option<CommandId>,
+    #[serde(default)]
+    default_member_permissions: Option<Permissions>,
+    #[serde(default)]
+    dm_permission: bool,
+    #[serde(flatten)]
+    command: Command,
+}
+
+impl From<RawApplicationCommand> for ApplicationCommand {
+    fn from(raw: RawApplicationCommand) -> Self {
+        let RawApplicationCommand { id, application_id, guild_id, default_member_permissions, dm_permission, command } = raw;
+        Self {
+            id,
+            application_id,
+            guild_id,
+            default_member_permissions,
+            dm_permission,
+            nsfw: command.settings().nsfw,
+            command,
+        }
+    }
+}
+
 serde_num_tag! {
     /// This command is sent to Discord
-    #[derive(Debug, PartialEq)]
-    pub enum Command = "type": u8 as CommandType {
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Command = "type": u8 as CommandType, unknown = Unknown {
         /// Slash commands; a text-based command that shows up when a user types /
         (1) = SlashCommand {
             /// Name of command, 1-32 characters
@@ -74,6 +107,9 @@ serde_num_tag! {
             /// Parameters for the command, max of 25
             #serde = default
             options: Vec<CommandOption>,
+            /// Settings shared by every type of command
+            #serde = flatten
+            settings: CommandSettings,
         },
         /// A UI-based command that shows up when you right click or tap on a user
         (2) = UserCommand {
@@ -84,6 +120,9 @@ serde_num_tag! {
             #serde = default
             #serde = skip_serializing_if = "HashMap::is_empty"
             name_localizations: HashMap<Locale, Cow<'static, str>>,
+            /// Settings shared by every type of command
+            #serde = flatten
+            settings: CommandSettings,
         },
         /// A UI-based command that shows up when you right click or tap on a message
         (3) = MessageCommand {
@@ -94,6 +133,9 @@ serde_num_tag! {
             #serde = default
             #serde = skip_serializing_if = "HashMap::is_empty"
             name_localizations: HashMap<Locale, Cow<'static, str>>,
+            /// Settings shared by every type of command
+            #serde = flatten
+            settings: CommandSettings,
         },
     }
 }
@@ -111,6 +153,7 @@ impl Command {
             description,
             description_localizations: Default::default(),
             options,
+            settings: CommandSettings::default(),
         }
     }
 
@@ -121,6 +164,7 @@ impl Command {
         Self::UserCommand {
             name: name.into(),
             name_localizations: Default::default(),
+            settings: CommandSettings::default(),
         }
     }
     pub fn message_command(
@@ -130,8 +174,86 @@ impl Command {
         Self::MessageCommand {
             name: name.into(),
             name_localizations: Default::default(),
+            settings: CommandSettings::default(),
+        }
+    }
+
+    /// The settings shared by every type of command.
+    pub const fn settings(&self) -> &CommandSettings {
+        match self {
+            Self::SlashCommand { settings, .. }
+            | Self::UserCommand { settings, .. }
+            | Self::MessageCommand { settings, .. } => settings,
         }
     }
+
+    /// Mutable access to the settings shared by every type of command.
+    pub fn settings_mut(&mut self) -> &mut CommandSettings {
+        match self {
+            Self::SlashCommand { settings, .. }
+            | Self::UserCommand { settings, .. }
+            | Self::MessageCommand { settings, .. } => settings,
+        }
+    }
+
+    /// Whether this command is age-restricted.
+    pub const fn is_nsfw(&self) -> bool {
+        self.settings().nsfw
+    }
+
+    /// Mark this command as age-restricted (or not), so that it is only shown to users who have
+    /// verified their age.
+    pub fn set_nsfw(&mut self, age_restricted: bool) {
+        self.settings_mut().nsfw = age_restricted;
+    }
+
+    /// Set where this command can be installed, `None` to use the app's default.
+    pub fn set_integration_types(&mut self, types: Option<Vec<ApplicationIntegrationType>>) {
+        self.settings_mut().integration_types = types;
+    }
+
+    /// Set where this command can be used, `None` to allow it everywhere.
+    pub fn set_contexts(&mut self, contexts: Option<Vec<InteractionContextType>>) {
+        self.settings_mut().contexts = contexts;
+    }
+}
+
+/// The settings that every type of [`Command`] has.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct CommandSettings {
+    /// Indicates whether the command is age-restricted, defaults to false
+    #[serde(default, skip_serializing_if = "bool::is_false")]
+    pub nsfw: bool,
+    /// Installation contexts where the command is available, only for globally-scoped commands.
+    /// Defaults to the app's configured contexts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integration_types: Option<Vec<ApplicationIntegrationType>>,
+    /// Interaction contexts where the command can be used, only for globally-scoped commands.
+    /// Defaults to all contexts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contexts: Option<Vec<InteractionContextType>>,
+}
+
+serde_repr! {
+    /// Where an app can be installed, which determines where its commands are available.
+    pub enum ApplicationIntegrationType: u8 {
+        /// App is installable to servers
+        GuildInstall = 0,
+        /// App is installable to users
+        UserInstall = 1,
+    }
+}
+
+serde_repr! {
+    /// Where a command can be used.
+    pub enum InteractionContextType: u8 {
+        /// Interaction can be used within servers
+        Guild = 0,
+        /// Interaction can be used within DMs with the app's bot user
+        BotDm = 1,
+        /// Interaction can be used within Group DMs and DMs other than the app's bot user
+        PrivateChannel = 2,
+    }
 }
 
 serde_num_tag! {
@@ -440,6 +562,7 @@ mod tests {
             name_localizations: Default::default(),
             description: "Send a random adorable animal photo".into(),
             description_localizations: Default::default(),
+            settings: CommandSettings::default(),
             options: vec![
                 CommandOption::String(OptionData {
                     name: "animal".into(),
@@ -497,6 +620,7 @@ mod tests {
             name_localizations: Default::default(),
             description: "Get or edit permissions for a user or a role".into(),
             description_localizations: Default::default(),
+            settings: CommandSettings::default(),
             options: vec![],
         };
         test(CORRECT, command);
@@ -528,6 +652,7 @@ mod tests {
             name_localizations: Default::default(),
             description: "Get or edit permissions for a user or a role".into(),
             description_localizations: Default::default(),
+            settings: CommandSettings::default(),
             options: vec![
                 CommandOption::SubCommandGroup(OptionData {
                     name: "user".into(),
@@ -604,6 +729,7 @@ mod tests {
             name_localizations: Default::default(),
             description: "Get or edit permissions for a user or a role".into(),
             description_localizations: Default::default(),
+            settings: CommandSettings::default(),
             options: vec![
                 CommandOption::SubCommandGroup(OptionData {
                     name: "user".into(),
@@ -770,6 +896,7 @@ mod tests {
             name_localizations: Default::default(),
             description: "Get or edit permissions for a user or a role".into(),
             description_localizations: Default::default(),
+            settings: CommandSettings::default(),
             options: vec![
                 CommandOption::SubCommandGroup(OptionData {
                     name: "user".into(),
@@ -911,4 +1038,22 @@ mod tests {
         };
         test(CORRECT, command);
     }
+
+    #[test]
+    fn application_command_nsfw() {
+        let command: ApplicationCommand = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "application_id": "2",
+            "type": 2,
+            "name": "Info",
+            "nsfw": true,
+            "contexts": [0],
+        })).unwrap();
+        assert!(command.nsfw);
+        assert!(command.command.is_nsfw());
+        assert_eq!(command.command.settings().contexts, Some(vec![InteractionContextType::Guild]));
+
+        let json = serde_json::to_value(&command).unwrap();
+        assert_eq!(json["nsfw"], true);
+    }
 }
\ No newline at end of file
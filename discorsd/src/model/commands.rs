@@ -16,11 +16,11 @@ use crate::errors::*;
 use crate::http::{ClientResult, DiscordClient};
 use crate::http::interaction::WebhookMessage;
 use crate::model::{command, ids::*};
-use crate::model::command::{Choice, CommandDataOption, CommandOption, OptionData, OptionType, SubCommandGroupOption, SubCommandOption};
+use crate::model::command::{ApplicationIntegrationType, Choice, CommandDataOption, InteractionContextType, CommandOption, OptionData, OptionType, SubCommandGroupOption, SubCommandOption};
 use crate::model::components::{ComponentId, SelectMenuType, SelectOption};
 use crate::model::guild::GuildMember;
 use crate::model::interaction::{ButtonPressData, DataOption, DmUser, GuildUser, HasValue, InteractionDataOption, InteractionOption, InteractionUser, MenuSelectData, MenuSelectDataRaw, ModalSubmitData, SubCommand, SubCommandGroup, TextSubmitData, Token};
-use crate::model::interaction_response::{InteractionMessage, InteractionResponse, Modal};
+use crate::model::interaction_response::{InteractionMessage, InteractionResponse, message, Modal};
 use crate::model::message::{Attachment, Message};
 use crate::model::user::User;
 
@@ -67,15 +67,24 @@ impl<A: ApplicationCommandData> InteractionPayload for A {}
 
 impl ApplicationCommandData for AppCommandData {}
 
-pub trait ComponentData: Send + Sync {}
+pub trait ComponentData: Send + Sync {
+    /// The message the component is attached to, if it's known.
+    fn message(&self) -> Option<&Message> { None }
+}
 
 impl<C: ComponentData> ApplicationCommandData for C {}
 
-impl ComponentData for ButtonPressData {}
+impl ComponentData for ButtonPressData {
+    fn message(&self) -> Option<&Message> { self.message.as_ref() }
+}
 
-impl ComponentData for MenuSelectDataRaw {}
+impl ComponentData for MenuSelectDataRaw {
+    fn message(&self) -> Option<&Message> { self.message.as_ref() }
+}
 
-impl ComponentData for MenuSelectData {}
+impl ComponentData for MenuSelectData {
+    fn message(&self) -> Option<&Message> { self.message.as_ref() }
+}
 
 impl ComponentData for ComponentId {}
 
@@ -190,6 +199,25 @@ impl<Data: InteractionPayload> InteractionUse<Data, Unused> {
         ).await.map(|_| self.into())
     }
 
+    /// Respond to this interaction with `content` and a file named `filename` containing `bytes`,
+    /// such as an image generated by the command.
+    pub async fn respond_with_file<Client, N, C>(
+        self,
+        client: Client,
+        filename: N,
+        bytes: Vec<u8>,
+        content: C,
+    ) -> ClientResult<InteractionUse<Data, Used>>
+        where Client: AsRef<DiscordClient> + Send,
+              N: ToString + Send,
+              C: Into<Cow<'static, str>> + Send,
+    {
+        self.respond(client, message(|m| {
+            m.content(content);
+            m.attach((filename, bytes));
+        })).await
+    }
+
     pub async fn defer<Client: AsRef<DiscordClient> + Send>(self, client: Client) -> ClientResult<InteractionUse<Data, Deferred>> {
         let client = client.as_ref();
         client.create_interaction_response(
@@ -278,9 +306,22 @@ impl<Data: InteractionPayload> InteractionUse<Data, Deferred> {
     }
 }
 
+impl<C: ComponentData, U: Usability> InteractionUse<C, U> {
+    /// The message whose component was used, such as the bot message a button was clicked on.
+    pub fn source_message(&self) -> Option<&Message> {
+        self.data.message()
+    }
+
+    /// The id of the [source message](Self::source_message), to edit or otherwise refer back to it.
+    pub fn source_message_id(&self) -> Option<MessageId> {
+        self.source_message().map(|message| message.id)
+    }
+}
+
 impl<C: ComponentData + Send, U: Usability + Send> InteractionUse<C, U>
     where InteractionUse<C, Used>: From<Self>,
 {
+    /// Edit the message this component is attached to, replacing it with `message`.
     pub async fn update<Client, Message>(self, client: Client, message: Message) -> ClientResult<InteractionUse<C, Used>>
         where Client: AsRef<DiscordClient> + Send,
               Message: Into<InteractionMessage> + Send,
@@ -293,6 +334,18 @@ impl<C: ComponentData + Send, U: Usability + Send> InteractionUse<C, U>
         ).await.map(|_| self.into())
     }
 
+    /// Edit the message this component is attached to in place, building the new content,
+    /// embeds, and components with `builder`.
+    pub async fn update_message<Client, F>(self, client: Client, builder: F) -> ClientResult<InteractionUse<C, Used>>
+        where Client: AsRef<DiscordClient> + Send,
+              F: FnOnce(&mut InteractionMessage) + Send,
+    {
+        self.update(client, message(builder)).await
+    }
+
+    /// Acknowledge this interaction without changing the message yet, for updates that take a
+    /// while. The user doesn't see a loading state. Edit the message later with
+    /// [`edit`](InteractionUse::edit).
     pub async fn defer_update<Client>(self, client: Client) -> ClientResult<InteractionUse<C, Used>>
         where Client: AsRef<DiscordClient> + Send,
     {
@@ -306,6 +359,10 @@ impl<C: ComponentData + Send, U: Usability + Send> InteractionUse<C, U>
 }
 
 impl<Data: InteractionPayload, U: NotUnused> InteractionUse<Data, U> {
+    /// The message this interaction responded with, to edit or delete it with
+    /// [`Message::edit`] and [`Message::delete`]. Uses the
+    /// [cached response](crate::cache::Cache::interaction_response) if there is one, and otherwise
+    /// fetches it from Discord.
     pub async fn get_message<B, State>(&self, state: State) -> ClientResult<Message>
         where B: 'static + Send + Sync,
               State: AsRef<BotState<B>> + Send
@@ -323,6 +380,16 @@ impl<Data: InteractionPayload, U: NotUnused> InteractionUse<Data, U> {
             ).await
         }
     }
+
+    /// A link to the message this interaction responded with, to point users at it from a
+    /// followup or another channel.
+    pub async fn message_link<B, State>(&self, state: State) -> ClientResult<String>
+        where B: 'static + Send + Sync,
+              State: AsRef<BotState<B>> + Send
+    {
+        self.get_message(state).await
+            .map(|message| message.jump_url(self.guild()))
+    }
 }
 
 // impl<Data: InteractionPayload, Use: Usability> From<InteractionUse<Data, Use>> for InteractionUse<Data, Used> {
@@ -361,7 +428,7 @@ macro_rules! option_primitives {
             impl<C: SlashCommandRaw> CommandData<C> for $ty {
                 type Options = InteractionDataOption;
 
-                fn from_options(option: Self::Options) -> Result<Self, CommandParseError> {
+                fn from_options(_: &C, option: Self::Options) -> Result<Self, CommandParseError> {
                     match option {
                         InteractionDataOption::$variant(
                             DataOption {
@@ -417,7 +484,7 @@ macro_rules! option_integers {
             impl<C: SlashCommandRaw> CommandData<C> for $ty {
                 type Options = InteractionDataOption;
 
-                fn from_options(option: Self::Options) -> Result<Self, CommandParseError> {
+                fn from_options(_: &C, option: Self::Options) -> Result<Self, CommandParseError> {
                     use std::convert::TryInto;
                     match option {
                         InteractionDataOption::Integer(
@@ -467,7 +534,7 @@ macro_rules! option_ids {
             impl<C: SlashCommandRaw> CommandData<C> for $id {
                 type Options = InteractionDataOption;
 
-                fn from_options(options: Self::Options) -> Result<Self, CommandParseError> {
+                fn from_options(_: &C, options: Self::Options) -> Result<Self, CommandParseError> {
                     match options {
                         InteractionDataOption::Integer(
                             DataOption {
@@ -802,9 +869,10 @@ impl VarargState {
 /// the big boi himself
 pub trait CommandData<Command: SlashCommandRaw>: Sized {
     type Options: OptionsLadder + Send;
-    /// function to go from (the options in a) `InteractionData` -> Self
+    /// function to go from (the options in a) `InteractionData` -> Self. `command` is the command
+    /// being run, for data whose parsing depends on the command's state
     #[allow(clippy::result_large_err)]
-    fn from_options(options: Self::Options) -> Result<Self, CommandParseError>;
+    fn from_options(command: &Command, options: Self::Options) -> Result<Self, CommandParseError>;
 
     type VecArg: VecArgLadder;
     // todo: VecArg *maybe* should have the Vec<> on it, so that this can just return one?
@@ -824,12 +892,21 @@ pub trait CommandData<Command: SlashCommandRaw>: Sized {
         unreachable!()
     }
     fn vararg_number() -> VarargState { VarargState::None }
+    /// Whether commands that use this as their data are age-restricted. Set with
+    /// `#[command(nsfw)]` when deriving `CommandData`.
+    const NSFW: bool = false;
+    /// Where commands that use this as their data can be installed, `None` for the app's default.
+    /// Set with `#[command(integration_types(...))]` when deriving `CommandData`.
+    const INTEGRATION_TYPES: Option<&'static [ApplicationIntegrationType]> = None;
+    /// Where commands that use this as their data can be used, `None` for everywhere. Set with
+    /// `#[command(contexts(...))]` when deriving `CommandData`.
+    const CONTEXTS: Option<&'static [InteractionContextType]> = None;
 }
 
 impl<C: SlashCommandRaw> CommandData<C> for Infallible {
     type Options = Self;
 
-    fn from_options(_: Self::Options) -> Result<Self, CommandParseError> {
+    fn from_options(_: &C, _: Self::Options) -> Result<Self, CommandParseError> {
         unreachable!()
     }
 
@@ -847,7 +924,7 @@ impl<C: SlashCommandRaw> CommandData<C> for Infallible {
 impl<Command: SlashCommandRaw> CommandData<Command> for () {
     type Options = Vec<InteractionDataOption>;
 
-    fn from_options(_: Self::Options) -> Result<Self, CommandParseError> {
+    fn from_options(_: &Command, _: Self::Options) -> Result<Self, CommandParseError> {
         Ok(())
     }
 
@@ -865,10 +942,10 @@ impl<Command: SlashCommandRaw> CommandData<Command> for () {
 impl<C: SlashCommandRaw, T: CommandData<C>> CommandData<C> for Option<T> {
     type Options = T::Options;
 
-    fn from_options(data: Self::Options) -> Result<Self, CommandParseError> {
+    fn from_options(command: &C, data: Self::Options) -> Result<Self, CommandParseError> {
         // `T::from_data` failing means that the data was the wrong type, not that it was absent
         // Absent data is handled before calling this function
-        Ok(Some(T::from_options(data)?))
+        Ok(Some(T::from_options(command, data)?))
     }
 
     type VecArg = T::VecArg;
@@ -889,8 +966,9 @@ impl<T, C, S> CommandData<C> for HashSet<T, S>
 {
     type Options = Vec<InteractionDataOption>;
 
-    fn from_options(options: Self::Options) -> Result<Self, CommandParseError> {
-        options.into_iter().map(T::from_options).collect()
+    #[allow(clippy::result_large_err)]
+    fn from_options(command: &C, options: Self::Options) -> Result<Self, CommandParseError> {
+        options.into_iter().map(|o| T::from_options(command, o)).collect()
     }
 
     type VecArg = CommandDataOption;
@@ -918,8 +996,9 @@ impl<T, C> CommandData<C> for BTreeSet<T>
 {
     type Options = Vec<InteractionDataOption>;
 
-    fn from_options(options: Self::Options) -> Result<Self, CommandParseError> {
-        options.into_iter().map(T::from_options).collect()
+    #[allow(clippy::result_large_err)]
+    fn from_options(command: &C, options: Self::Options) -> Result<Self, CommandParseError> {
+        options.into_iter().map(|o| T::from_options(command, o)).collect()
     }
 
     type VecArg = CommandDataOption;
@@ -948,8 +1027,9 @@ impl<T, C> CommandData<C> for Vec<T>
 {
     type Options = Vec<InteractionDataOption>;
 
-    fn from_options(options: Self::Options) -> Result<Self, CommandParseError> {
-        options.into_iter().map(T::from_options).collect()
+    #[allow(clippy::result_large_err)]
+    fn from_options(command: &C, options: Self::Options) -> Result<Self, CommandParseError> {
+        options.into_iter().map(|o| T::from_options(command, o)).collect()
     }
 
     type VecArg = CommandDataOption;
@@ -977,10 +1057,11 @@ impl<T, C, const N: usize> CommandData<C> for [T; N]
 {
     type Options = Vec<InteractionDataOption>;
 
-    fn from_options(options: Vec<InteractionDataOption>) -> Result<Self, CommandParseError> {
+    #[allow(clippy::result_large_err)]
+    fn from_options(command: &C, options: Vec<InteractionDataOption>) -> Result<Self, CommandParseError> {
         let n_recv = options.len();
         options.into_iter()
-            .map(T::from_options)
+            .map(|o| T::from_options(command, o))
             .collect::<Result<Vec<_>, _>>()?
             .try_into()
             .map_err(|_| CommandParseError::MissingOption(
@@ -1009,9 +1090,27 @@ impl<T, C, const N: usize> CommandData<C> for [T; N]
 pub trait MenuData: Sized + FromStr {
     type Data: SelectMenuType;
 
+    /// The minimum number of options that must be chosen in the menu, `None` for Discord's
+    /// default (1)
+    const MIN_VALUES: Option<usize> = None;
+    /// The maximum number of options that can be chosen in the menu, `None` for Discord's
+    /// default (1)
+    const MAX_VALUES: Option<usize> = None;
+
     fn into_option(self) -> <Self::Data as SelectMenuType>::SelectOption;
     fn all() -> Vec<Self>;
     fn options() -> Vec<<Self::Data as SelectMenuType>::SelectOption>;
+
+    /// Parse every value that was chosen in the menu.
+    ///
+    /// # Errors
+    ///
+    /// If any of the `values` fail to parse.
+    fn from_values(values: Vec<String>) -> Result<Vec<Self>, <Self as FromStr>::Err> {
+        values.iter()
+            .map(|value| value.parse())
+            .collect()
+    }
 }
 macro_rules! id_menu {
     ($($id:ty),+ $(,)?) => {
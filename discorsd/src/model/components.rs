@@ -1,8 +1,9 @@
 use std::borrow::Cow;
 use itertools::Itertools;
+use thiserror::Error;
 use serde::{Deserialize, Serialize};
-use serde_derive::{Deserialize, Serialize};
 
+use crate::commands::MenuData;
 use crate::model::channel::ChannelType;
 use crate::model::emoji::Emoji;
 use crate::model::ids::{ChannelId, MentionableId, RoleId, UserId};
@@ -96,6 +97,52 @@ impl<S> From<S> for ComponentId
     }
 }
 
+impl ComponentId {
+    /// The most characters Discord allows in a `custom_id`.
+    pub const MAX_LEN: usize = 100;
+    /// Separates the namespace from the rest of a [namespaced](Self::namespaced) id.
+    pub const SEPARATOR: char = ':';
+
+    /// The id `local` in `namespace`, which is routed to the command registered for `namespace`
+    /// (see [`BotState::register_button_namespace`](crate::BotState::register_button_namespace)),
+    /// so that commands using the same `local` ids don't collide.
+    ///
+    /// # Errors
+    ///
+    /// If `namespace` is empty or contains [`SEPARATOR`](Self::SEPARATOR), or if the id would be
+    /// longer than [`MAX_LEN`](Self::MAX_LEN).
+    pub fn namespaced(namespace: &str, local: &str) -> Result<Self, ComponentIdError> {
+        if namespace.is_empty() || namespace.contains(Self::SEPARATOR) {
+            return Err(ComponentIdError::Namespace(namespace.into()));
+        }
+        let id = format!("{namespace}{}{local}", Self::SEPARATOR);
+        let len = id.chars().count();
+        if len > Self::MAX_LEN {
+            return Err(ComponentIdError::TooLong { len });
+        }
+        Ok(Self(id))
+    }
+
+    /// The namespace of this id, if it was made with [`namespaced`](Self::namespaced).
+    pub fn namespace(&self) -> Option<&str> {
+        self.0.split_once(Self::SEPARATOR).map(|(namespace, _)| namespace)
+    }
+
+    /// This id without its [namespace](Self::namespace).
+    pub fn local(&self) -> &str {
+        self.0.split_once(Self::SEPARATOR).map_or(&self.0, |(_, local)| local)
+    }
+}
+
+/// A [`ComponentId`] could not be [namespaced](ComponentId::namespaced).
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum ComponentIdError {
+    #[error("namespace `{0}` must be non-empty and not contain `:`")]
+    Namespace(String),
+    #[error("custom id is {len} characters, but at most 100 are allowed")]
+    TooLong { len: usize },
+}
+
 // todo have custom_id and url as an enum of some sort?
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Button {
@@ -149,6 +196,12 @@ impl Button {
     pub fn disable(&mut self) {
         self.disabled = true;
     }
+
+    /// Set this button's id, for buttons that are handled by a namespace (see
+    /// [`ComponentId::namespaced`]) rather than registered individually.
+    pub fn custom_id(&mut self, id: ComponentId) {
+        self.custom_id = Some(id);
+    }
 }
 
 pub fn make_button<F: FnOnce(&mut Button)>(builder: F) -> Button {
@@ -239,6 +292,22 @@ impl<T: SelectMenuType> Menu<T> {
         }
     }
 
+    /// A menu with `D`'s options and minimum/maximum number of values.
+    pub fn for_data<D: MenuData<Data=T>>() -> Self {
+        Self {
+            options: D::options(),
+            min_values: D::MIN_VALUES,
+            max_values: D::MAX_VALUES,
+            ..Self::new()
+        }
+    }
+
+    /// Set this menu's id, for menus that are handled by a namespace (see
+    /// [`ComponentId::namespaced`]) rather than registered individually.
+    pub fn custom_id(&mut self, id: ComponentId) {
+        self.custom_id = id;
+    }
+
     /// custom placeholder text if nothing is selected, max 100 characters
     pub fn placeholder<S: Into<Cow<'static, str>>>(&mut self, placeholder: S) {
         self.placeholder = Some(placeholder.into());
@@ -630,4 +699,45 @@ mod tests {
         // };
         // todo
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn menu_for_data() {
+        #[derive(Debug)]
+        struct Pick(String);
+        impl std::str::FromStr for Pick {
+            type Err = ();
+            fn from_str(s: &str) -> Result<Self, ()> { Ok(Self(s.into())) }
+        }
+        impl MenuData for Pick {
+            type Data = String;
+            const MAX_VALUES: Option<usize> = Some(2);
+
+            fn into_option(self) -> SelectOption { self.0.into_option() }
+            fn all() -> Vec<Self> { vec![Self("a".into()), Self("b".into())] }
+            fn options() -> Vec<SelectOption> { Self::all().into_iter().map(Self::into_option).collect() }
+        }
+
+        let menu = serde_json::to_value(Menu::for_data::<Pick>()).unwrap();
+        assert_eq!(menu["max_values"], 2);
+        assert!(menu.get("min_values").is_none());
+        assert_eq!(menu["options"][1]["value"], "b");
+    }
+
+    #[test]
+    fn namespaced_component_id() {
+        let id = ComponentId::namespaced("roles", "add:5").unwrap();
+        assert_eq!(id, ComponentId::from("roles:add:5"));
+        assert_eq!(id.namespace(), Some("roles"));
+        assert_eq!(id.local(), "add:5");
+        assert_eq!(ComponentId::from("12").namespace(), None);
+        assert_eq!(ComponentId::from("12").local(), "12");
+
+        assert_eq!(ComponentId::namespaced("", "a"), Err(ComponentIdError::Namespace(String::new())));
+        assert_eq!(ComponentId::namespaced("a:b", "c"), Err(ComponentIdError::Namespace("a:b".into())));
+        assert!(ComponentId::namespaced("roles", &"x".repeat(94)).is_ok());
+        assert_eq!(
+            ComponentId::namespaced("roles", &"x".repeat(95)),
+            Err(ComponentIdError::TooLong { len: 101 }),
+        );
+    }
+}
@@ -1,16 +1,16 @@
-use std::borrow::Cow;
 use std::fmt;
 
 use futures::{StreamExt, TryStreamExt};
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
-use crate::http::{ClientResult, DiscordClient};
-use crate::model::{Gif, ImageFormat, Png};
+use crate::http::{ClientResult, DiscordClient, percent_encode};
+use crate::model::{cdn_asset_url, Gif, ImageFormat, ImageSize, Png};
 use crate::model::ids::*;
 pub use crate::model::ids::{EmojiId, RoleId};
 use crate::model::user::User;
 use crate::serde_utils::BoolExt;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum Emoji {
@@ -32,11 +32,13 @@ impl Emoji {
         }
     }
 
-    pub fn as_reaction(&self) -> Cow<'_, str> {
+    /// How this emoji is written in the path of reaction routes, ex.
+    /// [`CreateReaction`](crate::http::routes::Route::CreateReaction): the url encoded unicode
+    /// character, or `name:id` for a custom emoji.
+    pub fn as_reaction(&self) -> String {
         match self {
-            Self::Custom(CustomEmoji { id, name, animated, .. }) =>
-                format!("<{}:{}:{}>", if *animated { "a" } else { "" }, name, id).into(),
-            Self::Unicode { name } => name.into(),
+            Self::Custom(CustomEmoji { id, name, .. }) => format!("{}:{}", percent_encode(name), id),
+            Self::Unicode { name } => percent_encode(name),
         }
     }
 
@@ -142,6 +144,20 @@ impl CustomEmoji {
         };
         cdn!("emojis/{}.{}", self.id, ext)
     }
+
+    /// The url where this image can be retrieved from Discord in the format `I`, which is ignored
+    /// if this emoji is [animated](Self::animated) (the image will be a `.gif`). If `I` is an
+    /// animated format and this emoji isn't animated, `None` is returned.
+    pub fn image_url<I: ImageFormat>(&self) -> Option<String> {
+        self.image_url_sized::<I>(None)
+    }
+
+    /// Like [`image_url`](Self::image_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn image_url_sized<I: ImageFormat>(&self, size: Option<ImageSize>) -> Option<String> {
+        cdn_asset_url::<I>("emojis", &self.id.to_string(), self.animated, size)
+    }
 }
 
 impl From<CustomEmoji> for Emoji {
@@ -162,6 +178,12 @@ impl From<char> for Emoji {
     }
 }
 
+impl From<&str> for Emoji {
+    fn from(name: &str) -> Self {
+        Self::Unicode { name: name.to_string() }
+    }
+}
+
 impl DiscordClient {
     /// For each emoji in `emojis` in order, get the users who reacted with that emoji to
     /// this message.
@@ -181,4 +203,48 @@ impl DiscordClient {
             .try_collect()
             .await
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod emoji_tests {
+    use super::*;
+
+    #[test]
+    fn unicode_reaction() {
+        assert_eq!(Emoji::from("👍").as_reaction(), "%F0%9F%91%8D");
+        assert_eq!(Emoji::from('👍').as_reaction(), "%F0%9F%91%8D");
+    }
+
+    #[test]
+    fn custom_reaction() {
+        let emoji = Emoji::from(CustomEmoji::new(EmojiId(123), "name"));
+        assert_eq!(emoji.as_reaction(), "name:123");
+    }
+
+    #[test]
+    fn animated_custom_reaction() {
+        let mut custom = CustomEmoji::new(EmojiId(123), "party_parrot");
+        custom.animated = true;
+        assert_eq!(Emoji::from(custom).as_reaction(), "party_parrot:123");
+    }
+
+    #[test]
+    fn image_urls() {
+        use crate::model::WebP;
+
+        let mut custom = CustomEmoji::new(EmojiId(123), "party_parrot");
+        assert_eq!(custom.url(), "https://cdn.discordapp.com/emojis/123.png");
+        assert_eq!(
+            custom.image_url_sized::<WebP>(ImageSize::new(64)).as_deref(),
+            Some("https://cdn.discordapp.com/emojis/123.webp?size=64"),
+        );
+        assert_eq!(custom.image_url::<Gif>(), None);
+
+        custom.animated = true;
+        assert_eq!(custom.url(), "https://cdn.discordapp.com/emojis/123.gif");
+        assert_eq!(
+            custom.image_url::<Png>().as_deref(),
+            Some("https://cdn.discordapp.com/emojis/123.gif"),
+        );
+    }
+}
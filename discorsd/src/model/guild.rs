@@ -1,14 +1,17 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, Local, Utc};
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
-use crate::cache::IdMap;
-use crate::model::{ImageFormat, StillImage};
+use crate::cache::{Cache, IdMap};
+use crate::model::{cdn_image_url, DiscordError, DiscordErrorType, ImageFormat, ImageSize, StillImage};
 use crate::model::channel::Channel;
+use crate::model::command::CommandType;
 use crate::model::emoji::CustomEmoji;
+use crate::model::message::Sticker;
 use crate::model::ids::*;
 use crate::model::permissions::{Permissions, Role};
+use crate::model::stage_instance::StageInstance;
 use crate::model::user::User;
 use crate::model::voice::VoiceState;
 use crate::shard::dispatch::PresenceUpdate;
@@ -94,20 +97,34 @@ pub struct Guild {
     /// states of members currently in voice channels; lacks the guild_id key
     ///
     /// only sent within the [GuildCreate](crate::shard::dispatch::GuildCreate) event
+    #[serde(default)]
     pub voice_states: IdMap<VoiceState>,
     /// users in the guild
     ///
     /// only sent within the [GuildCreate](crate::shard::dispatch::GuildCreate) event
+    #[serde(default)]
     pub members: IdMap<GuildMember>,
     /// channels in the guild
     ///
     /// only sent within the [GuildCreate](crate::shard::dispatch::GuildCreate) event
+    #[serde(default)]
     pub channels: IdMap<Channel>,
     /// presences of the members in the guild, will only include non-offline members if the size is
     /// greater than `large threshold` only sent within the `GUILD_CREATE` event
     ///
     /// only sent within the [GuildCreate](crate::shard::dispatch::GuildCreate) event
+    #[serde(default)]
     pub presences: IdMap<PresenceUpdate>,
+    /// live stages in the guild
+    ///
+    /// only sent within the [`GuildCreate`](crate::shard::dispatch::GuildCreate) event
+    #[serde(default)]
+    pub stage_instances: IdMap<StageInstance>,
+    /// how many of this application's guild commands of each type are registered in the guild
+    ///
+    /// only sent within the [`GuildCreate`](crate::shard::dispatch::GuildCreate) event
+    #[serde(default)]
+    pub application_command_counts: HashMap<CommandType, u32>,
     /// the maximum number of presences for the guild (the default value, currently 25000, is in effect when `null` is returned)
     pub max_presences: Option<u32>,
     /// the maximum number of members for the guild
@@ -136,61 +153,142 @@ pub struct Guild {
 id_impl!(Guild => id: GuildId);
 
 impl Guild {
-    /// The url where this guild's icon can be retrieved from Discord, if it has an icon. The
-    /// desired format must be specified by `I`. If `I` is an animated format (currently only
-    /// [Gif](crate::model::Gif), the [icon](Guild::icon) must start with `a_` or `None` will be
-    /// returned.
+    /// The most guild commands of each [`CommandType`] an application can have in one guild.
+    pub const MAX_COMMANDS: u32 = 100;
+
+    /// How many of this application's guild commands of type `kind` are registered in this guild,
+    /// as of its [`GuildCreate`](crate::shard::dispatch::GuildCreate) event.
+    pub fn command_count(&self, kind: CommandType) -> u32 {
+        self.application_command_counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Checks that `new` more guild commands of type `kind` can be registered in this guild
+    /// without going over [`MAX_COMMANDS`](Self::MAX_COMMANDS), before making any requests.
     ///
-    /// The returned image size can be changed by appending a querystring of `?size=desired_size` to
-    /// the URL. Image size can be any power of two between 16 and 4096.
-    pub fn icon_url<I: ImageFormat>(&self) -> Option<String> {
-        if I::ANIMATED {
-            self.icon.as_ref().and_then(|icon| if icon.starts_with("a_") {
-                Some(cdn!("icons/{}/{}.{}", self.id, icon, I::EXTENSION))
-            } else {
-                None
+    /// Creating a command with the same name as an existing one replaces it instead of adding a
+    /// new command, so replaced commands should not be counted in `new`.
+    ///
+    /// # Errors
+    ///
+    /// A [`DiscordError`] whose code is
+    /// [`MaxApplicationCommands`](DiscordErrorType::MaxApplicationCommands), the same as Discord
+    /// would respond with, if there isn't room for `new` more commands.
+    pub fn check_command_limit(&self, kind: CommandType, new: u32) -> Result<(), DiscordError> {
+        if self.command_count(kind).saturating_add(new) > Self::MAX_COMMANDS {
+            Err(DiscordError {
+                code: DiscordErrorType::MaxApplicationCommands,
+                message: format!("Maximum number of application commands reached ({})", Self::MAX_COMMANDS),
+                errors: None,
             })
         } else {
-            self.icon.as_ref()
-                .map(|icon| cdn!("icons/{}/{}.{}", self.id, icon, I::EXTENSION))
+            Ok(())
         }
     }
 
+    /// Whether this guild has `feature` enabled.
+    pub fn has_feature(&self, feature: GuildFeature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// The url where this guild's icon can be retrieved from Discord, if it has an icon. The
+    /// desired format must be specified by `I`, but animated icons (starting with `a_`) are always
+    /// a [Gif](crate::model::Gif). If `I` is an animated format, the [icon](Guild::icon) must start
+    /// with `a_` or `None` will be returned.
+    pub fn icon_url<I: ImageFormat>(&self) -> Option<String> {
+        self.icon_url_sized::<I>(None)
+    }
+
+    /// Like [`icon_url`](Self::icon_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn icon_url_sized<I: ImageFormat>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.icon.as_ref()
+            .and_then(|icon| cdn_image_url::<I>(&format!("icons/{}", self.id), icon, size))
+    }
+
+    /// The url where this guild's banner can be retrieved from Discord, if it has one. Like
+    /// [`icon_url`](Self::icon_url), animated banners are always a [Gif](crate::model::Gif).
+    pub fn banner_url<I: ImageFormat>(&self) -> Option<String> {
+        self.banner_url_sized::<I>(None)
+    }
+
+    /// Like [`banner_url`](Self::banner_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn banner_url_sized<I: ImageFormat>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.banner.as_ref()
+            .and_then(|banner| cdn_image_url::<I>(&format!("banners/{}", self.id), banner, size))
+    }
+
     /// The url where this guild's splash can be retrieved from Discord, if it has one
     /// ([`GuildFeature::Discoverable`](GuildFeature::Discoverable) must be one of the guild's
     /// [`features`](Guild::features)). The desired format must be specified by `I`, and can only be
     /// a [`StillImage`](crate::model::StillImage) format.
-    ///
-    /// The returned image size can be changed by appending a querystring of `?size=desired_size` to
-    /// the URL. Image size can be any power of two between 16 and 4096.
     pub fn splash_url<I: StillImage>(&self) -> Option<String> {
+        self.splash_url_sized::<I>(None)
+    }
+
+    /// Like [`splash_url`](Self::splash_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn splash_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> Option<String> {
         self.splash.as_ref()
-            .map(|splash| cdn!("splashes/{}/{}.{}", self.id, splash, I::EXTENSION))
+            .and_then(|splash| cdn_image_url::<I>(&format!("splashes/{}", self.id), splash, size))
     }
 
     /// The url where this guild's discovery splash can be retrieved from Discord, if it has one.
     /// The desired format must be specified by `I`, and can only be a
     /// [`StillImage`](crate::model::StillImage) format.
-    ///
-    /// The returned image size can be changed by appending a querystring of `?size=desired_size` to
-    /// the URL. Image size can be any power of two between 16 and 4096.
     pub fn discovery_splash_url<I: StillImage>(&self) -> Option<String> {
+        self.discovery_splash_url_sized::<I>(None)
+    }
+
+    /// Like [`discovery_splash_url`](Self::discovery_splash_url), but for an image of `size`
+    /// pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn discovery_splash_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> Option<String> {
         self.discovery_splash.as_ref()
-            .map(|splash| cdn!("discovery-splashes/{}/{}.{}", self.id, splash, I::EXTENSION))
+            .and_then(|splash| cdn_image_url::<I>(&format!("discovery-splashes/{}", self.id), splash, size))
     }
 
-    /// The url where this guild's banner can be retrieved from Discord, if it has one. The
-    /// desired format must be specified by `I`, and can only be a
-    /// [`StillImage`](crate::model::StillImage) format.
+    /// Find the role in this guild named `name`, ignoring case. If more than one role has that
+    /// name, the oldest is returned.
+    pub fn role_named(&self, name: &str) -> Option<&Role> {
+        self.roles.iter()
+            .filter(|role| eq_ignore_case(&role.name, name))
+            .min_by_key(|role| role.id)
+    }
+
+    /// Find the channel in this guild named `name`, ignoring case. If more than one channel has
+    /// that name, the oldest is returned.
     ///
-    /// The returned image size can be changed by appending a querystring of `?size=desired_size` to
-    /// the URL. Image size can be any power of two between 16 and 4096.
-    pub fn banner_url<I: StillImage>(&self) -> Option<String> {
-        self.banner.as_ref()
-            .map(|banner| cdn!("banners/{}/{}.{}", self.id, banner, I::EXTENSION))
+    /// Only finds channels if [channels](Self::channels) was sent, as in
+    /// [`GuildCreate`](crate::shard::dispatch::GuildCreate).
+    pub fn channel_named(&self, name: &str) -> Option<&Channel> {
+        self.channels.iter()
+            .filter(|channel| channel.name().is_some_and(|channel| eq_ignore_case(channel, name)))
+            .min_by_key(Id::id)
+    }
+
+    /// Find the member of this guild whose nickname or username is `name`, ignoring case. If more
+    /// than one member has that name, the one whose user is oldest is returned.
+    ///
+    /// Only finds members if [members](Self::members) was sent, as in
+    /// [`GuildCreate`](crate::shard::dispatch::GuildCreate).
+    pub fn member_named(&self, name: &str) -> Option<&GuildMember> {
+        self.members.iter()
+            .filter(|member| member.nick.as_deref().is_some_and(|nick| eq_ignore_case(nick, name))
+                || eq_ignore_case(&member.user.username, name))
+            .min_by_key(Id::id)
     }
 }
 
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars().flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
 serde_repr! {
     pub enum NotificationLevel: u8 {
         AllMessage = 0,
@@ -248,7 +346,11 @@ bitflags! {
 }
 serde_bitflag!(SystemChannelFlags: u8);
 
-#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+/// A feature a guild has enabled.
+///
+/// Discord adds features often, so features discorsd doesn't know about yet are
+/// [`Unknown`](Self::Unknown) instead of failing to deserialize the whole guild.
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum GuildFeature {
     /// guild has access to set an animated guild banner image
@@ -285,6 +387,8 @@ pub enum GuildFeature {
     InviteSplash,
     /// guild has enabled Membership Screening
     MemberVerificationGateEnabled,
+    /// guild has increased custom soundboard sound slots
+    MoreSoundboard,
     /// guild has increased custom sticker slots
     MoreStickers,
     /// guild has access to create announcement channels
@@ -303,6 +407,8 @@ pub enum GuildFeature {
     RoleSubscriptionsAvailableForPurchase,
     /// guild has enabled role subscriptions
     RoleSubscriptionsEnabled,
+    /// guild has created soundboard sounds
+    Soundboard,
     /// guild has enabled ticketed events
     TicketedEventsEnabled,
     /// guild has access to set a vanity URL
@@ -313,19 +419,46 @@ pub enum GuildFeature {
     VipRegions,
     /// guild has enabled the welcome screen
     WelcomeScreenEnabled,
+    /// guild is able to set gradient colors to roles
+    EnhancedRoleColors,
+    /// guild has access to guest invites
+    GuestsEnabled,
     /// not documented
     ExposedToActivitiesWtpExperiment,
+    /// a feature that discorsd doesn't know about yet
+    #[serde(untagged)]
+    Unknown(String),
 }
 
+/// A guild the current user is in, as returned by
+/// [`DiscordClient::get_current_user_guilds`](crate::http::DiscordClient::get_current_user_guilds).
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct PartialGuild {
-    id: GuildId,
-    name: String,
-    icon: Option<String>,
-    owner: bool,
-    permissions: Permissions,
-    features: HashSet<GuildFeature>,
+    /// guild id
+    pub id: GuildId,
+    /// guild name
+    pub name: String,
+    /// icon hash
+    pub icon: Option<String>,
+    /// banner hash
+    #[serde(default)]
+    pub banner: Option<String>,
+    /// whether the current user is the owner of the guild
+    pub owner: bool,
+    /// the current user's permissions in the guild
+    pub permissions: Permissions,
+    /// enabled guild features
+    pub features: HashSet<GuildFeature>,
+    /// approximate number of members in this guild, if requested with
+    /// [`CurrentGuildQuery::with_counts`](crate::http::user::CurrentGuildQuery::with_counts)
+    #[serde(default)]
+    pub approximate_member_count: Option<u32>,
+    /// approximate number of non-offline members in this guild, if requested with
+    /// [`CurrentGuildQuery::with_counts`](crate::http::user::CurrentGuildQuery::with_counts)
+    #[serde(default)]
+    pub approximate_presence_count: Option<u32>,
 }
+id_impl!(PartialGuild => id: GuildId);
 
 /// A partial guild object. Represents an Offline Guild, or a Guild whose information has not been
 /// provided through [`GuildCreate`](crate::shard::dispatch::GuildCreate)
@@ -346,12 +479,57 @@ impl Id for UnavailableGuild {
     }
 }
 
-// todo?
-//  https://discord.com/developers/docs/resources/guild#guild-preview-object
+/// A preview of a guild, which can be fetched for any guild that is
+/// [discoverable](GuildFeature::Discoverable) or that the bot is in.
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GuildPreview {
+    /// guild id
     pub id: GuildId,
+    /// guild name (2-100 characters)
+    pub name: String,
+    /// icon hash
+    pub icon: Option<String>,
+    /// splash hash
+    pub splash: Option<String>,
+    /// discovery splash hash
+    pub discovery_splash: Option<String>,
+    /// custom guild emojis
+    pub emojis: IdMap<CustomEmoji>,
+    /// enabled guild features
+    pub features: HashSet<GuildFeature>,
+    /// approximate number of members in this guild
+    pub approximate_member_count: u32,
+    /// approximate number of online members in this guild
+    pub approximate_presence_count: u32,
+    /// the description for the guild
+    pub description: Option<String>,
+    /// custom guild stickers
+    #[serde(default)]
+    pub stickers: Vec<Sticker>,
+}
+id_impl!(GuildPreview => id: GuildId);
+
+/// The screen shown to new members of a [Community](GuildFeature::Community) guild.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WelcomeScreen {
+    /// the server description shown in the welcome screen
+    pub description: Option<String>,
+    /// the channels shown in the welcome screen, up to 5
+    pub welcome_channels: Vec<WelcomeScreenChannel>,
+}
+
+/// A channel shown in a guild's [`WelcomeScreen`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WelcomeScreenChannel {
+    /// the channel's id
+    pub channel_id: ChannelId,
+    /// the description shown for the channel
+    pub description: String,
+    /// the emoji id, if the emoji is custom
+    pub emoji_id: Option<EmojiId>,
+    /// the emoji name if custom, the unicode character if standard, or `None` if no emoji is set
+    pub emoji_name: Option<String>,
 }
-id_impl!(GuildPreview => GuildId);
 
 #[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Copy, Clone)]
 pub struct GuildWidget {
@@ -369,6 +547,9 @@ pub struct GuildMember {
     pub user: User,
     /// this users guild nickname
     pub nick: Option<String>,
+    /// the member's guild avatar hash, if they have set one for this guild
+    #[serde(default)]
+    pub avatar: Option<String>,
     /// array of role object ids
     pub roles: HashSet<RoleId>,
     /// when the user joined the guild
@@ -379,9 +560,16 @@ pub struct GuildMember {
     pub deaf: bool,
     /// whether the user is muted in voice channels
     pub mute: bool,
-    /// whether the user has passed the guild's Membership Screening requirements
+    /// whether the user has not yet passed the guild's Membership Screening requirements, see
+    /// [`is_pending`](Self::is_pending)
     #[serde(default)]
     pub pending: bool,
+    /// when the user's [timeout](GuildMember::is_timed_out) will expire, if they have been timed out
+    #[serde(default)]
+    pub communication_disabled_until: Option<DateTime<Utc>>,
+    /// guild member flags
+    #[serde(default)]
+    pub flags: GuildMemberFlags,
 }
 
 id_eq!(GuildMember);
@@ -394,16 +582,113 @@ impl Id for GuildMember {
 }
 
 impl GuildMember {
+    /// Whether this member hasn't yet passed the guild's Membership Screening. Until they do,
+    /// Discord treats them as having only the `@everyone` role's permissions.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Whether this member is currently timed out, and so can't send messages, react, or speak.
+    pub fn is_timed_out(&self) -> bool {
+        self.communication_disabled_until
+            .is_some_and(|until| until > Utc::now())
+    }
+
+    /// The url where this member's avatar in `guild` can be retrieved from Discord. If they haven't
+    /// set a guild-specific [avatar](Self::avatar), this is their [user](Self::user)'s
+    /// [avatar](User::avatar_url).
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn guild_avatar_url<I, G>(&self, guild: G) -> Option<String>
+        where
+            I: ImageFormat,
+            G: Id<Id=GuildId>,
+    {
+        self.guild_avatar_url_sized::<I, G>(guild, None)
+    }
+
+    /// Like [`guild_avatar_url`](Self::guild_avatar_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn guild_avatar_url_sized<I, G>(&self, guild: G, size: Option<ImageSize>) -> Option<String>
+        where
+            I: ImageFormat,
+            G: Id<Id=GuildId>,
+    {
+        match &self.avatar {
+            Some(avatar) => cdn_image_url::<I>(
+                &format!("guilds/{}/users/{}/avatars", guild.id(), self.user.id),
+                avatar,
+                size,
+            ),
+            None => self.user.avatar_url_sized::<I>(size),
+        }
+    }
+
+    /// The name Discord shows for this member in their guild: their [nick](Self::nick) if they
+    /// have one, otherwise their user's [display name](User::display_name).
+    pub fn display_name(&self) -> &str {
+        self.nick.as_deref()
+            .unwrap_or_else(|| self.user.display_name())
+    }
+
     pub fn nick_or_name(&self) -> &str {
         self.nick.as_deref()
             .unwrap_or(self.user.username.as_str())
     }
+
+    /// Whether this member has the role `role`.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn has_role<R: Id<Id=RoleId>>(&self, role: R) -> bool {
+        self.roles.contains(&role.id())
+    }
+
+    /// The permissions this member has in `guild` from its roles, not including any channel's
+    /// permission overwrites. A [pending](Self::is_pending) member only has `@everyone`'s
+    /// permissions. Use [`Permissions::get`](Permissions::get) for a specific channel.
+    ///
+    /// Returns `None` if `guild` isn't cached.
+    pub async fn permissions<G: Id<Id=GuildId> + Send>(&self, cache: &Cache, guild: G) -> Option<Permissions> {
+        let guild = cache.guild(guild).await?;
+        let everyone = guild.roles.iter().find(|r| r.name == "@everyone")?;
+        Some(Permissions::guild_permissions(self, &guild, everyone))
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+    pub struct GuildMemberFlags: u32 {
+        /// Member has left and rejoined the guild
+        const DID_REJOIN = 1 << 0;
+        /// Member has completed onboarding
+        const COMPLETED_ONBOARDING = 1 << 1;
+        /// Member is exempt from guild verification requirements
+        const BYPASSES_VERIFICATION = 1 << 2;
+        /// Member has started onboarding
+        const STARTED_ONBOARDING = 1 << 3;
+        /// Member is a guest and can only access the voice channel they were invited to
+        const IS_GUEST = 1 << 4;
+        /// Member has started Server Guide new member actions
+        const STARTED_HOME_ACTIONS = 1 << 5;
+        /// Member has completed Server Guide new member actions
+        const COMPLETED_HOME_ACTIONS = 1 << 6;
+        /// Member's username, display name, or nickname is blocked by AutoMod
+        const AUTOMOD_QUARANTINED_USERNAME = 1 << 7;
+        /// Member has dismissed the DM settings upsell
+        const DM_SETTINGS_UPSELL_ACKNOWLEDGED = 1 << 9;
+        /// Member's guild tag is blocked by AutoMod
+        const AUTOMOD_QUARANTINED_GUILD_TAG = 1 << 10;
+    }
 }
+serde_bitflag!(GuildMemberFlags: u32);
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GuildMemberUserless {
     /// this users guild nickname
     pub nick: Option<String>,
+    /// the member's guild avatar hash, if they have set one for this guild
+    #[serde(default)]
+    pub avatar: Option<String>,
     /// array of role object ids
     pub roles: Vec<RoleId>,
     /// when the user joined the guild
@@ -414,9 +699,15 @@ pub struct GuildMemberUserless {
     pub deaf: bool,
     /// whether the user is muted in voice channels
     pub mute: bool,
-    /// whether the user has passed the guild's Membership Screening requirements
+    /// whether the user has not yet passed the guild's Membership Screening requirements
     #[serde(default)]
     pub pending: bool,
+    /// when the user's [timeout](GuildMember::is_timed_out) will expire, if they have been timed out
+    #[serde(default)]
+    pub communication_disabled_until: Option<DateTime<Utc>>,
+    /// guild member flags
+    #[serde(default)]
+    pub flags: GuildMemberFlags,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -445,7 +736,7 @@ pub struct Integration {
     /// the behavior of expiring subscribers
     ///
     /// not provided for discord bot integrations
-    pub expire_behavior: Option<ExpireBehavior>,
+    pub expire_behavior: Option<IntegrationExpireBehavior>,
     /// the grace period (in days) before expiring subscribers
     ///
     /// not provided for discord bot integrations
@@ -476,13 +767,22 @@ pub struct Integration {
 id_impl!(Integration => IntegrationId);
 
 serde_repr! {
-    pub enum ExpireBehavior: u8 {
+    /// What happens to a subscriber of an [`Integration`] when their subscription expires
+    pub enum IntegrationExpireBehavior: u8 {
+        /// remove the integration's [role](Integration::role_id) from them
         RemoveRole = 0,
+        /// kick them from the guild
         Kick = 1,
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+/// The old name of [`IntegrationExpireBehavior`]
+#[deprecated = "renamed to `IntegrationExpireBehavior`"]
+pub type ExpireBehavior = IntegrationExpireBehavior;
+
+/// The account on the other service (ex, the Twitch channel or YouTube account) of an
+/// [`Integration`]. For discord bot integrations, this is the bot's application.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct IntegrationAccount {
     /// id of the account
     pub id: String,
@@ -516,4 +816,266 @@ pub struct Ban {
     /// Partial object, containing `id`, `username`, `avatar`, `discriminator`, and `public_flags`
     /// (I assume its same as in GuildMember)
     pub user: User,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod guild_tests {
+    use super::*;
+
+    fn guild() -> Guild {
+        serde_json::from_str(&crate::test_fixtures::guild(1, r#"[
+            { "id": "1", "name": "@everyone", "permissions": "1024", "position": 0, "color": 0, "hoist": false, "managed": false, "mentionable": false },
+            { "id": "3", "name": "Mods", "permissions": "8192", "position": 2, "color": 0, "hoist": false, "managed": false, "mentionable": false },
+            { "id": "2", "name": "mods", "permissions": "2048", "position": 1, "color": 0, "hoist": false, "managed": false, "mentionable": false },
+            { "id": "4", "name": "Admin", "permissions": "8", "position": 3, "color": 0, "hoist": false, "managed": false, "mentionable": false }
+        ]"#, r#"[
+            { "id": "20", "type": 0, "name": "general", "position": 0, "permission_overwrites": [] },
+            { "id": "21", "type": 2, "name": "General", "position": 1, "permission_overwrites": [], "bitrate": 64000, "user_limit": 0 },
+            { "id": "22", "type": 4, "name": "Ünïcode", "position": 2, "permission_overwrites": [] }
+        ]"#, r#"[
+            { "user": { "id": "11", "username": "mason", "discriminator": "0", "avatar": null }, "nick": "Supa Hot", "roles": ["2", "3"], "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false },
+            { "user": { "id": "12", "username": "supa hot", "discriminator": "0", "avatar": null }, "nick": null, "roles": ["4"], "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false },
+            { "user": { "id": "10", "username": "owner", "discriminator": "0", "avatar": null }, "nick": "boss", "roles": [], "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false }
+        ]"#)).unwrap()
+    }
+
+    #[test]
+    fn unknown_feature() {
+        let features: Vec<GuildFeature> = serde_json::from_str(
+            r#"["COMMUNITY", "MORE_SOUNDBOARD", "SOME_FUTURE_FEATURE"]"#
+        ).unwrap();
+        assert_eq!(features, [
+            GuildFeature::Community,
+            GuildFeature::MoreSoundboard,
+            GuildFeature::Unknown("SOME_FUTURE_FEATURE".into()),
+        ]);
+        assert_eq!(
+            serde_json::to_string(&features).unwrap(),
+            r#"["COMMUNITY","MORE_SOUNDBOARD","SOME_FUTURE_FEATURE"]"#,
+        );
+
+        let mut guild = guild();
+        assert!(!guild.has_feature(GuildFeature::Community));
+        guild.features.extend(features);
+        assert!(guild.has_feature(GuildFeature::Community));
+        assert!(guild.has_feature(GuildFeature::Unknown("SOME_FUTURE_FEATURE".into())));
+        assert!(!guild.has_feature(GuildFeature::News));
+    }
+
+    #[test]
+    fn role_named() {
+        let guild = guild();
+        assert_eq!(guild.role_named("@everyone").map(|r| r.id), Some(RoleId(1)));
+        // duplicate names find the oldest role
+        assert_eq!(guild.role_named("MODS").map(|r| r.id), Some(RoleId(2)));
+        assert!(guild.role_named("admins").is_none());
+    }
+
+    #[test]
+    fn channel_named() {
+        let guild = guild();
+        assert_eq!(guild.channel_named("GENERAL").map(Channel::id), Some(ChannelId(20)));
+        assert_eq!(guild.channel_named("ünÏcode").map(Channel::id), Some(ChannelId(22)));
+        assert!(guild.channel_named("random").is_none());
+    }
+
+    #[test]
+    fn member_named() {
+        let guild = guild();
+        assert_eq!(guild.member_named("Boss").map(GuildMember::id), Some(UserId(10)));
+        assert_eq!(guild.member_named("OWNER").map(GuildMember::id), Some(UserId(10)));
+        // one member's nickname is another's username
+        assert_eq!(guild.member_named("supa hot").map(GuildMember::id), Some(UserId(11)));
+        assert_eq!(guild.member_named("mason").map(GuildMember::id), Some(UserId(11)));
+        assert!(guild.member_named("nobody").is_none());
+    }
+
+    #[test]
+    fn has_role() {
+        let guild = guild();
+        let member = guild.members.get(UserId(11)).unwrap();
+        assert!(member.has_role(RoleId(2)));
+        assert!(member.has_role(RoleId(3)));
+        assert!(!member.has_role(RoleId(4)));
+    }
+
+    #[tokio::test]
+    async fn member_permissions() {
+        let cache = Cache::default();
+        let guild = guild();
+        let member = |id| guild.members.get(UserId(id)).unwrap().clone();
+        assert_eq!(member(11).permissions(&cache, GuildId(1)).await, None);
+        cache.guilds.write().await.insert(guild.clone());
+
+        assert_eq!(
+            member(11).permissions(&cache, GuildId(1)).await,
+            Some(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES | Permissions::MANAGE_MESSAGES),
+        );
+        // administrators and the owner have every permission
+        assert_eq!(member(12).permissions(&cache, GuildId(1)).await, Some(Permissions::all()));
+        assert_eq!(member(10).permissions(&cache, GuildId(1)).await, Some(Permissions::all()));
+
+        // pending members only have `@everyone`'s permissions, even if they have admin roles
+        let mut pending = member(12);
+        pending.pending = true;
+        assert!(pending.is_pending());
+        assert_eq!(pending.permissions(&cache, GuildId(1)).await, Some(Permissions::VIEW_CHANNEL));
+    }
+
+    #[test]
+    fn member_flags() {
+        let member: GuildMember = serde_json::from_str(r#"{
+          "user": { "id": "11", "username": "mason", "discriminator": "0", "avatar": null },
+          "roles": [],
+          "joined_at": "2017-07-11T17:27:07.299Z",
+          "deaf": false,
+          "mute": false,
+          "pending": true,
+          "flags": 3
+        }"#).unwrap();
+        assert!(member.is_pending());
+        assert_eq!(member.flags, GuildMemberFlags::DID_REJOIN | GuildMemberFlags::COMPLETED_ONBOARDING);
+
+        let member = guild().members.get(UserId(11)).unwrap().clone();
+        assert!(!member.is_pending());
+        assert_eq!(member.flags, GuildMemberFlags::empty());
+    }
+
+    #[test]
+    fn icon_and_banner_urls() {
+        use crate::model::{Gif, Jpeg, Png, WebP};
+
+        let mut guild = guild();
+        assert_eq!(guild.icon_url::<Png>(), None);
+        assert_eq!(guild.banner_url::<Png>(), None);
+
+        guild.icon = Some("abc".into());
+        guild.banner = Some("a_def".into());
+        assert_eq!(
+            guild.icon_url_sized::<Jpeg>(ImageSize::new(128)).as_deref(),
+            Some("https://cdn.discordapp.com/icons/1/abc.jpeg?size=128"),
+        );
+        assert_eq!(guild.icon_url::<Gif>(), None);
+        assert_eq!(
+            guild.banner_url::<WebP>().as_deref(),
+            Some("https://cdn.discordapp.com/banners/1/a_def.gif"),
+        );
+    }
+
+    #[test]
+    fn splash_and_role_icon_urls() {
+        use crate::model::{Png, WebP};
+
+        let mut guild = guild();
+        assert_eq!(guild.splash_url::<Png>(), None);
+        guild.splash = Some("abc".into());
+        guild.discovery_splash = Some("def".into());
+        assert_eq!(
+            guild.splash_url_sized::<Png>(ImageSize::new(1024)).as_deref(),
+            Some("https://cdn.discordapp.com/splashes/1/abc.png?size=1024"),
+        );
+        assert_eq!(
+            guild.discovery_splash_url::<WebP>().as_deref(),
+            Some("https://cdn.discordapp.com/discovery-splashes/1/def.webp"),
+        );
+
+        let role = guild.roles.get_mut(RoleId(1)).unwrap();
+        assert_eq!(role.icon_url::<Png>(), None);
+        role.icon = Some("ghi".into());
+        assert_eq!(
+            role.icon_url::<Png>().as_deref(),
+            Some("https://cdn.discordapp.com/role-icons/1/ghi.png"),
+        );
+    }
+
+    #[test]
+    fn member_timeout() {
+        let member = |until: &str| -> GuildMember {
+            serde_json::from_str(&format!(r#"{{
+              "user": {{ "id": "11", "username": "mason", "discriminator": "0", "avatar": null }},
+              "roles": [],
+              "joined_at": "2017-07-11T17:27:07.299Z",
+              "deaf": false,
+              "mute": false,
+              "communication_disabled_until": {until}
+            }}"#)).unwrap()
+        };
+
+        let untimed = member("null");
+        assert_eq!(untimed.communication_disabled_until, None);
+        assert!(!untimed.is_timed_out());
+
+        let expired = member(r#""2017-07-11T17:27:07.299Z""#);
+        assert!(expired.communication_disabled_until.is_some());
+        assert!(!expired.is_timed_out());
+
+        let timed_out = member(r#""9999-01-01T00:00:00+00:00""#);
+        assert!(timed_out.is_timed_out());
+    }
+
+    #[test]
+    fn guild_avatar_url() {
+        use crate::model::Png;
+
+        let member = |user_avatar: &str, avatar: &str| -> GuildMember {
+            serde_json::from_str(&format!(r#"{{
+              "user": {{ "id": "11", "username": "mason", "discriminator": "0", "avatar": {user_avatar} }},
+              "roles": [],
+              "joined_at": "2017-07-11T17:27:07.299Z",
+              "deaf": false,
+              "mute": false,
+              "avatar": {avatar}
+            }}"#)).unwrap()
+        };
+
+        let guild_avatar = member(r#""abc""#, r#""def""#);
+        assert_eq!(guild_avatar.avatar.as_deref(), Some("def"));
+        assert_eq!(
+            guild_avatar.guild_avatar_url::<Png, _>(GuildId(1)).as_deref(),
+            Some("https://cdn.discordapp.com/guilds/1/users/11/avatars/def.png"),
+        );
+
+        let user_avatar = member(r#""abc""#, "null");
+        assert_eq!(
+            user_avatar.guild_avatar_url::<Png, _>(GuildId(1)).as_deref(),
+            Some("https://cdn.discordapp.com/avatars/11/abc.png"),
+        );
+
+        let no_avatar = member("null", "null");
+        assert_eq!(no_avatar.guild_avatar_url::<Png, _>(GuildId(1)), None);
+    }
+
+    #[test]
+    fn display_names() {
+        let user = |discriminator: &str, global_name: &str| -> User {
+            serde_json::from_str(&format!(r#"{{
+              "id": "11",
+              "username": "mason",
+              "discriminator": "{discriminator}",
+              "global_name": {global_name},
+              "avatar": null
+            }}"#)).unwrap()
+        };
+        let member = |user: User, nick: Option<&str>| GuildMember {
+            nick: nick.map(ToString::to_string),
+            ..serde_json::from_value(serde_json::json!({
+                "user": user,
+                "roles": [],
+                "joined_at": "2017-07-11T17:27:07.299Z",
+                "deaf": false,
+                "mute": false,
+            })).unwrap()
+        };
+
+        let legacy = user("1337", "null");
+        assert_eq!(legacy.global_name, None);
+        assert_eq!(legacy.display_name(), "mason");
+        assert_eq!(member(legacy, None).display_name(), "mason");
+
+        let global = user("0", r#""Mason""#);
+        assert_eq!(global.global_name.as_deref(), Some("Mason"));
+        assert_eq!(global.display_name(), "Mason");
+        assert_eq!(member(global.clone(), None).display_name(), "Mason");
+        assert_eq!(member(global, Some("Supa Hot")).display_name(), "Supa Hot");
+    }
+}
@@ -32,6 +32,12 @@ macro_rules! id_impl {
                 }
             }
 
+            impl crate::model::message::TimestampMarkup for $id {
+                fn timestamp_styled(self, style: crate::model::message::TimestampStyle) -> String {
+                    crate::model::message::TimestampMarkup::timestamp_styled(self.timestamp(), style)
+                }
+            }
+
             impl Display for $id {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                     write!(f, "{}", self.0)
@@ -124,6 +130,10 @@ id_impl!(
     TeamId,
     TagId,
     RuleId,
+    StageInstanceId,
+    ScheduledEventId,
+    OnboardingPromptId,
+    PromptOptionId,
     // User or Role (but not channel)
     MentionableId,
 );
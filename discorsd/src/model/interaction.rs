@@ -1,12 +1,9 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display, Formatter};
-use std::iter;
 use std::vec::IntoIter;
 
 use chrono::{DateTime, Utc};
-use itertools::Itertools;
-use serde::{Deserialize, Deserializer};
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::IdMap;
 use crate::model::channel::ChannelType;
@@ -18,7 +15,7 @@ use crate::model::locales::Locale;
 use crate::model::message::{Attachment, Message};
 use crate::model::permissions::{Permissions, Role};
 use crate::model::user::User;
-use crate::serde_utils::null_as_default;
+use crate::serde_utils::{BoolExt, null_as_default};
 
 serde_num_tag! { just Deserialize =>
     /// hi
@@ -32,7 +29,29 @@ serde_num_tag! { just Deserialize =>
     }
 }
 
-serde_num_tag! { just Deserialize =>
+impl Interaction {
+    /// The id of this interaction, or `None` for a [`Ping`](Self::Ping).
+    pub const fn id(&self) -> Option<InteractionId> {
+        match self {
+            Self::Ping => None,
+            Self::ApplicationCommand(data) | Self::ApplicationCommandAutocomplete(data) => Some(data.id),
+            Self::MessageComponent(data) => Some(data.id),
+            Self::ModalSubmit(data) => Some(data.id),
+        }
+    }
+
+    /// The token to respond to this interaction with, or `None` for a [`Ping`](Self::Ping).
+    pub const fn token(&self) -> Option<&Token> {
+        match self {
+            Self::Ping => None,
+            Self::ApplicationCommand(data) | Self::ApplicationCommandAutocomplete(data) => Some(&data.token),
+            Self::MessageComponent(data) => Some(&data.token),
+            Self::ModalSubmit(data) => Some(&data.token),
+        }
+    }
+}
+
+serde_num_tag! {
     #[derive(Debug, Clone)]
     pub enum ApplicationCommandData = "type": CommandType {
         (CommandType::SlashCommand) = SlashCommand {
@@ -75,6 +94,21 @@ impl PartialEq for ApplicationCommandData {
 }
 
 impl ApplicationCommandData {
+    /// Data for a slash command named `name`, invoked with `options`.
+    pub fn slash_command<S: Into<String>>(id: CommandId, name: S, options: InteractionOption) -> Self {
+        Self::SlashCommand { id, name: name.into(), options }
+    }
+
+    /// Data for a user command named `name`, used on the user `target_id`.
+    pub fn user_command<S: Into<String>>(id: CommandId, name: S, target_id: UserId, resolved: ResolvedData) -> Self {
+        Self::UserCommand { id, name: name.into(), target_id, resolved }
+    }
+
+    /// Data for a message command named `name`, used on the message `target_id`.
+    pub fn message_command<S: Into<String>>(id: CommandId, name: S, target_id: MessageId, resolved: ResolvedData) -> Self {
+        Self::MessageCommand { id, name: name.into(), target_id, resolved }
+    }
+
     pub fn name(&self) -> &str {
         match self {
             Self::SlashCommand { name, .. } => name,
@@ -82,6 +116,16 @@ impl ApplicationCommandData {
             Self::MessageCommand { name, .. } => name,
         }
     }
+
+    /// The option the user is currently typing in, for an
+    /// [autocomplete](Interaction::ApplicationCommandAutocomplete) interaction. Always `None` for
+    /// user and message commands.
+    pub fn focused_option(&self) -> Option<&InteractionDataOption> {
+        match self {
+            Self::SlashCommand { options, .. } => options.focused_option(),
+            Self::UserCommand { .. } | Self::MessageCommand { .. } => None,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Hash)]
@@ -208,14 +252,27 @@ impl InteractionUser {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
-#[serde(try_from = "Vec<InteractionOptionRaw>")]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(try_from = "Vec<InteractionOptionRaw>", into = "Vec<InteractionOptionRaw>")]
 pub enum InteractionOption {
     Command(DataOption<SubCommand>),
     Group(DataOption<SubCommandGroup>),
     Values(Vec<InteractionDataOption>),
 }
 
+impl InteractionOption {
+    /// The option marked as [focused](DataOption::focused), looking inside any sub command or
+    /// group.
+    pub fn focused_option(&self) -> Option<&InteractionDataOption> {
+        let values = match self {
+            Self::Command(command) => &command.data.options,
+            Self::Group(group) => &group.data.options.data.options,
+            Self::Values(values) => values,
+        };
+        values.iter().find(|option| option.focused())
+    }
+}
+
 impl Default for InteractionOption {
     fn default() -> Self {
         Self::Values(Vec::new())
@@ -227,27 +284,30 @@ impl TryFrom<Vec<InteractionOptionRaw>> for InteractionOption {
     type Error = &'static str;
 
     fn try_from(value: Vec<InteractionOptionRaw>) -> Result<Self, Self::Error> {
-        println!("value = {:?}", value);
         fn values(first: InteractionDataOption, rest: IntoIter<InteractionOptionRaw>) -> Result<InteractionOption, &'static str> {
-            let vec = iter::once(Ok(first))
-                .chain(rest.map(|value| match value {
-                    InteractionOptionRaw::SubCommand(_) => Err("bad sc"),
-                    InteractionOptionRaw::SubCommandGroup(_) => Err("bad group"),
-                    InteractionOptionRaw::String(d) => Ok(InteractionDataOption::String(d)),
-                    InteractionOptionRaw::Integer(d) => Ok(InteractionDataOption::Integer(d)),
-                    InteractionOptionRaw::Boolean(d) => Ok(InteractionDataOption::Boolean(d)),
-                    InteractionOptionRaw::User(d) => Ok(InteractionDataOption::User(d)),
-                    InteractionOptionRaw::Channel(d) => Ok(InteractionDataOption::Channel(d)),
-                    InteractionOptionRaw::Role(d) => Ok(InteractionDataOption::Role(d)),
-                    InteractionOptionRaw::Mentionable(d) => Ok(InteractionDataOption::Mentionable(d)),
-                    InteractionOptionRaw::Number(d) => Ok(InteractionDataOption::Number(d)),
-                    InteractionOptionRaw::Attachment(d) => Ok(InteractionDataOption::Attachment(d)),
-                }))
-                .try_collect()?;
+            let mut vec = Vec::with_capacity(rest.len() + 1);
+            vec.push(first);
+            for value in rest {
+                vec.push(match value {
+                    InteractionOptionRaw::SubCommand(_) => return Err("bad sc"),
+                    InteractionOptionRaw::SubCommandGroup(_) => return Err("bad group"),
+                    InteractionOptionRaw::String(d) => InteractionDataOption::String(d),
+                    InteractionOptionRaw::Integer(d) => InteractionDataOption::Integer(d),
+                    InteractionOptionRaw::Boolean(d) => InteractionDataOption::Boolean(d),
+                    InteractionOptionRaw::User(d) => InteractionDataOption::User(d),
+                    InteractionOptionRaw::Channel(d) => InteractionDataOption::Channel(d),
+                    InteractionOptionRaw::Role(d) => InteractionDataOption::Role(d),
+                    InteractionOptionRaw::Mentionable(d) => InteractionDataOption::Mentionable(d),
+                    InteractionOptionRaw::Number(d) => InteractionDataOption::Number(d),
+                    InteractionOptionRaw::Attachment(d) => InteractionDataOption::Attachment(d),
+                });
+            }
             Ok(InteractionOption::Values(vec))
         }
         let mut rest = value.into_iter();
-        match rest.next().ok_or("no opts")? {
+        // Discord omits `options` when there are none, but treat an empty list the same way
+        let Some(first) = rest.next() else { return Ok(Self::default()) };
+        match first {
             InteractionOptionRaw::SubCommand(c) => Ok(Self::Command(c)),
             InteractionOptionRaw::SubCommandGroup(g) => Ok(Self::Group(g)),
             InteractionOptionRaw::String(d) => values(InteractionDataOption::String(d), rest),
@@ -263,7 +323,17 @@ impl TryFrom<Vec<InteractionOptionRaw>> for InteractionOption {
     }
 }
 
-serde_num_tag! { just Deserialize =>
+impl From<InteractionOption> for Vec<InteractionOptionRaw> {
+    fn from(option: InteractionOption) -> Self {
+        match option {
+            InteractionOption::Command(c) => vec![InteractionOptionRaw::SubCommand(c)],
+            InteractionOption::Group(g) => vec![InteractionOptionRaw::SubCommandGroup(g)],
+            InteractionOption::Values(values) => values.into_iter().map(InteractionOptionRaw::from).collect(),
+        }
+    }
+}
+
+serde_num_tag! {
     // old::InteractionDataOption
     #[derive(Debug, Clone)]
     pub enum InteractionOptionRaw = "type": CommandOptionType {
@@ -286,7 +356,7 @@ serde_num_tag! { just Deserialize =>
     }
 }
 
-serde_num_tag! { just Deserialize =>
+serde_num_tag! {
     // old::ValueOption
     #[derive(Debug, Clone)]
     pub enum InteractionDataOption = "type": CommandOptionType {
@@ -307,6 +377,22 @@ serde_num_tag! { just Deserialize =>
     }
 }
 
+impl From<InteractionDataOption> for InteractionOptionRaw {
+    fn from(option: InteractionDataOption) -> Self {
+        match option {
+            InteractionDataOption::String(d) => Self::String(d),
+            InteractionDataOption::Integer(d) => Self::Integer(d),
+            InteractionDataOption::Boolean(d) => Self::Boolean(d),
+            InteractionDataOption::User(d) => Self::User(d),
+            InteractionDataOption::Channel(d) => Self::Channel(d),
+            InteractionDataOption::Role(d) => Self::Role(d),
+            InteractionDataOption::Mentionable(d) => Self::Mentionable(d),
+            InteractionDataOption::Number(d) => Self::Number(d),
+            InteractionDataOption::Attachment(d) => Self::Attachment(d),
+        }
+    }
+}
+
 impl InteractionDataOption {
     pub fn name(&self) -> &str {
         match self {
@@ -321,6 +407,21 @@ impl InteractionDataOption {
             Self::Attachment(d) => &d.name,
         }
     }
+
+    /// Whether this is the option the user is currently typing in, for autocomplete.
+    pub const fn focused(&self) -> bool {
+        match self {
+            Self::String(d) => d.focused,
+            Self::Integer(d) => d.focused,
+            Self::Boolean(d) => d.focused,
+            Self::User(d) => d.focused,
+            Self::Channel(d) => d.focused,
+            Self::Role(d) => d.focused,
+            Self::Mentionable(d) => d.focused,
+            Self::Number(d) => d.focused,
+            Self::Attachment(d) => d.focused,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -330,6 +431,8 @@ pub struct HasOptions<T> {
 }
 
 mod __priv {
+    use serde::de::Error;
+
     use super::*;
 
     #[derive(Deserialize)]
@@ -346,13 +449,44 @@ mod __priv {
 
     impl<'de> Deserialize<'de> for HasOptions<DataOption<SubCommand>> {
         fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
-            let HasOptionShim { mut options } = HasOptionShim::<Vec<_>>::deserialize(d)?;
-            Ok(Self { options: options.remove(0) })
+            // a group always has exactly one sub command, so just take the first
+            let HasOptionShim { options } = HasOptionShim::<Vec<_>>::deserialize(d)?;
+            let options = options.into_iter()
+                .next()
+                .ok_or_else(|| D::Error::invalid_length(0, &"a sub command"))?;
+            Ok(Self { options })
+        }
+    }
+
+    #[derive(Serialize)]
+    struct HasOptionRefShim<'a, T> {
+        options: &'a T,
+    }
+
+    impl Serialize for HasOptions<Vec<InteractionDataOption>> {
+        fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            HasOptionRefShim { options: &self.options }.serialize(s)
+        }
+    }
+
+    /// the sub command in a group is still tagged with its type
+    #[derive(Serialize)]
+    struct TaggedSubCommand<'a> {
+        #[serde(rename = "type")]
+        kind: CommandOptionType,
+        #[serde(flatten)]
+        command: &'a DataOption<SubCommand>,
+    }
+
+    impl Serialize for HasOptions<DataOption<SubCommand>> {
+        fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            let options = [TaggedSubCommand { kind: CommandOptionType::SubCommand, command: &self.options }];
+            HasOptionRefShim { options: &options }.serialize(s)
         }
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct HasValue<T> {
     /// Value of the option resulting from user input
     pub value: T,
@@ -392,22 +526,22 @@ pub struct SubCommand {}
 #[derive(Debug, Clone)]
 pub struct SubCommandGroup {}
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct DataOption<T: OptionType> {
     /// 1-32 character name
     pub name: String,
     /// Localization dictionary for name field. Values follow the same restrictions as name
-    #[serde(deserialize_with = "null_as_default", default)]
+    #[serde(deserialize_with = "null_as_default", default, skip_serializing_if = "HashMap::is_empty")]
     pub name_localizations: HashMap<Locale, String>,
     /// value or sub options
     #[serde(flatten)]
     pub data: T::Data,
     /// true if this option is the currently focused option for autocomplete
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "bool::is_false")]
     pub focused: bool,
 }
 
-#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(default)]
 pub struct ResolvedData {
     /// the ids and User objects
@@ -425,7 +559,7 @@ pub struct ResolvedData {
     pub attachments: IdMap<Attachment>,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 // todo rename GuildMemberInfo (and flatten in as the data holder in GuildMember)
 pub struct PartialGuildMember {
     /// this users guild nickname
@@ -442,7 +576,7 @@ pub struct PartialGuildMember {
 }
 
 // todo threads should also have `thread_metadata` and `parent_id`
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct PartialChannel {
     /// the id of this channel
     pub id: ChannelId,
@@ -485,6 +619,9 @@ pub struct ButtonPressData {
     pub custom_id: ComponentId,
     #[serde(default)]
     pub resolved: ResolvedData,
+    /// the message the button is attached to, taken from the interaction's `message`
+    #[serde(skip)]
+    pub message: Option<Message>,
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
@@ -496,6 +633,9 @@ pub struct MenuSelectDataRaw {
     pub values: Vec<String>,
     #[serde(default)]
     pub resolved: ResolvedData,
+    /// the message the menu is attached to, taken from the interaction's `message`
+    #[serde(skip)]
+    pub message: Option<Message>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -503,6 +643,8 @@ pub struct MenuSelectData {
     /// the custom_id of the component
     pub custom_id: ComponentId,
     pub resolved: ResolvedData,
+    /// the message the menu is attached to
+    pub message: Option<Message>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -522,7 +664,10 @@ pub struct ModalSubmitData {
 
 #[cfg(test)]
 mod tests {
-    use crate::model::interaction::{ApplicationCommandData, Interaction};
+    use serde_json::json;
+
+    use crate::model::ids::{CommandId, MessageId, UserId};
+    use crate::model::interaction::{ApplicationCommandData, DataOption, HasValue, Interaction, InteractionDataOption, InteractionOption, InteractionOptionRaw, ResolvedData};
 
     #[test]
     fn slash_command() {
@@ -937,4 +1082,141 @@ mod tests {
     }"#;
         serde_json::from_str::<ApplicationCommandData>(JSON).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn command_data_from_interaction() {
+        const INTERACTION: &str = r#"{
+            "type": 2,
+            "id": "786008729715212338",
+            "application_id": "1421512",
+            "token": "A_UNIQUE_TOKEN",
+            "channel_id": "645027906669510667",
+            "data": {
+                "type": 1,
+                "id": "771825006014889984",
+                "name": "cardsearch",
+                "options": [{ "type": 3, "name": "cardname", "value": "The Gitrog Monster" }]
+            }
+        }"#;
+        let interaction: serde_json::Value = serde_json::from_str(INTERACTION).unwrap();
+        let data: ApplicationCommandData = serde_json::from_value(interaction["data"].clone()).unwrap();
+
+        let ApplicationCommandData::SlashCommand { id, name, options: InteractionOption::Values(values) } = &data else {
+            panic!("expected a slash command with values, got {data:?}")
+        };
+        assert_eq!(id.to_string(), "771825006014889984");
+        assert_eq!(name, "cardsearch");
+        assert!(matches!(
+            values.as_slice(),
+            [InteractionDataOption::String(DataOption { name, data: HasValue { value }, .. })]
+                if name == "cardname" && value == "The Gitrog Monster"
+        ));
+        assert_eq!(serde_json::to_value(&data).unwrap(), interaction["data"]);
+    }
+
+    #[test]
+    fn autocomplete_focused_option() {
+        const AUTOCOMPLETE_INTERACTION: &str = r#"{
+            "type": 4,
+            "id": "786008729715212338",
+            "application_id": "1421512",
+            "token": "A_UNIQUE_TOKEN",
+            "channel_id": "645027906669510667",
+            "user": {
+                "id": "53908232506183680",
+                "username": "Mason",
+                "avatar": null,
+                "discriminator": "1337"
+            },
+            "data": {
+                "type": 1,
+                "id": "771825006014889984",
+                "name": "cards",
+                "options": [{
+                    "type": 1,
+                    "name": "search",
+                    "options": [
+                        { "type": 4, "name": "count", "value": 3 },
+                        { "type": 3, "name": "cardname", "value": "Gitr", "focused": true }
+                    ]
+                }]
+            }
+        }"#;
+        let interaction: Interaction = serde_json::from_str(AUTOCOMPLETE_INTERACTION).unwrap();
+        let Interaction::ApplicationCommandAutocomplete(data) = interaction else {
+            panic!("expected an autocomplete interaction, got {interaction:?}")
+        };
+        let focused = data.data.focused_option().unwrap();
+        assert_eq!(focused.name(), "cardname");
+        assert!(matches!(
+            focused,
+            InteractionDataOption::String(DataOption { data: HasValue { value }, .. }) if value == "Gitr"
+        ));
+
+        let unfocused = ApplicationCommandData::slash_command(CommandId(1), "ping", InteractionOption::default());
+        assert!(unfocused.focused_option().is_none());
+    }
+
+    #[test]
+    fn command_data_round_trip() {
+        let id = CommandId(1234);
+        let commands = [
+            ApplicationCommandData::slash_command(id, "ping", InteractionOption::default()),
+            ApplicationCommandData::user_command(id, "info", UserId(5678), ResolvedData::default()),
+            ApplicationCommandData::message_command(id, "pin", MessageId(5678), ResolvedData::default()),
+        ];
+        for command in commands {
+            let json = serde_json::to_value(&command).unwrap();
+            let back: ApplicationCommandData = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(back.name(), command.name());
+            assert_eq!(serde_json::to_value(&back).unwrap(), json);
+        }
+    }
+
+    fn round_trip(raw: &serde_json::Value) {
+        let option: InteractionOption = serde_json::from_value(raw.clone()).unwrap();
+        let back: Vec<InteractionOptionRaw> = option.into();
+        assert_eq!(&serde_json::to_value(back).unwrap(), raw);
+    }
+
+    #[test]
+    fn option_round_trip_values() {
+        round_trip(&json!([
+            { "type": 3, "name": "cardname", "value": "The Gitrog Monster" },
+            { "type": 4, "name": "count", "value": 3 },
+            { "type": 5, "name": "foil", "value": true },
+            { "type": 6, "name": "owner", "value": "53908232506183680" },
+            { "type": 7, "name": "channel", "value": "1148347212850528367" },
+            { "type": 8, "name": "role", "value": "539082325061836999" },
+            { "type": 9, "name": "who", "value": "539082325061836999" },
+            { "type": 10, "name": "price", "value": 1.5, "focused": true },
+        ]));
+    }
+
+    #[test]
+    fn option_round_trip_command() {
+        round_trip(&json!([{
+            "type": 1,
+            "name": "add",
+            "options": [
+                { "type": 3, "name": "message", "value": "<@243418816510558208>" },
+            ],
+        }]));
+    }
+
+    #[test]
+    fn option_round_trip_group() {
+        round_trip(&json!([{
+            "type": 2,
+            "name": "post",
+            "options": [{
+                "type": 1,
+                "name": "message",
+                "options": [
+                    { "type": 7, "name": "channel", "value": "1148347212850528367" },
+                    { "type": 3, "name": "message", "value": "<@243418816510558208>" },
+                ],
+            }],
+        }]));
+    }
+}
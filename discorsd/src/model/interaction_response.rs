@@ -4,13 +4,13 @@ use std::fmt::Debug;
 use std::mem;
 use std::str::FromStr;
 
-use serde_derive::Serialize;
+use serde::Serialize;
 
 use crate::BotState;
 use crate::commands::{ArrayLen, MenuData};
 use crate::commands::component_command::{ButtonCommand, MenuCommand};
 use crate::commands::modal_command::ModalCommand;
-use crate::http::channel::{embed, MessageAttachment, RichEmbed};
+use crate::http::channel::{embed, MessageAttachment, MessageLimitError, MessageLimits, RichEmbed};
 use crate::model::command::Choice;
 use crate::model::components::{ActionRow, Button, Component, ComponentId, make_button, Menu, TextInput};
 use crate::model::message::{AllowedMentions, MessageFlags};
@@ -62,6 +62,28 @@ fn serialize_interaction_response() {
     println!("{json}");
 }
 
+#[test]
+fn serialize_update_message() {
+    let response = InteractionResponse::UpdateMessage(message(|m| {
+        m.content("Voted!");
+        m.embed(|e| e.title("Results"));
+    }));
+    assert_eq!(
+        serde_json::to_value(&response).unwrap(),
+        serde_json::json!({
+            "type": 7,
+            "data": {
+                "content": "Voted!",
+                "embeds": [{ "title": "Results" }],
+            }
+        }),
+    );
+    assert_eq!(
+        serde_json::to_value(&InteractionResponse::DeferredUpdateMessage).unwrap(),
+        serde_json::json!({ "type": 6 }),
+    );
+}
+
 #[derive(Serialize, Debug, Clone, Default)]
 pub struct InteractionMessage {
     /// is the response TTS
@@ -128,7 +150,37 @@ impl From<RichEmbed> for InteractionMessage {
     }
 }
 
+impl InteractionResponse {
+    /// Check that the message this responds with, if any, is within `limits`. See
+    /// [`CreateMessage::check_limits`](crate::http::channel::CreateMessage::check_limits).
+    ///
+    /// # Errors
+    ///
+    /// If the content or the total length of the embeds is longer than allowed by `limits`.
+    pub fn check_limits(&self, limits: MessageLimits) -> Result<(), MessageLimitError> {
+        match self {
+            Self::ChannelMessageWithSource(message)
+            | Self::UpdateMessage(message) => message.check_limits(limits),
+            Self::Pong
+            | Self::DeferredChannelMessageWithSource
+            | Self::DeferredUpdateMessage
+            | Self::ApplicationCommandAutocompleteResult(_)
+            | Self::Modal(_) => Ok(()),
+        }
+    }
+}
+
 impl InteractionMessage {
+    /// Check that this message's content and embeds are within `limits`. See
+    /// [`CreateMessage::check_limits`](crate::http::channel::CreateMessage::check_limits).
+    ///
+    /// # Errors
+    ///
+    /// If the content or the total length of the embeds is longer than allowed by `limits`.
+    pub fn check_limits(&self, limits: MessageLimits) -> Result<(), MessageLimitError> {
+        limits.check(&self.content, &self.embeds)
+    }
+
     pub fn build_with<F: FnOnce(&mut Self)>(mut with: Self, builder: F) -> Self {
         builder(&mut with);
         with
@@ -213,8 +265,7 @@ impl InteractionMessage {
               Component: From<Menu<D::Data>>,
               F: FnOnce(&mut Menu<D::Data>),
     {
-        let mut menu = Menu::<D::Data>::new();
-        menu.options = D::options();
+        let mut menu = Menu::for_data::<D>();
         builder(&mut menu);
         state.as_ref().register_menu(&mut menu, Box::new(command));
         self.components.push(ActionRow::menu(menu));
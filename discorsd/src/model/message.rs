@@ -3,7 +3,8 @@ use std::convert::TryFrom;
 use std::fmt::{Display, Formatter, Write};
 
 use chrono::{DateTime, Utc};
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::model::channel::{Channel, ChannelType};
 use crate::model::components::ActionRow;
@@ -12,6 +13,7 @@ use crate::model::ids::*;
 pub use crate::model::ids::MessageId;
 use crate::model::interaction::InteractionType;
 use crate::model::Png;
+use crate::model::poll::Poll;
 use crate::model::user::User;
 use crate::serde_utils::BoolExt;
 
@@ -124,6 +126,9 @@ pub struct Message {
     /// A generally increasing integer (there may be gaps or duplicates) that represents the approximate position of the message in a thread, it can be used to estimate the relative position of the message in a thread in company with total_message_sent on parent thread
     #[serde(skip_serializing_if = "Option::is_none")]
     pub position: Option<usize>,
+    /// the poll in this message, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll: Option<Poll>,
     /// type of message
     #[serde(rename = "type")]
     pub message_type: MessageType,
@@ -133,6 +138,12 @@ impl Message {
     pub fn cmid(&self) -> ChannelMessageId {
         self.into()
     }
+
+    /// A link that jumps to this message when clicked in Discord. `guild` is the guild this
+    /// message was sent in, or `None` if it was sent in a DM.
+    pub fn jump_url<G: Into<Option<GuildId>>>(&self, guild: G) -> String {
+        self.cmid().jump_url(guild)
+    }
 }
 
 id_impl!(Message => MessageId);
@@ -144,6 +155,17 @@ pub struct ChannelMessageId {
     pub message: MessageId,
 }
 
+impl ChannelMessageId {
+    /// A link that jumps to this message when clicked in Discord. `guild` is the guild this
+    /// message was sent in, or `None` if it was sent in a DM.
+    pub fn jump_url<G: Into<Option<GuildId>>>(self, guild: G) -> String {
+        match guild.into() {
+            Some(guild) => format!("https://discord.com/channels/{guild}/{}/{}", self.channel, self.message),
+            None => format!("https://discord.com/channels/@me/{}/{}", self.channel, self.message),
+        }
+    }
+}
+
 impl From<Message> for ChannelMessageId {
     fn from(m: Message) -> Self {
         Self {
@@ -172,7 +194,7 @@ impl From<&Message> for ChannelMessageId {
 // }
 
 serde_repr! {
-    pub enum MessageType: u8 {
+    pub enum MessageType: u8, unknown = Unknown {
         Default = 0,
         RecipientAdd = 1,
         RecipientRemove = 2,
@@ -286,6 +308,10 @@ bitflags! {
         const UNKNOWN_9 = 1 << 9;
         /// Undocumented so far
         const UNKNOWN_10 = 1 << 10;
+        /// this message will not trigger push and desktop notifications
+        const SUPPRESS_NOTIFICATIONS = 1 << 12;
+        /// this message is a voice message
+        const IS_VOICE_MESSAGE = 1 << 13;
     }
 }
 serde_bitflag!(MessageFlags: u16);
@@ -398,7 +424,8 @@ pub struct Reaction {
     pub emoji: Emoji,
 }
 
-#[derive(Deserialize, Serialize, Eq, PartialEq, Copy, Clone, Debug)]
+/// An RGB color, as used by [embeds](Embed) and [roles](crate::model::permissions::Role).
+#[derive(Deserialize, Serialize, Eq, PartialEq, Copy, Clone, Debug, Default, Hash)]
 pub struct Color(u32);
 
 impl Color {
@@ -406,31 +433,84 @@ impl Color {
 
     pub const BLUE: Self = Self::from_rgb(0x36, 0x93, 0xD1);
     pub const RED: Self = Self::from_rgb(0xBA, 0x46, 0x50);
+
+    /// Discord's brand color, `#5865F2`
+    pub const BLURPLE: Self = Self::from_rgb(0x58, 0x65, 0xF2);
+    pub const GREEN: Self = Self::from_rgb(0x57, 0xF2, 0x87);
+    pub const YELLOW: Self = Self::from_rgb(0xFE, 0xE7, 0x5C);
+    pub const FUCHSIA: Self = Self::from_rgb(0xEB, 0x45, 0x9E);
+    pub const WHITE: Self = Self::from_rgb(0xFF, 0xFF, 0xFF);
+    pub const BLACK: Self = Self::from_rgb(0x00, 0x00, 0x00);
 }
 
 impl Color {
     pub const fn from_rgb(r: u8, g: u8, b: u8) -> Self {
         Self(r as u32 * 0x10000 + g as u32 * 0x100 + b as u32)
     }
+
+    /// Parse a color from six hex digits, optionally prefixed by `#` or `0x`, ex `"#5865F2"`.
+    ///
+    /// # Errors
+    ///
+    /// If `hex` is not exactly six hex digits after removing its prefix.
+    pub fn from_hex(hex: &str) -> Result<Self, ColorParseError> {
+        let digits = hex.strip_prefix('#')
+            .or_else(|| hex.strip_prefix("0x"))
+            .unwrap_or(hex);
+        if digits.len() == 6 && digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            // all six bytes are ascii hex digits, so this can't fail
+            Ok(Self(u32::from_str_radix(digits, 16).unwrap()))
+        } else {
+            Err(ColorParseError(hex.into()))
+        }
+    }
+
+    /// The red, green, and blue components of this color.
+    pub const fn rgb(self) -> (u8, u8, u8) {
+        let [_, r, g, b] = self.0.to_be_bytes();
+        (r, g, b)
+    }
+}
+
+impl From<u32> for Color {
+    fn from(color: u32) -> Self {
+        Self(color & 0xFF_FF_FF)
+    }
+}
+
+impl From<Color> for u32 {
+    fn from(color: Color) -> Self {
+        color.0
+    }
 }
 
 impl TryFrom<&str> for Color {
-    type Error = ();
+    type Error = ColorParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::from_hex(value)
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = ColorParseError;
 
-    fn try_from(value: &str) -> Result<Self, ()> {
-        value.strip_prefix('#')
-            .or_else(|| value.strip_prefix("0x"))
-            .filter(|trim| trim.len() == 6)
-            .filter(|trim| trim.bytes().all(|b| b.is_ascii_hexdigit()))
-            .map(|trim| Self::from_rgb(
-                u8::from_str_radix(&trim[0..1], 16).unwrap(),
-                u8::from_str_radix(&trim[2..3], 16).unwrap(),
-                u8::from_str_radix(&trim[4..5], 16).unwrap(),
-            ))
-            .ok_or(())
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
     }
 }
 
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:06X}", self.0)
+    }
+}
+
+/// A string could not be parsed as a hex [`Color`].
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+#[error("`{0}` is not a hex color, expected six hex digits like `#5865F2`")]
+pub struct ColorParseError(pub String);
+
 #[derive(Deserialize, Serialize, Debug, Clone, Default, Eq, PartialEq)]
 pub struct Embed {
     /// title of embed
@@ -809,7 +889,8 @@ pub trait TextMarkup: Display {
 
 impl<D: Display> TextMarkup for D {}
 
-#[derive(Default, Copy, Clone, Eq, PartialEq)]
+/// How Discord should render a `<t:unix:style>` timestamp, in the reader's locale.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum TimestampStyle {
     /// 16:20
     ShortTime,
@@ -830,20 +911,55 @@ pub enum TimestampStyle {
 
 impl Display for TimestampStyle {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_char(self.as_char())
+    }
+}
+
+impl TimestampStyle {
+    /// The character Discord uses for this style in `<t:unix:style>` markup.
+    pub const fn as_char(self) -> char {
         match self {
-            Self::ShortTime => f.write_char('t'),
-            Self::LongTime => f.write_char('T'),
-            Self::ShortDate => f.write_char('d'),
-            Self::LongDate => f.write_char('D'),
-            Self::ShortDateTime => f.write_char('f'),
-            Self::LongDateTime => f.write_char('F'),
-            Self::Relative => f.write_char('R'),
+            Self::ShortTime => 't',
+            Self::LongTime => 'T',
+            Self::ShortDate => 'd',
+            Self::LongDate => 'D',
+            Self::ShortDateTime => 'f',
+            Self::LongDateTime => 'F',
+            Self::Relative => 'R',
         }
     }
 }
 
+impl TryFrom<char> for TimestampStyle {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            't' => Ok(Self::ShortTime),
+            'T' => Ok(Self::LongTime),
+            'd' => Ok(Self::ShortDate),
+            'D' => Ok(Self::LongDate),
+            'f' => Ok(Self::ShortDateTime),
+            'F' => Ok(Self::LongDateTime),
+            'R' => Ok(Self::Relative),
+            c => Err(c),
+        }
+    }
+}
+
+/// Format a point in time as Discord's `<t:unix:style>` markup, which clients render localized.
+///
+/// Implemented for [`DateTime<Utc>`](DateTime) and for every snowflake id, in which case the id's
+/// creation time is used.
 pub trait TimestampMarkup {
     fn timestamp_styled(self, style: TimestampStyle) -> String;
+
+    /// `<t:unix:R>`, ex "2 months ago"
+    fn timestamp_relative(self) -> String
+        where Self: Sized,
+    {
+        self.timestamp_styled(TimestampStyle::Relative)
+    }
 }
 
 impl TimestampMarkup for DateTime<Utc> {
@@ -944,4 +1060,81 @@ mod message_tests {
   "type": 0
 }"#)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn jump_url() {
+        let message: Message = serde_json::from_str(&crate::test_fixtures::message(3, 2)).unwrap();
+        assert_eq!(message.jump_url(GuildId(1)), "https://discord.com/channels/1/2/3");
+        assert_eq!(message.jump_url(None), "https://discord.com/channels/@me/2/3");
+    }
+
+    #[test]
+    fn mentions() {
+        use crate::model::channel::ChannelMarkup;
+        use crate::model::permissions::RoleMarkup;
+        use crate::model::user::UserMarkup;
+
+        assert_eq!(UserId(10).ping(), "<@10>");
+        assert_eq!(ChannelId(20).mention(), "<#20>");
+        assert_eq!(RoleId(30).mention(), "<@&30>");
+    }
+
+    #[test]
+    fn timestamp_styles() {
+        let time = DateTime::from_timestamp(1_618_953_630, 0).unwrap();
+        let styles = [
+            (TimestampStyle::ShortTime, "<t:1618953630:t>"),
+            (TimestampStyle::LongTime, "<t:1618953630:T>"),
+            (TimestampStyle::ShortDate, "<t:1618953630:d>"),
+            (TimestampStyle::LongDate, "<t:1618953630:D>"),
+            (TimestampStyle::ShortDateTime, "<t:1618953630:f>"),
+            (TimestampStyle::LongDateTime, "<t:1618953630:F>"),
+            (TimestampStyle::Relative, "<t:1618953630:R>"),
+        ];
+        for (style, markup) in styles {
+            assert_eq!(time.timestamp_styled(style), markup);
+            assert_eq!(TimestampStyle::try_from(style.as_char()), Ok(style));
+        }
+        assert_eq!(time.timestamp_relative(), "<t:1618953630:R>");
+        assert_eq!(TimestampStyle::try_from('x'), Err('x'));
+    }
+
+    #[test]
+    fn id_timestamp_markup() {
+        // 2016-04-30 11:18:25.796 UTC
+        let id = UserId(175_928_847_299_117_063);
+        assert_eq!(id.timestamp_styled(TimestampStyle::LongDate), "<t:1462015105:D>");
+    }
+
+    #[test]
+    fn color_rgb() {
+        let color = Color::from_rgb(0x12, 0x34, 0x56);
+        assert_eq!(u32::from(color), 0x12_34_56);
+        assert_eq!(color.rgb(), (0x12, 0x34, 0x56));
+        assert_eq!(Color::from(0xFF_12_34_56), color);
+        assert_eq!(color.to_string(), "#123456");
+    }
+
+    #[test]
+    fn color_hex() {
+        let blurple = Color::from_rgb(0x58, 0x65, 0xF2);
+        assert_eq!(Color::from_hex("#5865F2"), Ok(blurple));
+        assert_eq!(Color::from_hex("5865f2"), Ok(blurple));
+        assert_eq!(Color::from_hex("0x5865F2"), Ok(blurple));
+        assert_eq!("#5865F2".parse(), Ok(blurple));
+        assert_eq!(Color::try_from("#5865F2"), Ok(blurple));
+
+        for bad in ["", "#", "#5865F", "#5865F2A", "#5865G2", "##5865F2"] {
+            assert_eq!(Color::from_hex(bad), Err(ColorParseError(bad.into())));
+        }
+    }
+
+    #[test]
+    fn color_constants() {
+        assert_eq!(u32::from(Color::BLURPLE), 0x58_65_F2);
+        assert_eq!(u32::from(Color::WHITE), 0xFF_FF_FF);
+        assert_eq!(u32::from(Color::BLACK), 0);
+        assert_eq!(Color::RED.rgb(), (0xBA, 0x46, 0x50));
+        assert_eq!(Color::GOLD.to_string(), "#BC9D46");
+    }
+}
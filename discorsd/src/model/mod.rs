@@ -1,8 +1,9 @@
 //! Rust structures representing the information sent by Discord's API.
 
+use std::collections::BTreeMap;
 use std::fmt::{self, Display};
 
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use guild::Guild;
@@ -27,6 +28,9 @@ pub mod auto_moderation;
 pub mod command;
 pub mod interaction;
 pub mod interaction_response;
+pub mod stage_instance;
+pub mod poll;
+pub mod onboarding;
 
 /// Information returned from the `/gateway/bot` endpoint, as in
 /// [gateway](crate::http::DiscordClient::gateway_bot).
@@ -36,8 +40,29 @@ pub struct BotGateway {
     pub url: String,
     /// The recommended number of shards to use when connecting
     pub shards: u64,
-    // /// Information on the current session start limit
-    // session_start_limit: session_start_limit,
+    /// Information on the current session start limit
+    pub session_start_limit: SessionStartLimit,
+}
+
+/// How many more times the bot can [identify](crate::shard::model::Identify) today. Once the limit
+/// is used up, Discord won't let the bot connect until it resets.
+#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SessionStartLimit {
+    /// The total number of session starts the current user is allowed
+    pub total: u32,
+    /// The remaining number of session starts the current user is allowed
+    pub remaining: u32,
+    /// The number of milliseconds after which the limit resets
+    pub reset_after: u64,
+    /// The number of identify requests allowed per 5 seconds
+    pub max_concurrency: u32,
+}
+
+impl SessionStartLimit {
+    /// How long until the limit resets.
+    pub const fn reset_after(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.reset_after)
+    }
 }
 
 #[derive(Deserialize, Debug, Error)]
@@ -54,10 +79,67 @@ impl Display for DiscordError {
     }
 }
 
+impl DiscordError {
+    /// The per-field validation errors Discord sends with
+    /// [`InvalidFormBodyOrContentType`](DiscordErrorType::InvalidFormBodyOrContentType), or `None`
+    /// if there are no such errors (the raw errors are still available in [`errors`](Self::errors)).
+    pub fn field_errors(&self) -> Option<FieldErrors> {
+        self.errors.as_ref().and_then(FieldErrors::from_value)
+    }
+
+    /// Whether this error is temporary. See [`DiscordErrorType::is_transient`].
+    pub const fn is_transient(&self) -> bool {
+        self.code.is_transient()
+    }
+}
+
+/// The validation errors for a request, keyed by the path to each invalid field, such as
+/// `embeds.0.title`. Errors for the body as a whole have an empty path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldErrors(pub BTreeMap<String, Vec<FieldError>>);
+
+/// Why a field failed Discord's validation.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// for example, `BASE_TYPE_REQUIRED`
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldErrors {
+    /// Parse Discord's nested error object, in which each field's errors are in an `_errors` array.
+    /// Returns `None` if there are no field errors in `errors`.
+    pub fn from_value(errors: &serde_json::Value) -> Option<Self> {
+        fn walk(path: &str, value: &serde_json::Value, errors: &mut BTreeMap<String, Vec<FieldError>>) {
+            let serde_json::Value::Object(map) = value else { return };
+            for (key, value) in map {
+                if key == "_errors" {
+                    if let Ok(field_errors) = serde_json::from_value::<Vec<FieldError>>(value.clone()) {
+                        errors.entry(path.to_string()).or_default().extend(field_errors);
+                    }
+                } else if path.is_empty() {
+                    walk(key, value, errors);
+                } else {
+                    walk(&format!("{path}.{key}"), value, errors);
+                }
+            }
+        }
+
+        let mut field_errors = BTreeMap::new();
+        walk("", errors, &mut field_errors);
+        (!field_errors.is_empty()).then_some(Self(field_errors))
+    }
+
+    /// The errors for the field at `path`.
+    pub fn get(&self, path: &str) -> Option<&[FieldError]> {
+        self.0.get(path).map(Vec::as_slice)
+    }
+}
+
 serde_repr! {
     /// <https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes>
     #[allow(clippy::upper_case_acronyms)]
-    pub enum DiscordErrorType: u32 {
+    pub enum DiscordErrorType: u32, unknown = Unknown {
         /// General error (such as a malformed request body, amongst other things)
         General = 0,
         /// Unknown account
@@ -136,6 +218,8 @@ serde_repr! {
         MaxInvites = 30016,
         /// Guild already has a template
         GuildTemplateRepeat = 30031,
+        /// Maximum number of application commands reached (100)
+        MaxApplicationCommands = 30032,
         /// Unauthorized. Provide a valid token and try again
         Unauthorized = 40001,
         /// You need to verify your account in order to perform this action
@@ -146,6 +230,8 @@ serde_repr! {
         FeatureTempDisabled = 40006,
         /// The user is banned from this guild
         UserBanned = 40007,
+        /// Target user is not connected to voice
+        TargetNotInVoice = 40032,
         /// This message has already been crossposted
         AlreadyCrossposted = 40033,
         /// Missing access
@@ -211,6 +297,13 @@ serde_repr! {
     }
 }
 
+impl DiscordErrorType {
+    /// Whether this error is temporary, so the request may succeed if it is tried again later.
+    pub const fn is_transient(self) -> bool {
+        matches!(self, Self::ApiResourceOverloaded)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Application {
     /// the id of the app
@@ -248,6 +341,38 @@ pub struct Application {
     /// the application's public flags
     pub flags: Option<u32>,
 }
+id_impl!(Application => id: ApplicationId);
+
+impl Application {
+    /// The url where this application's icon can be retrieved from Discord, if it has one. The
+    /// desired format must be specified by `I`, and can only be a [`StillImage`] format.
+    pub fn icon_url<I: StillImage>(&self) -> Option<String> {
+        self.icon_url_sized::<I>(None)
+    }
+
+    /// Like [`icon_url`](Self::icon_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn icon_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.icon.as_ref()
+            .and_then(|icon| cdn_image_url::<I>(&format!("app-icons/{}", self.id), icon, size))
+    }
+
+    /// The url where this application's [cover image](Self::cover_image) can be retrieved from
+    /// Discord, if it has one. The desired format must be specified by `I`, and can only be a
+    /// [`StillImage`] format.
+    pub fn cover_image_url<I: StillImage>(&self) -> Option<String> {
+        self.cover_image_url_sized::<I>(None)
+    }
+
+    /// Like [`cover_image_url`](Self::cover_image_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn cover_image_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.cover_image.as_ref()
+            .and_then(|cover| cdn_image_url::<I>(&format!("app-icons/{}", self.id), cover, size))
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Team {
@@ -260,6 +385,23 @@ pub struct Team {
     /// the user id of the current team owner
     pub owner_user_id: UserId,
 }
+id_impl!(Team => id: TeamId);
+
+impl Team {
+    /// The url where this team's icon can be retrieved from Discord, if it has one. The desired
+    /// format must be specified by `I`, and can only be a [`StillImage`] format.
+    pub fn icon_url<I: StillImage>(&self) -> Option<String> {
+        self.icon_url_sized::<I>(None)
+    }
+
+    /// Like [`icon_url`](Self::icon_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn icon_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.icon.as_ref()
+            .and_then(|icon| cdn_image_url::<I>(&format!("team-icons/{}", self.id), icon, size))
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct TeamMember {
@@ -322,9 +464,227 @@ impl ImageFormat for Gif {
     const ANIMATED: bool = true;
 }
 
+/// The size of an image requested from Discord's CDN: a power of two between 16 and 4096.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageSize(u16);
+
+impl ImageSize {
+    pub const MIN: Self = Self(16);
+    pub const MAX: Self = Self(4096);
+
+    /// `None` if `size` is not a power of two between 16 and 4096.
+    pub const fn new(size: u16) -> Option<Self> {
+        if size.is_power_of_two() && size >= Self::MIN.0 && size <= Self::MAX.0 {
+            Some(Self(size))
+        } else {
+            None
+        }
+    }
+
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+}
+
+/// The CDN url for the image `hash` in `path`. Animated hashes (starting with `a_`) are always
+/// returned as a [Gif], and `None` is returned if `I` is animated but `hash` isn't.
+pub(crate) fn cdn_image_url<I: ImageFormat>(path: &str, hash: &str, size: Option<ImageSize>) -> Option<String> {
+    cdn_asset_url::<I>(path, hash, hash.starts_with("a_"), size)
+}
+
+/// Like [`cdn_image_url`], for assets (like emojis) that aren't marked as animated by their hash.
+pub(crate) fn cdn_asset_url<I: ImageFormat>(path: &str, hash: &str, animated: bool, size: Option<ImageSize>) -> Option<String> {
+    let extension = match (I::ANIMATED, animated) {
+        (true, false) => return None,
+        (_, true) => Gif::EXTENSION,
+        (false, false) => I::EXTENSION,
+    };
+    let url = cdn!("{}/{}.{}", path, hash, extension);
+    Some(match size {
+        Some(size) => format!("{url}?size={}", size.get()),
+        None => url,
+    })
+}
+
+#[cfg(test)]
+mod model_tests {
+    use super::*;
+
+    #[test]
+    fn unknown_enum_values() {
+        use crate::model::channel::ChannelType;
+        use crate::model::command::CommandType;
+        use crate::model::message::MessageType;
+
+        fn round_trip<T>(json: &str, expected: T)
+            where T: serde::de::DeserializeOwned + serde::Serialize + PartialEq + fmt::Debug,
+        {
+            let value: T = serde_json::from_str(json).unwrap();
+            assert_eq!(value, expected);
+            assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        }
+
+        round_trip("0", ChannelType::Text);
+        round_trip("99", ChannelType::Unknown(99));
+        round_trip("19", MessageType::Reply);
+        round_trip("250", MessageType::Unknown(250));
+        round_trip("3", CommandType::MessageCommand);
+        round_trip("4", CommandType::Unknown(4));
+        round_trip("10007", DiscordErrorType::UnknownMember);
+        round_trip("123456", DiscordErrorType::Unknown(123_456));
+
+        let error: DiscordError = serde_json::from_str(r#"{"code": 123456, "message": "Something new"}"#).unwrap();
+        assert_eq!(error.code, DiscordErrorType::Unknown(123_456));
+    }
+
+    #[test]
+    fn field_errors() {
+        const JSON: &str = r#"{
+            "code": 50035,
+            "errors": {
+                "content": {
+                    "_errors": [{ "code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 2000 or fewer in length." }]
+                },
+                "embeds": {
+                    "0": {
+                        "title": {
+                            "_errors": [{ "code": "BASE_TYPE_REQUIRED", "message": "This field is required" }]
+                        }
+                    }
+                }
+            },
+            "message": "Invalid Form Body"
+        }"#;
+        let error: DiscordError = serde_json::from_str(JSON).unwrap();
+        assert_eq!(error.code, DiscordErrorType::InvalidFormBodyOrContentType);
+        assert!(error.errors.is_some());
+
+        let field_errors = error.field_errors().unwrap();
+        assert_eq!(field_errors.0.len(), 2);
+        assert_eq!(
+            field_errors.get("content"),
+            Some([FieldError {
+                code: "BASE_TYPE_MAX_LENGTH".into(),
+                message: "Must be 2000 or fewer in length.".into(),
+            }].as_slice()),
+        );
+        assert_eq!(field_errors.get("embeds.0.title").unwrap()[0].code, "BASE_TYPE_REQUIRED");
+    }
+
+    #[test]
+    fn no_field_errors() {
+        let error: DiscordError = serde_json::from_str(r#"{ "code": 10003, "message": "Unknown Channel" }"#).unwrap();
+        assert!(error.field_errors().is_none());
+    }
+
+    #[test]
+    fn image_sizes() {
+        assert_eq!(ImageSize::new(16), Some(ImageSize::MIN));
+        assert_eq!(ImageSize::new(4096), Some(ImageSize::MAX));
+        assert_eq!(ImageSize::new(256).map(ImageSize::get), Some(256));
+        assert_eq!(ImageSize::new(8), None);
+        assert_eq!(ImageSize::new(100), None);
+        assert_eq!(ImageSize::new(8192), None);
+    }
+
+    #[test]
+    fn avatar_urls() {
+        let user = |avatar: &str| -> User {
+            serde_json::from_str(&format!(
+                r#"{{ "id": "10", "username": "mason", "discriminator": "0", "avatar": "{avatar}" }}"#
+            )).unwrap()
+        };
+        let still = user("abc");
+        let animated = user("a_abc");
+
+        assert_eq!(
+            still.avatar_url::<Png>().as_deref(),
+            Some("https://cdn.discordapp.com/avatars/10/abc.png"),
+        );
+        assert_eq!(
+            still.avatar_url_sized::<WebP>(ImageSize::new(64)).as_deref(),
+            Some("https://cdn.discordapp.com/avatars/10/abc.webp?size=64"),
+        );
+        assert_eq!(still.avatar_url::<Gif>(), None);
+        assert_eq!(
+            animated.avatar_url::<Png>().as_deref(),
+            Some("https://cdn.discordapp.com/avatars/10/a_abc.gif"),
+        );
+        assert_eq!(
+            animated.avatar_url_sized::<Gif>(ImageSize::new(4096)).as_deref(),
+            Some("https://cdn.discordapp.com/avatars/10/a_abc.gif?size=4096"),
+        );
+    }
+
+    #[test]
+    fn default_avatar_urls() {
+        let user = |id: &str, discriminator: &str| -> User {
+            serde_json::from_str(&format!(
+                r#"{{ "id": "{id}", "username": "mason", "discriminator": "{discriminator}", "avatar": null }}"#
+            )).unwrap()
+        };
+        let legacy = user("80351110224678912", "1337");
+        assert_eq!(legacy.default_avatar_url(), "https://cdn.discordapp.com/embed/avatars/2.png");
+        let migrated = user("80351110224678912", "0");
+        assert_eq!(migrated.default_avatar_url(), "https://cdn.discordapp.com/embed/avatars/5.png");
+        assert_eq!(migrated.display_avatar_url::<Png>(), migrated.default_avatar_url());
+        let migrated = user("12582912", "0");
+        assert_eq!(migrated.default_avatar_url(), "https://cdn.discordapp.com/embed/avatars/3.png");
+        let migrated = user("1", "0");
+        assert_eq!(migrated.default_avatar_url(), "https://cdn.discordapp.com/embed/avatars/0.png");
+        let legacy = user("1", "0004");
+        assert_eq!(legacy.default_avatar_url(), "https://cdn.discordapp.com/embed/avatars/4.png");
+    }
+
+    #[test]
+    fn user_tags() {
+        let user = |discriminator: &str| -> User {
+            serde_json::from_str(&format!(
+                r#"{{ "id": "10", "username": "mason", "discriminator": "{discriminator}", "avatar": null }}"#
+            )).unwrap()
+        };
+        let legacy = user("0420");
+        assert!(legacy.has_discriminator());
+        assert_eq!(legacy.tag(), "mason#0420");
+
+        let migrated = user("0");
+        assert!(!migrated.has_discriminator());
+        assert_eq!(migrated.tag(), "mason");
+    }
+
+    #[test]
+    fn application_urls() {
+        let application: Application = serde_json::from_str(r#"{
+          "id": "4",
+          "name": "Test Bot",
+          "icon": "abc",
+          "description": "tests things",
+          "bot_public": true,
+          "bot_require_code_grant": false,
+          "owner": { "id": "10", "username": "owner", "discriminator": "0", "avatar": null },
+          "summary": "",
+          "verify_key": "key",
+          "team": { "icon": "def", "id": "5", "members": [], "owner_user_id": "10" },
+          "cover_image": "ghi"
+        }"#).unwrap();
+        assert_eq!(
+            application.icon_url_sized::<Png>(ImageSize::new(32)).as_deref(),
+            Some("https://cdn.discordapp.com/app-icons/4/abc.png?size=32"),
+        );
+        assert_eq!(
+            application.cover_image_url::<WebP>().as_deref(),
+            Some("https://cdn.discordapp.com/app-icons/4/ghi.webp"),
+        );
+        assert_eq!(
+            application.team.unwrap().icon_url::<Jpeg>().as_deref(),
+            Some("https://cdn.discordapp.com/team-icons/5/def.jpeg"),
+        );
+    }
+}
+
 // #[cfg(test)]
 // mod tag_by_num {
-//     use serde_derive::{Deserialize, Serialize};
+//     use serde::{Deserialize, Serialize};
 //
 //     use crate::model::ids::*;
 //
@@ -0,0 +1,196 @@
+//! The onboarding flow that new members of a [Community](crate::model::guild::GuildFeature::Community)
+//! guild go through, which can be fetched and changed with
+//! [`DiscordClient::get_guild_onboarding`](crate::http::DiscordClient::get_guild_onboarding) and
+//! [`DiscordClient::modify_guild_onboarding`](crate::http::DiscordClient::modify_guild_onboarding).
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::model::emoji::{CustomEmoji, Emoji};
+use crate::model::ids::*;
+use crate::serde_utils::BoolExt;
+
+/// A guild's onboarding flow.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GuildOnboarding {
+    /// id of the guild this onboarding is part of
+    pub guild_id: GuildId,
+    /// the prompts shown during onboarding and in customize community
+    pub prompts: Vec<OnboardingPrompt>,
+    /// the channels that members get opted into automatically
+    pub default_channel_ids: Vec<ChannelId>,
+    /// whether onboarding is enabled in the guild
+    pub enabled: bool,
+    /// the criteria used to decide whether onboarding is satisfied
+    pub mode: OnboardingMode,
+}
+id_impl!(GuildOnboarding => guild_id: GuildId);
+
+/// A question asked during a guild's [onboarding](GuildOnboarding).
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OnboardingPrompt {
+    /// id of the prompt
+    pub id: OnboardingPromptId,
+    /// how the prompt is shown
+    #[serde(rename = "type")]
+    pub kind: PromptType,
+    /// the options available within the prompt
+    pub options: Vec<PromptOption>,
+    /// the title of the prompt
+    pub title: String,
+    /// whether users are limited to selecting one option for the prompt
+    pub single_select: bool,
+    /// whether the prompt is required before a user completes the onboarding flow
+    pub required: bool,
+    /// whether the prompt is present in the onboarding flow. If `false`, the prompt will only
+    /// appear in the Channels & Roles tab
+    pub in_onboarding: bool,
+}
+id_impl!(OnboardingPrompt => OnboardingPromptId);
+
+/// An option that can be chosen for an [`OnboardingPrompt`].
+///
+/// Discord sends the option's emoji as an emoji object, but expects it as separate `emoji_id`,
+/// `emoji_name`, and `emoji_animated` fields when modifying onboarding, so this is serialized that
+/// way.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PromptOption {
+    /// id of the prompt option
+    pub id: PromptOptionId,
+    /// the channels a member is added to when the option is selected
+    pub channel_ids: Vec<ChannelId>,
+    /// the roles assigned to a member when the option is selected
+    pub role_ids: Vec<RoleId>,
+    /// the emoji of the option
+    #[serde(default)]
+    pub emoji: Option<Emoji>,
+    /// the title of the option
+    pub title: String,
+    /// the description of the option
+    pub description: Option<String>,
+}
+id_impl!(PromptOption => PromptOptionId);
+
+impl Serialize for PromptOption {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Raw<'a> {
+            id: PromptOptionId,
+            channel_ids: &'a [ChannelId],
+            role_ids: &'a [RoleId],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            emoji_id: Option<EmojiId>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            emoji_name: Option<&'a str>,
+            #[serde(skip_serializing_if = "bool::is_false")]
+            emoji_animated: bool,
+            title: &'a str,
+            description: Option<&'a str>,
+        }
+
+        let (emoji_id, emoji_name, emoji_animated) = match &self.emoji {
+            Some(Emoji::Custom(CustomEmoji { id, name, animated, .. })) => (Some(*id), Some(name.as_str()), *animated),
+            Some(Emoji::Unicode { name }) => (None, Some(name.as_str()), false),
+            None => (None, None, false),
+        };
+        Raw {
+            id: self.id,
+            channel_ids: &self.channel_ids,
+            role_ids: &self.role_ids,
+            emoji_id,
+            emoji_name,
+            emoji_animated,
+            title: &self.title,
+            description: self.description.as_deref(),
+        }.serialize(serializer)
+    }
+}
+
+serde_repr! {
+    /// How an [`OnboardingPrompt`] is shown.
+    pub enum PromptType: u8 {
+        MultipleChoice = 0,
+        Dropdown = 1,
+    }
+}
+
+serde_repr! {
+    /// The criteria used to decide whether a guild's [onboarding](GuildOnboarding) is satisfied.
+    pub enum OnboardingMode: u8 {
+        /// counts only default channels towards constraints
+        OnboardingDefault = 0,
+        /// counts default channels and questions towards constraints
+        OnboardingAdvanced = 1,
+    }
+}
+
+#[cfg(test)]
+mod onboarding_tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+      "guild_id": "1",
+      "prompts": [
+        {
+          "id": "20",
+          "type": 0,
+          "options": [
+            {
+              "id": "30",
+              "channel_ids": ["40"],
+              "role_ids": [],
+              "emoji": { "id": null, "name": "🎮", "animated": false },
+              "title": "Gaming",
+              "description": null
+            },
+            {
+              "id": "31",
+              "channel_ids": [],
+              "role_ids": ["50"],
+              "emoji": { "id": "60", "name": "art", "animated": true },
+              "title": "Art",
+              "description": "For the artists"
+            }
+          ],
+          "title": "What are you here for?",
+          "single_select": false,
+          "required": true,
+          "in_onboarding": true
+        }
+      ],
+      "default_channel_ids": ["40", "41"],
+      "enabled": true,
+      "mode": 1
+    }"#;
+
+    #[test]
+    fn onboarding_response() {
+        let onboarding: GuildOnboarding = serde_json::from_str(JSON).unwrap();
+        assert_eq!(onboarding.guild_id, GuildId(1));
+        assert!(onboarding.enabled);
+        assert_eq!(onboarding.mode, OnboardingMode::OnboardingAdvanced);
+        assert_eq!(onboarding.default_channel_ids, [ChannelId(40), ChannelId(41)]);
+
+        let prompt = &onboarding.prompts[0];
+        assert_eq!(prompt.id, OnboardingPromptId(20));
+        assert_eq!(prompt.kind, PromptType::MultipleChoice);
+        assert!(prompt.required);
+        assert_eq!(prompt.options[0].emoji, Some(Emoji::Unicode { name: "🎮".into() }));
+        assert_eq!(prompt.options[0].channel_ids, [ChannelId(40)]);
+        assert_eq!(prompt.options[1].role_ids, [RoleId(50)]);
+        assert_eq!(prompt.options[1].description.as_deref(), Some("For the artists"));
+        assert!(matches!(&prompt.options[1].emoji, Some(Emoji::Custom(c)) if c.id == EmojiId(60) && c.animated));
+    }
+
+    #[test]
+    fn prompt_option_emoji_fields() {
+        let onboarding: GuildOnboarding = serde_json::from_str(JSON).unwrap();
+        let json = serde_json::to_value(&onboarding).unwrap();
+        let options = &json["prompts"][0]["options"];
+        assert_eq!(options[0]["emoji_name"], "🎮");
+        assert!(options[0].get("emoji_id").is_none());
+        assert!(options[0].get("emoji").is_none());
+        assert_eq!(options[1]["emoji_id"], "60");
+        assert_eq!(options[1]["emoji_name"], "art");
+        assert_eq!(options[1]["emoji_animated"], true);
+    }
+}
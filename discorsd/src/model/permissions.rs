@@ -3,12 +3,12 @@ use std::fmt::Formatter;
 
 use itertools::{Either, Itertools};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_derive::{Deserialize, Serialize};
 use serde::de::{Error, Visitor};
 
 use crate::cache::Cache;
 use crate::model::channel::{Channel, Overwrite};
-use crate::model::guild::GuildMember;
+use crate::model::guild::{Guild, GuildMember};
+use crate::model::{cdn_image_url, ImageSize, StillImage};
 use crate::model::ids::*;
 pub use crate::model::ids::RoleId;
 bitflags! {
@@ -206,9 +206,15 @@ impl Permissions {
 
     async fn base_permissions(cache: &Cache, member: &GuildMember, guild: GuildId, everyone: &Role) -> Self {
         let guild = cache.guild(guild).await.unwrap();
-        if guild.owner { return Self::all(); }
+        Self::guild_permissions(member, &guild, everyone)
+    }
+
+    /// The permissions `member` has in `guild` from its roles, before any channel's overwrites.
+    /// [Pending](GuildMember::is_pending) members only get `everyone`'s permissions.
+    pub(crate) fn guild_permissions(member: &GuildMember, guild: &Guild, everyone: &Role) -> Self {
+        if guild.owner_id == member.id() { return Self::all(); }
 
-        let permissions = member.roles.iter()
+        let permissions = Self::member_roles(member)
             .filter_map(|role| guild.roles.get(role))
             .fold(everyone.permissions, |perms, role_perms| perms | role_perms.permissions);
         if permissions.contains(Self::ADMINISTRATOR) {
@@ -218,6 +224,13 @@ impl Permissions {
         }
     }
 
+    /// The roles that count towards `member`'s permissions, which is none of them until they've
+    /// passed Membership Screening.
+    fn member_roles(member: &GuildMember) -> impl Iterator<Item=&RoleId> {
+        member.roles.iter()
+            .filter(|_| !member.is_pending())
+    }
+
     fn overwrites(self, member: &GuildMember, overwrites: Option<&[Overwrite]>, everyone: &Role) -> Self {
         // ADMINISTRATOR overrides any potential permission overwrites, so there is nothing to do here.
         if self.contains(Self::ADMINISTRATOR) { return Self::all(); }
@@ -238,7 +251,7 @@ impl Permissions {
             }
 
             // Apply role specific overwrites.
-            let (allow, deny) = member.roles.iter()
+            let (allow, deny) = Self::member_roles(member)
                 .filter_map(|id| role_overwrites.get(id))
                 .fold(
                     (Self::empty(), Self::empty()),
@@ -326,9 +339,31 @@ pub struct Role {
     pub mentionable: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<RoleTags>,
+    /// role icon hash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// role unicode emoji
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unicode_emoji: Option<String>,
 }
 id_impl!(Role => RoleId);
 
+impl Role {
+    /// The url where this role's icon can be retrieved from Discord, if it has one. The desired
+    /// format must be specified by `I`, and can only be a [`StillImage`] format.
+    pub fn icon_url<I: StillImage>(&self) -> Option<String> {
+        self.icon_url_sized::<I>(None)
+    }
+
+    /// Like [`icon_url`](Self::icon_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn icon_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.icon.as_ref()
+            .and_then(|icon| cdn_image_url::<I>(&format!("role-icons/{}", self.id), icon, size))
+    }
+}
+
 pub trait RoleMarkup: Id<Id=RoleId> {
     fn mention(&self) -> String {
         format!("<@&{}>", self.id())
@@ -0,0 +1,252 @@
+//! Polls, which can be attached to a [`Message`](crate::model::message::Message) when it is
+//! created.
+
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::emoji::Emoji;
+use crate::serde_utils::BoolExt;
+
+/// A poll on a message, as received from Discord.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Poll {
+    /// the question of the poll. Only `text` is supported.
+    pub question: PollMedia,
+    /// each of the answers available in the poll
+    pub answers: Vec<PollAnswer>,
+    /// when the poll ends. Can be `None` for non-expiring polls, which Discord may send in the
+    /// future.
+    pub expiry: Option<DateTime<Utc>>,
+    /// whether a user can select multiple answers
+    pub allow_multiselect: bool,
+    /// the layout type of the poll
+    pub layout_type: PollLayoutType,
+    /// the results of the poll. Discord may leave this out when the results aren't known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<PollResults>,
+}
+
+impl Poll {
+    /// How many votes `answer` has, or `None` if the [results](Self::results) aren't known.
+    pub fn votes(&self, answer: u32) -> Option<u32> {
+        self.results.as_ref().map(|results| results.votes(answer))
+    }
+}
+
+/// The text and/or emoji of a poll's question or answer.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PollMedia {
+    /// the text of the field. The question can be up to 300 characters, and answers up to 55.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<Cow<'static, str>>,
+    /// the emoji of the field. Questions can't have an emoji.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<Emoji>,
+}
+
+impl<S: Into<Cow<'static, str>>> From<S> for PollMedia {
+    fn from(text: S) -> Self {
+        Self { text: Some(text.into()), emoji: None }
+    }
+}
+
+/// One of the answers in a [`Poll`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PollAnswer {
+    /// the id of the answer, used for [counting votes](PollAnswerCount::id)
+    pub answer_id: u32,
+    /// the data of the answer
+    pub poll_media: PollMedia,
+}
+
+serde_repr! {
+    /// How a [`Poll`] is displayed.
+    pub enum PollLayoutType: u8 {
+        /// the default layout type
+        Default = 1,
+    }
+}
+
+/// The vote counts of a [`Poll`]. Discord counts votes in the background, so these may not be
+/// exact until [finalized](Self::is_finalized).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PollResults {
+    /// whether the votes have been precisely counted
+    pub is_finalized: bool,
+    /// the counts for each answer. Answers with no votes aren't included.
+    pub answer_counts: Vec<PollAnswerCount>,
+}
+
+impl PollResults {
+    /// How many votes `answer` has.
+    pub fn votes(&self, answer: u32) -> u32 {
+        self.answer_counts.iter()
+            .find(|count| count.id == answer)
+            .map_or(0, |count| count.count)
+    }
+}
+
+/// The number of votes for one answer of a [`Poll`].
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PollAnswerCount {
+    /// the [`answer_id`](PollAnswer::answer_id)
+    pub id: u32,
+    /// the number of votes for this answer
+    pub count: u32,
+    /// whether the current user voted for this answer
+    pub me_voted: bool,
+}
+
+/// A poll to send with a message, using
+/// [`CreateMessage::poll`](crate::http::channel::CreateMessage::poll).
+///
+/// ```rust
+/// # use discorsd::model::poll::CreatePoll;
+/// CreatePoll::build("Best color?", |p| {
+///     p.answer("Red");
+///     p.answer("Blue");
+///     p.duration(48);
+/// });
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CreatePoll {
+    /// the question of the poll. Only `text` is supported.
+    pub question: PollMedia,
+    /// each of the answers available in the poll, up to 10
+    pub answers: Vec<CreatePollAnswer>,
+    /// number of hours the poll should be open for, up to 32 days. Defaults to 24
+    pub duration: u32,
+    /// whether a user can select multiple answers
+    #[serde(skip_serializing_if = "bool::is_false")]
+    pub allow_multiselect: bool,
+    /// the layout type of the poll
+    pub layout_type: PollLayoutType,
+}
+
+/// An answer for a [`CreatePoll`]. Discord picks the answer ids.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CreatePollAnswer {
+    /// the data of the answer
+    pub poll_media: PollMedia,
+}
+
+impl CreatePoll {
+    /// Build a poll asking `question`.
+    pub fn build<Q, F>(question: Q, builder: F) -> Self
+        where Q: Into<Cow<'static, str>>,
+              F: FnOnce(&mut Self),
+    {
+        let mut poll = Self {
+            question: question.into().into(),
+            answers: Vec::new(),
+            duration: 24,
+            allow_multiselect: false,
+            layout_type: PollLayoutType::Default,
+        };
+        builder(&mut poll);
+        poll
+    }
+
+    /// Add an answer to this poll. `answer` can be text, or a [`PollMedia`] to have an emoji.
+    ///
+    /// # Panics
+    ///
+    /// If this poll already has 10 answers. See also [`try_answer`](Self::try_answer).
+    pub fn answer<A: Into<PollMedia>>(&mut self, answer: A) {
+        self.try_answer(answer)
+            .map_err(|_| "can't have more than 10 answers in a poll")
+            .unwrap();
+    }
+
+    /// Add an answer to this poll. `answer` can be text, or a [`PollMedia`] to have an emoji.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(answer)` if this poll already has 10 answers. See also
+    /// [`answer`](Self::answer).
+    pub fn try_answer<A: Into<PollMedia>>(&mut self, answer: A) -> Result<(), A> {
+        if self.answers.len() >= 10 {
+            Err(answer)
+        } else {
+            self.answers.push(CreatePollAnswer { poll_media: answer.into() });
+            Ok(())
+        }
+    }
+
+    /// Set how many hours this poll will be open for.
+    pub fn duration(&mut self, hours: u32) {
+        self.duration = hours;
+    }
+
+    /// Let users vote for more than one answer.
+    pub fn multiselect(&mut self) {
+        self.allow_multiselect = true;
+    }
+}
+
+#[cfg(test)]
+mod poll_tests {
+    use super::*;
+
+    #[test]
+    fn create_poll() {
+        let poll = CreatePoll::build("Best color?", |p| {
+            p.answer("Red");
+            p.answer(PollMedia { text: Some("Blue".into()), emoji: Some(Emoji::Unicode { name: "🟦".into() }) });
+            p.duration(48);
+            p.multiselect();
+        });
+        assert_eq!(
+            serde_json::to_value(&poll).unwrap(),
+            serde_json::json!({
+                "question": { "text": "Best color?" },
+                "answers": [
+                    { "poll_media": { "text": "Red" } },
+                    { "poll_media": { "text": "Blue", "emoji": { "name": "🟦" } } },
+                ],
+                "duration": 48,
+                "allow_multiselect": true,
+                "layout_type": 1,
+            }),
+        );
+    }
+
+    #[test]
+    fn too_many_answers() {
+        let mut poll = CreatePoll::build("Pick a number", |p| {
+            for n in 1..=10 {
+                p.answer(n.to_string());
+            }
+        });
+        assert_eq!(poll.try_answer("11"), Err("11"));
+        assert_eq!(poll.answers.len(), 10);
+    }
+
+    #[test]
+    fn poll_results() {
+        let poll: Poll = serde_json::from_str(r#"{
+            "question": { "text": "Best color?" },
+            "answers": [
+                { "answer_id": 1, "poll_media": { "text": "Red" } },
+                { "answer_id": 2, "poll_media": { "text": "Blue", "emoji": { "id": null, "name": "🟦" } } }
+            ],
+            "expiry": "2024-04-20T16:20:00.000000+00:00",
+            "allow_multiselect": false,
+            "layout_type": 1,
+            "results": {
+                "is_finalized": true,
+                "answer_counts": [{ "id": 2, "count": 5, "me_voted": true }]
+            }
+        }"#).unwrap();
+        assert_eq!(poll.question.text.as_deref(), Some("Best color?"));
+        assert_eq!(poll.answers[1].poll_media.emoji, Some(Emoji::Unicode { name: "🟦".into() }));
+        assert!(poll.expiry.is_some());
+        let results = poll.results.as_ref().unwrap();
+        assert!(results.is_finalized);
+        assert!(results.answer_counts[0].me_voted);
+        assert_eq!(poll.votes(1), Some(0));
+        assert_eq!(poll.votes(2), Some(5));
+    }
+}
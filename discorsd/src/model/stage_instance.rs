@@ -0,0 +1,54 @@
+//! Live stages, which are hosted in [`GuildStageVoice`](crate::model::channel::GuildStageVoice)
+//! channels.
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::ids::*;
+
+/// Holds information about a live stage.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StageInstance {
+    /// The id of this Stage instance
+    pub id: StageInstanceId,
+    /// The guild id of the associated Stage channel
+    pub guild_id: GuildId,
+    /// The id of the associated Stage channel
+    pub channel_id: ChannelId,
+    /// The topic of the Stage instance (1-120 characters)
+    pub topic: String,
+    /// The privacy level of the Stage instance
+    pub privacy_level: PrivacyLevel,
+}
+
+id_impl!(StageInstance => id: StageInstanceId);
+
+serde_repr! {
+    /// Who can see a [`StageInstance`].
+    pub enum PrivacyLevel: u8 {
+        /// The Stage instance is visible to only guild members.
+        GuildOnly = 2,
+    }
+}
+
+#[cfg(test)]
+mod stage_instance_tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        let stage: StageInstance = serde_json::from_str(r#"{
+            "id": "840647391636226060",
+            "guild_id": "197038439483310086",
+            "channel_id": "733488538393510049",
+            "topic": "Testing Testing, 123",
+            "privacy_level": 2,
+            "discoverable_disabled": false,
+            "guild_scheduled_event_id": null
+        }"#).unwrap();
+        assert_eq!(stage.id, StageInstanceId(840_647_391_636_226_060));
+        assert_eq!(stage.guild_id, GuildId(197_038_439_483_310_086));
+        assert_eq!(stage.channel_id, ChannelId(733_488_538_393_510_049));
+        assert_eq!(stage.topic, "Testing Testing, 123");
+        assert_eq!(stage.privacy_level, PrivacyLevel::GuildOnly);
+    }
+}
@@ -1,9 +1,9 @@
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::model::guild::Integration;
 use crate::model::ids::*;
 pub use crate::model::ids::UserId;
-use crate::model::ImageFormat;
+use crate::model::{cdn_image_url, ImageFormat, ImageSize, StillImage};
 use crate::model::locales::Locale;
 
 /// Users in Discord are generally considered the base entity. Users can spawn across the entire
@@ -21,10 +21,16 @@ pub struct User {
     ///
     /// Required OAuth2 scope: identify
     pub username: String,
-    /// the user's 4-digit discord-tag
+    /// the user's 4-digit discord-tag, or `0` for users on the new username system
     ///
     /// Required OAuth2 scope: identify
     pub discriminator: String,
+    /// the user's display name, if it is set. For bots, this is the application name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_name: Option<String>,
+    /// the name the client displays for this user, if it was sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
     /// the user's avatar hash
     ///
     /// Required OAuth2 scope: identify
@@ -91,33 +97,71 @@ impl Id for User {
 }
 
 impl User {
+    /// Whether this user is still on the legacy username system, and so has a non-zero
+    /// [discriminator](Self::discriminator).
+    pub fn has_discriminator(&self) -> bool {
+        !self.discriminator.is_empty() && self.discriminator.bytes().any(|b| b != b'0')
+    }
+
+    /// This user's tag: `username#1234` for users with a [discriminator](Self::has_discriminator),
+    /// or just their username for users on the new username system.
+    pub fn tag(&self) -> String {
+        if self.has_discriminator() {
+            format!("{}#{}", self.username, self.discriminator)
+        } else {
+            self.username.clone()
+        }
+    }
+
+    /// The name Discord shows for this user outside of a guild: their
+    /// [global name](Self::global_name) if they have one, otherwise their
+    /// [username](Self::username). In a guild, use
+    /// [`GuildMember::display_name`](crate::model::guild::GuildMember::display_name) to also
+    /// account for nicknames.
+    pub fn display_name(&self) -> &str {
+        self.global_name.as_deref()
+            .unwrap_or(&self.username)
+    }
+
     /// The url where this user's default avatar can be retrieved from Discord. The image will
-    /// always be a png and is one of the five default avatars.
-    #[allow(clippy::missing_panics_doc)]
+    /// always be a png and is one of the six default avatars. Users on the new username system
+    /// (whose [discriminator](User::discriminator) is `0`) get theirs based on their id.
     pub fn default_avatar_url(&self) -> String {
-        let disc: u16 = self.discriminator.parse()
-            .expect("a user's discriminator should be a 4 digit number");
-        cdn!("embed/avatars/{}.png", disc % 5)
+        let index = match self.discriminator.parse::<u64>() {
+            Ok(disc) if self.has_discriminator() => disc % 5,
+            _ => (self.id.0 >> 22) % 6,
+        };
+        cdn!("embed/avatars/{}.png", index)
     }
 
     /// The url where this user's avatar can be retrieved from Discord, if they have one. The
-    /// desired format must be specified by `I`. If `I` is an animated format (currently only
-    /// [Gif](crate::model::Gif), the [avatar](User::avatar) must start with `a_` or `None` will be
-    /// returned.
-    ///
-    /// The returned image size can be changed by appending a querystring of `?size=desired_size` to
-    /// the URL. Image size can be any power of two between 16 and 4096.
+    /// desired format must be specified by `I`, but animated avatars (starting with `a_`) are
+    /// always a [Gif](crate::model::Gif). If `I` is an animated format, the
+    /// [avatar](User::avatar) must start with `a_` or `None` will be returned.
     pub fn avatar_url<I: ImageFormat>(&self) -> Option<String> {
-        if I::ANIMATED {
-            self.avatar.as_ref().and_then(|avatar| if avatar.starts_with("a_") {
-                Some(cdn!("avatars/{}/{}.{}", self.id, avatar, I::EXTENSION))
-            } else {
-                None
-            })
-        } else {
-            self.avatar.as_ref()
-                .map(|avatar| cdn!("avatars/{}/{}.{}", self.id, avatar, I::EXTENSION))
-        }
+        self.avatar_url_sized::<I>(None)
+    }
+
+    /// Like [`avatar_url`](Self::avatar_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn avatar_url_sized<I: ImageFormat>(&self, size: Option<ImageSize>) -> Option<String> {
+        self.avatar.as_ref()
+            .and_then(|avatar| cdn_image_url::<I>(&format!("avatars/{}", self.id), avatar, size))
+    }
+
+    /// The url of this user's [avatar](Self::avatar_url), or their
+    /// [default avatar](Self::default_avatar_url) if they don't have one.
+    pub fn display_avatar_url<I: StillImage>(&self) -> String {
+        self.display_avatar_url_sized::<I>(None)
+    }
+
+    /// Like [`display_avatar_url`](Self::display_avatar_url), but for an image of `size` pixels.
+    ///
+    /// If `size` is `None`, Discord picks the size of the returned image.
+    pub fn display_avatar_url_sized<I: StillImage>(&self, size: Option<ImageSize>) -> String {
+        self.avatar_url_sized::<I>(size)
+            .unwrap_or_else(|| self.default_avatar_url())
     }
 }
 
@@ -1,4 +1,4 @@
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::model::guild::GuildMember;
 use crate::model::ids::*;
@@ -8,7 +8,7 @@ pub struct VoiceState {
     /// the guild id this voice state is for
     pub guild_id: Option<GuildId>,
     /// the channel id this user is connected to
-    pub channel_id: Option<GuildId>,
+    pub channel_id: Option<ChannelId>,
     /// the user id this voice state is for
     pub user_id: UserId,
     /// the guild member this voice state is for
@@ -33,6 +33,44 @@ pub struct VoiceState {
 
 id_impl!(VoiceState => user_id: UserId);
 
+/// Everything needed to connect to a guild's voice server, gathered from the
+/// [`VoiceStateUpdate`](crate::shard::dispatch::VoiceStateUpdate) and
+/// [`VoiceServerUpdate`](crate::shard::dispatch::VoiceServerUpdate) Discord sends after the bot
+/// [joins a voice channel](crate::BotState::join_voice).
+///
+/// Discorsd doesn't connect to voice itself, this is meant to be handed to a voice implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceConnectionInfo {
+    /// the guild the voice connection is in
+    pub guild_id: GuildId,
+    /// the voice channel the bot is connected to
+    pub channel_id: ChannelId,
+    /// the bot's user id
+    pub user_id: UserId,
+    /// the session id from the bot's [`VoiceState`]
+    pub session_id: String,
+    /// the voice connection token
+    pub token: String,
+    /// the voice server host
+    pub endpoint: String,
+}
+
+/// The halves of a [`VoiceConnectionInfo`] received so far, since Discord sends the state and
+/// server updates in either order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingVoiceConnection {
+    pub(crate) state: Option<(ChannelId, UserId, String)>,
+    pub(crate) server: Option<(String, String)>,
+}
+
+impl PendingVoiceConnection {
+    pub(crate) fn info(&self, guild_id: GuildId) -> Option<VoiceConnectionInfo> {
+        let (channel_id, user_id, session_id) = self.state.clone()?;
+        let (token, endpoint) = self.server.clone()?;
+        Some(VoiceConnectionInfo { guild_id, channel_id, user_id, session_id, token, endpoint })
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct VoiceRegion {
     /// unique ID for the region
@@ -13,8 +13,53 @@ use serde_json::error::Category;
 pub enum Error {
     Serde(serde_json::Error),
     Span(SpanError),
+    Body(BodyError),
 }
 
+/// An error parsing an HTTP response body, with the type that was expected and the start of the
+/// body that failed to parse.
+#[derive(Debug)]
+pub struct BodyError {
+    /// The name of the type the body was being parsed as.
+    pub type_name: &'static str,
+    /// The start of the body, truncated to at most [`MAX_SNIPPET`](Self::MAX_SNIPPET) bytes.
+    pub snippet: String,
+    /// Whether the body was longer than `snippet`.
+    pub truncated: bool,
+    pub error: Box<Error>,
+}
+
+impl BodyError {
+    pub const MAX_SNIPPET: usize = 500;
+
+    fn new<T>(body: &str, error: Error) -> Self {
+        let mut end = min(body.len(), Self::MAX_SNIPPET);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        Self {
+            type_name: std::any::type_name::<T>(),
+            snippet: body[..end].to_string(),
+            truncated: end < body.len(),
+            error: Box::new(error),
+        }
+    }
+}
+
+impl Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "failed to parse response as `{}`: {}\nbody: {}{}",
+               self.type_name,
+               self.error,
+               self.snippet,
+               if self.truncated { "..." } else { "" },
+        )
+    }
+}
+
+impl std::error::Error for BodyError {}
+
 #[derive(Debug)]
 pub struct SpanError {
     span: String,
@@ -70,6 +115,7 @@ impl Display for Error {
         match self {
             Self::Serde(serde) => write!(f, "{serde}"),
             Self::Span(span) => write!(f, "{span}"),
+            Self::Body(body) => write!(f, "{body}"),
         }
     }
 }
@@ -118,6 +164,17 @@ pub fn nice_from_str<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, Error> {
     }
 }
 
+/// Like [`nice_from_str`], but for a response body: an error also includes the name of `T` and a
+/// snippet of `body`. An empty body is parsed as `null`.
+///
+/// # Errors
+///
+/// If `body` can't be parsed as a `T`
+pub fn nice_from_body<T: DeserializeOwned>(body: &str) -> Result<T, Error> {
+    let body = if body.is_empty() { "null" } else { body };
+    nice_from_str(body).map_err(|e| Error::Body(BodyError::new::<T>(body, e)))
+}
+
 #[async_trait]
 pub trait NiceResponseJson {
     async fn nice_json<T: DeserializeOwned>(self) -> crate::http::ClientResult<T>;
@@ -126,11 +183,8 @@ pub trait NiceResponseJson {
 #[async_trait]
 impl NiceResponseJson for Response {
     async fn nice_json<T: DeserializeOwned>(self) -> crate::http::ClientResult<T> {
-        let mut text = self.text().await?;
-        if text.is_empty() {
-            text = "null".into();
-        }
-        Ok(nice_from_str(&text)?)
+        let text = self.text().await?;
+        Ok(nice_from_body(&text)?)
     }
 }
 
@@ -170,4 +224,39 @@ impl<T> SkipUnit for Vec<T> {
     fn should_skip(&self) -> bool {
         self.is_empty()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::ids::UserId;
+    use crate::model::user::User;
+
+    use super::*;
+
+    #[test]
+    fn body_error_has_type_and_snippet() {
+        let body = r#"{"message": "401: Unauthorized", "code": 0}"#;
+        let error = nice_from_body::<User>(body).unwrap_err();
+        let Error::Body(body_error) = &error else { panic!("expected a body error, got {error:?}") };
+        assert_eq!(body_error.snippet, body);
+        assert!(!body_error.truncated);
+        let message = error.to_string();
+        assert!(message.contains("User"), "{message}");
+        assert!(message.contains("401: Unauthorized"), "{message}");
+    }
+
+    #[test]
+    fn body_error_snippet_is_truncated() {
+        let body = format!("[{}", "\"é\",".repeat(BodyError::MAX_SNIPPET));
+        let error = nice_from_body::<Vec<UserId>>(&body).unwrap_err();
+        let Error::Body(body_error) = error else { panic!("expected a body error") };
+        assert!(body_error.truncated);
+        assert!(body_error.snippet.len() <= BodyError::MAX_SNIPPET);
+        assert!(body.starts_with(&body_error.snippet));
+    }
+
+    #[test]
+    fn empty_body_is_null() {
+        assert_eq!(nice_from_body::<Option<UserId>>("").unwrap(), None);
+    }
+}
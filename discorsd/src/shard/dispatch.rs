@@ -5,26 +5,32 @@ use std::convert::{TryFrom, TryInto};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use futures::StreamExt;
-use serde_derive::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
-use crate::cache::{Cache, IdMap, Update};
+use crate::cache::{Cache, IdMap, ThreadMembers, Update};
 use crate::model::auto_moderation::{Action, AutoModRule, TriggerType};
 use crate::model::channel::{Channel, ChannelType, Thread, ThreadMember};
 use crate::model::components::ActionRow;
 use crate::model::emoji::{CustomEmoji, Emoji};
-use crate::model::guild::{ExplicitFilterLevel, Guild, GuildFeature, GuildMember, Integration, MfaLevel, NotificationLevel, PremiumTier, SystemChannelFlags, UnavailableGuild, VerificationLevel};
+use crate::model::guild::{ExplicitFilterLevel, Guild, GuildFeature, GuildMember, GuildMemberFlags, Integration, MfaLevel, NotificationLevel, PremiumTier, SystemChannelFlags, UnavailableGuild, VerificationLevel};
 use crate::model::ids::*;
-use crate::model::interaction::{ApplicationCommandData, Interaction, InteractionData};
+use crate::model::interaction::{ApplicationCommandData, Interaction, InteractionData, PartialGuildMember};
 use crate::model::message::{Attachment, ChannelMention, ChannelMessageId, Embed, Message, MessageActivity, MessageApplication, MessageFlags, MessageInteraction, MessageReference, MessageType, Reaction, StickerItem};
 use crate::model::permissions::{Permissions, Role};
+use crate::model::poll::Poll;
+use crate::model::stage_instance::StageInstance;
 use crate::model::user::User;
 use crate::model::voice::VoiceState;
 use crate::shard::model::{Activity, StatusType};
 
+/// Every event the gateway dispatches to your bot.
+///
+/// These are what [`BotState::events`](crate::BotState::events) yields, after the cache has been
+/// updated with each one.
 #[allow(clippy::large_enum_variant)]
 #[derive(Deserialize, Debug, Clone)]
 #[serde(tag = "t", content = "d", rename_all = "SCREAMING_SNAKE_CASE")]
-pub(crate) enum DispatchPayload {
+pub enum Event {
     // Connection
     Ready(Ready),
     Resumed(Resumed),
@@ -117,9 +123,9 @@ pub(crate) enum DispatchPayload {
 }
 
 #[async_trait]
-impl Update for DispatchPayload {
+impl Update for Event {
     async fn update(&self, cache: &Cache) {
-        use DispatchPayload::*;
+        use Event::*;
         match self {
             Ready(ready) => ready.update(cache).await,
             Resumed(resumed) => resumed.update(cache).await,
@@ -134,7 +140,9 @@ impl Update for DispatchPayload {
             GuildBanRemove(ban_remove) => ban_remove.update(cache).await,
             GuildEmojisUpdate(emojis_update) => emojis_update.update(cache).await,
             GuildIntegrationsUpdate(integrations) => integrations.update(cache).await,
+            IntegrationCreate(create) => create.update(cache).await,
             IntegrationUpdate(update) => update.update(cache).await,
+            IntegrationDelete(delete) => delete.update(cache).await,
             GuildMemberAdd(member_add) => member_add.update(cache).await,
             GuildMemberRemove(member_remove) => member_remove.update(cache).await,
             GuildMemberUpdate(member_update) => member_update.update(cache).await,
@@ -163,24 +171,22 @@ impl Update for DispatchPayload {
             // ApplicationCommandUpdate(update) => update.update(cache).await,
             // ApplicationCommandDelete(delete) => delete.update(cache).await,
             ApplicationCommandPermissionsUpdate(update) => update.update(cache).await,
+            StageInstanceCreate(create) => create.update(cache).await,
+            StageInstanceUpdate(update) => update.update(cache).await,
+            StageInstanceDelete(delete) => delete.update(cache).await,
+            ThreadCreate(create) => create.update(cache).await,
+            ThreadUpdate(update) => update.update(cache).await,
+            ThreadDelete(delete) => delete.update(cache).await,
             // todo
-            ThreadCreate(_) => {}
-            ThreadUpdate(_) => {}
-            ThreadDelete(_) => {}
             ThreadListSync(_) => {}
-            ThreadMemberUpdate(_) => {}
-            ThreadMembersUpdate(_) => {}
+            ThreadMemberUpdate(update) => update.update(cache).await,
+            ThreadMembersUpdate(update) => update.update(cache).await,
             GuildStickersUpdate(_) => {}
             GuildScheduledEventCreate(_) => {}
             GuildScheduledEventUpdate(_) => {}
             GuildScheduledEventDelete(_) => {}
             GuildScheduledEventUserAdd(_) => {}
             GuildScheduledEventUserRemove(_) => {}
-            IntegrationCreate(_) => {}
-            IntegrationDelete(_) => {}
-            StageInstanceCreate(_) => {}
-            StageInstanceUpdate(_) => {}
-            StageInstanceDelete(_) => {}
             AutoModerationRuleCreate(_) => {}
             AutoModerationRuleUpdate(_) => {}
             AutoModerationRuleDelete(_) => {}
@@ -238,6 +244,8 @@ bitflags! {
         const GATEWAY_GUILD_MEMBERS_LIMITED = 1 << 15;
         const VERIFICATION_PENDING_GUILD_LIMIT = 1 << 16;
         const EMBEDDED = 1 << 17;
+        const GATEWAY_MESSAGE_CONTENT = 1 << 18;
+        const GATEWAY_MESSAGE_CONTENT_LIMITED = 1 << 19;
     }
 }
 serde_bitflag!(ApplicationFlags: u32);
@@ -295,8 +303,8 @@ impl Update for ChannelCreate {
                 by_user.insert(dm.recipient.id, dm.id);
                 by_id.insert(dm.clone());
             }
-            Channel::Voice(_) => {
-                // voice not implemented yet (ever)
+            Channel::Voice(voice) => {
+                cache.voice.write().await.insert(voice.clone());
             }
             Channel::GroupDm(_) => unreachable!("Bots cannot be in GroupDm channels"),
             Channel::Category(category) => {
@@ -308,13 +316,20 @@ impl Update for ChannelCreate {
             // Channel::Store(store) => {
             //     cache.stores.write().await.insert(store.clone())
             // }
-            // todo
-            Channel::AnnouncementThread(_) => {}
-            Channel::PublicThread(_) => {}
-            Channel::PrivateThread(_) => {}
-            Channel::GuildStageVoice(_) => {}
-            Channel::GuildDirectory(_) => {}
-            Channel::GuildForum(_) => {}
+            Channel::AnnouncementThread(thread)
+            | Channel::PublicThread(thread)
+            | Channel::PrivateThread(thread) => {
+                cache.threads.write().await.insert(thread.clone());
+            }
+            Channel::GuildStageVoice(stage) => {
+                cache.stages.write().await.insert(stage.clone());
+            }
+            Channel::GuildDirectory(directory) => {
+                cache.directories.write().await.insert(directory.clone());
+            }
+            Channel::GuildForum(forum) => {
+                cache.forums.write().await.insert(forum.clone());
+            }
         };
     }
 }
@@ -349,8 +364,10 @@ impl Update for ChannelUpdate {
                     *dm = channel.clone();
                 }
             }
-            Channel::Voice(_) => {
-                // voice not implemented yet (ever)
+            Channel::Voice(channel) => {
+                if let Some(voice) = cache.voice.write().await.get_mut(&channel) {
+                    *voice = channel.clone();
+                }
             }
             Channel::GroupDm(_) => unreachable!("Bots cannot be in GroupDm channels"),
             Channel::Category(channel) => {
@@ -368,13 +385,28 @@ impl Update for ChannelUpdate {
             //         *store = channel.clone();
             //     }
             // }
-            // todo
-            Channel::AnnouncementThread(_) => {}
-            Channel::PublicThread(_) => {}
-            Channel::PrivateThread(_) => {}
-            Channel::GuildStageVoice(_) => {}
-            Channel::GuildDirectory(_) => {}
-            Channel::GuildForum(_) => {}
+            Channel::AnnouncementThread(channel)
+            | Channel::PublicThread(channel)
+            | Channel::PrivateThread(channel) => {
+                if let Some(thread) = cache.threads.write().await.get_mut(&channel) {
+                    *thread = channel.clone();
+                }
+            }
+            Channel::GuildStageVoice(channel) => {
+                if let Some(stage) = cache.stages.write().await.get_mut(&channel) {
+                    *stage = channel.clone();
+                }
+            }
+            Channel::GuildDirectory(channel) => {
+                if let Some(directory) = cache.directories.write().await.get_mut(&channel) {
+                    *directory = channel.clone();
+                }
+            }
+            Channel::GuildForum(channel) => {
+                if let Some(forum) = cache.forums.write().await.get_mut(&channel) {
+                    *forum = channel.clone();
+                }
+            }
         };
     }
 }
@@ -400,15 +432,15 @@ impl Update for ChannelDelete {
             }
             Channel::Category(cat) => { cache.categories.write().await.remove(cat); }
             Channel::Announcement(news) => { cache.news.write().await.remove(news); }
+            Channel::GuildForum(forum) => { cache.forums.write().await.remove(forum); }
+            Channel::Voice(voice) => { cache.voice.write().await.remove(voice); }
+            Channel::AnnouncementThread(thread)
+            | Channel::PublicThread(thread)
+            | Channel::PrivateThread(thread) => { cache.threads.write().await.remove(thread); }
+            Channel::GuildStageVoice(stage) => { cache.stages.write().await.remove(stage); }
+            Channel::GuildDirectory(directory) => { cache.directories.write().await.remove(directory); }
             // Channel::Store(store) => { cache.stores.write().await.remove(store); },
-            Channel::Voice(_) | Channel::GroupDm(_) => {}
-            // todo
-            Channel::AnnouncementThread(_) => {}
-            Channel::PublicThread(_) => {}
-            Channel::PrivateThread(_) => {}
-            Channel::GuildStageVoice(_) => {}
-            Channel::GuildDirectory(_) => {}
-            Channel::GuildForum(_) => {}
+            Channel::GroupDm(_) => {}
         };
     }
 }
@@ -449,15 +481,14 @@ impl Update for ChannelPinsUpdate {
                         channel.last_pin_timestamp = last_pin_timestamp;
                     });
             }
-            Some(ChannelType::Voice | ChannelType::Category) => {}
-            Some(ChannelType::GroupDm) | None => {}
+            Some(ChannelType::Voice | ChannelType::Category | ChannelType::GuildForum) => {}
+            Some(ChannelType::GroupDm | ChannelType::Unknown(_)) | None => {}
             // todo
             Some(ChannelType::AnnouncementThread) => {}
             Some(ChannelType::PublicThread) => {}
             Some(ChannelType::PrivateThread) => {}
             Some(ChannelType::GuildStageVoice) => {}
             Some(ChannelType::GuildDirectory) => {}
-            Some(ChannelType::GuildForum) => {}
         }
         if let Some(guild_id) = guild_id {
             cache.guilds.write().await.entry(guild_id)
@@ -467,7 +498,7 @@ impl Update for ChannelPinsUpdate {
                             Channel::Text(channel) => channel.last_pin_timestamp = last_pin_timestamp,
                             Channel::Announcement(channel) => channel.last_pin_timestamp = last_pin_timestamp,
                             // no last timestamp
-                            Channel::Voice(_) | Channel::Category(_) => {}
+                            Channel::Voice(_) | Channel::Category(_) | Channel::GuildForum(_) => {}
                             // not in a guild
                             Channel::Dm(_) | Channel::GroupDm(_) => {}
                             // todo
@@ -476,7 +507,6 @@ impl Update for ChannelPinsUpdate {
                             Channel::PrivateThread(_) => {}
                             Channel::GuildStageVoice(_) => {}
                             Channel::GuildDirectory(_) => {}
-                            Channel::GuildForum(_) => {}
                         });
                 });
         }
@@ -502,6 +532,9 @@ pub struct GuildCreate {
 
 #[test]
 fn test_guild_create() {
+    use crate::model::command::CommandType;
+    use crate::model::DiscordErrorType;
+
     // const JSON: &str = r#"{"t":"GUILD_CREATE","s":2,"op":0,"d":{"owner_id":"243418816510558208","system_channel_id":"492122906864779276","guild_hashes":{"version":1,"roles":{"omitted":false,"hash":"OROePw"},"metadata":{"omitted":false,"hash":"aD+z8Q"},"channels":{"omitted":false,"hash":"AiVDlg"}},"system_channel_flags":0,"guild_scheduled_events":[],"afk_timeout":300,"region":"deprecated","stickers":[],"rules_channel_id":null,"stage_instances":[],"members":[{"user":{"username":"Avalon Bot (DEV)","public_flags":0,"id":"780237314734686208","global_name":null,"display_name":null,"discriminator":"6988","bot":true,"avatar_decoration":null,"avatar":"5b4212af734408382daff0cb4c0be97d"},"roles":["780239802196426773"],"premium_since":null,"pending":false,"nick":null,"mute":false,"joined_at":"2020-11-23T01:14:25.047000+00:00","flags":0,"deaf":false,"communication_disabled_until":null,"avatar":null}],"premium_subscription_count":0,"application_id":null,"safety_alerts_channel_id":null,"large":false,"default_message_notifications":0,"inventory_settings":null,"mfa_level":0,"discovery_splash":null,"max_video_channel_users":25,"description":null,"explicit_content_filter":0,"nsfw_level":0,"afk_channel_id":null,"icon":null,"id":"492122906864779274","hub_type":null,"incidents_data":null,"public_updates_channel_id":null,"voice_states":[],"home_header":null,"member_count":8,"vanity_url_code":null,"preferred_locale":"en-US","presences":[],"embedded_activities":[],"splash":null,"features":[],"application_command_counts":{"1":38},"threads":[],"banner":null,"joined_at":"2020-11-23T01:14:25.047000+00:00","max_members":500000,"roles":[{"version":0,"unicode_emoji":null,"tags":{},"position":0,"permissions":"137411140509249","name":"@everyone","mentionable":false,"managed":false,"id":"492122906864779274","icon":null,"hoist":false,"flags":0,"color":0},{"version":0,"unicode_emoji":null,"tags":{"bot_id":"492123129573802004"},"position":3,"permissions":"6509947968","name":"Avalon Bot","mentionable":false,"managed":true,"id":"492123963728855044","icon":null,"hoist":false,"flags":0,"color":0},{"version":0,"unicode_emoji":null,"tags":{},"position":6,"permissions":"6546775617","name":"myrole","mentionable":true,"managed":false,"id":"592892380609511445","icon":null,"hoist":false,"flags":0,"color":15844367},{"version":0,"unicode_emoji":null,"tags":{"bot_id":"713764953525583897"},"position":5,"permissions":"6442576960","name":"Rutgers Course Sniper","mentionable":false,"managed":true,"id":"713792310659514462","icon":null,"hoist":false,"flags":0,"color":0},{"version":1688673986671,"unicode_emoji":null,"tags":{"bot_id":"780237314734686208"},"position":2,"permissions":"8","name":"Avalon Bot (DEV)","mentionable":false,"managed":true,"id":"780239802196426773","icon":null,"hoist":false,"flags":0,"color":0},{"version":0,"unicode_emoji":null,"tags":{},"position":1,"permissions":"6546775617","name":"\"pretty color\"","mentionable":false,"managed":false,"id":"788583747771433020","icon":null,"hoist":false,"flags":0,"color":5533306},{"version":0,"unicode_emoji":null,"tags":{},"position":1,"permissions":"6546775617","name":"new role","mentionable":false,"managed":false,"id":"788893153209745409","icon":null,"hoist":false,"flags":0,"color":0},{"version":0,"unicode_emoji":null,"tags":{"bot_id":"928797878669955082"},"position":1,"permissions":"8799381367872","name":"Counter","mentionable":false,"managed":true,"id":"928808168023281716","icon":null,"hoist":false,"flags":0,"color":0},{"version":0,"unicode_emoji":null,"tags":{"bot_id":"268547439714238465"},"position":1,"permissions":"274878221376","name":"Scryfall","mentionable":false,"managed":true,"id":"991036300272480349","icon":null,"hoist":false,"flags":0,"color":0}],"emojis":[{"version":0,"roles":[],"require_colons":true,"name":"echan","managed":false,"id":"786354378885824513","available":true,"animated":false},{"version":0,"roles":[],"require_colons":true,"name":"bruh","managed":false,"id":"788901233016045599","available":true,"animated":false},{"version":0,"roles":[],"require_colons":true,"name":"Bonk","managed":false,"id":"839001103391260692","available":true,"animated":false},{"version":0,"roles":[],"require_colons":true,"name":"Bonk_2_Electric_Boogaloo","managed":false,"id":"839001103844114432","available":true,"animated":false},{"version":0,"roles":[],"require_colons":true,"name":"Bonk_cropped","managed":false,"id":"839001439601950750","available":true,"animated":false},{"version":0,"roles":[],"require_colons":true,"name":"Bonk_2_Electric_Boogaloo_cropped","managed":false,"id":"839001744443703317","available":true,"animated":false}],"latest_onboarding_question_id":null,"verification_level":0,"lazy":true,"premium_progress_bar_enabled":false,"nsfw":false,"unavailable":false,"channels":[{"version":0,"type":4,"position":0,"permission_overwrites":[],"name":"Text Channels","id":"492122906864779275","flags":0},{"version":0,"type":0,"topic":null,"rate_limit_per_user":0,"position":0,"permission_overwrites":[{"type":0,"id":"492122906864779274","deny":"0","allow":"0"}],"parent_id":"492122906864779275","name":"general","last_message_id":"991036430912454696","id":"492122906864779276","flags":0},{"version":0,"type":4,"position":2,"permission_overwrites":[],"name":"Voice Channels","id":"492122907300855818","flags":0},{"version":0,"user_limit":0,"type":2,"rtc_region":null,"rate_limit_per_user":0,"position":0,"permission_overwrites":[],"parent_id":"492122907300855818","name":"General","last_message_id":null,"id":"492122907300855819","flags":0,"bitrate":64000},{"version":0,"type":0,"topic":null,"rate_limit_per_user":0,"position":1,"permission_overwrites":[{"type":0,"id":"780239802196426773","deny":"1024","allow":"0"}],"parent_id":"492122906864779275","name":"avalon_bot","last_message_id":"849017251914842192","id":"492122962451759124","flags":0},{"version":1667238419348,"type":0,"topic":null,"rate_limit_per_user":0,"position":2,"permission_overwrites":[{"type":0,"id":"492123963728855044","deny":"1024","allow":"0"}],"parent_id":"492122906864779275","name":"dev_bot","last_pin_timestamp":"2023-07-06T20:05:55+00:00","last_message_id":"1131797440643735683","id":"780240796690808912","flags":0},{"version":0,"type":0,"topic":"topic lol","rate_limit_per_user":0,"position":3,"permission_overwrites":[{"type":1,"id":"592500196303437826","deny":"262144","allow":"16"}],"parent_id":"492122906864779275","name":"test-member-overwrite","last_message_id":"793351162396278814","id":"781369592982929408","flags":0}],"name":"Bots Bots Bots","max_stage_video_channel_users":50,"premium_tier":0}}"#;
     const JSON: &str = r#"{
   "owner_id": "243418816510558208",
@@ -926,12 +959,22 @@ fn test_guild_create() {
 }"#;
     let guild: Guild = serde_json::from_str(JSON).unwrap();
     println!("guild = {:#?}", guild);
+    let member = guild.members.get(UserId(780_237_314_734_686_208)).unwrap();
+    assert_eq!(member.communication_disabled_until, None);
+    assert!(!member.is_timed_out());
+
+    assert_eq!(guild.command_count(CommandType::SlashCommand), 38);
+    assert_eq!(guild.command_count(CommandType::UserCommand), 0);
+    assert!(guild.check_command_limit(CommandType::SlashCommand, 62).is_ok());
+    let error = guild.check_command_limit(CommandType::SlashCommand, 63).unwrap_err();
+    assert_eq!(error.code, DiscordErrorType::MaxApplicationCommands);
 }
 
 #[async_trait]
 impl Update for GuildCreate {
     async fn update(&self, cache: &Cache) {
-        let (mut t, mut c, mut n) = (Vec::new(), Vec::new(), Vec::new());
+        let (mut t, mut c, mut n, mut f) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        let (mut v, mut s, mut d) = (Vec::new(), Vec::new(), Vec::new());
         {
             let mut guard = cache.channel_types.write().await;
             self.guild.channels.iter()
@@ -953,26 +996,44 @@ impl Update for GuildCreate {
                             news.guild_id = Some(self.guild.id);
                             n.push(news);
                         }
+                        Channel::GuildForum(forum) => {
+                            let mut forum = forum.clone();
+                            forum.guild_id = Some(self.guild.id);
+                            f.push(forum);
+                        }
                         // Channel::Store(store) => s.push(store.clone()),
-                        Channel::Voice(_) => {
-                            // not (yet/ever) implemented
+                        Channel::Voice(voice) => {
+                            let mut voice = voice.clone();
+                            voice.guild_id = Some(self.guild.id);
+                            v.push(voice);
+                        }
+                        Channel::GuildStageVoice(stage) => {
+                            let mut stage = stage.clone();
+                            stage.guild_id = Some(self.guild.id);
+                            s.push(stage);
+                        }
+                        Channel::GuildDirectory(directory) => {
+                            let mut directory = directory.clone();
+                            directory.guild_id = Some(self.guild.id);
+                            d.push(directory);
                         }
                         Channel::Dm(_) | Channel::GroupDm(_) => {
                             // not part of a guild
                         }
-                        // todo
-                        Channel::AnnouncementThread(_) => {}
-                        Channel::PublicThread(_) => {}
-                        Channel::PrivateThread(_) => {}
-                        Channel::GuildStageVoice(_) => {}
-                        Channel::GuildDirectory(_) => {}
-                        Channel::GuildForum(_) => {}
+                        // threads are sent in `threads`, not `channels`
+                        Channel::AnnouncementThread(_)
+                        | Channel::PublicThread(_)
+                        | Channel::PrivateThread(_) => {}
                     }
                 });
         }
         cache.channels.write().await.extend(t);
         cache.categories.write().await.extend(c);
         cache.news.write().await.extend(n);
+        cache.forums.write().await.extend(f);
+        cache.voice.write().await.extend(v);
+        cache.stages.write().await.extend(s);
+        cache.directories.write().await.extend(d);
         // cache.stores.write().await.extend(s);
 
         let mut members = cache.members.write().await;
@@ -993,7 +1054,7 @@ impl Update for GuildCreate {
 /// Sent when a [Guild](Guild) is updated.
 #[derive(Deserialize, Debug, Clone)]
 pub struct GuildUpdate {
-    id: GuildId,
+    pub(crate) id: GuildId,
     name: Option<String>,
     icon: Option<String>,
     splash: Option<String>,
@@ -1160,6 +1221,24 @@ impl Update for EmojiUpdate {
     }
 }
 
+/// Sent when an integration is created.
+#[derive(Deserialize, Debug, Clone)]
+pub struct IntegrationCreate {
+    pub guild_id: GuildId,
+    #[serde(flatten)]
+    pub integration: Integration,
+}
+
+#[async_trait]
+impl Update for IntegrationCreate {
+    async fn update(&self, cache: &Cache) {
+        cache.integrations.write().await
+            .entry(self.guild_id)
+            .or_default()
+            .insert(self.integration.clone());
+    }
+}
+
 // why does this and GUILD_INTEGRATIONS_UPDATE exist? who knows
 #[derive(Deserialize, Debug, Clone)]
 pub struct IntegrationUpdate {
@@ -1170,7 +1249,32 @@ pub struct IntegrationUpdate {
 
 #[async_trait]
 impl Update for IntegrationUpdate {
-    async fn update(&self, _cache: &Cache) {}
+    async fn update(&self, cache: &Cache) {
+        cache.integrations.write().await
+            .entry(self.guild_id)
+            .or_default()
+            .insert(self.integration.clone());
+    }
+}
+
+/// Sent when an integration is deleted.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct IntegrationDelete {
+    /// integration id
+    pub id: IntegrationId,
+    /// id of the guild
+    pub guild_id: GuildId,
+    /// id of the bot/OAuth2 application for this discord integration
+    pub application_id: Option<ApplicationId>,
+}
+
+#[async_trait]
+impl Update for IntegrationDelete {
+    async fn update(&self, cache: &Cache) {
+        if let Some(integrations) = cache.integrations.write().await.get_mut(&self.guild_id) {
+            integrations.remove(self.id);
+        }
+    }
 }
 
 /// Sent when a guild integration is updated.
@@ -1202,9 +1306,8 @@ pub struct GuildMemberAdd {
 impl Update for GuildMemberAdd {
     async fn update(&self, cache: &Cache) {
         cache.members.write().await.entry(self.member.user.id)
-            .and_modify(|map| {
-                map.insert(self.guild_id, self.member.clone());
-            });
+            .or_default()
+            .insert(self.guild_id, self.member.clone());
         cache.guilds.write().await.entry(self.guild_id)
             .and_modify(|guild| guild.members.insert(self.member.clone()));
         cache.users.write().await.entry(&self.member).or_insert_with(|| self.member.user.clone());
@@ -1249,10 +1352,22 @@ pub struct GuildMemberUpdate {
     pub user: User,
     /// nickname of the user in the guild
     pub nick: Option<String>,
+    /// the member's guild avatar hash
+    #[serde(default)]
+    pub avatar: Option<String>,
     /// when the user joined the guild
     pub joined_at: DateTime<Utc>,
     /// when the user starting boosting the guild
     pub premium_since: Option<DateTime<Utc>>,
+    /// when the user's timeout will expire, if they are timed out
+    #[serde(default)]
+    pub communication_disabled_until: Option<DateTime<Utc>>,
+    /// whether the user has not yet passed the guild's Membership Screening requirements
+    #[serde(default)]
+    pub pending: bool,
+    /// guild member flags
+    #[serde(default)]
+    pub flags: GuildMemberFlags,
 }
 
 #[async_trait]
@@ -1266,9 +1381,13 @@ impl Update for GuildMemberUpdate {
             let new = self.clone();
             member.user = new.user;
             member.nick = new.nick;
+            member.avatar = new.avatar;
             member.roles = new.roles;
             member.joined_at = new.joined_at;
             member.premium_since = new.premium_since;
+            member.communication_disabled_until = new.communication_disabled_until;
+            member.pending = new.pending;
+            member.flags = new.flags;
         }
 
         if let Some(guild) = cache.guilds.write().await.get_mut(self.guild_id) {
@@ -1276,9 +1395,13 @@ impl Update for GuildMemberUpdate {
                 let s = self.clone();
                 member.user = s.user;
                 member.nick = s.nick;
+                member.avatar = s.avatar;
                 member.roles = s.roles;
                 member.joined_at = s.joined_at;
                 member.premium_since = s.premium_since;
+                member.communication_disabled_until = s.communication_disabled_until;
+                member.pending = s.pending;
+                member.flags = s.flags;
             }
         }
     }
@@ -1432,7 +1555,7 @@ impl Update for InviteDelete {
 #[derive(Deserialize, Debug, Clone)]
 #[serde(transparent)]
 pub struct MessageCreate {
-    pub(crate) message: Message,
+    pub message: Message,
 }
 
 #[async_trait]
@@ -1460,7 +1583,8 @@ impl Update for MessageCreate {
             }
             Some(ChannelType::Voice
                  | ChannelType::GroupDm
-                 | ChannelType::Category)
+                 | ChannelType::Category
+                 | ChannelType::Unknown(_))
             | None => {}
             // todo
             Some(ChannelType::AnnouncementThread) => {}
@@ -1473,7 +1597,7 @@ impl Update for MessageCreate {
         cache.users.write().await.insert(self.message.author.clone());
         cache.messages.write().await.insert(self.message.clone());
         if let Some(interaction) = self.message.interaction.clone() {
-            cache.interaction_responses.write().await.insert(interaction.id, self.message.clone());
+            cache.insert_interaction_response(interaction.id, self.message.clone()).await;
         }
     }
 }
@@ -1513,6 +1637,7 @@ pub struct MessageUpdate {
     pub(crate) components: Option<Vec<ActionRow>>,
     pub(crate) sticker_items: Option<Vec<StickerItem>>,
     pub(crate) position: Option<Option<usize>>,
+    pub(crate) poll: Option<Option<Poll>>,
 }
 
 impl TryFrom<MessageUpdate> for Message {
@@ -1553,6 +1678,7 @@ impl TryFrom<MessageUpdate> for Message {
                 components: update.components.unwrap_or_default(),
                 sticker_items: update.sticker_items.unwrap_or_default(),
                 position: update.position.unwrap_or_default(),
+                poll: update.poll.unwrap_or_default(),
             })
         }
         option(update).ok_or(())
@@ -1596,6 +1722,7 @@ impl Update for MessageUpdate {
                 update(&mut message.application, s.application);
                 update(&mut message.message_reference, s.message_reference);
                 update(&mut message.flags, s.flags);
+                update(&mut message.poll, s.poll);
                 if let Some(referenced) = s.referenced_message {
                     message.referenced_message = referenced.map(Box::new);
                 }
@@ -1623,6 +1750,7 @@ pub struct MessageDelete {
 impl Update for MessageDelete {
     async fn update(&self, cache: &Cache) {
         cache.messages.write().await.remove(self.id);
+        cache.interaction_responses.write().await.retain(|_, message| message.id != self.id);
         // don't forget that the lock on `channel_types` is held throughout all branches
         #[allow(clippy::significant_drop_in_scrutinee)]
         match cache.channel_types.read().await.get(&self.channel_id) {
@@ -1711,6 +1839,8 @@ impl Update for ReactionAdd {
                 .map_or(false, |me| me.id == self.user_id);
             if let Some(idx) = idx {
                 let reaction = &mut message.reactions[idx];
+                // the bot can only react once, so this is a replay (ex, after resuming)
+                if me && reaction.me { return; }
                 reaction.count += 1;
                 reaction.me |= me;
             } else {
@@ -1749,8 +1879,13 @@ impl Update for ReactionRemove {
                 .map_or(false, |me| me.id == self.user_id);
             if let Some(idx) = idx {
                 let reaction = &mut message.reactions[idx];
-                reaction.count -= 1;
+                // the bot's reaction was already removed, so this is a replay
+                if me && !reaction.me { return; }
+                reaction.count = reaction.count.saturating_sub(1);
                 reaction.me &= !me;
+                if reaction.count == 0 {
+                    message.reactions.remove(idx);
+                }
             }
         }
     }
@@ -1972,6 +2107,20 @@ impl Update for VoiceStateUpdate {
             if let Some(guild) = cache.guilds.write().await.get_mut(guild_id) {
                 guild.voice_states.insert(self.state.clone());
             }
+
+            let own_id = cache.user.read().await.as_ref().map(User::id);
+            if own_id == Some(self.state.user_id) {
+                let mut connections = cache.voice_connections.write().await;
+                if let Some(channel) = self.state.channel_id {
+                    connections.entry(guild_id).or_default().state = Some((
+                        channel,
+                        self.state.user_id,
+                        self.state.session_id.clone(),
+                    ));
+                } else {
+                    connections.remove(&guild_id);
+                }
+            }
         }
     }
 }
@@ -1982,13 +2131,19 @@ pub struct VoiceServerUpdate {
     pub token: String,
     /// the guild this voice server update is for
     pub guild_id: GuildId,
-    /// the voice server host
-    pub endpoint: String,
+    /// the voice server host. `None` if the voice server went away, another update will be sent
+    /// once a new one is allocated.
+    pub endpoint: Option<String>,
 }
 
 #[async_trait]
 impl Update for VoiceServerUpdate {
-    async fn update(&self, _cache: &Cache) {}
+    async fn update(&self, cache: &Cache) {
+        let mut connections = cache.voice_connections.write().await;
+        let connection = connections.entry(self.guild_id).or_default();
+        connection.server = self.endpoint.clone()
+            .map(|endpoint| (self.token.clone(), endpoint));
+    }
 }
 
 // Webhook Updates
@@ -2016,8 +2171,55 @@ pub struct InteractionCreate {
 
 #[async_trait]
 impl Update for InteractionCreate {
-    async fn update(&self, _cache: &Cache) {
-        // println!("self = {:#?}", self);
+    async fn update(&self, cache: &Cache) {
+        let (data, resolved) = match &self.interaction {
+            Interaction::ApplicationCommand(data) | Interaction::ApplicationCommandAutocomplete(data) => match &data.data {
+                ApplicationCommandData::UserCommand { resolved, .. }
+                | ApplicationCommandData::MessageCommand { resolved, .. } => (data, resolved),
+                ApplicationCommandData::SlashCommand { .. } => return,
+            },
+            Interaction::Ping
+            | Interaction::MessageComponent(_)
+            | Interaction::ModalSubmit(_) => return,
+        };
+
+        cache.users.write().await.extend(resolved.users.iter().cloned());
+
+        {
+            let mut channel_types = cache.channel_types.write().await;
+            for channel in &resolved.channels {
+                channel_types.entry(channel.id).or_insert(channel.kind);
+            }
+        }
+
+        let Some(guild_id) = data.user.guild_ref().map(|g| g.id) else { return };
+        // resolved members are partial, so only update the members that are already cached
+        let update_member = |member: &mut GuildMember, partial: &PartialGuildMember| {
+            member.nick.clone_from(&partial.nick);
+            member.roles.clone_from(&partial.roles);
+            member.joined_at = partial.joined_at;
+            member.premium_since = partial.premium_since;
+            member.pending = partial.pending;
+            if let Some(user) = resolved.users.get(member.user.id) {
+                member.user = user.clone();
+            }
+        };
+        {
+            let mut members = cache.members.write().await;
+            for (user, partial) in &resolved.members {
+                if let Some(member) = members.get_mut(user).and_then(|map| map.get_mut(&guild_id)) {
+                    update_member(member, partial);
+                }
+            }
+        }
+        if let Some(guild) = cache.guilds.write().await.get_mut(guild_id) {
+            for (&user, partial) in &resolved.members {
+                if let Some(member) = guild.members.get_mut(user) {
+                    update_member(member, partial);
+                }
+            }
+            guild.roles.extend(resolved.roles.iter().cloned());
+        }
     }
 }
 
@@ -2134,6 +2336,16 @@ pub struct ThreadCreate {
     // todo also has a `newly_created` boolean field
     #[serde(flatten)]
     thread: Thread,
+    #[serde(rename = "type")]
+    kind: ChannelType,
+}
+
+#[async_trait]
+impl Update for ThreadCreate {
+    async fn update(&self, cache: &Cache) {
+        cache.channel_types.write().await.insert(self.thread.id, self.kind);
+        cache.threads.write().await.insert(self.thread.clone());
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -2141,6 +2353,17 @@ pub struct ThreadCreate {
 pub struct ThreadUpdate {
     #[serde(flatten)]
     thread: Thread,
+    #[serde(rename = "type")]
+    kind: ChannelType,
+}
+
+#[async_trait]
+impl Update for ThreadUpdate {
+    async fn update(&self, cache: &Cache) {
+        // archived threads aren't necessarily cached, and are sent again when they're unarchived
+        cache.channel_types.write().await.insert(self.thread.id, self.kind);
+        cache.threads.write().await.insert(self.thread.clone());
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -2153,6 +2376,15 @@ pub struct ThreadDelete {
     kind: ChannelType,
 }
 
+#[async_trait]
+impl Update for ThreadDelete {
+    async fn update(&self, cache: &Cache) {
+        cache.channel_types.write().await.remove(&self.id);
+        cache.threads.write().await.remove(self.id);
+        cache.thread_members.write().await.remove(&self.id);
+    }
+}
+
 /// Sent when the current user gains access to a channel.
 #[derive(Deserialize, Debug, Clone)]
 pub struct ThreadListSync {
@@ -2169,10 +2401,65 @@ pub struct ThreadListSync {
 
 /// Sent when the thread member object for the current user is updated.
 #[derive(Deserialize, Debug, Clone)]
-pub struct ThreadMemberUpdate {}
+pub struct ThreadMemberUpdate {
+    /// the current user's thread member
+    #[serde(flatten)]
+    pub member: ThreadMember,
+    /// ID of the guild
+    pub guild_id: GuildId,
+}
+
+#[async_trait]
+impl Update for ThreadMemberUpdate {
+    async fn update(&self, cache: &Cache) {
+        if let (Some(thread), Some(user)) = (self.member.id, self.member.user_id) {
+            cache.thread_members.write().await
+                .entry(thread)
+                .or_insert_with(|| ThreadMembers::new(self.guild_id))
+                .members
+                .insert(user, self.member.clone());
+        }
+    }
+}
 
+/// Sent when anyone is added to or removed from a thread.
+///
+/// If the current user does not have the
+/// [`GUILD_MEMBERS`](crate::shard::intents::Intents::GUILD_MEMBERS) intent, then this event will
+/// only be sent if the current user was added to or removed from the thread.
 #[derive(Deserialize, Debug, Clone)]
-pub struct ThreadMembersUpdate {}
+pub struct ThreadMembersUpdate {
+    /// ID of the thread
+    pub id: ChannelId,
+    /// ID of the guild
+    pub guild_id: GuildId,
+    /// Approximate number of members in the thread, stops counting at 50
+    pub member_count: u8,
+    /// Users who were added to the thread
+    #[serde(default)]
+    pub added_members: Vec<ThreadMember>,
+    /// ID of the users who were removed from the thread
+    #[serde(default)]
+    pub removed_member_ids: Vec<UserId>,
+}
+
+#[async_trait]
+impl Update for ThreadMembersUpdate {
+    async fn update(&self, cache: &Cache) {
+        let mut thread_members = cache.thread_members.write().await;
+        let members = &mut thread_members.entry(self.id)
+            .or_insert_with(|| ThreadMembers::new(self.guild_id))
+            .members;
+        for member in &self.added_members {
+            if let Some(user) = member.user_id {
+                members.insert(user, member.clone());
+            }
+        }
+        for user in &self.removed_member_ids {
+            members.remove(user);
+        }
+    }
+}
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct StickerUpdate {}
@@ -2186,23 +2473,73 @@ pub struct GuildScheduledEventUpdate {}
 #[derive(Deserialize, Debug, Clone)]
 pub struct GuildScheduledEventDelete {}
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct GuildScheduledEventUserAdd {}
+/// Sent when a user has subscribed to a guild scheduled event.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuildScheduledEventUserAdd {
+    /// id of the guild scheduled event
+    pub guild_scheduled_event_id: ScheduledEventId,
+    /// id of the user
+    pub user_id: UserId,
+    /// id of the guild
+    pub guild_id: GuildId,
+}
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct GuildScheduledEventUserRemove {}
+/// Sent when a user has unsubscribed from a guild scheduled event.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuildScheduledEventUserRemove {
+    /// id of the guild scheduled event
+    pub guild_scheduled_event_id: ScheduledEventId,
+    /// id of the user
+    pub user_id: UserId,
+    /// id of the guild
+    pub guild_id: GuildId,
+}
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct IntegrationCreate {}
 
+/// Sent when a [`StageInstance`] is created (i.e. the Stage is now "live").
 #[derive(Deserialize, Debug, Clone)]
-pub struct IntegrationDelete {}
+#[serde(transparent)]
+pub struct StageInstanceCreate {
+    pub stage_instance: StageInstance,
+}
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct StageInstanceCreate {}
+#[async_trait]
+impl Update for StageInstanceCreate {
+    async fn update(&self, cache: &Cache) {
+        if let Some(guild) = cache.guilds.write().await.get_mut(self.stage_instance.guild_id) {
+            guild.stage_instances.insert(self.stage_instance.clone());
+        }
+    }
+}
 
+/// Sent when a [`StageInstance`] has been updated.
 #[derive(Deserialize, Debug, Clone)]
-pub struct StageInstanceUpdate {}
+#[serde(transparent)]
+pub struct StageInstanceUpdate {
+    pub stage_instance: StageInstance,
+}
 
+#[async_trait]
+impl Update for StageInstanceUpdate {
+    async fn update(&self, cache: &Cache) {
+        if let Some(guild) = cache.guilds.write().await.get_mut(self.stage_instance.guild_id) {
+            guild.stage_instances.insert(self.stage_instance.clone());
+        }
+    }
+}
+
+/// Sent when a [`StageInstance`] has been deleted (i.e. the Stage has been closed).
 #[derive(Deserialize, Debug, Clone)]
-pub struct StageInstanceDelete {}
\ No newline at end of file
+#[serde(transparent)]
+pub struct StageInstanceDelete {
+    pub stage_instance: StageInstance,
+}
+
+#[async_trait]
+impl Update for StageInstanceDelete {
+    async fn update(&self, cache: &Cache) {
+        if let Some(guild) = cache.guilds.write().await.get_mut(self.stage_instance.guild_id) {
+            guild.stage_instances.remove(&self.stage_instance);
+        }
+    }
+}
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::shard::dispatch::ApplicationFlags;
+
 bitflags! {
     /// Discord's Gateway Intents, which allow bots to opt in or out of receiving certain events.
     /// Using [Identify](crate::shard::model::Identify)'s [new](crate::shard::model::Identify::new)
@@ -30,4 +32,80 @@ bitflags! {
         const PRIVELEGED = Self::GUILD_PRESENCES.bits() | Self::GUILD_MEMBERS.bits() | Self::MESSAGE_CONTENT.bits();
     }
 }
-serde_bitflag!(Intents: u32);
\ No newline at end of file
+serde_bitflag!(Intents: u32);
+
+impl Intents {
+    /// Every intent that doesn't have to be enabled in the Developer Portal. This is what
+    /// [`Identify::new`](crate::shard::model::Identify::new) uses.
+    pub const fn non_privileged() -> Self {
+        Self::all().difference(Self::PRIVELEGED)
+    }
+
+    /// The intents a bot that only responds to application commands needs: just
+    /// [`GUILDS`](Self::GUILDS), so that guilds and channels are cached. Interactions are received
+    /// regardless of intents.
+    pub const fn default_for_commands() -> Self {
+        Self::GUILDS
+    }
+
+    /// The privileged intents in `self` ([`GUILD_MEMBERS`](Self::GUILD_MEMBERS),
+    /// [`GUILD_PRESENCES`](Self::GUILD_PRESENCES), and [`MESSAGE_CONTENT`](Self::MESSAGE_CONTENT)),
+    /// which must be enabled for the application in the Developer Portal.
+    #[must_use]
+    pub const fn privileged(self) -> Self {
+        self.intersection(Self::PRIVELEGED)
+    }
+
+    /// Whether `self` contains any privileged intents.
+    pub const fn is_privileged(self) -> bool {
+        self.intersects(Self::PRIVELEGED)
+    }
+
+    /// The privileged intents in `self` that an application with `flags` is not allowed to use.
+    /// Identifying with any of these makes Discord close the connection with code `4014`.
+    #[must_use]
+    pub fn disallowed_by(self, flags: ApplicationFlags) -> Self {
+        let mut allowed = self - Self::PRIVELEGED;
+        if flags.intersects(ApplicationFlags::GATEWAY_PRESENCE | ApplicationFlags::GATEWAY_PRESENCE_LIMITED) {
+            allowed |= Self::GUILD_PRESENCES;
+        }
+        if flags.intersects(ApplicationFlags::GATEWAY_GUILD_MEMBERS | ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED) {
+            allowed |= Self::GUILD_MEMBERS;
+        }
+        if flags.intersects(ApplicationFlags::GATEWAY_MESSAGE_CONTENT | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED) {
+            allowed |= Self::MESSAGE_CONTENT;
+        }
+        self - allowed
+    }
+}
+
+#[cfg(test)]
+mod intents_tests {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        let non_privileged = Intents::non_privileged();
+        assert!(!non_privileged.is_privileged());
+        assert!(non_privileged.contains(Intents::GUILDS | Intents::GUILD_MESSAGES | Intents::AUTO_MODERATION_ACTION_EXECUTION));
+        assert_eq!(non_privileged | Intents::PRIVELEGED, Intents::all());
+
+        assert_eq!(Intents::default_for_commands(), Intents::GUILDS);
+        assert!(!Intents::default_for_commands().is_privileged());
+
+        assert!(Intents::all().is_privileged());
+        assert_eq!(
+            Intents::all().privileged(),
+            Intents::GUILD_MEMBERS | Intents::GUILD_PRESENCES | Intents::MESSAGE_CONTENT,
+        );
+        assert_eq!((Intents::GUILDS | Intents::MESSAGE_CONTENT).privileged(), Intents::MESSAGE_CONTENT);
+    }
+
+    #[test]
+    fn disallowed() {
+        let intents = Intents::non_privileged() | Intents::GUILD_MEMBERS | Intents::MESSAGE_CONTENT;
+        assert_eq!(intents.disallowed_by(ApplicationFlags::empty()), Intents::GUILD_MEMBERS | Intents::MESSAGE_CONTENT);
+        assert_eq!(intents.disallowed_by(ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED), Intents::GUILD_MEMBERS);
+        assert!(Intents::non_privileged().disallowed_by(ApplicationFlags::empty()).is_empty());
+    }
+}
@@ -13,13 +13,13 @@ use async_tungstenite::{
 };
 use futures::{SinkExt, TryStreamExt};
 use itertools::Itertools;
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use rand::Rng;
 use thiserror::Error;
 use tokio::sync::RwLockWriteGuard;
 use tokio::time::{Duration, Instant};
 
-use dispatch::DispatchPayload;
+use dispatch::Event;
 use model::{HelloPayload, Payload, Resume};
 
 use crate::Bot;
@@ -27,8 +27,11 @@ use crate::bot::BotState;
 use crate::cache::Update;
 use crate::http::ClientError;
 use crate::macros::API_VERSION;
+use crate::model::{BotGateway, SessionStartLimit};
 use crate::model::command::ApplicationCommand;
 use crate::model::ids::{CommandId, Id};
+use crate::shard::dispatch::ApplicationFlags;
+use crate::shard::intents::Intents;
 use crate::shard::model::Heartbeat;
 
 pub mod model;
@@ -51,6 +54,8 @@ pub enum ShardError {
     NeedRestart,
     #[error("stream closed (resuming)")]
     NeedResume,
+    #[error("the application has not enabled the privileged intents {0:?}, enable them in the Developer Portal or remove them from `Bot::identify`")]
+    DisallowedIntents(Intents),
     #[error("other error: {0}")]
     Other(String),
 }
@@ -97,14 +102,100 @@ impl ConnectionAction {
     }
 }
 
+/// A transition in a [`Shard`]'s connection to the gateway, passed to
+/// [`Bot::shard_connected`](crate::Bot::shard_connected).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ShardConnection {
+    /// A new websocket connection was opened, and the shard will identify.
+    Connected,
+    /// A new websocket connection was opened in order to resume the previous session.
+    Resuming,
+    /// The shard received [`Ready`](dispatch::Ready), so the session is established.
+    Ready,
+    /// The shard received [`Resumed`](dispatch::Resumed), so the previous session is resumed.
+    Resumed,
+}
+
 fn gateway_params(url: &str) -> String {
     format!("{url}/?v={API_VERSION}&encoding=json")
 }
 
+/// Counts down the [`SessionStartLimit`] as new sessions are started.
+#[derive(Debug, Copy, Clone)]
+struct SessionStarts {
+    total: u32,
+    remaining: u32,
+    reset: Instant,
+}
+
+impl SessionStarts {
+    /// Discord's session start limit resets every day.
+    const RESET: Duration = Duration::from_hours(24);
+
+    fn new(limit: &SessionStartLimit) -> Self {
+        Self {
+            total: limit.total,
+            remaining: limit.remaining,
+            reset: Instant::now() + limit.reset_after(),
+        }
+    }
+
+    /// Count starting a new session, unless the limit has been used up, in which case return how
+    /// long to wait until it resets.
+    fn start(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        if now >= self.reset {
+            self.remaining = self.total;
+            self.reset = now + Self::RESET;
+        }
+        if self.remaining == 0 {
+            Some(self.reset - now)
+        } else {
+            self.remaining -= 1;
+            None
+        }
+    }
+}
+
+/// Throttles identifying across shards: Discord lets only one shard in each rate limit key
+/// (`shard_id % max_concurrency`) identify every 5 seconds.
+#[derive(Debug)]
+pub(crate) struct IdentifyGate {
+    /// When a shard in each rate limit key last identified.
+    buckets: Vec<tokio::sync::Mutex<Option<Instant>>>,
+    interval: Duration,
+}
+
+impl IdentifyGate {
+    /// How long each rate limit key waits between identifies.
+    pub(crate) const INTERVAL: Duration = Duration::from_secs(5);
+
+    pub(crate) fn new(max_concurrency: u32, interval: Duration) -> Self {
+        Self {
+            buckets: (0..max_concurrency.max(1)).map(|_| tokio::sync::Mutex::new(None)).collect(),
+            interval,
+        }
+    }
+
+    /// Wait until shard `shard_id` is allowed to identify. Shards with the same rate limit key
+    /// identify in the order that they call this.
+    async fn wait(&self, shard_id: u64) {
+        let bucket = usize::try_from(shard_id % self.buckets.len() as u64)
+            .expect("less than the number of buckets");
+        let mut last = self.buckets[bucket].lock().await;
+        if let Some(last) = *last {
+            tokio::time::sleep_until(last + self.interval).await;
+        }
+        *last = Some(Instant::now());
+    }
+}
+
 pub struct Shard<B: Bot + 'static> {
     pub shard_info: (u64, u64),
     state: Arc<BotState<B>>,
     session_id: Option<String>,
+    session_starts: Option<SessionStarts>,
+    pub(crate) identify_gate: Option<Arc<IdentifyGate>>,
     gateway: Option<String>,
     resume_gateway: Option<String>,
     seq: Option<u64>,
@@ -121,6 +212,8 @@ impl<B: Bot + 'static> Shard<B> {
             shard_info: (0, 0),
             state,
             session_id: None,
+            session_starts: None,
+            identify_gate: None,
             gateway: None,
             resume_gateway: None,
             seq: None,
@@ -131,16 +224,58 @@ impl<B: Bot + 'static> Shard<B> {
         }
     }
 
+    /// Checks that the bot's application has enabled every privileged intent it will identify
+    /// with, since otherwise Discord closes the connection with code `4014`.
+    ///
+    /// # Errors
+    ///
+    /// [`ShardError::DisallowedIntents`] if any intents are missing, or [`ShardError::Request`] if
+    /// getting the application fails.
+    pub(crate) async fn check_intents(&self) -> ShardResult<()> {
+        let application = self.state.application().await?;
+        let flags = ApplicationFlags::from_bits_truncate(application.flags.unwrap_or(0));
+        let disallowed = self.state.bot.identify().intents().disallowed_by(flags);
+        if disallowed.is_empty() {
+            Ok(())
+        } else {
+            Err(ShardError::DisallowedIntents(disallowed))
+        }
+    }
+
+    /// Connect using the gateway url and session start limit in `gateway`, instead of getting them
+    /// when the shard first connects.
+    pub(crate) fn use_gateway(&mut self, gateway: &BotGateway) {
+        self.gateway = Some(gateway_params(&gateway.url));
+        self.session_starts = Some(SessionStarts::new(&gateway.session_start_limit));
+    }
+
+    /// Wait until the session start limit allows starting a new session, if it's been used up.
+    async fn wait_for_session_start(&mut self) {
+        let Some(session_starts) = &mut self.session_starts else { return };
+        while let Some(delay) = session_starts.start() {
+            error!(
+                "Used all {} session starts for today, waiting {delay:?} for the limit to reset before identifying",
+                session_starts.total,
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
     async fn close<D: Into<Option<Duration>> + Send>(&mut self, close_frame: CloseFrame<'_>, delay: D) {
         // do this first so we don't hold it across the `.await`
         info!("closing: {:?}", close_frame);
-        if let Some(mut stream) = self.state.stream.write().await.take() {
+        let was_open = if let Some(mut stream) = self.state.stream.write().await.take() {
             if let Err(e) = stream.close(Some(close_frame)).await {
                 error!("{}", e);
             }
             info!("stream closed");
+            true
         } else {
             info!("stream was already closed");
+            false
+        };
+        if was_open {
+            self.disconnected().await;
         }
         if let Some(delay) = delay.into() {
             info!("delaying for {:?}", delay);
@@ -161,16 +296,21 @@ impl<B: Bot + 'static> Shard<B> {
         // need to (re)connect
         if self.state.stream.read().await.is_none() {
             if self.gateway.is_none() {
-                let url = self.state.client.gateway_bot()
-                    .await?
-                    .url;
-                let ws = gateway_params(&url);
-                self.gateway = Some(ws);
+                let gateway = self.state.client.gateway_bot().await?;
+                self.use_gateway(&gateway);
+            }
+            if self.session_id.is_none() {
+                self.wait_for_session_start().await;
             }
             let ws = self.gateway.as_deref().unwrap();
             info!("connecting to {}", ws);
             let (stream, _): (WsStream, _) = connect_async(ws).await?;
             *self.state.stream.write().await = Some(stream);
+            self.connection_event(if self.session_id.is_some() {
+                ShardConnection::Resuming
+            } else {
+                ShardConnection::Connected
+            }).await;
         }
 
         if let (Some(session), &Some(seq)) = (&self.session_id, &self.seq) {
@@ -179,7 +319,6 @@ impl<B: Bot + 'static> Shard<B> {
                 session_id: session.clone(),
                 seq,
             };
-            println!("resume (in _run) = {resume:?}");
             send(&mut self.state.stream.write().await, resume).await?;
         }
 
@@ -188,34 +327,75 @@ impl<B: Bot + 'static> Shard<B> {
             Err(ShardError::Request(_)) => {}
             Err(ShardError::Websocket(_)) => {
                 // as far as I can tell, all websocket errors are fatal
-                *self.state.stream.write().await = None;
+                self.drop_stream().await;
             }
             Err(ShardError::NeedRestart) => {
-                *self.state.stream.write().await = None;
+                self.drop_stream().await;
             }
-            Err(ShardError::NeedResume) => { todo!() }
-            Err(ShardError::Other(_)) => {}
+            Err(ShardError::DisallowedIntents(_) | ShardError::Other(_)) => {}
             Ok(ConnectionAction::None) => unreachable!(),
-            Ok(ConnectionAction::Resume) => {
-                println!("RESUMING!");
-                self.close(CloseFrame {
-                    code: CloseCode::Restart,
-                    reason: "Initiating resume".into(),
-                }, None).await;
-                let Some(resume_gateway) = &self.resume_gateway else {
-                    todo!("handle this?")
-                };
-                println!("resuming ({resume_gateway:?})");
-                let (stream, _): (WsStream, _) = connect_async(resume_gateway).await?;
-                *self.state.stream.write().await = Some(stream);
+            Err(ShardError::NeedResume) | Ok(ConnectionAction::Resume) => {
+                self.resume().await?;
             }
             Ok(ConnectionAction::Reconnect) => {
-                *self.state.stream.write().await = None;
+                self.drop_stream().await;
             }
         }
         Ok(())
     }
 
+    /// Closes the websocket and reconnects to the resume gateway, so that the next [`_run`] sends
+    /// [`Resume`] with the stored session id and sequence number.
+    ///
+    /// If there is no session to resume, or nowhere to resume it, the connection state is reset
+    /// instead, so the next [`_run`] connects and identifies with a new session. If the resume
+    /// itself fails, Discord sends a non-resumable [`InvalidSession`](Payload::InvalidSession),
+    /// which also starts a new session.
+    ///
+    /// [`_run`]: Self::_run
+    async fn resume(&mut self) -> ShardResult<()> {
+        self.close(CloseFrame {
+            code: CloseCode::Restart,
+            reason: "Initiating resume".into(),
+        }, None).await;
+        let gateway = self.resume_gateway.as_ref().or(self.gateway.as_ref());
+        let (Some(_), Some(_), Some(gateway)) = (&self.session_id, self.seq, gateway) else {
+            warn!("no session to resume, reconnecting instead");
+            self.reset_connection_state();
+            return Ok(());
+        };
+        info!("resuming ({gateway})");
+        let (stream, _): (WsStream, _) = connect_async(gateway.as_str()).await?;
+        *self.state.stream.write().await = Some(stream);
+        self.connection_event(ShardConnection::Resuming).await;
+        Ok(())
+    }
+
+    /// Drops the websocket connection (without sending a close frame), notifying the bot if there
+    /// was a connection to drop.
+    async fn drop_stream(&self) {
+        let was_open = self.state.stream.write().await.take().is_some();
+        if was_open {
+            self.disconnected().await;
+        }
+    }
+
+    /// Tell the bot that this shard's connection transitioned to `connection`.
+    async fn connection_event(&self, connection: ShardConnection) {
+        let state = &self.state;
+        if let Err(error) = state.bot.shard_connected(self.shard_info, connection, Arc::clone(state)).await {
+            state.bot.error(error, Arc::clone(state)).await;
+        }
+    }
+
+    /// Tell the bot that this shard's websocket connection was closed.
+    async fn disconnected(&self) {
+        let state = &self.state;
+        if let Err(error) = state.bot.shard_disconnected(self.shard_info, Arc::clone(state)).await {
+            state.bot.error(error, Arc::clone(state)).await;
+        }
+    }
+
     async fn events_loop(&mut self) -> ShardResult<ConnectionAction> {
         loop {
             if self.state.stream.read().await.is_none() {
@@ -233,17 +413,7 @@ impl<B: Bot + 'static> Shard<B> {
             if let Ok(next) = result {
                 match next {
                     Ok(Some(Message::Text(text))) => {
-                        // let read = nice_from_str(&text);
-                        let read = serde_json::from_str(&text);
-                        let payload = match read {
-                            Ok(payload) => payload,
-                            Err(payload_parse_error) => {
-                                error!("payload_parse_error = {}", payload_parse_error);
-                                println!("{text}");
-                                continue;
-                            }
-                        };
-                        let action = self.handle_payload(payload).await?;
+                        let action = self.handle_text(&text).await?;
                         if action.terminal() { return Ok(action); }
                     }
                     Ok(Some(Message::Close(close_frame))) => {
@@ -282,6 +452,21 @@ impl<B: Bot + 'static> Shard<B> {
         }
     }
 
+    async fn handle_text(&mut self, text: &str) -> ShardResult<ConnectionAction> {
+        self.state.record_raw(text);
+        // let read = nice_from_str(text);
+        let read = serde_json::from_str(text);
+        let payload = match read {
+            Ok(payload) => payload,
+            Err(payload_parse_error) => {
+                error!("payload_parse_error = {}", payload_parse_error);
+                debug!("unparsed payload = {text}");
+                return Ok(ConnectionAction::None);
+            }
+        };
+        self.handle_payload(payload).await
+    }
+
     async fn heartbeat(&mut self) -> Result<ConnectionAction, SendError> {
         if let (Some(heartbeat), Some(ack)) = (self.heartbeat, self.ack) {
             // If a client does not receive a heartbeat ACK between its attempts at sending
@@ -354,8 +539,8 @@ impl<B: Bot + 'static> Shard<B> {
             }
             Payload::InvalidSession(resumable) => {
                 info!("recv: Invalid Session");
-                if resumable {
-                    warn!("Resumable Invalid Session: anything special to do here?");
+                if resumable && self.session_id.is_some() && self.seq.is_some() {
+                    info!("Invalid Session is resumable, resuming session");
                     ConnectionAction::Resume
                 } else {
                     self.reset_connection_state();
@@ -381,6 +566,9 @@ impl<B: Bot + 'static> Shard<B> {
         self.heartbeat_interval = Some(delay);
 
         if self.session_id.is_none() {
+            if let Some(gate) = &self.identify_gate {
+                gate.wait(self.shard_info.0).await;
+            }
             send(&mut self.state.stream.write().await, self.state.bot.identify()).await?;
         }
 
@@ -388,9 +576,34 @@ impl<B: Bot + 'static> Shard<B> {
     }
 
     #[allow(clippy::too_many_lines)]
-    async fn handle_dispatch(&mut self, event: DispatchPayload) /*-> ShardResult<()>*/ {
-        use DispatchPayload::*;
+    async fn handle_dispatch(&mut self, event: Event) /*-> ShardResult<()>*/ {
+        use Event::*;
+        // `update` overwrites the cached guild/member, so keep the old one for the `Bot` method
+        let old_guild = match &event {
+            GuildUpdate(update) => self.state.cache.guild(update.id).await,
+            _ => None,
+        };
+        let old_member = match &event {
+            GuildMemberUpdate(update) => self.state.cache.member(update.guild_id, update.user.id).await,
+            _ => None,
+        };
         event.clone().update(&self.state.cache).await;
+        if self.state.events.receiver_count() != 0 {
+            // only fails if every subscriber has since been dropped
+            let _ = self.state.events.send(event.clone());
+        }
+        if let MessageCreate(create) = &event {
+            self.state.warn_if_missing_message_content(&create.message).await;
+        }
+        if let InteractionCreate(create) = &event {
+            if let Some(id) = create.interaction.id() {
+                let window = self.state.bot.duplicate_interaction_window();
+                if !self.state.first_interaction_use(id, window) {
+                    info!("Skipping interaction {id}, which was already handled");
+                    return;
+                }
+            }
+        }
         if let Ready(ready) = &event {
             // make sure were using the right API version
             assert_eq!(API_VERSION, ready.v);
@@ -422,7 +635,7 @@ impl<B: Bot + 'static> Shard<B> {
                 let user_commands = B::global_user_commands();
                 let message_commands = B::global_message_commands();
                 let global_commands = slash_commands
-                    .iter().map(|c| c.command())
+                    .iter().map(|c| c.command().into_owned())
                     .chain(user_commands.iter().map(|c| c.command()))
                     .chain(message_commands.iter().map(|c| c.command()))
                     .collect();
@@ -436,6 +649,42 @@ impl<B: Bot + 'static> Shard<B> {
                 self.state.global_user_commands.get_or_init(|| set_commands(&mut commands, user_commands));
                 self.state.global_message_commands.get_or_init(|| set_commands(&mut commands, message_commands));
             }
+            self.connection_event(ShardConnection::Ready).await;
+        } else if let Resumed(_) = &event {
+            self.connection_event(ShardConnection::Resumed).await;
+        }
+        let cache_ready = match &event {
+            Ready(ready) => {
+                let (generation, ready) = self.state.guilds_pending(ready.guilds.iter().map(|g| g.id));
+                if ready.is_none() {
+                    let state = Arc::clone(&self.state);
+                    tokio::spawn(async move {
+                        tokio::time::sleep(state.bot.cache_ready_timeout()).await;
+                        if let Some(guilds) = state.guilds_timed_out(generation) {
+                            warn!("Timed out waiting for guilds to be created, the cache is ready anyway");
+                            if let Err(error) = state.bot.cache_ready(guilds, Arc::clone(&state)).await {
+                                state.bot.error(error, Arc::clone(&state)).await;
+                            }
+                        }
+                    });
+                }
+                ready
+            }
+            GuildCreate(create) => self.state.guild_loaded(create.guild.id),
+            _ => None,
+        };
+        // the updated member, if a cached member started or stopped boosting
+        let boost = match (&event, &old_member) {
+            (GuildMemberUpdate(update), Some(old)) if old.premium_since.is_some() != update.premium_since.is_some() =>
+                self.state.cache.member(update.guild_id, update.user.id).await
+                    .map(|member| (update.guild_id, member)),
+            _ => None,
+        };
+        // reaction commands for deleted messages will never run again
+        match &event {
+            MessageDelete(delete) => self.state.remove_reaction_commands(&[delete.id]).await,
+            MessageDeleteBulk(delete) => self.state.remove_reaction_commands(&delete.ids).await,
+            _ => {}
         }
         let state = Arc::clone(&self.state);
         // todo panic if this panicked? (make a field in self for handlers, try_join them?)
@@ -465,6 +714,25 @@ impl<B: Bot + 'static> Shard<B> {
                     // });
                     state.bot.guild_create(guild.guild, Arc::clone(&state)).await
                 }
+                GuildUpdate(update) => match state.cache.guild(update.id).await {
+                    Some(new) => state.bot.guild_update(old_guild, new, Arc::clone(&state)).await,
+                    None => Ok(()),
+                },
+                GuildMemberAdd(add) => state.bot.guild_member_add(
+                    add.guild_id,
+                    add.member,
+                    Arc::clone(&state),
+                ).await,
+                GuildMemberRemove(remove) => state.bot.guild_member_remove(
+                    remove.guild_id,
+                    remove.user,
+                    Arc::clone(&state),
+                ).await,
+                GuildMemberUpdate(update) => state.bot.guild_member_update(
+                    old_member,
+                    update,
+                    Arc::clone(&state),
+                ).await,
                 MessageCreate(message) => state.bot.message_create(
                     message.message, Arc::clone(&state),
                 ).await,
@@ -486,6 +754,17 @@ impl<B: Bot + 'static> Shard<B> {
                     remove.into(),
                     Arc::clone(&state),
                 ).await,
+                IntegrationCreate(integration) => state.bot.integration_create(
+                    integration.guild_id,
+                    integration.integration,
+                    Arc::clone(&state),
+                ).await,
+                IntegrationDelete(delete) => state.bot.integration_delete(
+                    delete.guild_id,
+                    delete.id,
+                    delete.application_id,
+                    Arc::clone(&state),
+                ).await,
                 IntegrationUpdate(integration) => state.bot.integration_update(
                     integration.guild_id,
                     integration.integration,
@@ -501,11 +780,46 @@ impl<B: Bot + 'static> Shard<B> {
                     update.role,
                     Arc::clone(&state),
                 ).await,
+                StageInstanceCreate(create) => state.bot.stage_instance_create(
+                    create.stage_instance,
+                    Arc::clone(&state),
+                ).await,
+                StageInstanceUpdate(update) => state.bot.stage_instance_update(
+                    update.stage_instance,
+                    Arc::clone(&state),
+                ).await,
+                StageInstanceDelete(delete) => state.bot.stage_instance_delete(
+                    delete.stage_instance,
+                    Arc::clone(&state),
+                ).await,
+                GuildScheduledEventUserAdd(add) => state.bot.scheduled_event_user_add(
+                    add,
+                    Arc::clone(&state),
+                ).await,
+                GuildScheduledEventUserRemove(remove) => state.bot.scheduled_event_user_remove(
+                    remove,
+                    Arc::clone(&state),
+                ).await,
                 _ => Ok(())
             };
             if let Err(error) = result {
                 state.bot.error(error, Arc::clone(&state)).await;
             }
+            if let Some((guild, member)) = boost {
+                let result = if member.premium_since.is_some() {
+                    state.bot.member_boost_start(guild, member, Arc::clone(&state)).await
+                } else {
+                    state.bot.member_boost_end(guild, member, Arc::clone(&state)).await
+                };
+                if let Err(error) = result {
+                    state.bot.error(error, Arc::clone(&state)).await;
+                }
+            }
+            if let Some(guilds) = cache_ready {
+                if let Err(error) = state.bot.cache_ready(guilds, Arc::clone(&state)).await {
+                    state.bot.error(error, Arc::clone(&state)).await;
+                }
+            }
         });
 
         // Ok(())
@@ -545,4 +859,920 @@ pub(crate) async fn send<P>(stream: &mut RwLockWriteGuard<'_, Option<WsStream>>,
         .ok_or(SendError::NeedRestart)?
         .send(Message::Text(message)).await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod shard_tests {
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+    use std::sync::atomic::Ordering;
+
+    use async_trait::async_trait;
+    use futures::StreamExt;
+
+    use crate::errors::BotError;
+    use crate::http::DiscordClient;
+    use crate::cache::Cache;
+    use crate::model::channel::ThreadMember;
+    use crate::model::guild::{Guild, GuildMember, IntegrationExpireBehavior};
+    use crate::model::ids::{ApplicationId, ChannelId, GuildId, IntegrationId, MessageId, ScheduledEventId, StageInstanceId, UserId};
+    use crate::model::stage_instance::StageInstance;
+    use crate::model::user::User;
+    use crate::shard::model::Identify;
+
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    enum Lifecycle {
+        Connected((u64, u64), ShardConnection),
+        Disconnected((u64, u64)),
+    }
+
+    #[derive(Default)]
+    struct LifecycleBot {
+        events: Mutex<Vec<Lifecycle>>,
+    }
+
+    #[async_trait]
+    impl Bot for LifecycleBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn shard_connected(&self, shard: (u64, u64), connection: ShardConnection, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.events.lock().unwrap().push(Lifecycle::Connected(shard, connection));
+            Ok(())
+        }
+
+        async fn shard_disconnected(&self, shard: (u64, u64), _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.events.lock().unwrap().push(Lifecycle::Disconnected(shard));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn lifecycle_through_reconnect() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        let mut shard = Shard::new(Arc::clone(&state));
+        shard.shard_info = (1, 2);
+
+        // first connection
+        shard.connection_event(ShardConnection::Connected).await;
+        shard.connection_event(ShardConnection::Ready).await;
+        // the websocket dies, and the shard reconnects to resume
+        shard.disconnected().await;
+        // closing again is not another disconnection
+        shard.close(CloseFrame { code: CloseCode::Restart, reason: "test".into() }, None).await;
+        shard.connection_event(ShardConnection::Resuming).await;
+        let resumed = serde_json::from_str(r#"{"op":0,"s":2,"t":"RESUMED","d":{"_trace":[]}}"#).unwrap();
+        let action = shard.handle_payload(resumed).await.unwrap();
+        assert_eq!(action, ConnectionAction::None);
+
+        assert_eq!(
+            *state.bot.events.lock().unwrap(),
+            [
+                Lifecycle::Connected((1, 2), ShardConnection::Connected),
+                Lifecycle::Connected((1, 2), ShardConnection::Ready),
+                Lifecycle::Disconnected((1, 2)),
+                Lifecycle::Connected((1, 2), ShardConnection::Resuming),
+                Lifecycle::Connected((1, 2), ShardConnection::Resumed),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn events_stream_receives_dispatches() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        let mut shard = Shard::new(Arc::clone(&state));
+        let mut events = Box::pin(state.events());
+
+        let message_create = serde_json::from_str(&format!(
+            r#"{{"op":0,"s":1,"t":"MESSAGE_CREATE","d":{}}}"#,
+            crate::test_fixtures::message(3, 2),
+        )).unwrap();
+        let action = shard.handle_payload(message_create).await.unwrap();
+        assert_eq!(action, ConnectionAction::None);
+
+        match events.next().await {
+            Some(Event::MessageCreate(create)) => {
+                assert_eq!(create.message.id, MessageId(3));
+                assert_eq!(create.message.content, "Supa Hot");
+            }
+            other => panic!("expected MESSAGE_CREATE, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn last_raw_dispatch() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        let mut shard = Shard::new(Arc::clone(&state));
+        let typing = r#"{"op":0,"s":1,"t":"TYPING_START","d":{"channel_id":"2","user_id":"1","timestamp":1}}"#;
+        let resumed = r#"{"op":0,"s":2,"t":"RESUMED","d":{"_trace":[]}}"#;
+
+        // nothing is captured until asked for
+        shard.handle_text(typing).await.unwrap();
+        assert_eq!(state.last_raw("TYPING_START"), None);
+
+        state.capture_raw("TYPING_START");
+        assert_eq!(state.last_raw("TYPING_START"), None);
+        shard.handle_text(typing).await.unwrap();
+        shard.handle_text(resumed).await.unwrap();
+        assert_eq!(state.last_raw("TYPING_START").as_deref(), Some(typing));
+        assert_eq!(state.last_raw("RESUMED"), None);
+    }
+
+    struct StageBot(tokio::sync::mpsc::UnboundedSender<(&'static str, StageInstance)>);
+
+    #[async_trait]
+    impl Bot for StageBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn stage_instance_create(&self, stage: StageInstance, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(("create", stage)).unwrap();
+            Ok(())
+        }
+
+        async fn stage_instance_update(&self, stage: StageInstance, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(("update", stage)).unwrap();
+            Ok(())
+        }
+
+        async fn stage_instance_delete(&self, stage: StageInstance, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(("delete", stage)).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn stage_instance_dispatches() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), StageBot(tx)));
+        let mut shard = Shard::new(Arc::clone(&state));
+        let cached_topics = || async {
+            let guild = state.cache.guild(GuildId(1)).await.unwrap();
+            guild.stage_instances.iter().map(|s| s.topic.clone()).collect::<Vec<_>>()
+        };
+        let stage_event = |t: &str, topic: &str| format!(
+            r#"{{"op":0,"s":2,"t":"{t}","d":{{"id":"30","guild_id":"1","channel_id":"20","topic":"{topic}","privacy_level":2}}}}"#
+        );
+
+        let mut guild: serde_json::Value = serde_json::from_str(&crate::test_fixtures::guild(
+            1,
+            r#"[{ "id": "1", "name": "@everyone", "permissions": "0", "position": 0, "color": 0, "hoist": false, "managed": false, "mentionable": false }]"#,
+            "[]",
+            "[]",
+        )).unwrap();
+        guild["stage_instances"] = serde_json::json!([
+            {"id": "31", "guild_id": "1", "channel_id": "21", "topic": "existing", "privacy_level": 2}
+        ]);
+        shard.handle_text(&format!(r#"{{"op":0,"s":1,"t":"GUILD_CREATE","d":{guild}}}"#)).await.unwrap();
+        assert_eq!(cached_topics().await, ["existing"]);
+        shard.handle_text(r#"{"op":0,"s":2,"t":"STAGE_INSTANCE_DELETE","d":{"id":"31","guild_id":"1","channel_id":"21","topic":"existing","privacy_level":2}}"#).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().0, "delete");
+
+        shard.handle_text(&stage_event("STAGE_INSTANCE_CREATE", "rust")).await.unwrap();
+        let (event, created) = rx.recv().await.unwrap();
+        assert_eq!((event, created.id, created.topic.as_str()), ("create", StageInstanceId(30), "rust"));
+        assert_eq!(cached_topics().await, ["rust"]);
+
+        shard.handle_text(&stage_event("STAGE_INSTANCE_UPDATE", "more rust")).await.unwrap();
+        let (event, updated) = rx.recv().await.unwrap();
+        assert_eq!((event, updated.topic.as_str()), ("update", "more rust"));
+        assert_eq!(cached_topics().await, ["more rust"]);
+
+        shard.handle_text(&stage_event("STAGE_INSTANCE_DELETE", "more rust")).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().0, "delete");
+        assert!(cached_topics().await.is_empty());
+    }
+
+    struct IntentsBot;
+
+    #[async_trait]
+    impl Bot for IntentsBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        fn identify(&self) -> Identify {
+            Identify::new(self.token()).add_intents(Intents::GUILD_MEMBERS | Intents::MESSAGE_CONTENT)
+        }
+    }
+
+    #[tokio::test]
+    async fn disallowed_intents() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), IntentsBot));
+        let application = serde_json::from_str(&format!(r#"{{
+          "id": "4",
+          "name": "Test Bot",
+          "icon": null,
+          "description": "",
+          "bot_public": true,
+          "bot_require_code_grant": false,
+          "owner": {{ "id": "10", "username": "owner", "discriminator": "0", "avatar": null }},
+          "summary": "",
+          "verify_key": "key",
+          "team": null,
+          "flags": {}
+        }}"#, ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED.bits())).unwrap();
+        state.cache.full_application.set(application).unwrap();
+        let shard = Shard::new(state);
+
+        let error = shard.check_intents().await.unwrap_err();
+        assert!(matches!(error, ShardError::DisallowedIntents(intents) if intents == Intents::MESSAGE_CONTENT));
+        assert!(error.to_string().contains("MESSAGE_CONTENT"));
+        assert!(!error.to_string().contains("GUILD_MEMBERS"));
+    }
+
+    #[tokio::test]
+    async fn missing_message_content_warning() {
+        fn message_create(seq: u64, content: &str) -> Payload {
+            let mut message: serde_json::Value = serde_json::from_str(&crate::test_fixtures::message(seq, 2)).unwrap();
+            message["content"] = content.into();
+            serde_json::from_str(&format!(r#"{{"op":0,"s":{seq},"t":"MESSAGE_CREATE","d":{message}}}"#)).unwrap()
+        }
+
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        assert!(!state.has_message_content_intent());
+        let mut shard = Shard::new(Arc::clone(&state));
+
+        // messages with content don't warn
+        shard.handle_payload(message_create(1, "Supa Hot")).await.unwrap();
+        assert!(!state.message_content_warned.load(Ordering::Relaxed));
+        shard.handle_payload(message_create(2, "")).await.unwrap();
+        assert!(state.message_content_warned.load(Ordering::Relaxed));
+        // only warns once
+        let Payload::Dispatch { event: Event::MessageCreate(empty), .. } = message_create(3, "") else { unreachable!() };
+        assert!(!state.warn_if_missing_message_content(&empty.message).await);
+
+        let fresh = BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default());
+        assert!(fresh.warn_if_missing_message_content(&empty.message).await);
+        assert!(!fresh.warn_if_missing_message_content(&empty.message).await);
+
+        let with_intent = BotState::new(DiscordClient::builder(String::new()).build(), IntentsBot);
+        assert!(with_intent.has_message_content_intent());
+        assert!(!with_intent.warn_if_missing_message_content(&empty.message).await);
+    }
+
+    #[test]
+    fn scheduled_event_user_deserialize() {
+        let add: dispatch::GuildScheduledEventUserAdd = serde_json::from_str(
+            r#"{"guild_scheduled_event_id":"40","user_id":"2","guild_id":"1"}"#
+        ).unwrap();
+        assert_eq!(add.guild_scheduled_event_id, ScheduledEventId(40));
+        assert_eq!(add.user_id, UserId(2));
+        assert_eq!(add.guild_id, GuildId(1));
+    }
+
+    struct RsvpBot(tokio::sync::mpsc::UnboundedSender<(bool, ScheduledEventId, UserId)>);
+
+    #[async_trait]
+    impl Bot for RsvpBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn scheduled_event_user_add(&self, add: dispatch::GuildScheduledEventUserAdd, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send((true, add.guild_scheduled_event_id, add.user_id)).unwrap();
+            Ok(())
+        }
+
+        async fn scheduled_event_user_remove(&self, remove: dispatch::GuildScheduledEventUserRemove, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send((false, remove.guild_scheduled_event_id, remove.user_id)).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn scheduled_event_user_dispatches() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), RsvpBot(tx)));
+        let mut shard = Shard::new(state);
+
+        shard.handle_text(r#"{"op":0,"s":1,"t":"GUILD_SCHEDULED_EVENT_USER_ADD","d":{"guild_scheduled_event_id":"40","user_id":"2","guild_id":"1"}}"#).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), (true, ScheduledEventId(40), UserId(2)));
+        shard.handle_text(r#"{"op":0,"s":2,"t":"GUILD_SCHEDULED_EVENT_USER_REMOVE","d":{"guild_scheduled_event_id":"40","user_id":"2","guild_id":"1"}}"#).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), (false, ScheduledEventId(40), UserId(2)));
+    }
+
+    #[test]
+    fn integration_create_deserialize() {
+        let create: dispatch::IntegrationCreate = serde_json::from_str(r#"{
+          "guild_id": "1",
+          "id": "50",
+          "name": "Some Bot",
+          "type": "discord",
+          "enabled": true,
+          "account": { "id": "60", "name": "Some Bot" },
+          "application": { "id": "60", "name": "Some Bot", "icon": null, "description": "", "summary": "" }
+        }"#).unwrap();
+        assert_eq!(create.guild_id, GuildId(1));
+        assert_eq!(create.integration.id, IntegrationId(50));
+        assert_eq!(create.integration.integration_type, "discord");
+        assert_eq!(create.integration.application.unwrap().name, "Some Bot");
+    }
+
+    #[tokio::test]
+    async fn integration_update_twitch() {
+        let cache = Cache::default();
+        let update: dispatch::IntegrationUpdate = serde_json::from_str(r#"{
+          "guild_id": "1",
+          "id": "51",
+          "name": "some_streamer",
+          "type": "twitch",
+          "enabled": true,
+          "syncing": false,
+          "role_id": "80",
+          "enable_emoticons": true,
+          "expire_behavior": 1,
+          "expire_grace_period": 7,
+          "user": { "id": "2", "username": "streamer", "discriminator": "0", "avatar": null },
+          "account": { "id": "123456789", "name": "some_streamer" },
+          "synced_at": "2024-05-04T12:34:56.789000+00:00",
+          "subscriber_count": 42,
+          "revoked": false
+        }"#).unwrap();
+        let integration = &update.integration;
+        assert_eq!(integration.integration_type, "twitch");
+        assert_eq!(integration.expire_behavior, Some(IntegrationExpireBehavior::Kick));
+        assert_eq!(integration.expire_grace_period, Some(7));
+        assert_eq!(integration.account.id, "123456789");
+        assert_eq!(integration.account.name, "some_streamer");
+        assert!(integration.synced_at.is_some());
+        assert_eq!(integration.subscriber_count, Some(42));
+
+        update.update(&cache).await;
+        let cached = cache.integration(GuildId(1), IntegrationId(51)).await.unwrap();
+        assert_eq!(cached.subscriber_count, Some(42));
+        assert_eq!(cache.integrations(GuildId(1)).await.len(), 1);
+
+        let delete: dispatch::IntegrationDelete = serde_json::from_str(r#"{"id":"51","guild_id":"1"}"#).unwrap();
+        delete.update(&cache).await;
+        assert!(cache.integrations(GuildId(1)).await.is_empty());
+    }
+
+    #[test]
+    fn integration_delete_deserialize() {
+        let delete: dispatch::IntegrationDelete = serde_json::from_str(
+            r#"{"id":"50","guild_id":"1","application_id":"60"}"#
+        ).unwrap();
+        assert_eq!(delete.id, IntegrationId(50));
+        assert_eq!(delete.guild_id, GuildId(1));
+        assert_eq!(delete.application_id, Some(ApplicationId(60)));
+
+        let delete: dispatch::IntegrationDelete = serde_json::from_str(r#"{"id":"50","guild_id":"1"}"#).unwrap();
+        assert_eq!(delete.application_id, None);
+    }
+
+    #[tokio::test]
+    async fn thread_member_updates() {
+        let cache = Cache::default();
+        let own_update: dispatch::ThreadMemberUpdate = serde_json::from_str(r#"{
+          "id": "70",
+          "user_id": "2",
+          "join_timestamp": "2022-01-09T17:27:07.299Z",
+          "flags": 1,
+          "guild_id": "1"
+        }"#).unwrap();
+        assert_eq!(own_update.guild_id, GuildId(1));
+        assert_eq!(own_update.member.id, Some(ChannelId(70)));
+        assert_eq!(own_update.member.flags, 1);
+        own_update.update(&cache).await;
+        let users = |members: Vec<ThreadMember>| members.into_iter().filter_map(|m| m.user_id).sorted().collect_vec();
+        assert_eq!(users(cache.thread_members(ChannelId(70)).await), [UserId(2)]);
+
+        let members_event: dispatch::ThreadMembersUpdate = serde_json::from_str(r#"{
+          "id": "70",
+          "guild_id": "1",
+          "member_count": 2,
+          "added_members": [
+            { "id": "70", "user_id": "3", "join_timestamp": "2022-01-09T17:30:00.000Z", "flags": 0 }
+          ]
+        }"#).unwrap();
+        assert_eq!(members_event.member_count, 2);
+        assert!(members_event.removed_member_ids.is_empty());
+        members_event.update(&cache).await;
+        assert_eq!(users(cache.thread_members(ChannelId(70)).await), [UserId(2), UserId(3)]);
+
+        let members_event: dispatch::ThreadMembersUpdate = serde_json::from_str(
+            r#"{"id":"70","guild_id":"1","member_count":1,"removed_member_ids":["2"]}"#
+        ).unwrap();
+        assert!(members_event.added_members.is_empty());
+        members_event.update(&cache).await;
+        assert_eq!(users(cache.thread_members(ChannelId(70)).await), [UserId(3)]);
+        assert!(cache.thread_members(ChannelId(71)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn resumable_invalid_session() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        let mut shard = Shard::new(Arc::clone(&state));
+        shard.session_id = Some("session".into());
+        shard.seq = Some(5);
+
+        let invalid_session = serde_json::from_str(r#"{"op":9,"d":true}"#).unwrap();
+        let action = shard.handle_payload(invalid_session).await.unwrap();
+        assert_eq!(action, ConnectionAction::Resume);
+        assert_eq!(shard.session_id.as_deref(), Some("session"));
+        assert_eq!(shard.seq, Some(5));
+
+        // the shard reconnects, sends `Resume`, and Discord replays the missed events
+        shard.connection_event(ShardConnection::Resuming).await;
+        let resumed = serde_json::from_str(r#"{"op":0,"s":6,"t":"RESUMED","d":{"_trace":[]}}"#).unwrap();
+        let action = shard.handle_payload(resumed).await.unwrap();
+        assert_eq!(action, ConnectionAction::None);
+        assert_eq!(shard.session_id.as_deref(), Some("session"));
+        assert_eq!(shard.seq, Some(6));
+        assert_eq!(
+            *state.bot.events.lock().unwrap(),
+            [
+                Lifecycle::Connected((0, 0), ShardConnection::Resuming),
+                Lifecycle::Connected((0, 0), ShardConnection::Resumed),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_without_session_reconnects() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        let mut shard = Shard::new(Arc::clone(&state));
+
+        // nothing to resume, so a new session is started
+        shard.seq = Some(5);
+        shard.resume().await.unwrap();
+        assert_eq!(shard.seq, None);
+
+        // a session, but nowhere to resume it
+        shard.session_id = Some("session".into());
+        shard.seq = Some(5);
+        shard.resume().await.unwrap();
+        assert_eq!(shard.session_id, None);
+        assert_eq!(shard.seq, None);
+        assert!(state.bot.events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_start_limit() {
+        let gateway: BotGateway = serde_json::from_str(r#"{
+            "url": "wss://gateway.discord.gg",
+            "shards": 1,
+            "session_start_limit": { "total": 1000, "remaining": 1, "reset_after": 60000, "max_concurrency": 1 }
+        }"#).unwrap();
+        assert_eq!(gateway.session_start_limit.reset_after(), Duration::from_mins(1));
+
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), LifecycleBot::default()));
+        let mut shard = Shard::new(state);
+        shard.use_gateway(&gateway);
+        assert_eq!(shard.gateway.as_deref(), Some("wss://gateway.discord.gg/?v=10&encoding=json"));
+
+        // the last session start is allowed
+        tokio::time::timeout(Duration::from_millis(50), shard.wait_for_session_start()).await.unwrap();
+        // but then identifying is blocked until the limit resets
+        let blocked = tokio::time::timeout(Duration::from_millis(50), shard.wait_for_session_start()).await;
+        assert!(blocked.is_err());
+        let delay = shard.session_starts.unwrap().start().unwrap();
+        assert!(delay <= Duration::from_mins(1) && delay > Duration::from_secs(59));
+    }
+
+    #[tokio::test]
+    async fn staggered_identifies() {
+        const INTERVAL: Duration = Duration::from_millis(100);
+        let gate = IdentifyGate::new(2, INTERVAL);
+        let start = Instant::now();
+        // polled in order, so shards in the same rate limit key identify in order of their id
+        let identified = futures::future::join_all((0..8).map(|shard| {
+            let gate = &gate;
+            async move {
+                gate.wait(shard).await;
+                (shard, start.elapsed())
+            }
+        })).await;
+
+        for (shard, elapsed) in identified {
+            // shards 0 & 1 identify immediately, then 2 & 3, and so on
+            let slot = elapsed.as_millis() / INTERVAL.as_millis();
+            assert_eq!(u64::try_from(slot).unwrap(), shard / 2, "shard {shard} identified after {elapsed:?}");
+        }
+    }
+
+    #[test]
+    fn token_redacted() {
+        let token = "MTIzNDU2Nzg5.secret.token";
+        let identify = Identify::new(token.into());
+        let resume = Resume { token: token.into(), session_id: "session".into(), seq: 5 };
+        for debug in [
+            format!("{identify:?}"),
+            format!("{resume:?}"),
+            format!("{:?}", Payload::Resume(resume)),
+        ] {
+            assert!(!debug.contains(token), "{debug}");
+            assert!(debug.contains("Bot ***"), "{debug}");
+        }
+    }
+
+    struct InteractionBot(tokio::sync::mpsc::UnboundedSender<crate::model::ids::InteractionId>);
+
+    #[async_trait]
+    impl Bot for InteractionBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn interaction(&self, interaction: crate::model::interaction::Interaction, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(interaction.id().unwrap()).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_interaction_handled_once() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), InteractionBot(tx)));
+        let mut shard = Shard::new(Arc::clone(&state));
+        let interaction_create = |seq: u64| serde_json::from_str(&format!(r#"{{"op":0,"s":{seq},"t":"INTERACTION_CREATE","d":{{
+            "version": 1,
+            "type": 3,
+            "token": "unique_interaction_token",
+            "id": "846462639134605312",
+            "application_id": "290926444748734465",
+            "channel_id": "345626669114982999",
+            "user": {{ "id": "53908232506183680", "username": "Mason", "discriminator": "1337", "avatar": null }},
+            "data": {{ "custom_id": "click_one", "component_type": 2 }}
+        }}}}"#)).unwrap();
+
+        shard.handle_payload(interaction_create(1)).await.unwrap();
+        // replayed after resuming
+        shard.handle_payload(interaction_create(2)).await.unwrap();
+
+        let interaction = crate::model::ids::InteractionId(846_462_639_134_605_312);
+        assert_eq!(rx.recv().await, Some(interaction));
+        let again = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+        assert!(again.is_err(), "interaction handled twice");
+
+        // outside the window, the id is handled again
+        assert!(!state.first_interaction_use(interaction, Duration::from_mins(1)));
+        assert!(state.first_interaction_use(interaction, Duration::ZERO));
+    }
+
+    struct GuildUpdateBot(tokio::sync::mpsc::UnboundedSender<(Option<String>, Option<String>)>);
+
+    #[async_trait]
+    impl Bot for GuildUpdateBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn guild_update(&self, old: Option<Guild>, new: Guild, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send((old.and_then(|g| g.name), new.name)).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn guild_update_old_state() {
+        let guild = |name: &str| {
+            let mut guild: serde_json::Value = serde_json::from_str(&crate::test_fixtures::guild(1, "[]", "[]", "[]")).unwrap();
+            guild["name"] = name.into();
+            guild
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), GuildUpdateBot(tx)));
+        let guild_create: dispatch::GuildCreate = serde_json::from_value(guild("Old Name")).unwrap();
+        guild_create.update(&state.cache).await;
+
+        let mut shard = Shard::new(Arc::clone(&state));
+        let guild_update = serde_json::from_str(
+            &format!(r#"{{"op":0,"s":1,"t":"GUILD_UPDATE","d":{}}}"#, guild("New Name"))
+        ).unwrap();
+        shard.handle_payload(guild_update).await.unwrap();
+
+        let names = rx.recv().await.unwrap();
+        assert_eq!(names, (Some("Old Name".to_string()), Some("New Name".to_string())));
+        assert_eq!(state.cache.guild(GuildId(1)).await.unwrap().name.as_deref(), Some("New Name"));
+    }
+
+    #[derive(Debug)]
+    #[allow(clippy::large_enum_variant)]
+    enum MemberEvent {
+        Add(GuildId, GuildMember),
+        Remove(GuildId, User),
+        Update(Option<GuildMember>, dispatch::GuildMemberUpdate),
+    }
+
+    struct MemberBot(tokio::sync::mpsc::UnboundedSender<MemberEvent>);
+
+    #[async_trait]
+    impl Bot for MemberBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn guild_member_add(&self, guild: GuildId, member: GuildMember, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(MemberEvent::Add(guild, member)).unwrap();
+            Ok(())
+        }
+
+        async fn guild_member_remove(&self, guild: GuildId, user: User, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(MemberEvent::Remove(guild, user)).unwrap();
+            Ok(())
+        }
+
+        async fn guild_member_update(&self, old: Option<GuildMember>, update: dispatch::GuildMemberUpdate, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(MemberEvent::Update(old, update)).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn guild_member_events() {
+        const USER: &str = r#"{ "id": "11", "username": "mason", "discriminator": "0", "avatar": null }"#;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), MemberBot(tx)));
+        let mut shard = Shard::new(Arc::clone(&state));
+        let payload = |seq: u64, t: &str, d: String| serde_json::from_str(
+            &format!(r#"{{"op":0,"s":{seq},"t":"{t}","d":{d}}}"#)
+        ).unwrap();
+
+        shard.handle_payload(payload(1, "GUILD_MEMBER_ADD", format!(r#"{{
+            "guild_id": "1", "user": {USER}, "nick": null, "roles": [],
+            "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false
+        }}"#))).await.unwrap();
+        let Some(MemberEvent::Add(guild, member)) = rx.recv().await else { panic!("expected a member add") };
+        assert_eq!(guild, GuildId(1));
+        assert_eq!(member.user.id, UserId(11));
+
+        shard.handle_payload(payload(2, "GUILD_MEMBER_UPDATE", format!(r#"{{
+            "guild_id": "1", "user": {USER}, "nick": "Supa Hot", "roles": [],
+            "joined_at": "2017-07-11T17:27:07.299Z", "premium_since": null
+        }}"#))).await.unwrap();
+        let Some(MemberEvent::Update(old, update)) = rx.recv().await else { panic!("expected a member update") };
+        assert_eq!(old.map(|m| m.nick), Some(None));
+        assert_eq!(update.guild_id, GuildId(1));
+        assert_eq!(update.nick.as_deref(), Some("Supa Hot"));
+
+        shard.handle_payload(payload(3, "GUILD_MEMBER_REMOVE", format!(r#"{{
+            "guild_id": "1", "user": {USER}
+        }}"#))).await.unwrap();
+        let Some(MemberEvent::Remove(guild, user)) = rx.recv().await else { panic!("expected a member remove") };
+        assert_eq!(guild, GuildId(1));
+        assert_eq!(user.id, UserId(11));
+        assert!(state.cache.member(GuildId(1), UserId(11)).await.is_none());
+    }
+
+    struct BoostBot(tokio::sync::mpsc::UnboundedSender<(bool, GuildId, GuildMember)>);
+
+    #[async_trait]
+    impl Bot for BoostBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn member_boost_start(&self, guild: GuildId, member: GuildMember, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send((true, guild, member)).unwrap();
+            Ok(())
+        }
+
+        async fn member_boost_end(&self, guild: GuildId, member: GuildMember, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send((false, guild, member)).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn member_boost_events() {
+        const USER: &str = r#"{ "id": "11", "username": "mason", "discriminator": "0", "avatar": null }"#;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), BoostBot(tx)));
+        let mut shard = Shard::new(Arc::clone(&state));
+        let payload = |seq: u64, t: &str, d: String| serde_json::from_str(
+            &format!(r#"{{"op":0,"s":{seq},"t":"{t}","d":{d}}}"#)
+        ).unwrap();
+        let update = |seq: u64, premium_since: &str| payload(seq, "GUILD_MEMBER_UPDATE", format!(r#"{{
+            "guild_id": "1", "user": {USER}, "nick": null, "roles": [],
+            "joined_at": "2017-07-11T17:27:07.299Z", "premium_since": {premium_since}
+        }}"#));
+
+        shard.handle_payload(payload(1, "GUILD_MEMBER_ADD", format!(r#"{{
+            "guild_id": "1", "user": {USER}, "nick": null, "roles": [],
+            "joined_at": "2017-07-11T17:27:07.299Z", "deaf": false, "mute": false
+        }}"#))).await.unwrap();
+
+        shard.handle_payload(update(2, r#""2024-01-01T00:00:00.000Z""#)).await.unwrap();
+        let (started, guild, member) = rx.recv().await.unwrap();
+        assert!(started);
+        assert_eq!(guild, GuildId(1));
+        assert_eq!(member.user.id, UserId(11));
+        assert!(member.premium_since.is_some());
+
+        // still boosting, so nothing fires
+        shard.handle_payload(update(3, r#""2024-01-01T00:00:00.000Z""#)).await.unwrap();
+
+        shard.handle_payload(update(4, "null")).await.unwrap();
+        let (started, _, member) = rx.recv().await.unwrap();
+        assert!(!started);
+        assert!(member.premium_since.is_none());
+
+        let again = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+        assert!(again.is_err());
+    }
+
+    struct CacheReadyBot(tokio::sync::mpsc::UnboundedSender<Vec<GuildId>>);
+
+    #[async_trait]
+    impl Bot for CacheReadyBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+
+        async fn cache_ready(&self, guilds: Vec<GuildId>, _: Arc<BotState<Self>>) -> Result<(), BotError<Infallible>> {
+            self.0.send(guilds).unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_ready_after_last_guild() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), CacheReadyBot(tx)));
+        // so that `Ready` doesn't try to create the global commands
+        state.global_slash_commands.get_or_init(HashMap::new);
+        let mut shard = Shard::new(Arc::clone(&state));
+        let ready = serde_json::from_str(&format!(r#"{{"op":0,"s":1,"t":"READY","d":{{
+            "v": {API_VERSION},
+            "user": {{ "id": "10", "username": "bot", "discriminator": "0", "avatar": null }},
+            "guilds": [{{ "id": "1", "unavailable": true }}, {{ "id": "2", "unavailable": true }}],
+            "session_id": "session",
+            "resume_gateway_url": "wss://gateway.discord.gg",
+            "application": {{ "id": "4", "flags": 0 }}
+        }}}}"#)).unwrap();
+        let guild_create = |seq: u64, id: u64| serde_json::from_str(&format!(
+            r#"{{"op":0,"s":{seq},"t":"GUILD_CREATE","d":{}}}"#,
+            crate::test_fixtures::guild(id, "[]", "[]", "[]"),
+        )).unwrap();
+
+        shard.handle_payload(ready).await.unwrap();
+        shard.handle_payload(guild_create(2, 1)).await.unwrap();
+        let early = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+        assert!(early.is_err(), "cache ready before every guild was created");
+
+        shard.handle_payload(guild_create(3, 2)).await.unwrap();
+        assert_eq!(rx.recv().await, Some(vec![GuildId(1), GuildId(2)]));
+
+        // guilds joined later don't make the cache ready again
+        shard.handle_payload(guild_create(4, 3)).await.unwrap();
+        let again = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+        assert!(again.is_err(), "cache ready twice");
+    }
+
+    #[test]
+    fn cache_ready_timeout() {
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), ());
+        let (generation, ready) = state.guilds_pending([GuildId(1), GuildId(2)]);
+        assert_eq!(ready, None);
+        assert_eq!(state.guild_loaded(GuildId(1)), None);
+        assert_eq!(state.guilds_timed_out(generation), Some(vec![GuildId(1), GuildId(2)]));
+        // the wait already ended
+        assert_eq!(state.guild_loaded(GuildId(2)), None);
+        assert_eq!(state.guilds_timed_out(generation), None);
+
+        // a timeout from an earlier `Ready` doesn't end a later wait
+        let (later, _) = state.guilds_pending([GuildId(3)]);
+        assert_ne!(later, generation);
+        assert_eq!(state.guilds_timed_out(generation), None);
+        assert_eq!(state.guild_loaded(GuildId(3)), Some(vec![GuildId(3)]));
+
+        assert_eq!(state.guilds_pending([]).1, Some(vec![]));
+    }
+
+    #[tokio::test]
+    async fn voice_connection() {
+        use crate::model::voice::VoiceConnectionInfo;
+        use crate::shard::model::UpdateVoiceStatus;
+
+        let join = UpdateVoiceStatus::join(GuildId(1), ChannelId(2));
+        assert_eq!(
+            serde_json::to_value(Payload::from(join)).unwrap(),
+            serde_json::json!({
+                "op": 4,
+                "d": { "guild_id": "1", "channel_id": "2", "self_mute": false, "self_deaf": false },
+            }),
+        );
+        assert_eq!(
+            serde_json::to_value(Payload::from(UpdateVoiceStatus::leave(GuildId(1)))).unwrap()["d"]["channel_id"],
+            serde_json::Value::Null,
+        );
+        // no gateway connection yet
+        let state = BotState::new(DiscordClient::builder(String::new()).build(), ());
+        assert!(matches!(state.join_voice(GuildId(1), ChannelId(2)).await, Err(ShardError::NeedRestart)));
+
+        let cache = Cache::default();
+        *cache.user.write().await = Some(serde_json::from_str(
+            r#"{ "id": "10", "username": "bot", "discriminator": "0", "avatar": null }"#
+        ).unwrap());
+        let voice_state = |user: u64, channel: &str| -> dispatch::VoiceStateUpdate {
+            serde_json::from_str(&format!(r#"{{
+              "guild_id": "1",
+              "channel_id": {channel},
+              "user_id": "{user}",
+              "session_id": "session{user}",
+              "deaf": false,
+              "mute": false,
+              "self_deaf": false,
+              "self_mute": false,
+              "self_video": false,
+              "suppress": false
+            }}"#)).unwrap()
+        };
+        let server: dispatch::VoiceServerUpdate = serde_json::from_str(
+            r#"{"token":"my_token","guild_id":"1","endpoint":"sweetwater-12345.discord.media:2048"}"#
+        ).unwrap();
+
+        server.update(&cache).await;
+        assert_eq!(cache.voice_connection(GuildId(1)).await, None);
+        // other users' voice states aren't the bot's session
+        voice_state(11, r#""2""#).update(&cache).await;
+        assert_eq!(cache.voice_connection(GuildId(1)).await, None);
+        voice_state(10, r#""2""#).update(&cache).await;
+        assert_eq!(cache.voice_connection(GuildId(1)).await, Some(VoiceConnectionInfo {
+            guild_id: GuildId(1),
+            channel_id: ChannelId(2),
+            user_id: UserId(10),
+            session_id: "session10".into(),
+            token: "my_token".into(),
+            endpoint: "sweetwater-12345.discord.media:2048".into(),
+        }));
+
+        let lost: dispatch::VoiceServerUpdate = serde_json::from_str(
+            r#"{"token":"my_token","guild_id":"1","endpoint":null}"#
+        ).unwrap();
+        lost.update(&cache).await;
+        assert_eq!(cache.voice_connection(GuildId(1)).await, None);
+        server.update(&cache).await;
+        assert!(cache.voice_connection(GuildId(1)).await.is_some());
+
+        voice_state(10, "null").update(&cache).await;
+        assert_eq!(cache.voice_connection(GuildId(1)).await, None);
+    }
+
+    struct ReactionBot;
+
+    #[async_trait]
+    impl Bot for ReactionBot {
+        type Error = Infallible;
+
+        fn token(&self) -> String { String::new() }
+    }
+
+    #[derive(Debug, Clone)]
+    struct PollReactions(MessageId);
+
+    #[async_trait]
+    impl crate::commands::ReactionCommand for PollReactions {
+        type Bot = ReactionBot;
+
+        fn applies(&self, reaction: &dispatch::ReactionUpdate) -> bool {
+            reaction.message_id == self.0
+        }
+
+        fn message(&self) -> Option<MessageId> {
+            Some(self.0)
+        }
+
+        async fn run(&self, _: Arc<BotState<ReactionBot>>, _: dispatch::ReactionUpdate) -> Result<(), BotError<Infallible>> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn deleted_message_reaction_commands() {
+        let state = Arc::new(BotState::new(DiscordClient::builder(String::new()).build(), ReactionBot));
+        state.reaction_commands.write().await.extend([
+            Box::new(PollReactions(MessageId(1))) as Box<dyn crate::commands::ReactionCommand<Bot=ReactionBot>>,
+            Box::new(PollReactions(MessageId(2))),
+            Box::new(PollReactions(MessageId(3))),
+            Box::new(PollReactions(MessageId(4))),
+        ]);
+        let remaining = |state: &Arc<BotState<ReactionBot>>| {
+            let state = Arc::clone(state);
+            async move {
+                state.reaction_commands.read().await.iter()
+                    .filter_map(|command| command.message())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let mut shard = Shard::new(Arc::clone(&state));
+        let delete = serde_json::from_str(
+            r#"{"op":0,"s":1,"t":"MESSAGE_DELETE","d":{"id":"1","channel_id":"10"}}"#
+        ).unwrap();
+        shard.handle_payload(delete).await.unwrap();
+        assert_eq!(remaining(&state).await, [MessageId(2), MessageId(3), MessageId(4)]);
+
+        let delete_bulk = serde_json::from_str(
+            r#"{"op":0,"s":2,"t":"MESSAGE_DELETE_BULK","d":{"ids":["2","4"],"channel_id":"10"}}"#
+        ).unwrap();
+        shard.handle_payload(delete_bulk).await.unwrap();
+        assert_eq!(remaining(&state).await, [MessageId(3)]);
+    }
+}
@@ -1,16 +1,16 @@
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
 
-use serde::{de, Serialize, Serializer};
+use serde::{de, Deserialize, Serialize, Serializer};
 use serde::ser::{Error, SerializeMap};
-use serde_derive::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 
+use crate::http::REDACTED_TOKEN;
 use crate::model::emoji::Emoji;
-use crate::model::ids::{ApplicationId, ChannelId, GuildId, UserId};
+use crate::model::ids::{ApplicationId, ChannelId, GuildId, Id, UserId};
 use crate::serde_utils::BoolExt;
 use crate::serde_utils::nice_from_str;
-use crate::shard::dispatch::DispatchPayload;
+use crate::shard::dispatch::Event;
 use crate::shard::intents::Intents;
 
 #[derive(Deserialize, Debug)]
@@ -18,7 +18,7 @@ use crate::shard::intents::Intents;
 pub(crate) enum Payload {
     /// Receive: An event was dispatched.
     Dispatch {
-        event: DispatchPayload,
+        event: Event,
         seq_num: u64,
     },
     /// Send/Receive: Fired periodically by the client to keep the connection alive, fired by
@@ -29,7 +29,6 @@ pub(crate) enum Payload {
     /// Send: Update the client's presence.
     UpdateStatus(UpdateStatus),
     /// Send: Used to join/leave or move between voice channels.
-    #[allow(dead_code)]
     UpdateVoiceStatus(UpdateVoiceStatus),
     /// Send: Resume a previous session that was disconnected.
     Resume(Resume),
@@ -166,7 +165,7 @@ impl Display for Heartbeat {
 }
 
 /// Used to trigger the initial handshake with the gateway.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Clone)]
 pub struct Identify {
     /// authentication token
     pub(crate) token: String,
@@ -203,7 +202,7 @@ impl Identify {
             shard: None,
             presence: None,
             guild_subscriptions: None,
-            intents: Intents::all() ^ Intents::PRIVELEGED,
+            intents: Intents::non_privileged(),
         }
     }
 
@@ -221,6 +220,11 @@ impl Identify {
         self
     }
 
+    /// The intents this bot will identify with.
+    pub const fn intents(&self) -> Intents {
+        self.intents
+    }
+
     /// Add intents to the default
     #[must_use]
     pub fn add_intents(mut self, intents: Intents) -> Self {
@@ -241,6 +245,21 @@ impl From<Identify> for Payload {
     }
 }
 
+impl fmt::Debug for Identify {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Identify")
+            .field("token", &REDACTED_TOKEN)
+            .field("properties", &self.properties)
+            .field("compress", &self.compress)
+            .field("large_threshold", &self.large_threshold)
+            .field("shard", &self.shard)
+            .field("presence", &self.presence)
+            .field("guild_subscriptions", &self.guild_subscriptions)
+            .field("intents", &self.intents)
+            .finish()
+    }
+}
+
 impl Display for Identify {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut debug_struct = f.debug_struct("Identify");
@@ -287,7 +306,7 @@ impl Default for ConnectionProperties {
 }
 
 /// Used to replay missed events when a disconnected client resumes.
-#[derive(Serialize, Debug)]
+#[derive(Serialize)]
 pub struct Resume {
     /// session token
     pub token: String,
@@ -303,6 +322,16 @@ impl From<Resume> for Payload {
     }
 }
 
+impl fmt::Debug for Resume {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Resume")
+            .field("token", &REDACTED_TOKEN)
+            .field("session_id", &self.session_id)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
 /// Don't display the token, this impl is used in `Shard::send`
 impl Display for Resume {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -606,7 +635,7 @@ bitflags! {
 serde_bitflag!(ActivityFlags: u8);
 
 /// Sent when a client wants to join, move, or disconnect from a voice channel.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct UpdateVoiceStatus {
     /// id of the guild
     pub guild_id: GuildId,
@@ -617,3 +646,30 @@ pub struct UpdateVoiceStatus {
     /// is the client deafened
     pub self_deaf: bool,
 }
+
+impl UpdateVoiceStatus {
+    /// Join (or move to) `channel` in `guild`, unmuted and undeafened.
+    pub fn join<G, C>(guild: G, channel: C) -> Self
+        where G: Id<Id=GuildId>,
+              C: Id<Id=ChannelId>,
+    {
+        Self { guild_id: guild.id(), channel_id: Some(channel.id()), self_mute: false, self_deaf: false }
+    }
+
+    /// Disconnect from whatever voice channel the bot is in in `guild`.
+    pub fn leave<G: Id<Id=GuildId>>(guild: G) -> Self {
+        Self { guild_id: guild.id(), channel_id: None, self_mute: false, self_deaf: false }
+    }
+}
+
+impl From<UpdateVoiceStatus> for Payload {
+    fn from(status: UpdateVoiceStatus) -> Self {
+        Self::UpdateVoiceStatus(status)
+    }
+}
+
+impl Display for UpdateVoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -1,6 +1,9 @@
 use async_tungstenite::tungstenite::protocol::CloseFrame;
 use async_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use crate::BotState;
+use crate::model::ids::{ChannelId, GuildId, Id};
+use crate::shard::ShardResult;
+use crate::shard::model::UpdateVoiceStatus;
 
 impl<B: Send + Sync> BotState<B> {
     pub async fn log_out(&self) {
@@ -14,5 +17,43 @@ impl<B: Send + Sync> BotState<B> {
             .unwrap();
     }
 
+    /// Join, move between, or leave voice channels, and set whether the bot is muted or deafened.
+    ///
+    /// Once in a channel, Discord will respond with a
+    /// [`VoiceStateUpdate`](crate::shard::dispatch::VoiceStateUpdate) and a
+    /// [`VoiceServerUpdate`](crate::shard::dispatch::VoiceServerUpdate), which are combined into
+    /// [`Cache::voice_connection`](crate::cache::Cache::voice_connection).
+    ///
+    /// # Errors
+    ///
+    /// If the gateway connection is closed or the payload can't be sent.
+    pub async fn update_voice_state(&self, status: UpdateVoiceStatus) -> ShardResult<()> {
+        crate::shard::send(&mut self.stream.write().await, status).await?;
+        Ok(())
+    }
+
+    /// Join (or move to) the voice channel `channel` in `guild`. See
+    /// [`update_voice_state`](Self::update_voice_state).
+    ///
+    /// # Errors
+    ///
+    /// If the gateway connection is closed or the payload can't be sent.
+    pub async fn join_voice<G, C>(&self, guild: G, channel: C) -> ShardResult<()>
+        where G: Id<Id=GuildId> + Send,
+              C: Id<Id=ChannelId> + Send,
+    {
+        self.update_voice_state(UpdateVoiceStatus::join(guild, channel)).await
+    }
+
+    /// Leave whatever voice channel the bot is in in `guild`. See
+    /// [`update_voice_state`](Self::update_voice_state).
+    ///
+    /// # Errors
+    ///
+    /// If the gateway connection is closed or the payload can't be sent.
+    pub async fn leave_voice<G: Id<Id=GuildId> + Send>(&self, guild: G) -> ShardResult<()> {
+        self.update_voice_state(UpdateVoiceStatus::leave(guild)).await
+    }
+
     // todo request guild members, etc
-}
\ No newline at end of file
+}
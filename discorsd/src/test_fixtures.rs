@@ -0,0 +1,50 @@
+//! JSON shared by tests across modules.
+
+/// A guild named "Test Server" with id `id`, and the given JSON arrays of `roles`, `channels`, and
+/// `members`.
+pub(crate) fn guild(id: u64, roles: &str, channels: &str, members: &str) -> String {
+    format!(r#"{{
+      "id": "{id}",
+      "name": "Test Server",
+      "icon": null,
+      "splash": null,
+      "discovery_splash": null,
+      "features": [],
+      "emojis": [],
+      "owner_id": "10",
+      "region": "us-west",
+      "afk_channel_id": null,
+      "afk_timeout": 300,
+      "system_channel_id": null,
+      "verification_level": 0,
+      "roles": {roles},
+      "channels": {channels},
+      "members": {members},
+      "default_message_notifications": 1,
+      "mfa_level": 0,
+      "explicit_content_filter": 0,
+      "premium_tier": 0,
+      "system_channel_flags": 0,
+      "rules_channel_id": null
+    }}"#)
+}
+
+/// A message saying "Supa Hot" with id `id` in channel `channel`, sent by user `1`.
+pub(crate) fn message(id: u64, channel: u64) -> String {
+    format!(r#"{{
+      "id": "{id}",
+      "channel_id": "{channel}",
+      "author": {{ "id": "1", "username": "Mason", "discriminator": "9999", "avatar": null }},
+      "content": "Supa Hot",
+      "timestamp": "2017-07-11T17:27:07.299Z",
+      "edited_timestamp": null,
+      "tts": false,
+      "mention_everyone": false,
+      "mentions": [],
+      "mention_roles": [],
+      "attachments": [],
+      "embeds": [],
+      "pinned": false,
+      "type": 0
+    }}"#)
+}